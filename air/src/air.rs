@@ -1,3 +1,5 @@
+use alloc::string::String;
+use alloc::vec::Vec;
 use core::ops::{Add, Mul, Sub};
 
 use p3_field::{ExtensionField, Field, FieldAlgebra, FieldExtensionAlgebra};
@@ -9,18 +11,75 @@ pub trait BaseAir<F>: Sync {
     /// The number of columns (a.k.a. registers) in this AIR.
     fn width(&self) -> usize;
 
+    /// Names for the columns of the main trace, in order, which serve diagnostics such as
+    /// constraint failure reports. `None` if this AIR doesn't name its columns.
+    fn column_names(&self) -> Option<Vec<String>> {
+        None
+    }
+
     fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
         None
     }
-}
 
-///  An AIR with 0 or more public values.
-pub trait BaseAirWithPublicValues<F>: BaseAir<F> {
+    /// The number of public values this AIR's constraints may refer to.
     fn num_public_values(&self) -> usize {
         0
     }
+
+    /// The number of extension field columns in the permutation trace, a second trace which is
+    /// generated (via `generate_permutation_trace`) only after the main trace has been committed.
+    fn permutation_width(&self) -> usize {
+        0
+    }
+
+    /// The number of extension field challenges to sample, after committing to the main trace,
+    /// which are used to generate the permutation trace.
+    fn num_permutation_challenges(&self) -> usize {
+        0
+    }
+
+    /// The permutation trace column whose last row holds this table's cumulative sum (see
+    /// `MultiTableAirBuilder::cumulative_sum`). Only meaningful if `permutation_width` is nonzero,
+    /// and defaults to the last column.
+    fn cumulative_sum_column(&self) -> usize {
+        self.permutation_width() - 1
+    }
+
+    /// Generate the permutation trace from the main trace and the sampled challenges.
+    ///
+    /// Returns `None` if this AIR has no permutation trace.
+    fn generate_permutation_trace<EF>(
+        &self,
+        _preprocessed: Option<&RowMajorMatrix<F>>,
+        _main: &RowMajorMatrix<F>,
+        _challenges: &[EF],
+    ) -> Option<RowMajorMatrix<EF>>
+    where
+        F: Field,
+        EF: ExtensionField<F>,
+    {
+        None
+    }
+
+    /// Whether provers may evaluate this AIR's constraints from their symbolic form, compiled once
+    /// per proof, rather than by calling `Air::eval` at every quotient domain point.
+    ///
+    /// The symbolic form has no extension field constants, so this must only be enabled if every
+    /// extension field expression built in `eval` comes from permutation columns, permutation
+    /// challenges, the cumulative sum and base field values.
+    fn compile_constraints(&self) -> bool {
+        false
+    }
 }
 
+///  An AIR with 0 or more public values.
+///
+/// Every AIR has a number of public values, `BaseAir::num_public_values`, so this is implemented
+/// for all of them, and only remains so that bounds which name it still hold.
+pub trait BaseAirWithPublicValues<F>: BaseAir<F> {}
+
+impl<F, A: BaseAir<F> + ?Sized> BaseAirWithPublicValues<F> for A {}
+
 /// An AIR that works with a particular `AirBuilder`.
 pub trait Air<AB: AirBuilder>: BaseAir<AB::F> {
     fn eval(&self, builder: &mut AB);
@@ -102,6 +161,12 @@ pub trait AirBuilder: Sized {
 
     fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I);
 
+    /// Names the constraints asserted after this call, until the next call to `label`.
+    ///
+    /// Labels only serve diagnostics, e.g. in constraint failure reports; builders which don't
+    /// report on individual constraints ignore them.
+    fn label(&mut self, _label: &'static str) {}
+
     fn assert_one<I: Into<Self::Expr>>(&mut self, x: I) {
         self.assert_zero(x.into() - Self::Expr::ONE);
     }
@@ -170,6 +235,17 @@ pub trait PermutationAirBuilder: ExtensionBuilder {
     fn permutation_randomness(&self) -> &[Self::RandomVar];
 }
 
+/// A `PermutationAirBuilder` for an AIR whose permutation argument need not balance on its own,
+/// since it's one of several tables in a multi-table proof.
+pub trait MultiTableAirBuilder: PermutationAirBuilder {
+    type Sum: Into<Self::ExprEF> + Copy;
+
+    /// The claimed final value of this table's permutation running sum, which is the last row of
+    /// the column given by `BaseAir::cumulative_sum_column`. The claimed sums of all tables must add
+    /// up to zero.
+    fn cumulative_sum(&self) -> Self::Sum;
+}
+
 #[derive(Debug)]
 pub struct FilteredAirBuilder<'a, AB: AirBuilder> {
     pub inner: &'a mut AB,
@@ -207,6 +283,16 @@ impl<'a, AB: AirBuilder> AirBuilder for FilteredAirBuilder<'a, AB> {
     fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
         self.inner.assert_zero(self.condition() * x.into());
     }
+
+    fn label(&mut self, label: &'static str) {
+        self.inner.label(label);
+    }
+}
+
+impl<'a, AB: PairBuilder> PairBuilder for FilteredAirBuilder<'a, AB> {
+    fn preprocessed(&self) -> Self::M {
+        self.inner.preprocessed()
+    }
 }
 
 impl<'a, AB: ExtensionBuilder> ExtensionBuilder for FilteredAirBuilder<'a, AB> {
@@ -235,3 +321,11 @@ impl<'a, AB: PermutationAirBuilder> PermutationAirBuilder for FilteredAirBuilder
         self.inner.permutation_randomness()
     }
 }
+
+impl<'a, AB: MultiTableAirBuilder> MultiTableAirBuilder for FilteredAirBuilder<'a, AB> {
+    type Sum = AB::Sum;
+
+    fn cumulative_sum(&self) -> Self::Sum {
+        self.inner.cumulative_sum()
+    }
+}
@@ -113,7 +113,9 @@ mod tests {
     use core::array;
 
     use p3_field::{PrimeField32, PrimeField64, TwoAdicField};
-    use p3_field_testing::{test_field, test_field_dft, test_two_adic_field};
+    use p3_field_testing::{
+        test_field, test_field_dft, test_field_subgroup_dft, test_two_adic_field,
+    };
 
     use super::*;
 
@@ -235,4 +237,11 @@ mod tests {
         crate::BabyBear,
         p3_monty_31::dft::RecursiveDft<_>
     );
+
+    test_field_subgroup_dft!(
+        mixed_radix,
+        crate::BabyBear,
+        p3_dft::MixedRadixDft,
+        [1, 2, 3, 4, 5, 6, 10, 12, 15, 20, 30, 60, 64, 96, 240]
+    );
 }
@@ -0,0 +1,20 @@
+//! A multilinear polynomial commitment scheme in the style of
+//! [Basefold](https://eprint.iacr.org/2023/1705).
+//!
+//! Polynomials are committed to as Reed-Solomon codewords of their coefficients, like a FRI-based
+//! PCS. An evaluation claim is reduced by a sumcheck which runs in lockstep with FRI's commit
+//! phase: each fold of the codeword by a challenge fixes one variable of the polynomial to it, so
+//! the polynomial FRI ends with is the one the sumcheck's final claim is about.
+
+#![no_std]
+
+extern crate alloc;
+
+mod pcs;
+mod proof;
+pub mod prover;
+mod utils;
+pub mod verifier;
+
+pub use pcs::*;
+pub use proof::*;
@@ -0,0 +1,202 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use itertools::izip;
+use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
+use p3_commit::{Mmcs, MultilinearPcs};
+use p3_dft::TwoAdicSubgroupDft;
+use p3_field::{ExtensionField, Field, TwoAdicField};
+use p3_fri::{BatchOpening, FriConfig};
+use p3_matrix::bitrev::BitReversableMatrix;
+use p3_matrix::dense::{RowMajorMatrix, RowMajorMatrixView};
+use p3_matrix::{Dimensions, Matrix};
+use p3_maybe_rayon::prelude::*;
+use p3_util::log2_strict_usize;
+use tracing::instrument;
+
+use crate::utils::{eq_evals, evals_to_coeffs};
+use crate::verifier::BasefoldError;
+use crate::{prover, verifier, BasefoldProof};
+
+/// A Basefold [`MultilinearPcs`], which commits to multilinear polynomials with `InputMmcs` and
+/// runs FRI's commit phase with `FriMmcs`.
+///
+/// FRI must fold in pairs, as each fold fixes one variable, but may stop at a final polynomial of
+/// any length.
+#[derive(Debug)]
+pub struct BasefoldPcs<Val, Dft, InputMmcs, FriMmcs> {
+    dft: Dft,
+    mmcs: InputMmcs,
+    fri: FriConfig<FriMmcs>,
+    _phantom: PhantomData<Val>,
+}
+
+impl<Val, Dft, InputMmcs, FriMmcs> BasefoldPcs<Val, Dft, InputMmcs, FriMmcs> {
+    pub const fn new(dft: Dft, mmcs: InputMmcs, fri: FriConfig<FriMmcs>) -> Self {
+        assert!(fri.log_arity == 1, "Basefold folds one variable at a time");
+        Self {
+            dft,
+            mmcs,
+            fri,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// The data a [`BasefoldPcs`] prover keeps for a commitment.
+pub struct BasefoldProverData<Val: Field, InputMmcs: Mmcs<Val>> {
+    /// The committed polynomials' evaluations over the hypercube, as passed to `commit`.
+    evaluations: RowMajorMatrix<Val>,
+    /// The committed codewords, in bit-reversed order, with the pairs which fold together in each
+    /// row.
+    data: InputMmcs::ProverData<RowMajorMatrix<Val>>,
+}
+
+impl<Val, Dft, InputMmcs, FriMmcs, Challenge, Challenger> MultilinearPcs<Challenge, Challenger>
+    for BasefoldPcs<Val, Dft, InputMmcs, FriMmcs>
+where
+    Val: TwoAdicField,
+    Dft: TwoAdicSubgroupDft<Val>,
+    InputMmcs: Mmcs<Val>,
+    FriMmcs: Mmcs<Challenge>,
+    Challenge: TwoAdicField + ExtensionField<Val>,
+    Challenger:
+        FieldChallenger<Val> + CanObserve<FriMmcs::Commitment> + GrindingChallenger<Witness = Val>,
+{
+    type Val = Val;
+    type Commitment = InputMmcs::Commitment;
+    type ProverData = BasefoldProverData<Val, InputMmcs>;
+    type Proof = BasefoldProof<Challenge, FriMmcs, Val, BatchOpening<Val, InputMmcs>>;
+    type Error = BasefoldError<FriMmcs::Error, InputMmcs::Error>;
+
+    fn parameters(&self) -> Vec<usize> {
+        let mut parameters = self.fri.parameters();
+        parameters.extend(self.mmcs.parameters());
+        parameters.extend(self.fri.mmcs.parameters());
+        parameters
+    }
+
+    #[instrument(name = "Basefold commit", skip_all)]
+    fn commit(&self, evaluations: RowMajorMatrix<Val>) -> (Self::Commitment, Self::ProverData) {
+        let width = evaluations.width();
+        let num_vars = log2_strict_usize(evaluations.height());
+
+        // The multilinear coefficient of the monomial in the variables given by the bits of `i` is
+        // the coefficient of `X^i`, so folding the codeword of this univariate polynomial in pairs
+        // with FRI fixes its first variable.
+        let mut coeffs = evaluations.clone();
+        evals_to_coeffs(&mut coeffs);
+        coeffs
+            .values
+            .resize(width << (num_vars + self.fri.log_blowup), Val::ZERO);
+        let lde = self
+            .dft
+            .dft_batch(coeffs)
+            .bit_reverse_rows()
+            .to_row_major_matrix();
+
+        // In bit-reversed order, the evaluations which fold together are adjacent.
+        let leaves = RowMajorMatrix::new(lde.values, 2 * width);
+        let (commitment, data) = self.mmcs.commit_matrix(leaves);
+        (commitment, BasefoldProverData { evaluations, data })
+    }
+
+    fn open(
+        &self,
+        prover_data: &Self::ProverData,
+        point: &[Challenge],
+        challenger: &mut Challenger,
+    ) -> (Vec<Challenge>, Self::Proof) {
+        let evaluations = &prover_data.evaluations;
+        let width = evaluations.width();
+        assert_eq!(evaluations.height(), 1 << point.len());
+
+        let values = evaluations.par_rows().zip(eq_evals(point)).par_fold_reduce(
+            || vec![Challenge::ZERO; width],
+            |mut acc, (row, eq)| {
+                izip!(&mut acc, row).for_each(|(a, v)| *a += eq * v);
+                acc
+            },
+            |mut acc, other| {
+                izip!(&mut acc, other).for_each(|(a, v)| *a += v);
+                acc
+            },
+        );
+        for &value in &values {
+            challenger.observe_ext_element(value);
+        }
+
+        // Batch combination challenge
+        let alpha: Challenge = challenger.sample_ext_element();
+        let evals = evaluations.dot_ext_powers(alpha).collect();
+        let leaves = self.mmcs.get_matrices(&prover_data.data).pop().unwrap();
+        let codeword = RowMajorMatrixView::new(&leaves.values, width)
+            .dot_ext_powers(alpha)
+            .collect();
+
+        let proof = prover::prove(&self.fri, evals, codeword, point, challenger, |index| {
+            let (opened_values, opening_proof) =
+                self.mmcs.open_batch(index >> 1, &prover_data.data);
+            BatchOpening {
+                opened_values,
+                opening_proof,
+            }
+        });
+
+        (values, proof)
+    }
+
+    fn verify(
+        &self,
+        commitment: &Self::Commitment,
+        point: &[Challenge],
+        values: &[Challenge],
+        proof: &Self::Proof,
+        challenger: &mut Challenger,
+    ) -> Result<(), Self::Error> {
+        for &value in values {
+            challenger.observe_ext_element(value);
+        }
+
+        // Batch combination challenge
+        let alpha: Challenge = challenger.sample_ext_element();
+        let claim = alpha
+            .powers()
+            .zip(values)
+            .map(|(power, &value)| power * value)
+            .sum();
+
+        let dims = &[Dimensions {
+            width: 2 * values.len(),
+            height: 1 << (point.len() + self.fri.log_blowup - 1),
+        }];
+        verifier::verify(
+            &self.fri,
+            point,
+            claim,
+            proof,
+            challenger,
+            |index, input_proof: &BatchOpening<Val, InputMmcs>| {
+                self.mmcs.verify_batch(
+                    commitment,
+                    dims,
+                    index >> 1,
+                    &input_proof.opened_values,
+                    &input_proof.opening_proof,
+                )?;
+                // Each row holds the two codeword evaluations of every polynomial.
+                let row = &input_proof.opened_values[0];
+                let (lo, hi) = row.split_at(row.len() / 2);
+                let combine = |evals: &[Val]| {
+                    alpha
+                        .powers()
+                        .zip(evals)
+                        .map(|(power, &eval)| power * eval)
+                        .sum()
+                };
+                Ok([combine(lo), combine(hi)])
+            },
+        )
+    }
+}
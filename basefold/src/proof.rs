@@ -0,0 +1,44 @@
+use alloc::vec::Vec;
+
+use p3_commit::Mmcs;
+use p3_field::Field;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound(
+    serialize = "Witness: Serialize, InputProof: Serialize",
+    deserialize = "Witness: Deserialize<'de>, InputProof: Deserialize<'de>"
+))]
+pub struct BasefoldProof<F: Field, M: Mmcs<F>, Witness, InputProof> {
+    /// The sumcheck's round polynomials, each given by its evaluations at 0, 1 and 2.
+    pub sumcheck_evals: Vec<[F; 3]>,
+    /// The commitments to the codeword after each fold but the last.
+    pub commit_phase_commits: Vec<M::Commitment>,
+    pub query_proofs: Vec<QueryProof<F, M, InputProof>>,
+    /// The coefficients of the multilinear polynomial left after the sumcheck, which are also
+    /// those of the univariate polynomial the codeword folds down to, lowest degree first.
+    pub final_poly: Vec<F>,
+    pub pow_witness: Witness,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound(
+    serialize = "InputProof: Serialize",
+    deserialize = "InputProof: Deserialize<'de>",
+))]
+pub struct QueryProof<F: Field, M: Mmcs<F>, InputProof> {
+    pub input_proof: InputProof,
+    /// For each commit phase commitment, this contains openings of a commit phase codeword at the
+    /// queried location, along with an opening proof.
+    pub commit_phase_openings: Vec<CommitPhaseProofStep<F, M>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(bound = "")]
+pub struct CommitPhaseProofStep<F: Field, M: Mmcs<F>> {
+    /// The opening of the commit phase codeword at the location which folds together with the
+    /// queried one.
+    pub sibling_values: Vec<F>,
+
+    pub opening_proof: M::Proof,
+}
@@ -0,0 +1,143 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter;
+use core::marker::PhantomData;
+
+use itertools::Itertools;
+use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
+use p3_commit::Mmcs;
+use p3_dft::{Radix2Dit, TwoAdicSubgroupDft};
+use p3_field::{ExtensionField, Field, TwoAdicField};
+use p3_fri::{FriConfig, FriGenericConfig, TwoAdicFriGenericConfig};
+use p3_matrix::dense::{RowMajorMatrix, RowMajorMatrixView};
+use p3_util::{log2_strict_usize, reverse_slice_index_bits};
+use tracing::{info_span, instrument};
+
+use crate::utils::{eq_evals, fix_first_variable, round_poly};
+use crate::{BasefoldProof, CommitPhaseProofStep, QueryProof};
+
+/// Prove that the multilinear polynomial with the given evaluations over the hypercube takes the
+/// value `sum_x evals[x] eq(point, x)` at `point`.
+///
+/// `codeword` is the polynomial's codeword, as committed to in bit-reversed order, and
+/// `open_input` opens it at a query.
+#[instrument(name = "Basefold prover", skip_all)]
+pub fn prove<Val, Challenge, M, Challenger, InputProof>(
+    config: &FriConfig<M>,
+    evals: Vec<Challenge>,
+    codeword: Vec<Challenge>,
+    point: &[Challenge],
+    challenger: &mut Challenger,
+    open_input: impl Fn(usize) -> InputProof,
+) -> BasefoldProof<Challenge, M, Challenger::Witness, InputProof>
+where
+    Val: Field,
+    Challenge: ExtensionField<Val> + TwoAdicField,
+    M: Mmcs<Challenge>,
+    Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<M::Commitment>,
+{
+    assert_eq!(config.log_arity, 1, "Basefold folds one variable at a time");
+    assert_eq!(evals.len(), 1 << point.len());
+    assert_eq!(codeword.len(), evals.len() << config.log_blowup);
+
+    let log_max_height = log2_strict_usize(codeword.len());
+    let num_rounds = point.len() - config.log_final_poly_len.min(point.len());
+    let g: TwoAdicFriGenericConfig<(), ()> = TwoAdicFriGenericConfig(PhantomData);
+
+    let mut evals = evals;
+    let mut eq = eq_evals(point);
+    let mut folded = codeword;
+    let mut sumcheck_evals = vec![];
+    let mut commits = vec![];
+    let mut data: Vec<M::ProverData<RowMajorMatrix<Challenge>>> = vec![];
+
+    for round in 0..num_rounds {
+        let round_evals = round_poly(&evals, &eq);
+        for &eval in &round_evals {
+            challenger.observe_ext_element(eval);
+        }
+        sumcheck_evals.push(round_evals);
+
+        // Folding the codeword by `r` fixes the polynomial's first variable to `r`.
+        let r: Challenge = challenger.sample_ext_element();
+        evals = fix_first_variable(&evals, r);
+        eq = fix_first_variable(&eq, r);
+        // In bit-reversed order, the evaluations which fold together are adjacent. After the
+        // first round, the codeword was passed to the MMCS, so get a reference to it.
+        folded = match data.last() {
+            Some(prover_data) => {
+                let leaves = config.mmcs.get_matrices(prover_data).pop().unwrap();
+                g.fold_matrix(r, RowMajorMatrixView::new(&leaves.values, 2))
+            }
+            None => g.fold_matrix(r, RowMajorMatrix::new(folded, 2)),
+        };
+
+        if round + 1 < num_rounds {
+            let leaves = RowMajorMatrix::new(core::mem::take(&mut folded), 2);
+            let (commit, prover_data) = config.mmcs.commit_matrix(leaves);
+            challenger.observe(commit.clone());
+            commits.push(commit);
+            data.push(prover_data);
+        }
+    }
+
+    // We're left with the codeword of the polynomial in the remaining variables, over the
+    // subgroup in bit-reversed order.
+    let final_poly_len = folded.len() >> config.log_blowup;
+    reverse_slice_index_bits(&mut folded);
+    let mut final_poly = Radix2Dit::default().idft(folded);
+    assert!(
+        final_poly[final_poly_len..].iter().all(|c| c.is_zero()),
+        "final polynomial has too high a degree"
+    );
+    final_poly.truncate(final_poly_len);
+    for &coeff in &final_poly {
+        challenger.observe_ext_element(coeff);
+    }
+
+    let pow_witness = challenger.grind(config.proof_of_work_bits);
+
+    let query_proofs = info_span!("query phase").in_scope(|| {
+        iter::repeat_with(|| challenger.sample_bits(log_max_height))
+            .take(config.num_queries)
+            .map(|index| QueryProof {
+                input_proof: open_input(index),
+                commit_phase_openings: answer_query(config, &data, index >> 1),
+            })
+            .collect()
+    });
+
+    BasefoldProof {
+        sumcheck_evals,
+        commit_phase_commits: commits,
+        query_proofs,
+        final_poly,
+        pow_witness,
+    }
+}
+
+/// Open each commit phase codeword at the pair of locations which fold into the next query index.
+fn answer_query<F, M>(
+    config: &FriConfig<M>,
+    data: &[M::ProverData<RowMajorMatrix<F>>],
+    mut index: usize,
+) -> Vec<CommitPhaseProofStep<F, M>>
+where
+    F: Field,
+    M: Mmcs<F>,
+{
+    data.iter()
+        .map(|prover_data| {
+            let index_row = index >> 1;
+            let (mut opened_rows, opening_proof) = config.mmcs.open_batch(index_row, prover_data);
+            let mut sibling_values = opened_rows.pop().unwrap();
+            sibling_values.remove(index & 1);
+            index = index_row;
+
+            CommitPhaseProofStep {
+                sibling_values,
+                opening_proof,
+            }
+        })
+        .collect_vec()
+}
@@ -0,0 +1,85 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::izip;
+use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_maybe_rayon::prelude::*;
+use p3_util::log2_strict_usize;
+
+/// Convert the evaluations of multilinear polynomials over the hypercube, one per column, to their
+/// coefficients in the monomial basis, in place. Row `i` then holds the coefficients of the
+/// monomial in the variables given by the set bits of `i`.
+pub(crate) fn evals_to_coeffs<F: Field>(mat: &mut RowMajorMatrix<F>) {
+    let width = mat.width();
+    for log_half in 0..log2_strict_usize(mat.height()) {
+        let half = width << log_half;
+        mat.values.par_chunks_exact_mut(2 * half).for_each(|chunk| {
+            let (lo, hi) = chunk.split_at_mut(half);
+            izip!(hi, lo).for_each(|(h, &mut l)| *h -= l);
+        });
+    }
+}
+
+/// The evaluations of `eq(point, x) = prod_j (point_j x_j + (1 - point_j) (1 - x_j))` over the
+/// hypercube.
+pub(crate) fn eq_evals<F: Field>(point: &[F]) -> Vec<F> {
+    let mut evals = vec![F::ONE];
+    for &z in point {
+        let hi = evals.iter().map(|&e| e * z).collect::<Vec<_>>();
+        izip!(&mut evals, &hi).for_each(|(lo, &hi)| *lo -= hi);
+        evals.extend(hi);
+    }
+    evals
+}
+
+/// `eq(x, y)` for two points of the same dimension.
+pub(crate) fn eq_eval<F: Field>(x: &[F], y: &[F]) -> F {
+    izip!(x, y)
+        .map(|(&x, &y)| x * y + (F::ONE - x) * (F::ONE - y))
+        .product()
+}
+
+/// Fix the first variable of a multilinear polynomial, given by its evaluations over the
+/// hypercube, to `r`.
+pub(crate) fn fix_first_variable<F: Field>(evals: &[F], r: F) -> Vec<F> {
+    evals
+        .par_chunks_exact(2)
+        .map(|pair| pair[0] + r * (pair[1] - pair[0]))
+        .collect()
+}
+
+/// The evaluations at 0, 1 and 2 of the sum of `f(t, x) g(t, x)` over the rest of the hypercube,
+/// as a polynomial in the first variable `t`.
+pub(crate) fn round_poly<F: Field>(f: &[F], g: &[F]) -> [F; 3] {
+    f.par_chunks_exact(2)
+        .zip(g.par_chunks_exact(2))
+        .par_fold_reduce(
+            || [F::ZERO; 3],
+            |[h0, h1, h2], (f, g)| {
+                let f2 = f[1].double() - f[0];
+                let g2 = g[1].double() - g[0];
+                [h0 + f[0] * g[0], h1 + f[1] * g[1], h2 + f2 * g2]
+            },
+            |[a0, a1, a2], [b0, b1, b2]| [a0 + b0, a1 + b1, a2 + b2],
+        )
+}
+
+/// Evaluate at `r` the polynomial of degree at most 2 with the given evaluations at 0, 1 and 2.
+pub(crate) fn interpolate_round_poly<F: Field>([h0, h1, h2]: [F; 3], r: F) -> F {
+    let (r1, r2) = (r - F::ONE, r - F::TWO);
+    (h0 * r1 * r2 + h2 * r * r1).halve() - h1 * r * r2
+}
+
+/// Evaluate a multilinear polynomial, given by its coefficients in the monomial basis, at `point`.
+pub(crate) fn eval_multilinear_coeffs<F: Field>(coeffs: &[F], point: &[F]) -> F {
+    let mut coeffs = coeffs.to_vec();
+    for &x in point {
+        coeffs = coeffs
+            .chunks_exact(2)
+            .map(|pair| pair[0] + x * pair[1])
+            .collect();
+    }
+    coeffs[0]
+}
@@ -0,0 +1,155 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use itertools::izip;
+use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
+use p3_commit::Mmcs;
+use p3_field::{ExtensionField, Field, TwoAdicField};
+use p3_fri::{FriConfig, FriGenericConfig, TwoAdicFriGenericConfig};
+use p3_matrix::Dimensions;
+use p3_util::reverse_bits_len;
+
+use crate::utils::{eq_eval, eval_multilinear_coeffs, interpolate_round_poly};
+use crate::BasefoldProof;
+
+#[derive(Debug)]
+pub enum BasefoldError<CommitMmcsErr, InputError> {
+    InvalidProofShape,
+    CommitPhaseMmcsError(CommitMmcsErr),
+    InputError(InputError),
+    /// A sumcheck round polynomial doesn't sum to the previous round's claim.
+    SumcheckMismatch,
+    /// The final polynomial doesn't match the sumcheck's final claim.
+    EvaluationMismatch,
+    FinalPolyMismatch,
+    InvalidPowWitness,
+}
+
+/// Verify a Basefold proof that the committed multilinear polynomial takes the value `claim` at
+/// `point`.
+///
+/// `open_input` checks the opening of the committed codeword at a query, and returns the pair of
+/// evaluations which fold together there, in the order they're committed.
+pub fn verify<Val, Challenge, M, Challenger, InputProof, InputError>(
+    config: &FriConfig<M>,
+    point: &[Challenge],
+    claim: Challenge,
+    proof: &BasefoldProof<Challenge, M, Challenger::Witness, InputProof>,
+    challenger: &mut Challenger,
+    open_input: impl Fn(usize, &InputProof) -> Result<[Challenge; 2], InputError>,
+) -> Result<(), BasefoldError<M::Error, InputError>>
+where
+    Val: Field,
+    Challenge: ExtensionField<Val> + TwoAdicField,
+    M: Mmcs<Challenge>,
+    Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<M::Commitment>,
+{
+    // Basefold folds one variable at a time, which `BasefoldPcs` checks when it's built.
+    let num_rounds = point.len() - config.log_final_poly_len.min(point.len());
+    if config.log_arity != 1
+        || proof.sumcheck_evals.len() != num_rounds
+        || proof.commit_phase_commits.len() != num_rounds.saturating_sub(1)
+        || proof.final_poly.len() != 1 << (point.len() - num_rounds)
+        || proof.query_proofs.len() != config.num_queries
+    {
+        return Err(BasefoldError::InvalidProofShape);
+    }
+
+    let mut claim = claim;
+    let mut rs = Vec::with_capacity(num_rounds);
+    for (round, &round_evals) in proof.sumcheck_evals.iter().enumerate() {
+        if round_evals[0] + round_evals[1] != claim {
+            return Err(BasefoldError::SumcheckMismatch);
+        }
+        for eval in round_evals {
+            challenger.observe_ext_element(eval);
+        }
+        let r: Challenge = challenger.sample_ext_element();
+        claim = interpolate_round_poly(round_evals, r);
+        rs.push(r);
+        if let Some(commit) = proof.commit_phase_commits.get(round) {
+            challenger.observe(commit.clone());
+        }
+    }
+
+    // The sum left is that of the final polynomial times `eq` over the remaining variables.
+    let (fixed_point, rest_point) = point.split_at(num_rounds);
+    if eq_eval(fixed_point, &rs) * eval_multilinear_coeffs(&proof.final_poly, rest_point) != claim {
+        return Err(BasefoldError::EvaluationMismatch);
+    }
+    for &coeff in &proof.final_poly {
+        challenger.observe_ext_element(coeff);
+    }
+
+    if !challenger.check_witness(config.proof_of_work_bits, proof.pow_witness) {
+        return Err(BasefoldError::InvalidPowWitness);
+    }
+
+    let g: TwoAdicFriGenericConfig<(), ()> = TwoAdicFriGenericConfig(PhantomData);
+    let log_max_height = point.len() + config.log_blowup;
+    let log_final_height = log_max_height - num_rounds;
+
+    for qp in &proof.query_proofs {
+        let index = challenger.sample_bits(log_max_height);
+        let input_evals = open_input(index, &qp.input_proof).map_err(BasefoldError::InputError)?;
+        if qp.commit_phase_openings.len() != proof.commit_phase_commits.len() {
+            return Err(BasefoldError::InvalidProofShape);
+        }
+
+        let mut index = index;
+        let mut log_height = log_max_height;
+        let mut folded_eval = if num_rounds == 0 {
+            input_evals[index & 1]
+        } else {
+            index >>= 1;
+            log_height -= 1;
+            g.fold_row(index, log_height, rs[0], input_evals.into_iter())
+        };
+
+        for (&r, commit, opening) in izip!(
+            rs.iter().skip(1),
+            &proof.commit_phase_commits,
+            &qp.commit_phase_openings
+        ) {
+            if opening.sibling_values.len() != 1 {
+                return Err(BasefoldError::InvalidProofShape);
+            }
+            let mut evals = opening.sibling_values.clone();
+            evals.insert(index & 1, folded_eval);
+
+            index >>= 1;
+            log_height -= 1;
+            let dims = &[Dimensions {
+                width: 2,
+                height: 1 << log_height,
+            }];
+            config
+                .mmcs
+                .verify_batch(
+                    commit,
+                    dims,
+                    index,
+                    &[evals.clone()],
+                    &opening.opening_proof,
+                )
+                .map_err(BasefoldError::CommitPhaseMmcsError)?;
+
+            folded_eval = g.fold_row(index, log_height, r, evals.into_iter());
+        }
+
+        // The final codeword is over the subgroup, in bit-reversed order.
+        debug_assert_eq!(log_height, log_final_height);
+        let x = Challenge::two_adic_generator(log_final_height)
+            .exp_u64(reverse_bits_len(index, log_final_height) as u64);
+        let final_eval = proof
+            .final_poly
+            .iter()
+            .rev()
+            .fold(Challenge::ZERO, |acc, &coeff| acc * x + coeff);
+        if folded_eval != final_eval {
+            return Err(BasefoldError::FinalPolyMismatch);
+        }
+    }
+
+    Ok(())
+}
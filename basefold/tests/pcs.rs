@@ -0,0 +1,151 @@
+use p3_basefold::verifier::BasefoldError;
+use p3_basefold::BasefoldPcs;
+use p3_challenger::CanSampleBits;
+use p3_commit::MultilinearPcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::FieldAlgebra;
+use p3_fri::FriConfig;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_uni_stark::testing::{
+    make_fri_config, make_mmcs, Challenge, ChallengeMmcs, Challenger, Perm, Val, ValMmcs,
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+type Dft = Radix2DitParallel<Val>;
+type MyPcs = BasefoldPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+type Proof = <MyPcs as MultilinearPcs<Challenge, Challenger>>::Proof;
+type Error = <MyPcs as MultilinearPcs<Challenge, Challenger>>::Error;
+
+fn get_pcs<R: Rng>(rng: &mut R, log_final_poly_len: usize) -> (Perm, MyPcs) {
+    let perm = Perm::new_from_rng_128(rng);
+    let fri_config = FriConfig {
+        log_final_poly_len,
+        ..make_fri_config(&perm, 1, 10, 8)
+    };
+    let pcs = MyPcs::new(Dft::default(), make_mmcs(&perm), fri_config);
+    (perm, pcs)
+}
+
+/// Evaluate the multilinear polynomials with the given evaluations over the hypercube at `point`,
+/// by fixing one variable at a time.
+fn eval_naive(evals: &RowMajorMatrix<Val>, point: &[Challenge]) -> Vec<Challenge> {
+    (0..evals.width())
+        .map(|col| {
+            let mut column: Vec<Challenge> = evals
+                .rows()
+                .map(|mut row| row.nth(col).unwrap().into())
+                .collect();
+            for &z in point {
+                column = column
+                    .chunks_exact(2)
+                    .map(|pair| pair[0] + z * (pair[1] - pair[0]))
+                    .collect();
+            }
+            column[0]
+        })
+        .collect()
+}
+
+fn verify(
+    perm: &Perm,
+    pcs: &MyPcs,
+    commitment: &<MyPcs as MultilinearPcs<Challenge, Challenger>>::Commitment,
+    point: &[Challenge],
+    values: &[Challenge],
+    proof: &Proof,
+) -> Result<usize, Error> {
+    let mut challenger = Challenger::new(perm.clone());
+    pcs.verify(commitment, point, values, proof, &mut challenger)?;
+    Ok(challenger.sample_bits(8))
+}
+
+fn do_test_open<R: Rng>(rng: &mut R, log_final_poly_len: usize, num_vars: usize, width: usize) {
+    let (perm, pcs) = get_pcs(rng, log_final_poly_len);
+    let evals = RowMajorMatrix::<Val>::rand(rng, 1 << num_vars, width);
+    let point: Vec<Challenge> = (0..num_vars).map(|_| rng.gen()).collect();
+    let (commitment, data) = MultilinearPcs::<Challenge, Challenger>::commit(&pcs, evals.clone());
+
+    let mut challenger = Challenger::new(perm.clone());
+    let (values, proof) = pcs.open(&data, &point, &mut challenger);
+    assert_eq!(values, eval_naive(&evals, &point));
+    assert_eq!(
+        proof.final_poly.len(),
+        1 << log_final_poly_len.min(num_vars)
+    );
+
+    let v_sample = verify(&perm, &pcs, &commitment, &point, &values, &proof).unwrap();
+    assert_eq!(
+        challenger.sample_bits(8),
+        v_sample,
+        "prover and verifier transcript have same state after opening"
+    );
+}
+
+#[test]
+fn test_basefold_open() {
+    for i in 0..4 {
+        let mut rng = ChaCha20Rng::seed_from_u64(i);
+        for num_vars in [0, 1, 2, 5, 10] {
+            do_test_open(&mut rng, 0, num_vars, 1);
+            do_test_open(&mut rng, 0, num_vars, 7);
+        }
+    }
+}
+
+#[test]
+fn test_basefold_open_final_poly() {
+    for i in 0..4 {
+        let mut rng = ChaCha20Rng::seed_from_u64(i);
+        do_test_open(&mut rng, 3, 8, 5);
+        // The polynomials already have few enough variables, so there's no sumcheck.
+        do_test_open(&mut rng, 3, 2, 5);
+    }
+}
+
+#[test]
+fn test_basefold_rejects_wrong_values() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let (perm, pcs) = get_pcs(&mut rng, 0);
+    let evals = RowMajorMatrix::<Val>::rand(&mut rng, 1 << 6, 3);
+    let point: Vec<Challenge> = (0..6).map(|_| rng.gen()).collect();
+    let (commitment, data) = MultilinearPcs::<Challenge, Challenger>::commit(&pcs, evals);
+    let (values, proof) = pcs.open(&data, &point, &mut Challenger::new(perm.clone()));
+    assert!(verify(&perm, &pcs, &commitment, &point, &values, &proof).is_ok());
+
+    let mut bad_values = values.clone();
+    bad_values[1] += Challenge::ONE;
+    assert!(matches!(
+        verify(&perm, &pcs, &commitment, &point, &bad_values, &proof),
+        Err(BasefoldError::SumcheckMismatch)
+    ));
+
+    let mut bad_point = point.clone();
+    bad_point[4] += Challenge::ONE;
+    assert!(verify(&perm, &pcs, &commitment, &bad_point, &values, &proof).is_err());
+
+    // The final polynomial must agree with the sumcheck.
+    let mut bad_proof = proof.clone();
+    bad_proof.final_poly[0] += Challenge::ONE;
+    assert!(matches!(
+        verify(&perm, &pcs, &commitment, &point, &values, &bad_proof),
+        Err(BasefoldError::EvaluationMismatch)
+    ));
+
+    // A different polynomial with the same values at the point can't be opened against the
+    // original commitment.
+    let other_evals = RowMajorMatrix::<Val>::rand(&mut rng, 1 << 6, 3);
+    let (_, other_data) = MultilinearPcs::<Challenge, Challenger>::commit(&pcs, other_evals);
+    let (other_values, other_proof) =
+        pcs.open(&other_data, &point, &mut Challenger::new(perm.clone()));
+    assert!(verify(
+        &perm,
+        &pcs,
+        &commitment,
+        &point,
+        &other_values,
+        &other_proof
+    )
+    .is_err());
+}
@@ -55,6 +55,8 @@ fn main() -> Result<(), impl Debug> {
 
     let fri_config = FriConfig {
         log_blowup: 1,
+        log_arity: 1,
+        log_final_poly_len: 0,
         num_queries: 100,
         proof_of_work_bits: 16,
         mmcs: challenge_mmcs,
@@ -69,7 +71,8 @@ fn main() -> Result<(), impl Debug> {
     let config = MyConfig::new(pcs);
 
     let mut challenger = Challenger::from_hasher(vec![], byte_hash);
-    let proof = prove(&config, &Blake3Air {}, &mut challenger, trace, &vec![]);
+    let proof =
+        prove(&config, &Blake3Air {}, &mut challenger, trace, &vec![]).expect("proving failed");
 
     let mut challenger = Challenger::from_hasher(vec![], byte_hash);
     verify(&config, &Blake3Air {}, &mut challenger, &proof, &vec![])
@@ -287,7 +287,7 @@ impl TwoAdicField for Bn254Fr {
 #[cfg(test)]
 mod tests {
     use num_traits::One;
-    use p3_field_testing::test_field;
+    use p3_field_testing::{test_field, test_field_subgroup_dft};
 
     use super::*;
 
@@ -379,4 +379,18 @@ mod tests {
     }
 
     test_field!(crate::Bn254Fr);
+
+    // The multiplicative group has order `2^28 * 3^2 * 13 * 29 * 983 * ...`.
+    test_field_subgroup_dft!(
+        mixed_radix,
+        crate::Bn254Fr,
+        p3_dft::MixedRadixDft,
+        [1, 2, 3, 4, 6, 9, 13, 18, 29, 36, 117]
+    );
+    test_field_subgroup_dft!(
+        bluestein,
+        crate::Bn254Fr,
+        p3_dft::BluesteinDft<p3_dft::Radix2Dit<_>>,
+        [1, 13, 26, 29, 58, 377]
+    );
 }
@@ -2,7 +2,7 @@ use alloc::vec;
 use alloc::vec::Vec;
 
 use p3_field::{ExtensionField, Field, PrimeField64};
-use p3_symmetric::{CryptographicPermutation, Hash};
+use p3_symmetric::{CryptographicPermutation, Hash, MerkleCap};
 
 use crate::{CanObserve, CanSample, CanSampleBits, FieldChallenger};
 
@@ -103,6 +103,19 @@ where
     }
 }
 
+impl<F, P, const N: usize, const WIDTH: usize, const RATE: usize> CanObserve<MerkleCap<F, F, N>>
+    for DuplexChallenger<F, P, WIDTH, RATE>
+where
+    F: Copy,
+    P: CryptographicPermutation<[F; WIDTH]>,
+{
+    fn observe(&mut self, values: MerkleCap<F, F, N>) {
+        for digest in values {
+            self.observe(digest);
+        }
+    }
+}
+
 // for TrivialPcs
 impl<F, P, const WIDTH: usize, const RATE: usize> CanObserve<Vec<Vec<F>>>
     for DuplexChallenger<F, P, WIDTH, RATE>
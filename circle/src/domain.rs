@@ -106,6 +106,8 @@ impl<F: ComplexExtendable> PolynomialSpace for CircleDomain<F> {
 
     fn next_point<Ext: ExtensionField<Self::Val>>(&self, x: Ext) -> Option<Ext> {
         // Only in standard position do we have an algebraic expression to access the next point.
+        // There, the rows of a trace in natural order lie at `shift + i * generator(log_n)`, so
+        // the next row is one step of the generator of the whole domain away.
         if self.is_standard() {
             Some(
                 (Point::from_projective_line(x) + Point::generator(self.log_n))
@@ -176,6 +178,8 @@ impl<F: ComplexExtendable> PolynomialSpace for CircleDomain<F> {
         point: Ext,
     ) -> LagrangeSelectors<Ext> {
         let point = Point::from_projective_line(point);
+        // The first row lies at `shift`. In standard position, `shift` generates a group of twice
+        // the domain's size, so the last row lies at `shift - generator(log_n) = -shift`.
         LagrangeSelectors {
             is_first_row: self.s_p(self.shift, point),
             is_last_row: self.s_p(-self.shift, point),
@@ -328,10 +332,8 @@ mod tests {
         }
     }
 
-    #[test]
-    fn selectors() {
+    fn do_test_selectors(log_n: usize) {
         type F = Mersenne31;
-        let log_n = 8;
         let n = 1 << log_n;
 
         let d = CircleDomain::<F>::standard(log_n);
@@ -373,9 +375,9 @@ mod tests {
         assert_eq!(&is_last_row[..n - 1], &vec![F::ZERO; n - 1]);
         assert_ne!(is_last_row[n - 1], F::ZERO);
 
-        // Nonzero everywhere on domain but last point
+        // One everywhere on domain but last point
         let is_transition = coset_to_d(&sels.is_transition);
-        assert_ne!(&is_transition[..n - 1], &vec![F::ZERO; n - 1]);
+        assert_eq!(&is_transition[..n - 1], &vec![F::ONE; n - 1]);
         assert_eq!(is_transition[n - 1], F::ZERO);
 
         // Zeroifier coefficients look like [0.. (n times), 1, 0.. (n-1 times)]
@@ -396,6 +398,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn selectors() {
+        // The smallest domain CirclePcs commits to has 4 points.
+        for log_n in [2, 3, 8] {
+            do_test_selectors(log_n);
+        }
+    }
+
+    #[test]
+    fn next_point_only_in_standard_position() {
+        type F = Mersenne31;
+        let d = CircleDomain::<F>::standard(4);
+        let twin_coset = d.split_domains(2)[1];
+        assert!(d.next_point(d.first_point()).is_some());
+        assert!(twin_coset.next_point(twin_coset.first_point()).is_none());
+    }
+
     #[test]
     fn test_circle_domain() {
         do_test_circle_domain(4, 8);
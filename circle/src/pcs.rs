@@ -25,6 +25,11 @@ use crate::prover::prove;
 use crate::verifier::verify;
 use crate::{cfft_permute_index, CfftPermutable, CircleEvaluations, CircleFriProof};
 
+/// A PCS over circle domains, opened with circle FRI.
+///
+/// Circle FRI folds in pairs down to a constant, so the FRI configuration must have a `log_arity`
+/// of 1 and a `log_final_poly_len` of 0. Opening with any other configuration panics, and
+/// verifying returns an error.
 #[derive(Debug)]
 pub struct CirclePcs<Val: Field, InputMmcs, FriMmcs> {
     pub mmcs: InputMmcs,
@@ -32,6 +37,20 @@ pub struct CirclePcs<Val: Field, InputMmcs, FriMmcs> {
     pub _phantom: PhantomData<Val>,
 }
 
+impl<Val: Field, InputMmcs, FriMmcs> CirclePcs<Val, InputMmcs, FriMmcs> {
+    pub const fn new(mmcs: InputMmcs, fri_config: FriConfig<FriMmcs>) -> Self {
+        Self {
+            mmcs,
+            fri_config,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub const fn fri_config(&self) -> &FriConfig<FriMmcs> {
+        &self.fri_config
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(bound = "")]
 pub struct BatchOpening<Val: Field, InputMmcs: Mmcs<Val>> {
@@ -99,6 +118,13 @@ where
         CircleDomain::standard(log2_strict_usize(degree))
     }
 
+    fn parameters(&self) -> Vec<usize> {
+        let mut parameters = self.fri_config.parameters();
+        parameters.extend(self.mmcs.parameters());
+        parameters.extend(self.fri_config.mmcs.parameters());
+        parameters
+    }
+
     fn commit(
         &self,
         evaluations: Vec<(Self::Domain, RowMajorMatrix<Val>)>,
@@ -512,17 +538,15 @@ mod tests {
 
         let fri_config = FriConfig {
             log_blowup: 1,
+            log_arity: 1,
+            log_final_poly_len: 0,
             num_queries: 2,
             proof_of_work_bits: 1,
             mmcs: challenge_mmcs,
         };
 
         type Pcs = CirclePcs<Val, ValMmcs, ChallengeMmcs>;
-        let pcs = Pcs {
-            mmcs: val_mmcs,
-            fri_config,
-            _phantom: PhantomData,
-        };
+        let pcs = Pcs::new(val_mmcs, fri_config);
 
         let log_n = 10;
 
@@ -28,6 +28,11 @@ where
     Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<M::Commitment>,
     G: FriGenericConfig<Challenge>,
 {
+    assert!(
+        config.log_arity == 1 && config.log_final_poly_len == 0,
+        "circle FRI only supports folding in pairs down to a constant"
+    );
+
     // check sorted descending
     assert!(inputs
         .iter()
@@ -25,6 +25,11 @@ where
     Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<M::Commitment>,
     G: FriGenericConfig<Challenge>,
 {
+    // Circle FRI only supports folding in pairs down to a constant.
+    if config.log_arity != 1 || config.log_final_poly_len != 0 {
+        return Err(FriError::InvalidProofShape);
+    }
+
     let betas: Vec<Challenge> = proof
         .commit_phase_commits
         .iter()
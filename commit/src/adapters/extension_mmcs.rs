@@ -6,7 +6,7 @@ use p3_field::{ExtensionField, Field};
 use p3_matrix::extension::FlatMatrixView;
 use p3_matrix::{Dimensions, Matrix};
 
-use crate::Mmcs;
+use crate::{HashCount, Mmcs, MmcsCostModel};
 
 #[derive(Clone, Debug)]
 pub struct ExtensionMmcs<F, EF, InnerMmcs> {
@@ -32,8 +32,13 @@ where
     type ProverData<M> = InnerMmcs::ProverData<FlatMatrixView<F, EF, M>>;
     type Commitment = InnerMmcs::Commitment;
     type Proof = InnerMmcs::Proof;
+    type MultiProof = InnerMmcs::MultiProof;
     type Error = InnerMmcs::Error;
 
+    fn parameters(&self) -> Vec<usize> {
+        self.inner.parameters()
+    }
+
     fn commit<M: Matrix<EF>>(&self, inputs: Vec<M>) -> (Self::Commitment, Self::ProverData<M>) {
         self.inner
             .commit(inputs.into_iter().map(FlatMatrixView::new).collect())
@@ -45,10 +50,16 @@ where
         prover_data: &Self::ProverData<M>,
     ) -> (Vec<Vec<EF>>, Self::Proof) {
         let (opened_base_values, proof) = self.inner.open_batch(index, prover_data);
-        let opened_ext_values = opened_base_values
-            .into_iter()
-            .map(|row| row.chunks(EF::D).map(EF::from_base_slice).collect())
-            .collect();
+        (ext_rows(opened_base_values), proof)
+    }
+
+    fn open_multi_batch<M: Matrix<EF>>(
+        &self,
+        indices: &[usize],
+        prover_data: &Self::ProverData<M>,
+    ) -> (Vec<Vec<Vec<EF>>>, Self::MultiProof) {
+        let (opened_base_values, proof) = self.inner.open_multi_batch(indices, prover_data);
+        let opened_ext_values = opened_base_values.into_iter().map(ext_rows).collect();
         (opened_ext_values, proof)
     }
 
@@ -68,23 +79,102 @@ where
         opened_values: &[Vec<EF>],
         proof: &Self::Proof,
     ) -> Result<(), Self::Error> {
-        let opened_base_values: Vec<Vec<F>> = opened_values
-            .iter()
-            .map(|row| {
-                row.iter()
-                    .flat_map(|el| el.as_base_slice())
-                    .copied()
-                    .collect()
-            })
-            .collect();
-        let base_dimensions = dimensions
+        let opened_base_values = base_rows::<F, EF>(opened_values);
+        let base_dimensions = base_dimensions::<F, EF>(dimensions);
+        self.inner
+            .verify_batch(commit, &base_dimensions, index, &opened_base_values, proof)
+    }
+
+    fn verify_multi_batch(
+        &self,
+        commit: &Self::Commitment,
+        dimensions: &[Dimensions],
+        indices: &[usize],
+        opened_values: &[Vec<Vec<EF>>],
+        proof: &Self::MultiProof,
+    ) -> Result<(), Self::Error> {
+        let opened_base_values = opened_values
             .iter()
-            .map(|dim| Dimensions {
-                width: dim.width * EF::D,
-                height: dim.height,
-            })
+            .map(|rows| base_rows::<F, EF>(rows))
             .collect::<Vec<_>>();
+        let base_dimensions = base_dimensions::<F, EF>(dimensions);
+        self.inner.verify_multi_batch(
+            commit,
+            &base_dimensions,
+            indices,
+            &opened_base_values,
+            proof,
+        )
+    }
+}
+
+/// Reassemble rows of flattened base field values into extension field values.
+fn ext_rows<F: Field, EF: ExtensionField<F>>(rows: Vec<Vec<F>>) -> Vec<Vec<EF>> {
+    rows.into_iter()
+        .map(|row| row.chunks(EF::D).map(EF::from_base_slice).collect())
+        .collect()
+}
+
+/// Flatten rows of extension field values into their base field coefficients.
+fn base_rows<F: Field, EF: ExtensionField<F>>(rows: &[Vec<EF>]) -> Vec<Vec<F>> {
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .flat_map(|el| el.as_base_slice())
+                .copied()
+                .collect()
+        })
+        .collect()
+}
+
+impl<F, EF, InnerMmcs> MmcsCostModel for ExtensionMmcs<F, EF, InnerMmcs>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    InnerMmcs: MmcsCostModel,
+{
+    fn commitment_bytes(&self) -> usize {
+        self.inner.commitment_bytes()
+    }
+
+    fn value_bytes(&self) -> usize {
+        self.inner.value_bytes() * EF::D
+    }
+
+    fn opening_proof_bytes(&self, dimensions: &[Dimensions]) -> usize {
         self.inner
-            .verify_batch(commit, &base_dimensions, index, &opened_base_values, proof)
+            .opening_proof_bytes(&base_dimensions::<F, EF>(dimensions))
+    }
+
+    fn multi_opening_proof_bytes(&self, dimensions: &[Dimensions], num_indices: usize) -> usize {
+        self.inner
+            .multi_opening_proof_bytes(&base_dimensions::<F, EF>(dimensions), num_indices)
     }
+
+    fn commit_cost(&self, dimensions: &[Dimensions]) -> HashCount {
+        self.inner
+            .commit_cost(&base_dimensions::<F, EF>(dimensions))
+    }
+
+    fn verify_cost(&self, dimensions: &[Dimensions]) -> HashCount {
+        self.inner
+            .verify_cost(&base_dimensions::<F, EF>(dimensions))
+    }
+
+    fn multi_verify_cost(&self, dimensions: &[Dimensions], num_indices: usize) -> HashCount {
+        self.inner
+            .multi_verify_cost(&base_dimensions::<F, EF>(dimensions), num_indices)
+    }
+}
+
+/// The dimensions of the flattened base field matrices corresponding to some extension field
+/// matrices.
+fn base_dimensions<F: Field, EF: ExtensionField<F>>(dimensions: &[Dimensions]) -> Vec<Dimensions> {
+    dimensions
+        .iter()
+        .map(|dim| Dimensions {
+            width: dim.width * EF::D,
+            height: dim.height,
+        })
+        .collect()
 }
@@ -0,0 +1,127 @@
+//! Models of the proof sizes and hashing work of commitment schemes, for choosing parameters.
+
+use alloc::vec::Vec;
+use core::iter::Sum;
+use core::ops::{Add, AddAssign, Mul};
+
+use p3_matrix::Dimensions;
+
+/// Hashing work, counted in calls to a hasher and a two-to-one compression function.
+///
+/// Hashers may absorb any number of elements per call, so the number of elements absorbed is
+/// counted too. For a sponge, dividing it by the rate gives roughly the number of permutations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HashCount {
+    /// Calls to the hasher, such as one for each Merkle leaf.
+    pub hashes: usize,
+    /// Field elements absorbed by those calls.
+    pub hashed_elements: usize,
+    /// Calls to the compression function.
+    pub compressions: usize,
+}
+
+impl Add for HashCount {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            hashes: self.hashes + rhs.hashes,
+            hashed_elements: self.hashed_elements + rhs.hashed_elements,
+            compressions: self.compressions + rhs.compressions,
+        }
+    }
+}
+
+impl AddAssign for HashCount {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Mul<usize> for HashCount {
+    type Output = Self;
+
+    fn mul(self, rhs: usize) -> Self {
+        Self {
+            hashes: self.hashes * rhs,
+            hashed_elements: self.hashed_elements * rhs,
+            compressions: self.compressions * rhs,
+        }
+    }
+}
+
+impl Sum for HashCount {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+/// A model of an [`Mmcs`](crate::Mmcs)'s costs.
+///
+/// Sizes are in bytes, assuming values and digests are serialized at their in-memory size, as with
+/// a fixed-width encoding. Variable-length encodings may differ somewhat.
+pub trait MmcsCostModel {
+    /// The serialized size of a commitment.
+    fn commitment_bytes(&self) -> usize;
+
+    /// The serialized size of one committed value.
+    fn value_bytes(&self) -> usize;
+
+    /// The serialized size of the proof for one batch opening of matrices with the given
+    /// dimensions, not including the opened values themselves.
+    fn opening_proof_bytes(&self, dimensions: &[Dimensions]) -> usize;
+
+    /// The hashing needed to commit to matrices with the given dimensions.
+    fn commit_cost(&self, dimensions: &[Dimensions]) -> HashCount;
+
+    /// The hashing needed to verify one batch opening of matrices with the given dimensions.
+    fn verify_cost(&self, dimensions: &[Dimensions]) -> HashCount;
+
+    /// The expected serialized size of the proof for one multi-batch opening of matrices with the
+    /// given dimensions, at `num_indices` uniformly random indices, not including the opened
+    /// values themselves.
+    fn multi_opening_proof_bytes(&self, dimensions: &[Dimensions], num_indices: usize) -> usize {
+        num_indices * self.opening_proof_bytes(dimensions)
+    }
+
+    /// The expected hashing needed to verify one multi-batch opening of matrices with the given
+    /// dimensions, at `num_indices` uniformly random indices.
+    fn multi_verify_cost(&self, dimensions: &[Dimensions], num_indices: usize) -> HashCount {
+        self.verify_cost(dimensions) * num_indices
+    }
+}
+
+/// The shape of a matrix committed to by a PCS, and how many points it's opened at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MatrixShape {
+    /// The log2 of the size of the matrix's domain.
+    pub log_degree: usize,
+    pub width: usize,
+    pub num_points: usize,
+}
+
+/// The estimated size and hashing work of some PCS commitments and an opening proof for them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PcsCost {
+    /// The serialized size of one commitment.
+    pub commitment_bytes: usize,
+    /// The prover's hashing to commit to each round.
+    pub commit_hashes: Vec<HashCount>,
+    /// The serialized size of the parts of the opening proof which open committed matrices, such
+    /// as the opened rows and their Merkle proofs.
+    pub input_proof_bytes: usize,
+    /// The serialized size of the rest of the opening proof, such as FRI's commit phase
+    /// commitments and openings, final polynomial and proof-of-work witness.
+    pub commit_phase_bytes: usize,
+    /// The prover's hashing to produce the opening proof, not counting `commit_hashes`.
+    pub open_hashes: HashCount,
+    /// The verifier's hashing to check the opening proof.
+    pub verify_hashes: HashCount,
+}
+
+/// A model of a [`Pcs`](crate::Pcs)'s costs.
+pub trait PcsCostModel {
+    /// Estimate the cost of committing to each round of matrices, then opening them all. Sizes
+    /// are as described for [`MmcsCostModel`].
+    fn estimate_cost(&self, rounds: &[Vec<MatrixShape>]) -> PcsCost;
+}
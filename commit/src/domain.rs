@@ -24,7 +24,9 @@ pub trait PolynomialSpace: Copy {
 
     fn first_point(&self) -> Self::Val;
 
-    // This is only defined for cosets.
+    // The point after `x` in the domain's row order, used to open the next row of a trace.
+    // This is only defined for cosets, and `None` when there is no algebraic step between rows,
+    // e.g. for circle twin-cosets that are not in standard position.
     fn next_point<Ext: ExtensionField<Self::Val>>(&self, x: Ext) -> Option<Ext>;
 
     // There are many choices for this, but we must pick a canonical one
@@ -44,7 +46,8 @@ pub trait PolynomialSpace: Copy {
 
     fn zp_at_point<Ext: ExtensionField<Self::Val>>(&self, point: Ext) -> Ext;
 
-    // Unnormalized
+    // Unnormalized: `is_first_row` and `is_last_row` are nonzero multiples of the Lagrange basis
+    // polynomials of the first and last points, and `is_transition` vanishes only at the last point.
     fn selectors_at_point<Ext: ExtensionField<Self::Val>>(
         &self,
         point: Ext,
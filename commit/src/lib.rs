@@ -5,14 +5,20 @@
 extern crate alloc;
 
 mod adapters;
+mod cost;
 mod domain;
 mod mmcs;
+mod multilinear_pcs;
 mod pcs;
+mod proof_format;
 
 #[cfg(any(test, feature = "test-utils"))]
 pub mod testing;
 
 pub use adapters::*;
+pub use cost::*;
 pub use domain::*;
 pub use mmcs::*;
+pub use multilinear_pcs::*;
 pub use pcs::*;
+pub use proof_format::*;
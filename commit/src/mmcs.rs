@@ -16,12 +16,26 @@ use serde::Serialize;
 /// with the largest height. For matrices with smaller heights, some bits of the row index are
 /// removed (from the least-significant side) to get the effective row index. These semantics are
 /// useful in the FRI protocol. See the documentation for `open_batch` for more details.
+///
+/// Implementations whose proofs at different indices have nothing to share can implement the
+/// multi-index items by setting `MultiProof` to `Vec<Self::Proof>`, and calling
+/// [`open_multi_batch_separately`] and [`verify_multi_batch_separately`] from `open_multi_batch`
+/// and `verify_multi_batch`.
 pub trait Mmcs<T: Send + Sync>: Clone {
     type ProverData<M>;
     type Commitment: Clone + Serialize + DeserializeOwned;
     type Proof: Clone + Serialize + DeserializeOwned;
+    /// A proof for the openings at several indices at once. See
+    /// [`open_multi_batch`](Mmcs::open_multi_batch).
+    type MultiProof: Clone + Serialize + DeserializeOwned;
     type Error: Debug;
 
+    /// The parameters of this scheme which affect its commitments and proofs, such as a Merkle cap
+    /// height. PCSs include them in [`Pcs::parameters`](crate::Pcs::parameters).
+    fn parameters(&self) -> Vec<usize> {
+        vec![]
+    }
+
     fn commit<M: Matrix<T>>(&self, inputs: Vec<M>) -> (Self::Commitment, Self::ProverData<M>);
 
     fn commit_matrix<M: Matrix<T>>(&self, input: M) -> (Self::Commitment, Self::ProverData<M>) {
@@ -45,6 +59,19 @@ pub trait Mmcs<T: Send + Sync>: Clone {
         prover_data: &Self::ProverData<M>,
     ) -> (Vec<Vec<T>>, Self::Proof);
 
+    /// Opens a batch of rows from committed matrices at each of several indices, with one proof
+    /// for all of them. Returns `(openings, proof)`, where `openings[i]` is what `open_batch`
+    /// would return for `indices[i]`.
+    ///
+    /// The proof may share whatever the proofs for individual indices have in common, such as the
+    /// upper parts of Merkle paths. Schemes with nothing to share can use
+    /// [`open_multi_batch_separately`], with a `MultiProof` of `Vec<Self::Proof>`.
+    fn open_multi_batch<M: Matrix<T>>(
+        &self,
+        indices: &[usize],
+        prover_data: &Self::ProverData<M>,
+    ) -> (Vec<Vec<Vec<T>>>, Self::MultiProof);
+
     /// Get the matrices that were committed to.
     fn get_matrices<'a, M: Matrix<T>>(&self, prover_data: &'a Self::ProverData<M>) -> Vec<&'a M>;
 
@@ -76,4 +103,59 @@ pub trait Mmcs<T: Send + Sync>: Clone {
         opened_values: &[Vec<T>],
         proof: &Self::Proof,
     ) -> Result<(), Self::Error>;
+
+    /// Verify an opening made by `open_multi_batch`, where `opened_values[i]` are the openings at
+    /// `indices[i]`, as for `verify_batch`.
+    fn verify_multi_batch(
+        &self,
+        commit: &Self::Commitment,
+        dimensions: &[Dimensions],
+        indices: &[usize],
+        opened_values: &[Vec<Vec<T>>],
+        proof: &Self::MultiProof,
+    ) -> Result<(), Self::Error>;
+}
+
+/// Open a batch of rows at each index with a separate call to `open_batch`, for an MMCS whose
+/// proofs have nothing to share.
+pub fn open_multi_batch_separately<T, MC, M>(
+    mmcs: &MC,
+    indices: &[usize],
+    prover_data: &MC::ProverData<M>,
+) -> (Vec<Vec<Vec<T>>>, Vec<MC::Proof>)
+where
+    T: Send + Sync,
+    MC: Mmcs<T>,
+    M: Matrix<T>,
+{
+    indices
+        .iter()
+        .map(|&index| mmcs.open_batch(index, prover_data))
+        .unzip()
+}
+
+/// Verify an opening made by [`open_multi_batch_separately`] with a separate call to
+/// `verify_batch` for each index. `wrong_size` is the error for a proof with the wrong number of
+/// openings.
+pub fn verify_multi_batch_separately<T, MC>(
+    mmcs: &MC,
+    commit: &MC::Commitment,
+    dimensions: &[Dimensions],
+    indices: &[usize],
+    opened_values: &[Vec<Vec<T>>],
+    proofs: &[MC::Proof],
+    wrong_size: impl FnOnce() -> MC::Error,
+) -> Result<(), MC::Error>
+where
+    T: Send + Sync,
+    MC: Mmcs<T>,
+{
+    if opened_values.len() != indices.len() || proofs.len() != indices.len() {
+        return Err(wrong_size());
+    }
+    indices.iter().zip(opened_values).zip(proofs).try_for_each(
+        |((&index, opened_values), proof)| {
+            mmcs.verify_batch(commit, dimensions, index, opened_values, proof)
+        },
+    )
 }
@@ -0,0 +1,62 @@
+//! Traits for multilinear polynomial commitment schemes.
+
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+use p3_field::{ExtensionField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// A (not necessarily hiding) commitment scheme for multilinear polynomials, as used by
+/// sumcheck-based provers.
+///
+/// Polynomials in `n` variables are given by their evaluations over the boolean hypercube
+/// `{0, 1}^n`, one polynomial per column of a matrix of height `2^n`. Row `i` holds the
+/// evaluations at the point whose `j`th coordinate is bit `j` of `i`.
+pub trait MultilinearPcs<Challenge, Challenger>
+where
+    Challenge: ExtensionField<Self::Val>,
+{
+    type Val: Field;
+
+    /// The commitment that's sent to the verifier.
+    type Commitment: Clone + Serialize + DeserializeOwned;
+
+    /// Data that the prover stores for committed polynomials, to help the prover with opening.
+    type ProverData;
+
+    /// The opening argument.
+    type Proof: Clone + Serialize + DeserializeOwned;
+
+    type Error: Debug;
+
+    /// The parameters of this scheme which affect its proofs, for binding into a transcript, as
+    /// with [`Pcs::parameters`](crate::Pcs::parameters).
+    fn parameters(&self) -> Vec<usize>;
+
+    /// Commit to the multilinear polynomials with the given evaluations over the hypercube.
+    fn commit(
+        &self,
+        evaluations: RowMajorMatrix<Self::Val>,
+    ) -> (Self::Commitment, Self::ProverData);
+
+    /// Open every committed polynomial at `point`, returning their evaluations there.
+    fn open(
+        &self,
+        prover_data: &Self::ProverData,
+        point: &[Challenge],
+        challenger: &mut Challenger,
+    ) -> (Vec<Challenge>, Self::Proof);
+
+    /// Verify that the committed polynomials, in `point.len()` variables, take the given `values`
+    /// at `point`.
+    fn verify(
+        &self,
+        commitment: &Self::Commitment,
+        point: &[Challenge],
+        values: &[Challenge],
+        proof: &Self::Proof,
+        challenger: &mut Challenger,
+    ) -> Result<(), Self::Error>;
+}
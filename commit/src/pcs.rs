@@ -32,15 +32,79 @@ where
 
     type Error: Debug;
 
+    /// Whether the scheme hides committed polynomials. STARKs over a hiding scheme run in
+    /// zero-knowledge mode: they commit to witness-dependent polynomials with
+    /// [`Pcs::commit_randomized`], and their quotients are masked by [`Pcs::commit_quotients`].
+    const ZK: bool = false;
+
     /// This should return a coset domain (s.t. Domain::next_point returns Some)
     fn natural_domain_for_degree(&self, degree: usize) -> Self::Domain;
 
+    /// The parameters of this scheme which affect its proofs, such as a FRI blowup or number of
+    /// queries, and those of its MMCSs. STARKs observe these before anything else, so that a proof
+    /// made under one configuration is never accepted under another.
+    ///
+    /// Defaults to no parameters, which binds nothing; schemes with parameters must override this
+    /// for their proofs to be bound to them.
+    fn parameters(&self) -> Vec<usize> {
+        Vec::new()
+    }
+
+    /// The log2 of the factor by which committed evaluations are extended, if the scheme is
+    /// limited to one. [`Pcs::get_evaluations_on_domain`] supports domains up to `2^log_blowup`
+    /// times as large as the committed ones, so this bounds the degree of the constraints a STARK
+    /// can prove. `None` if the scheme can evaluate over domains of any size.
+    fn log_blowup(&self) -> Option<usize> {
+        None
+    }
+
     #[allow(clippy::type_complexity)]
     fn commit(
         &self,
         evaluations: Vec<(Self::Domain, RowMajorMatrix<Val<Self::Domain>>)>,
     ) -> (Self::Commitment, Self::ProverData);
 
+    /// Commit to random polynomials agreeing with the given evaluations on their domains.
+    ///
+    /// For evaluations of `f` over a domain `D`, the committed polynomial is `f + Z_D r` for a
+    /// uniformly random `r` of degree less than `|D|`, so that opening it at up to `|D|` points
+    /// outside `D` reveals nothing about `f`. It must be opened over the domain
+    /// `natural_domain_for_degree(2 * |D|)`.
+    ///
+    /// Only hiding schemes need to support this; others return `None`.
+    #[allow(clippy::type_complexity)]
+    fn commit_randomized(
+        &self,
+        _evaluations: Vec<(Self::Domain, RowMajorMatrix<Val<Self::Domain>>)>,
+    ) -> Option<(Self::Commitment, Self::ProverData)> {
+        None
+    }
+
+    /// Commit to the chunks of some quotient polynomials. Each quotient is given by its evaluations
+    /// over a quotient domain, and split into the given number of chunks, one for each domain of
+    /// `quotient_domain.split_domains(num_chunks)`, in order.
+    ///
+    /// The verifier recombines the chunks `q_i` into the quotient `sum_i q_i prod_{j != i} Z_j / c_ij`,
+    /// where `Z_j` vanishes on the `j`th chunk domain and `c_ij` is its value at the first point of
+    /// the `i`th. Hiding schemes commit to each chunk as in [`Pcs::commit_randomized`], with masks
+    /// chosen to cancel out in this recombination.
+    #[allow(clippy::type_complexity)]
+    fn commit_quotients(
+        &self,
+        quotients: Vec<(Self::Domain, RowMajorMatrix<Val<Self::Domain>>, usize)>,
+    ) -> (Self::Commitment, Self::ProverData) {
+        let chunks = quotients
+            .into_iter()
+            .flat_map(|(quotient_domain, evaluations, num_chunks)| {
+                quotient_domain
+                    .split_domains(num_chunks)
+                    .into_iter()
+                    .zip(quotient_domain.split_evals(num_chunks, evaluations))
+            })
+            .collect();
+        self.commit(chunks)
+    }
+
     fn get_evaluations_on_domain<'a>(
         &self,
         prover_data: &'a Self::ProverData,
@@ -0,0 +1,110 @@
+//! A versioned binary format for proofs, which records the configuration they were made under.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use p3_field::{ExtensionField, Field};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// The bytes every encoded proof starts with.
+pub const PROOF_MAGIC: [u8; 4] = *b"P3PF";
+
+/// The version of the format written by [`encode_proof`], which follows [`PROOF_MAGIC`].
+pub const PROOF_FORMAT_VERSION: u8 = 1;
+
+/// The parts of a configuration which a proof can only be read and verified under.
+///
+/// Encoded proofs carry the fingerprint of the configuration they were made under, and are only
+/// decoded under a configuration with the same fingerprint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigFingerprint {
+    /// The order of the base field, in little-endian bytes.
+    pub field_order: Vec<u8>,
+    /// The degree of the challenge field over the base field.
+    pub extension_degree: usize,
+    /// The commitment scheme's parameters; see [`Pcs::parameters`](crate::Pcs::parameters).
+    pub parameters: Vec<usize>,
+    /// The name of a type which identifies the commitment scheme and its hashers, such as that of
+    /// its MMCS or proofs.
+    pub commitment_scheme: String,
+}
+
+impl ConfigFingerprint {
+    pub fn new<Val: Field, Challenge: ExtensionField<Val>>(
+        parameters: Vec<usize>,
+        commitment_scheme: String,
+    ) -> Self {
+        Self {
+            field_order: Val::order().to_bytes_le(),
+            extension_degree: Challenge::D,
+            parameters,
+            commitment_scheme,
+        }
+    }
+}
+
+/// An error decoding a proof with [`decode_proof`].
+#[derive(Debug)]
+pub enum ProofFormatError {
+    /// The bytes don't start with [`PROOF_MAGIC`].
+    BadMagic,
+    /// The proof is encoded in a version of the format other than [`PROOF_FORMAT_VERSION`].
+    UnsupportedVersion(u8),
+    /// The proof was made under a different configuration.
+    ConfigMismatch {
+        expected: ConfigFingerprint,
+        found: ConfigFingerprint,
+    },
+    /// There were bytes left over after the proof.
+    TrailingBytes,
+    /// The fingerprint or proof couldn't be decoded.
+    Postcard(postcard::Error),
+}
+
+/// Encode a proof made under the configuration with the given fingerprint.
+///
+/// The encoding is [`PROOF_MAGIC`], then the [`PROOF_FORMAT_VERSION`] byte, then the `postcard`
+/// encodings of the fingerprint and the proof.
+pub fn encode_proof<P: Serialize>(
+    fingerprint: &ConfigFingerprint,
+    proof: &P,
+) -> Result<Vec<u8>, postcard::Error> {
+    let mut bytes = PROOF_MAGIC.to_vec();
+    bytes.push(PROOF_FORMAT_VERSION);
+    bytes.extend(postcard::to_allocvec(fingerprint)?);
+    bytes.extend(postcard::to_allocvec(proof)?);
+    Ok(bytes)
+}
+
+/// Decode a proof encoded by [`encode_proof`], checking that it was made under the configuration
+/// with the given fingerprint.
+pub fn decode_proof<P: DeserializeOwned>(
+    fingerprint: &ConfigFingerprint,
+    bytes: &[u8],
+) -> Result<P, ProofFormatError> {
+    let bytes = bytes
+        .strip_prefix(&PROOF_MAGIC)
+        .ok_or(ProofFormatError::BadMagic)?;
+    let (&version, bytes) = bytes.split_first().ok_or(ProofFormatError::Postcard(
+        postcard::Error::DeserializeUnexpectedEnd,
+    ))?;
+    if version != PROOF_FORMAT_VERSION {
+        return Err(ProofFormatError::UnsupportedVersion(version));
+    }
+
+    let (found, bytes): (ConfigFingerprint, _) =
+        postcard::take_from_bytes(bytes).map_err(ProofFormatError::Postcard)?;
+    if found != *fingerprint {
+        return Err(ProofFormatError::ConfigMismatch {
+            expected: fingerprint.clone(),
+            found,
+        });
+    }
+
+    let (proof, bytes) = postcard::take_from_bytes(bytes).map_err(ProofFormatError::Postcard)?;
+    if !bytes.is_empty() {
+        return Err(ProofFormatError::TrailingBytes);
+    }
+    Ok(proof)
+}
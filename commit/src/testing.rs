@@ -59,6 +59,10 @@ where
         }
     }
 
+    fn parameters(&self) -> Vec<usize> {
+        vec![self.log_n]
+    }
+
     fn commit(
         &self,
         evaluations: Vec<(Self::Domain, RowMajorMatrix<Val>)>,
@@ -0,0 +1,114 @@
+use alloc::vec::Vec;
+use core::iter;
+
+use p3_field::{batch_multiplicative_inverse, TwoAdicField};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+
+use crate::mixed_radix::prime_factors;
+use crate::{root_of_unity, MixedRadixDft, SubgroupDft, TwoAdicSubgroupDft};
+
+/// Bluestein's algorithm, which computes a DFT of any order as a convolution of at least twice its
+/// length, using a two-adic DFT.
+///
+/// Orders whose prime factors are all at most 5 are left to [`MixedRadixDft`], which is faster for
+/// them, so this serves as its fallback for orders with larger prime factors. The convolution's
+/// length is the power of two at least `2n - 1` for order `n`, which must be within the field's
+/// two-adicity.
+#[derive(Default, Clone, Debug)]
+pub struct BluesteinDft<Dft> {
+    dft: Dft,
+}
+
+impl<Dft> BluesteinDft<Dft> {
+    pub const fn new(dft: Dft) -> Self {
+        Self { dft }
+    }
+
+    /// Compute the DFT of each column in `mat` with Bluestein's algorithm, whatever its height's
+    /// prime factors.
+    pub fn bluestein_batch<F>(&self, mat: RowMajorMatrix<F>) -> RowMajorMatrix<F>
+    where
+        F: TwoAdicField,
+        Dft: TwoAdicSubgroupDft<F>,
+    {
+        let n = mat.height();
+        let w = mat.width();
+        let root: F = root_of_unity(n)
+            .unwrap_or_else(|| panic!("the multiplicative group has no subgroup of order {n}"));
+
+        // Since `jk = C(j + k, 2) - C(j, 2) - C(k, 2)`, where `C(j, 2) = j (j - 1) / 2`,
+        //     X_k = w^-C(k, 2) \sum_j (x_j w^-C(j, 2)) w^C(j + k, 2),
+        // which is a correlation with the chirp `w^C(t, 2)`. Reversing `x`, it's a convolution,
+        // which is unaffected by wrapping around if its length is at least `2n - 1`.
+        let conv_len = (2 * n - 1).next_power_of_two();
+        let mut chirp: Vec<F> = iter::successors(Some((F::ONE, F::ONE)), |&(c, root_t)| {
+            Some((c * root_t, root_t * root))
+        })
+        .map(|(c, _)| c)
+        .take(2 * n - 1)
+        .collect();
+        let inv_chirp = batch_multiplicative_inverse(&chirp[..n]);
+
+        let mut weighted = F::zero_vec(conv_len * w);
+        for (j, (row, &weight)) in mat.values.chunks_exact(w).zip(&inv_chirp).enumerate() {
+            for (out, &x) in weighted[(n - 1 - j) * w..][..w].iter_mut().zip(row) {
+                *out = x * weight;
+            }
+        }
+        chirp.resize(conv_len, F::ZERO);
+
+        let chirp_evals = self.dft.dft(chirp);
+        let mut evals = self
+            .dft
+            .dft_batch(RowMajorMatrix::new(weighted, w))
+            .to_row_major_matrix();
+        for (row, eval) in evals.rows_mut().zip(chirp_evals) {
+            row.iter_mut().for_each(|x| *x *= eval);
+        }
+        let conv = self.dft.idft_batch(evals);
+
+        let mut values = conv.values[(n - 1) * w..(2 * n - 1) * w].to_vec();
+        for (row, weight) in values.chunks_exact_mut(w).zip(inv_chirp) {
+            row.iter_mut().for_each(|x| *x *= weight);
+        }
+        RowMajorMatrix::new(values, w)
+    }
+}
+
+impl<F, Dft> SubgroupDft<F> for BluesteinDft<Dft>
+where
+    F: TwoAdicField,
+    Dft: TwoAdicSubgroupDft<F>,
+{
+    fn dft_batch(&self, mat: RowMajorMatrix<F>) -> RowMajorMatrix<F> {
+        if prime_factors(mat.height()).iter().all(|&p| p <= 5) {
+            MixedRadixDft.dft_batch(mat)
+        } else {
+            self.bluestein_batch(mat)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_matrix::dense::RowMajorMatrix;
+    use rand::thread_rng;
+
+    use crate::{BluesteinDft, MixedRadixDft, Radix2Dit, SubgroupDft};
+
+    #[test]
+    fn bluestein_matches_mixed_radix() {
+        type F = BabyBear;
+        let dft = BluesteinDft::new(Radix2Dit::<F>::default());
+        let mut rng = thread_rng();
+        for h in [1, 2, 3, 5, 6, 15, 20, 60, 96] {
+            let mat = RowMajorMatrix::<F>::rand(&mut rng, h, 3);
+            assert_eq!(
+                dft.bluestein_batch(mat.clone()),
+                MixedRadixDft.dft_batch(mat)
+            );
+        }
+    }
+}
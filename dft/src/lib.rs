@@ -4,7 +4,9 @@
 
 extern crate alloc;
 
+mod bluestein;
 mod butterflies;
+mod mixed_radix;
 mod naive;
 mod radix_2_bowers;
 mod radix_2_dit;
@@ -12,7 +14,9 @@ mod radix_2_dit_parallel;
 mod traits;
 mod util;
 
+pub use bluestein::*;
 pub use butterflies::*;
+pub use mixed_radix::*;
 pub use naive::*;
 pub use radix_2_bowers::*;
 pub use radix_2_dit::*;
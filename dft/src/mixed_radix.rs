@@ -0,0 +1,200 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::{izip, Itertools};
+use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_maybe_rayon::prelude::*;
+
+use crate::{root_of_unity, SubgroupDft};
+
+/// A mixed-radix Cooley-Tukey DFT, for subgroups of any order dividing that of the multiplicative
+/// group.
+///
+/// The order is split into its prime factors, with dedicated butterflies for factors of 2, 3 and
+/// 5. Any other prime factor `p` takes `O(p^2)` operations, so for orders with large prime factors
+/// in two-adic fields, [`BluesteinDft`](crate::BluesteinDft) is faster.
+#[derive(Default, Clone, Debug)]
+pub struct MixedRadixDft;
+
+impl<F: Field> SubgroupDft<F> for MixedRadixDft {
+    fn dft_batch(&self, mat: RowMajorMatrix<F>) -> RowMajorMatrix<F> {
+        let h = mat.height();
+        let root = root_of_unity(h)
+            .unwrap_or_else(|| panic!("the multiplicative group has no subgroup of order {h}"));
+        mixed_radix_dft(mat, &prime_factors(h), root)
+    }
+}
+
+/// The prime factors of `n`, with multiplicity, in ascending order.
+pub(crate) fn prime_factors(mut n: usize) -> Vec<usize> {
+    let mut factors = Vec::new();
+    let mut p = 2;
+    while p * p <= n {
+        while n % p == 0 {
+            factors.push(p);
+            n /= p;
+        }
+        p += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors
+}
+
+/// The DFT of each column of `mat`, whose height is the product of `factors`, over the powers of
+/// `root`, a primitive root of unity of that order.
+fn mixed_radix_dft<F: Field>(
+    mat: RowMajorMatrix<F>,
+    factors: &[usize],
+    root: F,
+) -> RowMajorMatrix<F> {
+    let Some((&radix, factors)) = factors.split_first() else {
+        return mat;
+    };
+    let w = mat.width();
+    let m = mat.height() / radix;
+
+    // Transform the rows at each offset mod `radix` separately, over the subgroup of order `m`.
+    let sub_root = root.exp_u64(radix as u64);
+    let subs: Vec<RowMajorMatrix<F>> = (0..radix)
+        .into_par_iter()
+        .map(|offset| {
+            let values = mat
+                .values
+                .chunks_exact(w)
+                .skip(offset)
+                .step_by(radix)
+                .flatten()
+                .copied()
+                .collect();
+            mixed_radix_dft(RowMajorMatrix::new(values, w), factors, sub_root)
+        })
+        .collect();
+
+    // Row `k + m q` of the result is the `radix`-point DFT, at `q`, of row `k` of each offset's
+    // transform, twiddled by `root^(offset k)`.
+    let butterfly = SmallDft::new(radix, root.exp_u64(m as u64));
+    let butterflies: Vec<Vec<F>> = root
+        .powers()
+        .take(m)
+        .collect_vec()
+        .into_par_iter()
+        .enumerate()
+        .map(|(k, root_k)| {
+            let twiddles = root_k.powers().take(radix).collect_vec();
+            let mut out = vec![F::ZERO; radix * w];
+            let mut evals = vec![F::ZERO; radix];
+            let mut scratch = Vec::new();
+            for c in 0..w {
+                for (eval, sub, &twiddle) in izip!(&mut evals, &subs, &twiddles) {
+                    *eval = twiddle * sub.values[k * w + c];
+                }
+                butterfly.apply(&mut evals, &mut scratch);
+                for (q, &eval) in evals.iter().enumerate() {
+                    out[q * w + c] = eval;
+                }
+            }
+            out
+        })
+        .collect();
+
+    let mut values = F::zero_vec(radix * m * w);
+    for (k, out) in butterflies.iter().enumerate() {
+        for (q, row) in out.chunks_exact(w).enumerate() {
+            values[(q * m + k) * w..][..w].copy_from_slice(row);
+        }
+    }
+    RowMajorMatrix::new(values, w)
+}
+
+/// A DFT of `radix` points, over the powers of a primitive `radix`th root of unity `w`.
+enum SmallDft<F> {
+    Radix2,
+    /// With `h = (w - w^2) / 2`.
+    Radix3 {
+        h: F,
+    },
+    /// With `c_i = (w^i + w^-i) / 2` and `s_i = (w^i - w^-i) / 2`.
+    Radix5 {
+        c1: F,
+        s1: F,
+        c2: F,
+        s2: F,
+    },
+    /// The powers of `w`, for a direct evaluation.
+    Generic {
+        roots: Vec<F>,
+    },
+}
+
+impl<F: Field> SmallDft<F> {
+    fn new(radix: usize, root: F) -> Self {
+        match radix {
+            2 => Self::Radix2,
+            3 => Self::Radix3 {
+                h: (root - root.square()).halve(),
+            },
+            5 => {
+                let [w1, w2, w3, w4] = [root, root.square(), root.cube(), root.exp_u64(4)];
+                Self::Radix5 {
+                    c1: (w1 + w4).halve(),
+                    s1: (w1 - w4).halve(),
+                    c2: (w2 + w3).halve(),
+                    s2: (w2 - w3).halve(),
+                }
+            }
+            _ => Self::Generic {
+                roots: root.powers().take(radix).collect(),
+            },
+        }
+    }
+
+    /// Replace `evals` by their DFT, using `scratch` for space if needed.
+    fn apply(&self, evals: &mut [F], scratch: &mut Vec<F>) {
+        match self {
+            Self::Radix2 => {
+                let (a, b) = (evals[0], evals[1]);
+                evals[0] = a + b;
+                evals[1] = a - b;
+            }
+            Self::Radix3 { h } => {
+                // Since `w + w^2 = -1`, `w b + w^2 c = -(b + c) / 2 + h (b - c)`.
+                let a = evals[0];
+                let (u, v) = (evals[1] + evals[2], evals[1] - evals[2]);
+                let (p, q) = (a - u.halve(), *h * v);
+                evals[0] = a + u;
+                evals[1] = p + q;
+                evals[2] = p - q;
+            }
+            Self::Radix5 { c1, s1, c2, s2 } => {
+                // Pair up the points at `w^i` and `w^-i`, which contribute `c_i` times their sum
+                // and `s_i` times their difference, or its negation at the conjugate output.
+                let a = evals[0];
+                let (u1, v1) = (evals[1] + evals[4], evals[1] - evals[4]);
+                let (u2, v2) = (evals[2] + evals[3], evals[2] - evals[3]);
+                let (p1, q1) = (a + *c1 * u1 + *c2 * u2, *s1 * v1 + *s2 * v2);
+                let (p2, q2) = (a + *c2 * u1 + *c1 * u2, *s2 * v1 - *s1 * v2);
+                evals[0] = a + u1 + u2;
+                evals[1] = p1 + q1;
+                evals[2] = p2 + q2;
+                evals[3] = p2 - q2;
+                evals[4] = p1 - q1;
+            }
+            Self::Generic { roots } => {
+                let n = roots.len();
+                scratch.clear();
+                scratch.extend((0..n).map(|q| {
+                    evals
+                        .iter()
+                        .enumerate()
+                        .map(|(j, &eval)| roots[j * q % n] * eval)
+                        .sum::<F>()
+                }));
+                evals.copy_from_slice(scratch);
+            }
+        }
+    }
+}
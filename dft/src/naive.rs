@@ -1,11 +1,11 @@
 use alloc::vec;
 
-use p3_field::TwoAdicField;
+use p3_field::{Field, TwoAdicField};
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::Matrix;
 use p3_util::log2_strict_usize;
 
-use crate::TwoAdicSubgroupDft;
+use crate::{root_of_unity, SubgroupDft, TwoAdicSubgroupDft};
 
 #[derive(Default, Clone, Debug)]
 pub struct NaiveDft;
@@ -31,6 +31,33 @@ impl<F: TwoAdicField> TwoAdicSubgroupDft<F> for NaiveDft {
     }
 }
 
+/// A naive [`SubgroupDft`], over subgroups of any order.
+///
+/// This is separate from [`NaiveDft`] as the two order power-of-two subgroups differently; see
+/// [`SubgroupDft`].
+#[derive(Default, Clone, Debug)]
+pub struct NaiveSubgroupDft;
+
+impl<F: Field> SubgroupDft<F> for NaiveSubgroupDft {
+    fn dft_batch(&self, mat: RowMajorMatrix<F>) -> RowMajorMatrix<F> {
+        let w = mat.width();
+        let h = mat.height();
+        let g: F = root_of_unity(h)
+            .unwrap_or_else(|| panic!("the multiplicative group has no subgroup of order {h}"));
+
+        let mut res = RowMajorMatrix::new(vec![F::ZERO; w * h], w);
+        for (res_r, point) in g.powers().take(h).enumerate() {
+            for (src_r, point_power) in point.powers().take(h).enumerate() {
+                for c in 0..w {
+                    res.values[res_r * w + c] += point_power * mat.values[src_r * w + c]
+                }
+            }
+        }
+
+        res
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use alloc::vec;
@@ -1,10 +1,12 @@
 use alloc::vec::Vec;
+use core::borrow::BorrowMut;
 
-use p3_field::TwoAdicField;
+use p3_field::{Field, TwoAdicField};
 use p3_matrix::bitrev::BitReversableMatrix;
-use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::dense::{DenseMatrix, DenseStorage, RowMajorMatrix};
 use p3_matrix::util::swap_rows;
 use p3_matrix::Matrix;
+use p3_maybe_rayon::prelude::*;
 
 use crate::util::{coset_shift_cols, divide_by_height};
 
@@ -123,4 +125,100 @@ pub trait TwoAdicSubgroupDft<F: TwoAdicField>: Clone + Default {
         );
         self.coset_dft_batch(coeffs, shift)
     }
+
+    /// Compute the low-degree extension of each column in `mat` onto a coset of a larger subgroup,
+    /// as `coset_lde_batch` does, and write it to `out` with its rows in bit-reversed order, which
+    /// is the order it's committed in.
+    ///
+    /// The columns are extended `chunk_width` at a time, so only `mat` and `out` ever hold the whole
+    /// matrix. With both stored on disk, e.g. as an `MmapMatrix`, the memory used is bounded by the
+    /// extension of a single chunk, and `out` can be committed out of core with
+    /// `MerkleTreeMmcs::commit_with_options`. `TwoAdicFriPcs` doesn't take this path, as its prover
+    /// data holds its LDEs in memory.
+    fn coset_lde_batch_chunked<M, S>(
+        &self,
+        mat: &M,
+        added_bits: usize,
+        shift: F,
+        chunk_width: usize,
+        out: &mut DenseMatrix<F, S>,
+    ) where
+        M: Matrix<F>,
+        S: DenseStorage<F> + BorrowMut<[F]>,
+    {
+        assert!(chunk_width > 0, "chunk width must be positive");
+        assert_eq!(out.width(), mat.width());
+        assert_eq!(out.height(), mat.height() << added_bits);
+
+        for start in (0..mat.width()).step_by(chunk_width) {
+            let width = chunk_width.min(mat.width() - start);
+            let chunk = RowMajorMatrix::new(
+                mat.rows()
+                    .flat_map(|row| row.skip(start).take(width))
+                    .collect(),
+                width,
+            );
+            let lde = self
+                .coset_lde_batch(chunk, added_bits, shift)
+                .bit_reverse_rows();
+            out.par_rows_mut().enumerate().for_each(|(r, out_row)| {
+                for (dst, src) in out_row[start..start + width].iter_mut().zip(lde.row(r)) {
+                    *dst = src;
+                }
+            });
+        }
+    }
+}
+
+/// A DFT over multiplicative subgroups of any order dividing that of the multiplicative group,
+/// rather than only two-adic ones.
+///
+/// The subgroup of order `n` is taken in the order of the powers of
+/// [`root_of_unity`](crate::root_of_unity)`(n)`, which for a power of two `n` needn't match the
+/// order used by a [`TwoAdicSubgroupDft`], so no DFT implements both traits.
+pub trait SubgroupDft<F: Field>: Clone + Default {
+    /// Compute the discrete Fourier transform (DFT) `vec`.
+    fn dft(&self, vec: Vec<F>) -> Vec<F> {
+        self.dft_batch(RowMajorMatrix::new_col(vec)).values
+    }
+
+    /// Compute the discrete Fourier transform (DFT) of each column in `mat`, whose height must
+    /// divide the order of the multiplicative group. This is the only method an implementer needs
+    /// to define.
+    fn dft_batch(&self, mat: RowMajorMatrix<F>) -> RowMajorMatrix<F>;
+
+    /// Compute the "coset DFT" of each column in `mat`, i.e. evaluate it over the subgroup
+    /// multiplied by `shift`.
+    fn coset_dft_batch(&self, mut mat: RowMajorMatrix<F>, shift: F) -> RowMajorMatrix<F> {
+        coset_shift_cols(&mut mat, shift);
+        self.dft_batch(mat)
+    }
+
+    /// Compute the inverse DFT of `vec`.
+    fn idft(&self, vec: Vec<F>) -> Vec<F> {
+        self.idft_batch(RowMajorMatrix::new_col(vec)).values
+    }
+
+    /// Compute the inverse DFT of each column in `mat`.
+    fn idft_batch(&self, mat: RowMajorMatrix<F>) -> RowMajorMatrix<F> {
+        let mut dft = self.dft_batch(mat);
+        let h = dft.height();
+
+        divide_by_height(&mut dft);
+
+        // Evaluating at the inverse points reverses all rows but the first.
+        for row in 1..(h + 1) / 2 {
+            swap_rows(&mut dft, row, h - row);
+        }
+
+        dft
+    }
+
+    /// Compute the "coset iDFT" of each column in `mat`, the inverse of
+    /// [`coset_dft_batch`](Self::coset_dft_batch).
+    fn coset_idft_batch(&self, mat: RowMajorMatrix<F>, shift: F) -> RowMajorMatrix<F> {
+        let mut mat = self.idft_batch(mat);
+        coset_shift_cols(&mut mat, shift.inverse());
+        mat
+    }
 }
@@ -1,5 +1,6 @@
 use core::borrow::BorrowMut;
 
+use num_bigint::BigUint;
 use p3_field::Field;
 use p3_matrix::dense::{DenseMatrix, DenseStorage, RowMajorMatrix};
 use p3_matrix::Matrix;
@@ -23,3 +24,23 @@ pub(crate) fn coset_shift_cols<F: Field>(mat: &mut RowMajorMatrix<F>, shift: F)
             })
         });
 }
+
+/// A primitive `n`th root of unity, which generates the multiplicative subgroup of order `n`, or
+/// `None` if `n` doesn't divide the order of the multiplicative group.
+///
+/// This is `F::GENERATOR^(|F^*| / n)`, which for a power of two `n` needn't be the
+/// `two_adic_generator` of a two-adic field.
+pub fn root_of_unity<F: Field>(n: usize) -> Option<F> {
+    let group_order = F::order() - 1u32;
+    if n == 0 || &group_order % n != BigUint::default() {
+        return None;
+    }
+    // Exponentiate by one 64-bit digit of the exponent at a time, as it may not fit in a `u64`.
+    let mut root = F::ONE;
+    let mut base = F::GENERATOR;
+    for digit in (group_order / n).to_u64_digits() {
+        root *= base.exp_u64(digit);
+        base = base.exp_power_of_2(64);
+    }
+    Some(root)
+}
@@ -1,10 +1,47 @@
-use p3_dft::{NaiveDft, TwoAdicSubgroupDft};
-use p3_field::TwoAdicField;
+use p3_dft::{NaiveDft, NaiveSubgroupDft, SubgroupDft, TwoAdicSubgroupDft};
+use p3_field::{Field, TwoAdicField};
+use p3_matrix::bitrev::BitReversableMatrix;
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::Matrix;
 use rand::distributions::{Distribution, Standard};
 use rand::thread_rng;
 
+/// Check that `dft` matches `naive` on random matrices of each of the given heights.
+fn check_matches_naive<F, M>(
+    heights: impl IntoIterator<Item = usize>,
+    dft: impl Fn(RowMajorMatrix<F>) -> M,
+    naive: impl Fn(RowMajorMatrix<F>) -> RowMajorMatrix<F>,
+) where
+    F: Field,
+    Standard: Distribution<F>,
+    M: Matrix<F>,
+{
+    let mut rng = thread_rng();
+    for h in heights {
+        let mat = RowMajorMatrix::<F>::rand(&mut rng, h, 3);
+        let expected = naive(mat.clone());
+        assert_eq!(expected, dft(mat).to_row_major_matrix(), "height {h}");
+    }
+}
+
+/// Check that `inverse` undoes `forward` on random matrices of each of the given heights.
+fn check_inverse<F, M>(
+    heights: impl IntoIterator<Item = usize>,
+    forward: impl Fn(RowMajorMatrix<F>) -> M,
+    inverse: impl Fn(RowMajorMatrix<F>) -> RowMajorMatrix<F>,
+) where
+    F: Field,
+    Standard: Distribution<F>,
+    M: Matrix<F>,
+{
+    let mut rng = thread_rng();
+    for h in heights {
+        let original = RowMajorMatrix::<F>::rand(&mut rng, h, 3);
+        let output = forward(original.clone()).to_row_major_matrix();
+        assert_eq!(original, inverse(output), "height {h}");
+    }
+}
+
 pub fn test_dft_matches_naive<F, Dft>()
 where
     F: TwoAdicField,
@@ -12,14 +49,11 @@ where
     Dft: TwoAdicSubgroupDft<F>,
 {
     let dft = Dft::default();
-    let mut rng = thread_rng();
-    for log_h in 0..12 {
-        let h = 1 << log_h;
-        let mat = RowMajorMatrix::<F>::rand(&mut rng, h, 3);
-        let dft_naive = NaiveDft.dft_batch(mat.clone());
-        let dft_result = dft.dft_batch(mat);
-        assert_eq!(dft_naive, dft_result.to_row_major_matrix());
-    }
+    check_matches_naive(
+        (0..12).map(|log_h| 1 << log_h),
+        |mat| dft.dft_batch(mat),
+        |mat| NaiveDft.dft_batch(mat),
+    );
 }
 
 pub fn test_coset_dft_matches_naive<F, Dft>()
@@ -29,15 +63,12 @@ where
     Dft: TwoAdicSubgroupDft<F>,
 {
     let dft = Dft::default();
-    let mut rng = thread_rng();
-    for log_h in 0..5 {
-        let h = 1 << log_h;
-        let mat = RowMajorMatrix::<F>::rand(&mut rng, h, 3);
-        let shift = F::GENERATOR;
-        let coset_dft_naive = NaiveDft.coset_dft_batch(mat.clone(), shift);
-        let coset_dft_result = dft.coset_dft_batch(mat, shift);
-        assert_eq!(coset_dft_naive, coset_dft_result.to_row_major_matrix());
-    }
+    let shift = F::GENERATOR;
+    check_matches_naive(
+        (0..5).map(|log_h| 1 << log_h),
+        |mat| dft.coset_dft_batch(mat, shift),
+        |mat| NaiveDft.coset_dft_batch(mat, shift),
+    );
 }
 
 pub fn test_idft_matches_naive<F, Dft>()
@@ -47,14 +78,11 @@ where
     Dft: TwoAdicSubgroupDft<F>,
 {
     let dft = Dft::default();
-    let mut rng = thread_rng();
-    for log_h in 0..12 {
-        let h = 1 << log_h;
-        let mat = RowMajorMatrix::<F>::rand(&mut rng, h, 3);
-        let idft_naive = NaiveDft.idft_batch(mat.clone());
-        let idft_result = dft.idft_batch(mat.clone());
-        assert_eq!(idft_naive, idft_result.to_row_major_matrix());
-    }
+    check_matches_naive(
+        (0..12).map(|log_h| 1 << log_h),
+        |mat| dft.idft_batch(mat),
+        |mat| NaiveDft.idft_batch(mat),
+    );
 }
 
 pub fn test_coset_idft_matches_naive<F, Dft>()
@@ -64,15 +92,12 @@ where
     Dft: TwoAdicSubgroupDft<F>,
 {
     let dft = Dft::default();
-    let mut rng = thread_rng();
-    for log_h in 0..5 {
-        let h = 1 << log_h;
-        let mat = RowMajorMatrix::<F>::rand(&mut rng, h, 3);
-        let shift = F::GENERATOR;
-        let idft_naive = NaiveDft.coset_idft_batch(mat.clone(), shift);
-        let idft_result = dft.coset_idft_batch(mat, shift);
-        assert_eq!(idft_naive, idft_result.to_row_major_matrix());
-    }
+    let shift = F::GENERATOR;
+    check_matches_naive(
+        (0..5).map(|log_h| 1 << log_h),
+        |mat| dft.coset_idft_batch(mat, shift),
+        |mat| NaiveDft.coset_idft_batch(mat, shift),
+    );
 }
 
 pub fn test_lde_matches_naive<F, Dft>()
@@ -82,17 +107,29 @@ where
     Dft: TwoAdicSubgroupDft<F>,
 {
     let dft = Dft::default();
-    let mut rng = thread_rng();
-    for log_h in 0..5 {
-        let h = 1 << log_h;
-        let mat = RowMajorMatrix::<F>::rand(&mut rng, h, 3);
-        let lde_naive = NaiveDft.lde_batch(mat.clone(), 1);
-        let lde_result = dft.lde_batch(mat, 1);
-        assert_eq!(lde_naive, lde_result.to_row_major_matrix());
-    }
+    check_matches_naive(
+        (0..5).map(|log_h| 1 << log_h),
+        |mat| dft.lde_batch(mat, 1),
+        |mat| NaiveDft.lde_batch(mat, 1),
+    );
 }
 
 pub fn test_coset_lde_matches_naive<F, Dft>()
+where
+    F: TwoAdicField,
+    Standard: Distribution<F>,
+    Dft: TwoAdicSubgroupDft<F>,
+{
+    let dft = Dft::default();
+    let shift = F::GENERATOR;
+    check_matches_naive(
+        (0..5).map(|log_h| 1 << log_h),
+        |mat| dft.coset_lde_batch(mat, 1, shift),
+        |mat| NaiveDft.coset_lde_batch(mat, 1, shift),
+    );
+}
+
+pub fn test_coset_lde_chunked_matches_unchunked<F, Dft>()
 where
     F: TwoAdicField,
     Standard: Distribution<F>,
@@ -102,11 +139,17 @@ where
     let mut rng = thread_rng();
     for log_h in 0..5 {
         let h = 1 << log_h;
-        let mat = RowMajorMatrix::<F>::rand(&mut rng, h, 3);
+        let mat = RowMajorMatrix::<F>::rand(&mut rng, h, 5);
         let shift = F::GENERATOR;
-        let coset_lde_naive = NaiveDft.coset_lde_batch(mat.clone(), 1, shift);
-        let coset_lde_result = dft.coset_lde_batch(mat, 1, shift);
-        assert_eq!(coset_lde_naive, coset_lde_result.to_row_major_matrix());
+        let coset_lde = dft
+            .coset_lde_batch(mat.clone(), 2, shift)
+            .bit_reverse_rows()
+            .to_row_major_matrix();
+        for chunk_width in [1, 2, 5, 8] {
+            let mut chunked = RowMajorMatrix::default(5, h << 2);
+            dft.coset_lde_batch_chunked(&mat, 2, shift, chunk_width, &mut chunked);
+            assert_eq!(coset_lde, chunked);
+        }
     }
 }
 
@@ -117,14 +160,74 @@ where
     Dft: TwoAdicSubgroupDft<F>,
 {
     let dft = Dft::default();
-    let mut rng = thread_rng();
-    for log_h in 0..12 {
-        let h = 1 << log_h;
-        let original = RowMajorMatrix::<F>::rand(&mut rng, h, 3);
-        let dft_output = dft.dft_batch(original.clone());
-        let idft_output = dft.idft_batch(dft_output.to_row_major_matrix());
-        assert_eq!(original, idft_output.to_row_major_matrix());
-    }
+    check_inverse(
+        (0..12).map(|log_h| 1 << log_h),
+        |mat| dft.dft_batch(mat),
+        |mat| dft.idft_batch(mat),
+    );
+}
+
+pub fn test_subgroup_dft_matches_naive<F, Dft>(heights: &[usize])
+where
+    F: Field,
+    Standard: Distribution<F>,
+    Dft: SubgroupDft<F>,
+{
+    let dft = Dft::default();
+    check_matches_naive(
+        heights.iter().copied(),
+        |mat| dft.dft_batch(mat),
+        |mat| NaiveSubgroupDft.dft_batch(mat),
+    );
+}
+
+pub fn test_subgroup_coset_dft_matches_naive<F, Dft>(heights: &[usize])
+where
+    F: Field,
+    Standard: Distribution<F>,
+    Dft: SubgroupDft<F>,
+{
+    let dft = Dft::default();
+    let shift = F::GENERATOR;
+    check_matches_naive(
+        heights.iter().copied(),
+        |mat| dft.coset_dft_batch(mat, shift),
+        |mat| NaiveSubgroupDft.coset_dft_batch(mat, shift),
+    );
+}
+
+pub fn test_subgroup_idft_matches_naive<F, Dft>(heights: &[usize])
+where
+    F: Field,
+    Standard: Distribution<F>,
+    Dft: SubgroupDft<F>,
+{
+    let dft = Dft::default();
+    check_matches_naive(
+        heights.iter().copied(),
+        |mat| dft.idft_batch(mat),
+        |mat| NaiveSubgroupDft.idft_batch(mat),
+    );
+}
+
+pub fn test_subgroup_dft_idft_consistency<F, Dft>(heights: &[usize])
+where
+    F: Field,
+    Standard: Distribution<F>,
+    Dft: SubgroupDft<F>,
+{
+    let dft = Dft::default();
+    let shift = F::GENERATOR;
+    check_inverse(
+        heights.iter().copied(),
+        |mat| dft.dft_batch(mat),
+        |mat| dft.idft_batch(mat),
+    );
+    check_inverse(
+        heights.iter().copied(),
+        |mat| dft.coset_dft_batch(mat, shift),
+        |mat| dft.coset_idft_batch(mat, shift),
+    );
 }
 
 #[macro_export]
@@ -161,6 +264,11 @@ macro_rules! test_field_dft {
                 $crate::test_coset_lde_matches_naive::<$field, $dft>();
             }
 
+            #[test]
+            fn coset_lde_chunked_matches_unchunked() {
+                $crate::test_coset_lde_chunked_matches_unchunked::<$field, $dft>();
+            }
+
             #[test]
             fn dft_idft_consistency() {
                 $crate::test_dft_idft_consistency::<$field, $dft>();
@@ -168,3 +276,32 @@ macro_rules! test_field_dft {
         }
     };
 }
+
+/// Test a [`SubgroupDft`] over subgroups of the given orders, which must divide the order of the
+/// field's multiplicative group.
+#[macro_export]
+macro_rules! test_field_subgroup_dft {
+    ($mod:ident, $field:ty, $dft:ty, $sizes:expr) => {
+        mod $mod {
+            #[test]
+            fn dft_matches_naive() {
+                $crate::test_subgroup_dft_matches_naive::<$field, $dft>(&$sizes);
+            }
+
+            #[test]
+            fn coset_dft_matches_naive() {
+                $crate::test_subgroup_coset_dft_matches_naive::<$field, $dft>(&$sizes);
+            }
+
+            #[test]
+            fn idft_matches_naive() {
+                $crate::test_subgroup_idft_matches_naive::<$field, $dft>(&$sizes);
+            }
+
+            #[test]
+            fn dft_idft_consistency() {
+                $crate::test_subgroup_dft_idft_consistency::<$field, $dft>(&$sizes);
+            }
+        }
+    };
+}
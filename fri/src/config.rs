@@ -1,12 +1,20 @@
+use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt::Debug;
 
-use p3_field::Field;
+use p3_commit::{ConfigFingerprint, Mmcs};
+use p3_field::{ExtensionField, Field};
 use p3_matrix::Matrix;
+use p3_util::pretty_name;
 
 #[derive(Debug)]
 pub struct FriConfig<M> {
     pub log_blowup: usize,
+    /// The log2 of the number of evaluations folded into one in each commit phase round.
+    pub log_arity: usize,
+    /// The log2 of the number of coefficients of the final polynomial, which is sent in the clear
+    /// once the codeword has been folded down to its degree.
+    pub log_final_poly_len: usize,
     pub num_queries: usize,
     pub proof_of_work_bits: usize,
     pub mmcs: M,
@@ -17,14 +25,90 @@ impl<M> FriConfig<M> {
         1 << self.log_blowup
     }
 
+    pub const fn arity(&self) -> usize {
+        1 << self.log_arity
+    }
+
+    pub const fn final_poly_len(&self) -> usize {
+        1 << self.log_final_poly_len
+    }
+
     /// Returns the soundness bits of this FRI instance based on the
     /// [ethSTARK](https://eprint.iacr.org/2021/582) conjecture.
     ///
-    /// Certain users may instead want to look at proven soundness, a more complex calculation which
-    /// isn't currently supported by this crate.
+    /// This only accounts for queries and grinding. For the soundness of a whole STARK, including
+    /// proven soundness in the list decoding regime, see `StarkSoundness` in `p3-uni-stark`.
     pub fn conjectured_soundness_bits(&self) -> usize {
         self.log_blowup * self.num_queries + self.proof_of_work_bits
     }
+
+    /// The parameters which affect FRI proofs, for binding into a transcript. See
+    /// [`Pcs::parameters`](p3_commit::Pcs::parameters).
+    pub fn parameters(&self) -> Vec<usize> {
+        vec![
+            self.log_blowup,
+            self.log_arity,
+            self.log_final_poly_len,
+            self.num_queries,
+            self.proof_of_work_bits,
+        ]
+    }
+
+    /// The fingerprint of this configuration and its MMCS, for encoding FRI proofs over base field
+    /// `Val` and challenge field `Challenge`.
+    ///
+    /// This doesn't cover the MMCS the inputs are committed with. For proofs which open inputs,
+    /// use the fingerprint of the whole PCS, such as
+    /// [`TwoAdicFriPcs::fingerprint`](crate::TwoAdicFriPcs::fingerprint).
+    pub fn fingerprint<Val: Field, Challenge: ExtensionField<Val>>(&self) -> ConfigFingerprint
+    where
+        M: Mmcs<Challenge>,
+    {
+        let mut parameters = self.parameters();
+        parameters.extend(self.mmcs.parameters());
+        ConfigFingerprint::new::<Val, Challenge>(parameters, pretty_name::<M>())
+    }
+
+    /// The log2 arity of each commit phase round, when folding input codewords of the given log2
+    /// heights, sorted in descending order.
+    ///
+    /// Rounds fold by `arity()` where possible, but by less where that would pass the height of an
+    /// input, which must be added in, or the final height. Folding stops once `final_poly_len()`
+    /// coefficients remain, or earlier if an input is smaller than that. Inputs of height at most
+    /// `blowup()` are the codewords of constants, and are ignored.
+    ///
+    /// Panics if `log_arity` is 0, which the PCSs using FRI reject when they're built.
+    pub fn commit_phase_log_arities(&self, log_heights: &[usize]) -> Vec<usize> {
+        assert!(self.log_arity > 0, "FRI must fold by at least 2");
+        let mut log_height = log_heights.first().copied().unwrap_or(self.log_blowup);
+        let log_final_height = self.log_final_height(log_heights);
+        let mut log_arities = vec![];
+        while log_height > log_final_height {
+            let log_next_stop = log_heights
+                .iter()
+                .copied()
+                .find(|&h| h < log_height && h > log_final_height)
+                .unwrap_or(log_final_height);
+            let log_arity = self.log_arity.min(log_height - log_next_stop);
+            log_arities.push(log_arity);
+            log_height -= log_arity;
+        }
+        log_arities
+    }
+
+    /// The log2 height of the final codeword, when folding input codewords of the given log2
+    /// heights. See [`commit_phase_log_arities`](Self::commit_phase_log_arities).
+    pub fn log_final_height(&self, log_heights: &[usize]) -> usize {
+        let log_max_height = log_heights.first().copied().unwrap_or(self.log_blowup);
+        log_heights
+            .iter()
+            .copied()
+            .filter(|&h| h > self.log_blowup)
+            .fold(
+                (self.log_blowup + self.log_final_poly_len).min(log_max_height),
+                usize::min,
+            )
+    }
 }
 
 /// Whereas `FriConfig` encompasses parameters the end user can set, `FriGenericConfig` is
@@ -37,9 +121,10 @@ pub trait FriGenericConfig<F: Field> {
     /// They will be passed to our callbacks, but ignored (shifted off) by FRI.
     fn extra_query_index_bits(&self) -> usize;
 
-    /// Fold a row, returning a single column.
-    /// Right now the input row will always be 2 columns wide,
-    /// but we may support higher folding arity in the future.
+    /// Fold a row of two evaluations, returning a single column.
+    ///
+    /// FRI folds by higher arities by folding in pairs repeatedly, with challenges `beta`,
+    /// `beta^2`, `beta^4` and so on.
     fn fold_row(
         &self,
         index: usize,
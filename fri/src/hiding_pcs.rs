@@ -1,9 +1,14 @@
+use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 use core::fmt::Debug;
 
+use itertools::izip;
 use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
-use p3_commit::{Mmcs, OpenedValues, Pcs, TwoAdicMultiplicativeCoset};
+use p3_commit::{
+    MatrixShape, Mmcs, MmcsCostModel, OpenedValues, Pcs, PcsCost, PcsCostModel, PolynomialSpace,
+    TwoAdicMultiplicativeCoset,
+};
 use p3_dft::TwoAdicSubgroupDft;
 use p3_field::{ExtensionField, Field, TwoAdicField};
 use p3_matrix::dense::RowMajorMatrix;
@@ -14,7 +19,7 @@ use rand::Rng;
 use tracing::instrument;
 
 use crate::verifier::FriError;
-use crate::{BatchOpening, FriConfig, FriProof, TwoAdicFriPcs};
+use crate::{FriConfig, FriProof, MultiBatchOpening, TwoAdicFriPcs};
 
 /// A hiding FRI PCS. Both MMCSs must also be hiding; this is not enforced at compile time so it's
 /// the user's responsibility to configure.
@@ -62,15 +67,27 @@ where
     /// The second item is the usual FRI proof.
     type Proof = (
         OpenedValues<Challenge>,
-        FriProof<Challenge, FriMmcs, Val, Vec<BatchOpening<Val, InputMmcs>>>,
+        FriProof<Challenge, FriMmcs, Val, Vec<MultiBatchOpening<Val, InputMmcs>>>,
     );
     type Error = FriError<FriMmcs::Error, InputMmcs::Error>;
 
+    const ZK: bool = true;
+
     fn natural_domain_for_degree(&self, degree: usize) -> Self::Domain {
         <TwoAdicFriPcs<Val, Dft, InputMmcs, FriMmcs> as Pcs<Challenge, Challenger>>::natural_domain_for_degree(
             &self.inner, degree)
     }
 
+    fn parameters(&self) -> Vec<usize> {
+        let mut parameters = self.inner.all_parameters::<Challenge>();
+        parameters.push(self.num_random_codewords);
+        parameters
+    }
+
+    fn log_blowup(&self) -> Option<usize> {
+        Some(self.inner.fri_config().log_blowup)
+    }
+
     fn commit(
         &self,
         evaluations: Vec<(Self::Domain, RowMajorMatrix<Val>)>,
@@ -90,6 +107,72 @@ where
         )
     }
 
+    fn commit_randomized(
+        &self,
+        evaluations: Vec<(Self::Domain, RowMajorMatrix<Val>)>,
+    ) -> Option<(Self::Commitment, Self::ProverData)> {
+        let randomized_evaluations = evaluations
+            .into_iter()
+            .map(|(domain, evals)| {
+                let mask = RowMajorMatrix::rand(
+                    &mut *self.rng.borrow_mut(),
+                    evals.height(),
+                    evals.width(),
+                );
+                self.add_mask(domain, evals, mask)
+            })
+            .collect();
+        Some(<Self as Pcs<Challenge, Challenger>>::commit(
+            self,
+            randomized_evaluations,
+        ))
+    }
+
+    fn commit_quotients(
+        &self,
+        quotients: Vec<(Self::Domain, RowMajorMatrix<Val>, usize)>,
+    ) -> (Self::Commitment, Self::ProverData) {
+        let mut masked_chunks = vec![];
+        for (quotient_domain, evals, num_chunks) in quotients {
+            let domains = quotient_domain.split_domains(num_chunks);
+            let chunks = quotient_domain.split_evals(num_chunks, evals);
+
+            // The chunk masks `Z_i r_i` appear in the recombined quotient as `Z r_i / c_i`, where
+            // `Z` vanishes on the whole quotient domain. Picking all but the last `r_i` at random,
+            // and the last so that `sum_i r_i / c_i = 0`, makes them cancel out.
+            let cs = domains
+                .iter()
+                .enumerate()
+                .map(|(i, domain)| {
+                    domains
+                        .iter()
+                        .enumerate()
+                        .filter(|&(j, _)| j != i)
+                        .map(|(_, other)| other.zp_at_point(domain.first_point()))
+                        .product::<Val>()
+                })
+                .collect::<Vec<_>>();
+            let (height, width) = (chunks[0].height(), chunks[0].width());
+            let mut masks = (1..num_chunks)
+                .map(|_| RowMajorMatrix::rand(&mut *self.rng.borrow_mut(), height, width))
+                .collect::<Vec<_>>();
+            let mut last_mask = RowMajorMatrix::new(Val::zero_vec(height * width), width);
+            for (mask, &c) in masks.iter().zip(&cs) {
+                let scale = -cs[num_chunks - 1] / c;
+                for (l, &r) in last_mask.values.iter_mut().zip(&mask.values) {
+                    *l += scale * r;
+                }
+            }
+            masks.push(last_mask);
+
+            masked_chunks.extend(
+                izip!(domains, chunks, masks)
+                    .map(|(domain, chunk, mask)| self.add_mask(domain, chunk, mask)),
+            );
+        }
+        <Self as Pcs<Challenge, Challenger>>::commit(self, masked_chunks)
+    }
+
     fn get_evaluations_on_domain<'a>(
         &self,
         prover_data: &'a Self::ProverData,
@@ -182,6 +265,66 @@ where
     }
 }
 
+impl<Val, Dft, InputMmcs, FriMmcs, R> PcsCostModel for HidingFriPcs<Val, Dft, InputMmcs, FriMmcs, R>
+where
+    InputMmcs: MmcsCostModel,
+    FriMmcs: MmcsCostModel,
+{
+    fn estimate_cost(&self, rounds: &[Vec<MatrixShape>]) -> PcsCost {
+        // Random codewords are appended to every committed matrix, and their opened values are sent
+        // alongside the inner proof.
+        let widened_rounds = rounds
+            .iter()
+            .map(|round| {
+                round
+                    .iter()
+                    .map(|&shape| MatrixShape {
+                        width: shape.width + self.num_random_codewords,
+                        ..shape
+                    })
+                    .collect()
+            })
+            .collect::<Vec<_>>();
+        let mut cost = self.inner.estimate_cost(&widened_rounds);
+        let num_openings: usize = rounds.iter().flatten().map(|shape| shape.num_points).sum();
+        cost.input_proof_bytes +=
+            num_openings * self.num_random_codewords * self.inner.fri_config().mmcs.value_bytes();
+        cost
+    }
+}
+
+impl<Val, Dft, InputMmcs, FriMmcs, R> HidingFriPcs<Val, Dft, InputMmcs, FriMmcs, R>
+where
+    Val: TwoAdicField,
+    Dft: TwoAdicSubgroupDft<Val>,
+{
+    /// Given the evaluations of `f` over `domain`, and the coefficients of `r`, returns the
+    /// evaluations of `f + Z_D r` over the natural domain of twice the size.
+    #[instrument(level = "debug", skip_all)]
+    fn add_mask(
+        &self,
+        domain: TwoAdicMultiplicativeCoset<Val>,
+        evals: RowMajorMatrix<Val>,
+        mask: RowMajorMatrix<Val>,
+    ) -> (TwoAdicMultiplicativeCoset<Val>, RowMajorMatrix<Val>) {
+        let mut coeffs = self.inner.dft.coset_idft_batch(evals, domain.shift);
+        // Z_D(X) = (X / shift)^n - 1.
+        let z_leading_coeff = domain.shift.exp_power_of_2(domain.log_n).inverse();
+        for (c, &r) in coeffs.values.iter_mut().zip(&mask.values) {
+            *c -= r;
+        }
+        coeffs
+            .values
+            .extend(mask.values.iter().map(|&r| r * z_leading_coeff));
+        let extended_domain = TwoAdicMultiplicativeCoset {
+            log_n: domain.log_n + 1,
+            shift: Val::ONE,
+        };
+        let extended_evals = self.inner.dft.dft_batch(coeffs).to_row_major_matrix();
+        (extended_domain, extended_evals)
+    }
+}
+
 #[instrument(level = "debug", skip_all)]
 fn add_random_cols<Val, R>(
     mat: RowMajorMatrix<Val>,
@@ -1,7 +1,10 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
 use alloc::vec::Vec;
 
-use p3_commit::Mmcs;
+use p3_commit::{decode_proof, encode_proof, ConfigFingerprint, Mmcs, ProofFormatError};
 use p3_field::Field;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -11,32 +14,158 @@ use serde::{Deserialize, Serialize};
 ))]
 pub struct FriProof<F: Field, M: Mmcs<F>, Witness, InputProof> {
     pub commit_phase_commits: Vec<M::Commitment>,
-    pub query_proofs: Vec<QueryProof<F, M, InputProof>>,
-    // This could become Vec<FC::Challenge> if this library was generalized to support non-constant
-    // final polynomials.
-    pub final_poly: F,
+    /// The openings of the inputs at every query index.
+    pub input_proof: InputProof,
+    /// For each commit phase commitment, the openings of its codeword at every query index.
+    pub commit_phase_openings: Vec<CommitPhaseOpenings<F, M>>,
+    /// The coefficients of the polynomial the commit phase folds down to, lowest degree first.
+    pub final_poly: Vec<F>,
     pub pow_witness: Witness,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound = "")]
+pub struct CommitPhaseOpenings<F: Field, M: Mmcs<F>> {
+    /// For each query, the openings of the commit phase codeword at the locations which fold
+    /// together with the queried one, in order, not including the queried location itself.
+    pub sibling_values: Vec<Vec<F>>,
+
+    /// One opening proof for the rows of every query.
+    pub opening_proof: M::MultiProof,
+}
+
+/// A [`FriProof`] without the values its verifier can work out for itself, made by
+/// [`verifier::compress`](crate::verifier::compress) and checked by
+/// [`verifier::verify_compressed`](crate::verifier::verify_compressed).
+///
+/// A commit phase row opened by several queries is only sent once, and without the values at the
+/// queried locations, which the verifier has just folded itself. The opening proofs are those of
+/// the uncompressed proof.
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(bound(
-    serialize = "InputProof: Serialize",
-    deserialize = "InputProof: Deserialize<'de>",
+    serialize = "Witness: Serialize, InputProof: Serialize",
+    deserialize = "Witness: Deserialize<'de>, InputProof: Deserialize<'de>"
 ))]
-pub struct QueryProof<F: Field, M: Mmcs<F>, InputProof> {
+pub struct CompressedFriProof<F: Field, M: Mmcs<F>, Witness, InputProof> {
+    pub commit_phase_commits: Vec<M::Commitment>,
+    /// The openings of the inputs at every distinct query index.
     pub input_proof: InputProof,
-    /// For each commit phase commitment, this contains openings of a commit phase codeword at the
-    /// queried location, along with an opening proof.
-    pub commit_phase_openings: Vec<CommitPhaseProofStep<F, M>>,
+    pub commit_phase_openings: Vec<CompressedCommitPhaseOpenings<F, M>>,
+    pub final_poly: Vec<F>,
+    pub pow_witness: Witness,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(bound = "")]
-pub struct CommitPhaseProofStep<F: Field, M: Mmcs<F>> {
-    /// The opening of the commit phase codeword at the sibling location.
-    // This may change to Vec<FC::Challenge> if the library is generalized to support other FRI
-    // folding arities besides 2, meaning that there can be multiple siblings.
-    pub sibling_value: F,
+pub struct CompressedCommitPhaseOpenings<F: Field, M: Mmcs<F>> {
+    /// For each distinct row of the commit phase codeword the queries open, in the order they're
+    /// first opened, the values at the locations no query lands on, in order.
+    pub unqueried_values: Vec<Vec<F>>,
+
+    /// One opening proof for the rows of every query.
+    pub opening_proof: M::MultiProof,
+}
+
+impl<F, M, Witness, InputProof> FriProof<F, M, Witness, InputProof>
+where
+    F: Field,
+    M: Mmcs<F>,
+    Witness: Serialize + DeserializeOwned,
+    InputProof: Serialize + DeserializeOwned,
+{
+    /// Encode the proof with [`encode_proof`], for a configuration with the given fingerprint,
+    /// such as that of [`TwoAdicFriPcs::fingerprint`](crate::TwoAdicFriPcs::fingerprint).
+    pub fn to_bytes(&self, fingerprint: &ConfigFingerprint) -> Result<Vec<u8>, postcard::Error> {
+        encode_proof(fingerprint, self)
+    }
+
+    /// Decode a proof encoded by [`Self::to_bytes`] for a configuration with the same fingerprint.
+    pub fn from_bytes(
+        fingerprint: &ConfigFingerprint,
+        bytes: &[u8],
+    ) -> Result<Self, ProofFormatError> {
+        decode_proof(fingerprint, bytes)
+    }
+}
+
+impl<F, M, Witness, InputProof> CompressedFriProof<F, M, Witness, InputProof>
+where
+    F: Field,
+    M: Mmcs<F>,
+    Witness: Serialize + DeserializeOwned,
+    InputProof: Serialize + DeserializeOwned,
+{
+    /// Encode the proof with [`encode_proof`], as [`FriProof::to_bytes`] does.
+    pub fn to_bytes(&self, fingerprint: &ConfigFingerprint) -> Result<Vec<u8>, postcard::Error> {
+        encode_proof(fingerprint, self)
+    }
+
+    /// Decode a proof encoded by [`Self::to_bytes`] for a configuration with the same fingerprint.
+    pub fn from_bytes(
+        fingerprint: &ConfigFingerprint,
+        bytes: &[u8],
+    ) -> Result<Self, ProofFormatError> {
+        decode_proof(fingerprint, bytes)
+    }
+
+    /// The version of the encoding written by [`Self::to_legacy_bytes`], as its first byte.
+    #[deprecated(note = "use `to_bytes`, which also records the configuration")]
+    pub const FORMAT_VERSION: u8 = 1;
+
+    /// Encode the proof as [`Self::FORMAT_VERSION`] followed by its `postcard` encoding, without
+    /// the magic bytes or configuration fingerprint of [`Self::to_bytes`].
+    #[deprecated(note = "use `to_bytes`, which also records the configuration")]
+    #[allow(deprecated)]
+    pub fn to_legacy_bytes(&self) -> Result<Vec<u8>, postcard::Error> {
+        let mut bytes = vec![Self::FORMAT_VERSION];
+        bytes.extend(postcard::to_allocvec(self)?);
+        Ok(bytes)
+    }
+
+    /// Decode a proof encoded by [`Self::to_legacy_bytes`].
+    #[deprecated(note = "use `from_bytes`, which also checks the configuration")]
+    #[allow(deprecated)]
+    pub fn from_legacy_bytes(bytes: &[u8]) -> Result<Self, ProofDecodingError> {
+        let (&version, rest) = bytes.split_first().ok_or(ProofDecodingError::Postcard(
+            postcard::Error::DeserializeUnexpectedEnd,
+        ))?;
+        if version != Self::FORMAT_VERSION {
+            return Err(ProofDecodingError::UnsupportedVersion(version));
+        }
+        let (proof, rest) =
+            postcard::take_from_bytes(rest).map_err(ProofDecodingError::Postcard)?;
+        if !rest.is_empty() {
+            return Err(ProofDecodingError::TrailingBytes);
+        }
+        Ok(proof)
+    }
+}
+
+/// An error decoding a [`CompressedFriProof`] with
+/// [`CompressedFriProof::from_legacy_bytes`].
+#[deprecated(note = "use `ProofFormatError`, returned by `CompressedFriProof::from_bytes`")]
+#[derive(Debug)]
+pub enum ProofDecodingError {
+    /// The encoding is of a version other than [`CompressedFriProof::FORMAT_VERSION`].
+    UnsupportedVersion(u8),
+    /// There were bytes left over after the proof.
+    TrailingBytes,
+    Postcard(postcard::Error),
+}
 
-    pub opening_proof: M::Proof,
+/// For each index, the position of its first occurrence among the distinct indices, and the
+/// distinct indices in the order they first occur.
+pub(crate) fn dedup_indices(indices: &[usize]) -> (Vec<usize>, Vec<usize>) {
+    let mut distinct = Vec::new();
+    let mut positions = BTreeMap::new();
+    let slots = indices
+        .iter()
+        .map(|&index| {
+            *positions.entry(index).or_insert_with(|| {
+                distinct.push(index);
+                distinct.len() - 1
+            })
+        })
+        .collect();
+    (slots, distinct)
 }
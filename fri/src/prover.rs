@@ -5,12 +5,13 @@ use core::iter;
 use itertools::{izip, Itertools};
 use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
 use p3_commit::Mmcs;
-use p3_field::{ExtensionField, Field};
-use p3_matrix::dense::RowMajorMatrix;
-use p3_util::log2_strict_usize;
+use p3_dft::{Radix2Dit, TwoAdicSubgroupDft};
+use p3_field::{ExtensionField, Field, TwoAdicField};
+use p3_matrix::dense::{RowMajorMatrix, RowMajorMatrixView};
+use p3_util::{log2_strict_usize, reverse_slice_index_bits};
 use tracing::{info_span, instrument};
 
-use crate::{CommitPhaseProofStep, FriConfig, FriGenericConfig, FriProof, QueryProof};
+use crate::{CommitPhaseOpenings, FriConfig, FriGenericConfig, FriProof};
 
 #[instrument(name = "FRI prover", skip_all)]
 pub fn prove<G, Val, Challenge, M, Challenger>(
@@ -18,11 +19,11 @@ pub fn prove<G, Val, Challenge, M, Challenger>(
     config: &FriConfig<M>,
     inputs: Vec<Vec<Challenge>>,
     challenger: &mut Challenger,
-    open_input: impl Fn(usize) -> G::InputProof,
+    open_input: impl Fn(&[usize]) -> G::InputProof,
 ) -> FriProof<Challenge, M, Challenger::Witness, G::InputProof>
 where
     Val: Field,
-    Challenge: ExtensionField<Val>,
+    Challenge: ExtensionField<Val> + TwoAdicField,
     M: Mmcs<Challenge>,
     Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<M::Commitment>,
     G: FriGenericConfig<Challenge>,
@@ -39,23 +40,29 @@ where
 
     let pow_witness = challenger.grind(config.proof_of_work_bits);
 
-    let query_proofs = info_span!("query phase").in_scope(|| {
-        iter::repeat_with(|| challenger.sample_bits(log_max_height + g.extra_query_index_bits()))
-            .take(config.num_queries)
-            .map(|index| QueryProof {
-                input_proof: open_input(index),
-                commit_phase_openings: answer_query(
-                    config,
-                    &commit_phase_result.data,
-                    index >> g.extra_query_index_bits(),
-                ),
-            })
-            .collect()
+    let (input_proof, commit_phase_openings) = info_span!("query phase").in_scope(|| {
+        let indices = iter::repeat_with(|| {
+            challenger.sample_bits(log_max_height + g.extra_query_index_bits())
+        })
+        .take(config.num_queries)
+        .collect_vec();
+        let input_proof = open_input(&indices);
+        let commit_phase_openings = answer_queries(
+            config,
+            &commit_phase_result.data,
+            &commit_phase_result.log_arities,
+            indices
+                .iter()
+                .map(|index| index >> g.extra_query_index_bits())
+                .collect(),
+        );
+        (input_proof, commit_phase_openings)
     });
 
     FriProof {
         commit_phase_commits: commit_phase_result.commits,
-        query_proofs,
+        input_proof,
+        commit_phase_openings,
         final_poly: commit_phase_result.final_poly,
         pow_witness,
     }
@@ -64,7 +71,8 @@ where
 struct CommitPhaseResult<F: Field, M: Mmcs<F>> {
     commits: Vec<M::Commitment>,
     data: Vec<M::ProverData<RowMajorMatrix<F>>>,
-    final_poly: F,
+    log_arities: Vec<usize>,
+    final_poly: Vec<F>,
 }
 
 #[instrument(name = "commit phase", skip_all)]
@@ -76,25 +84,32 @@ fn commit_phase<G, Val, Challenge, M, Challenger>(
 ) -> CommitPhaseResult<Challenge, M>
 where
     Val: Field,
-    Challenge: ExtensionField<Val>,
+    Challenge: ExtensionField<Val> + TwoAdicField,
     M: Mmcs<Challenge>,
     Challenger: FieldChallenger<Val> + CanObserve<M::Commitment>,
     G: FriGenericConfig<Challenge>,
 {
+    let log_heights = inputs
+        .iter()
+        .map(|v| log2_strict_usize(v.len()))
+        .collect_vec();
+    let log_arities = config.commit_phase_log_arities(&log_heights);
+
     let mut inputs_iter = inputs.into_iter().peekable();
     let mut folded = inputs_iter.next().unwrap();
     let mut commits = vec![];
     let mut data = vec![];
 
-    while folded.len() > config.blowup() {
-        let leaves = RowMajorMatrix::new(folded, 2);
+    for &log_arity in &log_arities {
+        // In bit-reversed order, the evaluations which fold together are adjacent.
+        let leaves = RowMajorMatrix::new(folded, 1 << log_arity);
         let (commit, prover_data) = config.mmcs.commit_matrix(leaves);
         challenger.observe(commit.clone());
 
         let beta: Challenge = challenger.sample_ext_element();
         // We passed ownership of `current` to the MMCS, so get a reference to it
         let leaves = config.mmcs.get_matrices(&prover_data).pop().unwrap();
-        folded = g.fold_matrix(beta, leaves.as_view());
+        folded = fold_matrix_by_arity(g, beta, log_arity, &leaves.values);
 
         commits.push(commit);
         data.push(prover_data);
@@ -104,46 +119,79 @@ where
         }
     }
 
-    // We should be left with `blowup` evaluations of a constant polynomial.
-    assert_eq!(folded.len(), config.blowup());
-    let final_poly = folded[0];
-    for x in folded {
-        assert_eq!(x, final_poly);
+    // We should be left with the evaluations of a polynomial of degree less than the final
+    // polynomial's length. The folding formulas absorb the LDE's coset shift, so these are over
+    // the subgroup, in bit-reversed order.
+    let final_poly_len = folded.len() >> config.log_blowup;
+    reverse_slice_index_bits(&mut folded);
+    let mut final_poly = Radix2Dit::default().idft(folded);
+    assert!(
+        final_poly[final_poly_len..].iter().all(|c| c.is_zero()),
+        "final polynomial has too high a degree"
+    );
+    final_poly.truncate(final_poly_len);
+    for &coeff in &final_poly {
+        challenger.observe_ext_element(coeff);
     }
-    challenger.observe_ext_element(final_poly);
 
     CommitPhaseResult {
         commits,
         data,
+        log_arities,
         final_poly,
     }
 }
 
-fn answer_query<F, M>(
+/// Fold a codeword by `2^log_arity`, by folding it in pairs `log_arity` times, with challenges
+/// `beta`, `beta^2`, `beta^4` and so on.
+fn fold_matrix_by_arity<G, F>(g: &G, beta: F, log_arity: usize, values: &[F]) -> Vec<F>
+where
+    F: Field,
+    G: FriGenericConfig<F>,
+{
+    let mut folded = g.fold_matrix(beta, RowMajorMatrixView::new(values, 2));
+    let mut beta = beta;
+    for _ in 1..log_arity {
+        beta = beta.square();
+        folded = g.fold_matrix(beta, RowMajorMatrix::new(folded, 2));
+    }
+    folded
+}
+
+/// Open each commit phase codeword at the rows which fold into each query's next index, with one
+/// multi-opening per round.
+fn answer_queries<F, M>(
     config: &FriConfig<M>,
     commit_phase_commits: &[M::ProverData<RowMajorMatrix<F>>],
-    index: usize,
-) -> Vec<CommitPhaseProofStep<F, M>>
+    log_arities: &[usize],
+    mut indices: Vec<usize>,
+) -> Vec<CommitPhaseOpenings<F, M>>
 where
     F: Field,
     M: Mmcs<F>,
 {
-    commit_phase_commits
-        .iter()
-        .enumerate()
-        .map(|(i, commit)| {
-            let index_i = index >> i;
-            let index_i_sibling = index_i ^ 1;
-            let index_pair = index_i >> 1;
-
-            let (mut opened_rows, opening_proof) = config.mmcs.open_batch(index_pair, commit);
-            assert_eq!(opened_rows.len(), 1);
-            let opened_row = opened_rows.pop().unwrap();
-            assert_eq!(opened_row.len(), 2, "Committed data should be in pairs");
-            let sibling_value = opened_row[index_i_sibling % 2];
-
-            CommitPhaseProofStep {
-                sibling_value,
+    izip!(commit_phase_commits, log_arities)
+        .map(|(commit, &log_arity)| {
+            let index_rows = indices.iter().map(|index| index >> log_arity).collect_vec();
+
+            let (opened_rows, opening_proof) = config.mmcs.open_multi_batch(&index_rows, commit);
+            let sibling_values = izip!(opened_rows, &indices)
+                .map(|(mut opened_rows, index)| {
+                    assert_eq!(opened_rows.len(), 1);
+                    let mut sibling_values = opened_rows.pop().unwrap();
+                    assert_eq!(
+                        sibling_values.len(),
+                        1 << log_arity,
+                        "Committed data should be in rows of the arity"
+                    );
+                    sibling_values.remove(index % (1 << log_arity));
+                    sibling_values
+                })
+                .collect();
+            indices = index_rows;
+
+            CommitPhaseOpenings {
+                sibling_values,
                 opening_proof,
             }
         })
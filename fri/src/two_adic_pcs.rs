@@ -2,11 +2,16 @@ use alloc::collections::BTreeMap;
 use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt::Debug;
+use core::iter;
 use core::marker::PhantomData;
+use core::mem::size_of;
 
 use itertools::{izip, Itertools};
 use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
-use p3_commit::{Mmcs, OpenedValues, Pcs, PolynomialSpace, TwoAdicMultiplicativeCoset};
+use p3_commit::{
+    ConfigFingerprint, HashCount, MatrixShape, Mmcs, MmcsCostModel, OpenedValues, Pcs, PcsCost,
+    PcsCostModel, PolynomialSpace, TwoAdicMultiplicativeCoset,
+};
 use p3_dft::TwoAdicSubgroupDft;
 use p3_field::{
     batch_multiplicative_inverse, cyclic_subgroup_coset_known_order, dot_product, ExtensionField,
@@ -18,16 +23,17 @@ use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::{Dimensions, Matrix};
 use p3_maybe_rayon::prelude::*;
 use p3_util::linear_map::LinearMap;
-use p3_util::{log2_strict_usize, reverse_bits_len, reverse_slice_index_bits, VecExt};
+use p3_util::{log2_strict_usize, pretty_name, reverse_bits_len, reverse_slice_index_bits, VecExt};
 use serde::{Deserialize, Serialize};
 use tracing::{info_span, instrument};
 
+use crate::proof::dedup_indices;
 use crate::verifier::{self, FriError};
-use crate::{prover, FriConfig, FriGenericConfig, FriProof};
+use crate::{prover, CompressedFriProof, FriConfig, FriGenericConfig, FriProof};
 
 #[derive(Debug)]
 pub struct TwoAdicFriPcs<Val, Dft, InputMmcs, FriMmcs> {
-    dft: Dft,
+    pub(crate) dft: Dft,
     mmcs: InputMmcs,
     fri: FriConfig<FriMmcs>,
     _phantom: PhantomData<Val>,
@@ -35,6 +41,7 @@ pub struct TwoAdicFriPcs<Val, Dft, InputMmcs, FriMmcs> {
 
 impl<Val, Dft, InputMmcs, FriMmcs> TwoAdicFriPcs<Val, Dft, InputMmcs, FriMmcs> {
     pub const fn new(dft: Dft, mmcs: InputMmcs, fri: FriConfig<FriMmcs>) -> Self {
+        assert!(fri.log_arity > 0, "FRI must fold by at least 2");
         Self {
             dft,
             mmcs,
@@ -42,6 +49,40 @@ impl<Val, Dft, InputMmcs, FriMmcs> TwoAdicFriPcs<Val, Dft, InputMmcs, FriMmcs> {
             _phantom: PhantomData,
         }
     }
+
+    pub const fn fri_config(&self) -> &FriConfig<FriMmcs> {
+        &self.fri
+    }
+
+    /// The parameters of the FRI configuration, followed by those of the input and FRI MMCSs.
+    pub(crate) fn all_parameters<Challenge>(&self) -> Vec<usize>
+    where
+        Val: Field,
+        Challenge: Field,
+        InputMmcs: Mmcs<Val>,
+        FriMmcs: Mmcs<Challenge>,
+    {
+        let mut parameters = self.fri.parameters();
+        parameters.extend(self.mmcs.parameters());
+        parameters.extend(self.fri.mmcs.parameters());
+        parameters
+    }
+
+    /// The fingerprint of this scheme, for encoding its proofs over challenge field `Challenge`.
+    ///
+    /// This matches the fingerprint of a STARK configuration over this scheme.
+    pub fn fingerprint<Challenge>(&self) -> ConfigFingerprint
+    where
+        Val: Field,
+        Challenge: ExtensionField<Val>,
+        InputMmcs: Mmcs<Val>,
+        FriMmcs: Mmcs<Challenge>,
+    {
+        ConfigFingerprint::new::<Val, Challenge>(
+            self.all_parameters::<Challenge>(),
+            pretty_name::<TwoAdicFriProof<Val, Challenge, InputMmcs, FriMmcs>>(),
+        )
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -51,12 +92,21 @@ pub struct BatchOpening<Val: Field, InputMmcs: Mmcs<Val>> {
     pub opening_proof: <InputMmcs as Mmcs<Val>>::Proof,
 }
 
+/// A round's openings at every query index, with one proof for all of them.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound = "")]
+pub struct MultiBatchOpening<Val: Field, InputMmcs: Mmcs<Val>> {
+    /// For each query, the opened row of each matrix.
+    pub opened_values: Vec<Vec<Vec<Val>>>,
+    pub opening_proof: <InputMmcs as Mmcs<Val>>::MultiProof,
+}
+
 pub struct TwoAdicFriGenericConfig<InputProof, InputError>(
     pub PhantomData<(InputProof, InputError)>,
 );
 
 pub type TwoAdicFriGenericConfigForMmcs<F, M> =
-    TwoAdicFriGenericConfig<Vec<BatchOpening<F, M>>, <M as Mmcs<F>>::Error>;
+    TwoAdicFriGenericConfig<Vec<MultiBatchOpening<F, M>>, <M as Mmcs<F>>::Error>;
 
 impl<F: TwoAdicField, InputProof, InputError: Debug> FriGenericConfig<F>
     for TwoAdicFriGenericConfig<InputProof, InputError>
@@ -143,7 +193,7 @@ where
     type Domain = TwoAdicMultiplicativeCoset<Val>;
     type Commitment = InputMmcs::Commitment;
     type ProverData = InputMmcs::ProverData<RowMajorMatrix<Val>>;
-    type Proof = FriProof<Challenge, FriMmcs, Val, Vec<BatchOpening<Val, InputMmcs>>>;
+    type Proof = FriProof<Challenge, FriMmcs, Val, Vec<MultiBatchOpening<Val, InputMmcs>>>;
     type Error = FriError<FriMmcs::Error, InputMmcs::Error>;
 
     fn natural_domain_for_degree(&self, degree: usize) -> Self::Domain {
@@ -154,6 +204,17 @@ where
         }
     }
 
+    fn parameters(&self) -> Vec<usize> {
+        self.all_parameters::<Challenge>()
+    }
+
+    fn log_blowup(&self) -> Option<usize> {
+        Some(self.fri.log_blowup)
+    }
+
+    /// The LDEs are computed and committed in memory, as the prover data holds them as
+    /// `RowMajorMatrix`s. Traces too large for that must be committed outside this PCS; see
+    /// `TwoAdicSubgroupDft::coset_lde_batch_chunked`.
     fn commit(
         &self,
         evaluations: Vec<(Self::Domain, RowMajorMatrix<Val>)>,
@@ -200,143 +261,17 @@ where
         )>,
         challenger: &mut Challenger,
     ) -> (OpenedValues<Challenge>, Self::Proof) {
-        /*
-
-        A quick rundown of the optimizations in this function:
-        We are trying to compute sum_i alpha^i * (p(X) - y)/(X - z),
-        for each z an opening point, y = p(z). Each p(X) is given as evaluations in bit-reversed order
-        in the columns of the matrices. y is computed by barycentric interpolation.
-        X and p(X) are in the base field; alpha, y and z are in the extension.
-        The primary goal is to minimize extension multiplications.
-
-        - Instead of computing all alpha^i, we just compute alpha^i for i up to the largest width
-        of a matrix, then multiply by an "alpha offset" when accumulating.
-              a^0 x0 + a^1 x1 + a^2 x2 + a^3 x3 + ...
-            = a^0 ( a^0 x0 + a^1 x1 ) + a^2 ( a^0 x2 + a^1 x3 ) + ...
-            (see `alpha_pows`, `alpha_pow_offset`, `num_reduced`)
-
-        - For each unique point z, we precompute 1/(X-z) for the largest subgroup opened at this point.
-        Since we compute it in bit-reversed order, smaller subgroups can simply truncate the vector.
-            (see `inv_denoms`)
-
-        - Then, for each matrix (with columns p_i) and opening point z, we want:
-            for each row (corresponding to subgroup element X):
-                reduced[X] += alpha_offset * sum_i [ alpha^i * inv_denom[X] * (p_i[X] - y[i]) ]
-
-            We can factor out inv_denom, and expand what's left:
-                reduced[X] += alpha_offset * inv_denom[X] * sum_i [ alpha^i * p_i[X] - alpha^i * y[i] ]
-
-            And separate the sum:
-                reduced[X] += alpha_offset * inv_denom[X] * [ sum_i [ alpha^i * p_i[X] ] - sum_i [ alpha^i * y[i] ] ]
-
-            And now the last sum doesn't depend on X, so we can precompute that for the matrix, too.
-            So the hot loop (that depends on both X and i) is just:
-                sum_i [ alpha^i * p_i[X] ]
-
-            with alpha^i an extension, p_i[X] a base
-
-        */
-
         // Batch combination challenge
         let alpha: Challenge = challenger.sample_ext_element();
 
-        let mats_and_points = rounds
-            .iter()
-            .map(|(data, points)| {
-                (
-                    self.mmcs
-                        .get_matrices(data)
-                        .into_iter()
-                        .map(|m| m.as_view())
-                        .collect_vec(),
-                    points,
-                )
-            })
-            .collect_vec();
-        let mats = mats_and_points
-            .iter()
-            .flat_map(|(mats, _)| mats)
-            .collect_vec();
-
-        let global_max_height = mats.iter().map(|m| m.height()).max().unwrap();
-        let log_global_max_height = log2_strict_usize(global_max_height);
-
-        // For each unique opening point z, we will find the largest degree bound
-        // for that point, and precompute 1/(z - X) for the largest subgroup (in bitrev order).
-        let inv_denoms = compute_inverse_denominators(&mats_and_points, Val::GENERATOR);
-
-        let mut all_opened_values: OpenedValues<Challenge> = vec![];
-
-        let mut reduced_openings: [_; 32] = core::array::from_fn(|_| None);
-        let mut num_reduced = [0; 32];
-
-        for (mats, points) in mats_and_points {
-            let opened_values_for_round = all_opened_values.pushed_mut(vec![]);
-            for (mat, points_for_mat) in izip!(mats, points) {
-                let log_height = log2_strict_usize(mat.height());
-                let reduced_opening_for_log_height = reduced_openings[log_height]
-                    .get_or_insert_with(|| vec![Challenge::ZERO; mat.height()]);
-                debug_assert_eq!(reduced_opening_for_log_height.len(), mat.height());
-
-                let opened_values_for_mat = opened_values_for_round.pushed_mut(vec![]);
-                for &point in points_for_mat {
-                    let _guard =
-                        info_span!("reduce matrix quotient", dims = %mat.dimensions()).entered();
-
-                    // Use Barycentric interpolation to evaluate the matrix at the given point.
-                    let ys = info_span!("compute opened values with Lagrange interpolation")
-                        .in_scope(|| {
-                            let h = mat.height() >> self.fri.log_blowup;
-                            let (low_coset, _) = mat.split_rows(h);
-                            let mut inv_denoms = inv_denoms.get(&point).unwrap()[..h].to_vec();
-                            reverse_slice_index_bits(&mut inv_denoms);
-                            interpolate_coset(
-                                &BitReversalPerm::new_view(low_coset),
-                                Val::GENERATOR,
-                                point,
-                                Some(&inv_denoms),
-                            )
-                        });
-
-                    let alpha_pow_offset = alpha.exp_u64(num_reduced[log_height] as u64);
-                    let reduced_ys: Challenge = dot_product(alpha.powers(), ys.iter().copied());
-
-                    info_span!("reduce rows").in_scope(|| {
-                        mat.dot_ext_powers(alpha)
-                            .zip(reduced_opening_for_log_height.par_iter_mut())
-                            // This might be longer, but zip will truncate to smaller subgroup
-                            // (which is ok because it's bitrev)
-                            .zip(inv_denoms.get(&point).unwrap().par_iter())
-                            .for_each(|((reduced_row, ro), &inv_denom)| {
-                                *ro += alpha_pow_offset * (reduced_ys - reduced_row) * inv_denom
-                            });
-                    });
-
-                    num_reduced[log_height] += mat.width();
-                    opened_values_for_mat.push(ys);
-                }
-            }
-        }
-
-        let fri_input = reduced_openings.into_iter().rev().flatten().collect_vec();
+        let (all_opened_values, fri_input) =
+            reduce_openings(&self.mmcs, self.fri.log_blowup, &rounds, alpha);
 
         let g: TwoAdicFriGenericConfigForMmcs<Val, InputMmcs> =
             TwoAdicFriGenericConfig(PhantomData);
 
-        let fri_proof = prover::prove(&g, &self.fri, fri_input, challenger, |index| {
-            rounds
-                .iter()
-                .map(|(data, _)| {
-                    let log_max_height = log2_strict_usize(self.mmcs.get_max_height(data));
-                    let bits_reduced = log_global_max_height - log_max_height;
-                    let reduced_index = index >> bits_reduced;
-                    let (opened_values, opening_proof) = self.mmcs.open_batch(reduced_index, data);
-                    BatchOpening {
-                        opened_values,
-                        opening_proof,
-                    }
-                })
-                .collect()
+        let fri_proof = prover::prove(&g, &self.fri, fri_input, challenger, |indices| {
+            open_input(&self.mmcs, &rounds, indices)
         });
 
         (all_opened_values, fri_proof)
@@ -363,87 +298,825 @@ where
         proof: &Self::Proof,
         challenger: &mut Challenger,
     ) -> Result<(), Self::Error> {
-        // Batch combination challenge
-        let alpha: Challenge = challenger.sample_ext_element();
+        let lde_points = LdePoints::new(self.log_global_max_height(&rounds));
+        verify_with_points(
+            &self.mmcs,
+            &self.fri,
+            rounds,
+            proof,
+            challenger,
+            &lde_points,
+        )
+    }
+}
 
-        let log_global_max_height = proof.commit_phase_commits.len() + self.fri.log_blowup;
+/// The error type of [`TwoAdicFriPcs`]'s verification.
+pub type TwoAdicFriPcsError<Val, Challenge, InputMmcs, FriMmcs> =
+    FriError<<FriMmcs as Mmcs<Challenge>>::Error, <InputMmcs as Mmcs<Val>>::Error>;
 
-        let g: TwoAdicFriGenericConfigForMmcs<Val, InputMmcs> =
-            TwoAdicFriGenericConfig(PhantomData);
+/// An opening proof of [`TwoAdicFriPcs`].
+pub type TwoAdicFriProof<Val, Challenge, InputMmcs, FriMmcs> =
+    FriProof<Challenge, FriMmcs, Val, Vec<MultiBatchOpening<Val, InputMmcs>>>;
 
-        verifier::verify(&g, &self.fri, proof, challenger, |index, input_proof| {
-            // TODO: separate this out into functions
+/// An opening proof of [`TwoAdicFriPcs`], compressed by [`TwoAdicFriPcs::compress_proof`].
+pub type CompressedTwoAdicFriProof<Val, Challenge, InputMmcs, FriMmcs> =
+    CompressedFriProof<Challenge, FriMmcs, Val, Vec<MultiBatchOpening<Val, InputMmcs>>>;
 
-            // log_height -> (alpha_pow, reduced_opening)
-            let mut reduced_openings = BTreeMap::<usize, (Challenge, Challenge)>::new();
+/// An opening proof to check with [`TwoAdicFriPcs::verify_many`], with the other arguments
+/// [`Pcs::verify`] takes.
+pub struct FriVerifierInstance<'a, Val, Challenge, InputMmcs, FriMmcs, Challenger>
+where
+    Val: TwoAdicField,
+    Challenge: Field,
+    InputMmcs: Mmcs<Val>,
+    FriMmcs: Mmcs<Challenge>,
+{
+    pub rounds: Vec<VerifierOpeningRound<Val, Challenge, InputMmcs>>,
+    pub proof: &'a FriProof<Challenge, FriMmcs, Val, Vec<MultiBatchOpening<Val, InputMmcs>>>,
+    /// The challenger, in the state it would be passed to [`Pcs::verify`] in.
+    pub challenger: Challenger,
+}
 
-            for (batch_opening, (batch_commit, mats)) in izip!(input_proof, &rounds) {
-                let batch_heights = mats
+impl<Val, Dft, InputMmcs, FriMmcs> TwoAdicFriPcs<Val, Dft, InputMmcs, FriMmcs>
+where
+    Val: TwoAdicField,
+    InputMmcs: Mmcs<Val>,
+{
+    /// Verify many opening proofs in parallel, returning the result of each in order.
+    ///
+    /// This is equivalent to calling [`Pcs::verify`] on each instance, but the points of the LDE
+    /// domains are computed from one table of generator powers, shared by every query of every
+    /// proof.
+    ///
+    /// Those points are all proofs of equal height have in common. The denominators `x - z` which
+    /// the prover precomputes over whole domains can't be shared, as each proof samples its own
+    /// opening points `z` and query points `x` from its transcript, and inverting them over whole
+    /// domains would cost far more than the verifier's one inversion per query.
+    #[instrument(name = "verify many FRI proofs", skip_all, fields(num_proofs = instances.len()))]
+    pub fn verify_many<'a, Challenge, Challenger>(
+        &self,
+        instances: Vec<FriVerifierInstance<'a, Val, Challenge, InputMmcs, FriMmcs, Challenger>>,
+    ) -> Vec<Result<(), TwoAdicFriPcsError<Val, Challenge, InputMmcs, FriMmcs>>>
+    where
+        InputMmcs: Sync,
+        Challenge: TwoAdicField + ExtensionField<Val>,
+        FriMmcs: Mmcs<Challenge> + Sync,
+        Challenger: FieldChallenger<Val>
+            + CanObserve<FriMmcs::Commitment>
+            + GrindingChallenger<Witness = Val>,
+        FriVerifierInstance<'a, Val, Challenge, InputMmcs, FriMmcs, Challenger>: Send,
+        TwoAdicFriPcsError<Val, Challenge, InputMmcs, FriMmcs>: Send,
+    {
+        let max_log_height = instances
+            .iter()
+            .map(|instance| self.log_global_max_height(&instance.rounds))
+            .max()
+            .unwrap_or(0);
+        let lde_points = LdePoints::new(max_log_height);
+        // Verification doesn't need the DFT, which may not be shareable between threads.
+        let (mmcs, fri) = (&self.mmcs, &self.fri);
+
+        instances
+            .into_par_iter()
+            .map(|mut instance| {
+                verify_with_points(
+                    mmcs,
+                    fri,
+                    instance.rounds,
+                    instance.proof,
+                    &mut instance.challenger,
+                    &lde_points,
+                )
+            })
+            .collect()
+    }
+
+    /// Compress an opening proof made by [`Pcs::open`], given the rounds and challenger
+    /// [`Pcs::verify`] would be passed.
+    ///
+    /// Besides compressing the commit phase openings, this keeps the input openings of each
+    /// distinct query index only once.
+    pub fn compress_proof<Challenge, Challenger>(
+        &self,
+        rounds: &[VerifierOpeningRound<Val, Challenge, InputMmcs>],
+        proof: &FriProof<Challenge, FriMmcs, Val, Vec<MultiBatchOpening<Val, InputMmcs>>>,
+        challenger: &mut Challenger,
+    ) -> Result<
+        CompressedTwoAdicFriProof<Val, Challenge, InputMmcs, FriMmcs>,
+        TwoAdicFriPcsError<Val, Challenge, InputMmcs, FriMmcs>,
+    >
+    where
+        Challenge: TwoAdicField + ExtensionField<Val>,
+        FriMmcs: Mmcs<Challenge>,
+        Challenger: FieldChallenger<Val>
+            + CanObserve<FriMmcs::Commitment>
+            + GrindingChallenger<Witness = Val>,
+    {
+        // Sample alpha, as the verifier does, to reach the FRI transcript.
+        let _alpha: Challenge = challenger.sample_ext_element();
+
+        let log_heights = fri_log_heights(self.fri.log_blowup, rounds);
+        let g: TwoAdicFriGenericConfigForMmcs<Val, InputMmcs> =
+            TwoAdicFriGenericConfig(PhantomData);
+
+        verifier::compress(
+            &g,
+            &self.fri,
+            &log_heights,
+            proof,
+            challenger,
+            |indices, input_proof| {
+                let (_, distinct) = dedup_indices(indices);
+                let first_queries = distinct
                     .iter()
-                    .map(|(domain, _)| domain.size() << self.fri.log_blowup)
+                    .map(|index| indices.iter().position(|i| i == index).unwrap())
                     .collect_vec();
-                let batch_dims = batch_heights
+                input_proof
                     .iter()
-                    // TODO: MMCS doesn't really need width; we put 0 for now.
-                    .map(|&height| Dimensions { width: 0, height })
+                    .map(|batch_opening| {
+                        (batch_opening.opened_values.len() == indices.len()).then(|| {
+                            MultiBatchOpening {
+                                opened_values: first_queries
+                                    .iter()
+                                    .map(|&query| batch_opening.opened_values[query].clone())
+                                    .collect(),
+                                opening_proof: batch_opening.opening_proof.clone(),
+                            }
+                        })
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    /// Verify an opening proof compressed by [`Self::compress_proof`], as [`Pcs::verify`] verifies
+    /// an uncompressed one.
+    pub fn verify_compressed<Challenge, Challenger>(
+        &self,
+        rounds: Vec<VerifierOpeningRound<Val, Challenge, InputMmcs>>,
+        proof: &CompressedTwoAdicFriProof<Val, Challenge, InputMmcs, FriMmcs>,
+        challenger: &mut Challenger,
+    ) -> Result<(), TwoAdicFriPcsError<Val, Challenge, InputMmcs, FriMmcs>>
+    where
+        Challenge: TwoAdicField + ExtensionField<Val>,
+        FriMmcs: Mmcs<Challenge>,
+        Challenger: FieldChallenger<Val>
+            + CanObserve<FriMmcs::Commitment>
+            + GrindingChallenger<Witness = Val>,
+    {
+        let lde_points = LdePoints::new(self.log_global_max_height(&rounds));
+
+        // Batch combination challenge
+        let alpha: Challenge = challenger.sample_ext_element();
+
+        let log_heights = fri_log_heights(self.fri.log_blowup, &rounds);
+        let g: TwoAdicFriGenericConfigForMmcs<Val, InputMmcs> =
+            TwoAdicFriGenericConfig(PhantomData);
+
+        verifier::verify_compressed(
+            &g,
+            &self.fri,
+            &log_heights,
+            proof,
+            challenger,
+            |indices, input_proof| {
+                let (slots, distinct) = dedup_indices(indices);
+                let input_proof = input_proof
+                    .iter()
+                    .map(|batch_opening| MultiBatchOpening {
+                        // A round with the wrong number of openings is left for the MMCS to
+                        // reject.
+                        opened_values: if batch_opening.opened_values.len() == distinct.len() {
+                            slots
+                                .iter()
+                                .map(|&slot| batch_opening.opened_values[slot].clone())
+                                .collect()
+                        } else {
+                            batch_opening.opened_values.clone()
+                        },
+                        opening_proof: batch_opening.opening_proof.clone(),
+                    })
                     .collect_vec();
+                fri_reduced_openings(
+                    &self.mmcs,
+                    self.fri.log_blowup,
+                    &rounds,
+                    alpha,
+                    &lde_points,
+                    indices,
+                    &input_proof,
+                )
+            },
+        )
+    }
 
-                let batch_max_height = batch_heights.iter().max().expect("Empty batch?");
-                let log_batch_max_height = log2_strict_usize(*batch_max_height);
-                let bits_reduced = log_global_max_height - log_batch_max_height;
-                let reduced_index = index >> bits_reduced;
-
-                self.mmcs.verify_batch(
-                    batch_commit,
-                    &batch_dims,
-                    reduced_index,
-                    &batch_opening.opened_values,
-                    &batch_opening.opening_proof,
-                )?;
-                for (mat_opening, (mat_domain, mat_points_and_values)) in
-                    izip!(&batch_opening.opened_values, mats)
-                {
-                    let log_height = log2_strict_usize(mat_domain.size()) + self.fri.log_blowup;
-
-                    let bits_reduced = log_global_max_height - log_height;
-                    let rev_reduced_index = reverse_bits_len(index >> bits_reduced, log_height);
-
-                    // todo: this can be nicer with domain methods?
-
-                    let x = Val::GENERATOR
-                        * Val::two_adic_generator(log_height).exp_u64(rev_reduced_index as u64);
-
-                    let (alpha_pow, ro) = reduced_openings
-                        .entry(log_height)
-                        .or_insert((Challenge::ONE, Challenge::ZERO));
-
-                    for (z, ps_at_z) in mat_points_and_values {
-                        for (&p_at_x, &p_at_z) in izip!(mat_opening, ps_at_z) {
-                            let quotient = (-p_at_z + p_at_x) / (-*z + x);
-                            *ro += *alpha_pow * quotient;
-                            *alpha_pow *= alpha;
-                        }
-                    }
-                }
-            }
+    /// The log2 height of the largest LDE of the rounds' matrices.
+    fn log_global_max_height<Challenge: Field>(
+        &self,
+        rounds: &[VerifierOpeningRound<Val, Challenge, InputMmcs>],
+    ) -> usize {
+        log_global_max_height(self.fri.log_blowup, rounds)
+    }
+}
 
-            // `reduced_openings` would have a log_height = log_blowup entry only if there was a
-            // trace matrix of height 1. In this case the reduced opening can be skipped as it will
-            // not be checked against any commit phase commit.
-            if let Some((_alpha_pow, ro)) = reduced_openings.remove(&self.fri.log_blowup) {
+/// Verify an opening proof as [`Pcs::verify`] does, using `lde_points` for the points of the
+/// LDE domains.
+fn verify_with_points<Val, Challenge, InputMmcs, FriMmcs, Challenger>(
+    mmcs: &InputMmcs,
+    fri: &FriConfig<FriMmcs>,
+    rounds: Vec<VerifierOpeningRound<Val, Challenge, InputMmcs>>,
+    proof: &FriProof<Challenge, FriMmcs, Val, Vec<MultiBatchOpening<Val, InputMmcs>>>,
+    challenger: &mut Challenger,
+    lde_points: &LdePoints<Val>,
+) -> Result<(), FriError<FriMmcs::Error, InputMmcs::Error>>
+where
+    Val: TwoAdicField,
+    Challenge: TwoAdicField + ExtensionField<Val>,
+    InputMmcs: Mmcs<Val>,
+    FriMmcs: Mmcs<Challenge>,
+    Challenger:
+        FieldChallenger<Val> + CanObserve<FriMmcs::Commitment> + GrindingChallenger<Witness = Val>,
+{
+    // Batch combination challenge
+    let alpha: Challenge = challenger.sample_ext_element();
+
+    let log_heights = fri_log_heights(fri.log_blowup, &rounds);
+
+    let g: TwoAdicFriGenericConfigForMmcs<Val, InputMmcs> = TwoAdicFriGenericConfig(PhantomData);
+
+    verifier::verify(
+        &g,
+        fri,
+        &log_heights,
+        proof,
+        challenger,
+        |indices, input_proof| {
+            fri_reduced_openings(
+                mmcs,
+                fri.log_blowup,
+                &rounds,
+                alpha,
+                lde_points,
+                indices,
+                input_proof,
+            )
+        },
+    )
+}
+
+/// The distinct log2 heights of the rounds' LDEs, in descending order.
+fn fri_log_heights<Val, Challenge, InputMmcs>(
+    log_blowup: usize,
+    rounds: &[VerifierOpeningRound<Val, Challenge, InputMmcs>],
+) -> Vec<usize>
+where
+    Val: TwoAdicField,
+    InputMmcs: Mmcs<Val>,
+{
+    rounds
+        .iter()
+        .flat_map(|(_, mats)| {
+            mats.iter()
+                .map(|(domain, _)| log2_strict_usize(domain.size()) + log_blowup)
+        })
+        .sorted_by(|l, r| r.cmp(l))
+        .dedup()
+        .collect_vec()
+}
+
+/// Check the input openings with [`verify_input`], and return each query's reduced openings as
+/// the FRI verifier takes them.
+fn fri_reduced_openings<Val, Challenge, InputMmcs>(
+    mmcs: &InputMmcs,
+    log_blowup: usize,
+    rounds: &[VerifierOpeningRound<Val, Challenge, InputMmcs>],
+    alpha: Challenge,
+    lde_points: &LdePoints<Val>,
+    indices: &[usize],
+    input_proof: &[MultiBatchOpening<Val, InputMmcs>],
+) -> Result<Vec<Vec<(usize, Challenge)>>, InputMmcs::Error>
+where
+    Val: TwoAdicField,
+    Challenge: ExtensionField<Val>,
+    InputMmcs: Mmcs<Val>,
+{
+    let reduced_openings = verify_input(
+        mmcs,
+        log_blowup,
+        rounds,
+        alpha,
+        lde_points,
+        indices,
+        input_proof,
+    )?;
+
+    Ok(reduced_openings
+        .into_iter()
+        .map(|mut reduced_openings| {
+            // `reduced_openings` would have a log_height = log_blowup entry only if there
+            // was a trace matrix of height 1. In this case the reduced opening can be
+            // skipped as it will not be checked against any commit phase commit.
+            if let Some(ro) = reduced_openings.remove(&log_blowup) {
                 debug_assert!(ro.is_zero());
             }
 
             // Return reduced openings descending by log_height.
-            Ok(reduced_openings
-                .into_iter()
-                .rev()
-                .map(|(log_height, (_alpha_pow, ro))| (log_height, ro))
-                .collect())
+            reduced_openings.into_iter().rev().collect()
         })
-        .expect("fri err");
+        .collect())
+}
 
-        Ok(())
+/// The points of the LDE domains `GENERATOR <g>`, for two-adic generators `g`, in bit-reversed
+/// order.
+///
+/// Rather than exponentiating a generator for each point, this keeps the powers `g^(2^k)` of the
+/// largest subgroup's generator, from which any point of any smaller domain is a product. The
+/// table can be shared by every query of every proof with LDEs up to its height.
+#[derive(Clone, Debug)]
+pub struct LdePoints<F> {
+    generator_powers: Vec<F>,
+}
+
+impl<F: TwoAdicField> LdePoints<F> {
+    pub fn new(max_log_height: usize) -> Self {
+        let generator_powers = iter::successors(Some(F::two_adic_generator(max_log_height)), |g| {
+            Some(g.square())
+        })
+        .take(max_log_height)
+        .collect();
+        Self { generator_powers }
+    }
+
+    pub fn max_log_height(&self) -> usize {
+        self.generator_powers.len()
+    }
+
+    /// The point at `index` of the bit-reversed LDE domain of height `2^log_height`.
+    pub fn point(&self, log_height: usize, index: usize) -> F {
+        F::GENERATOR * self.subgroup_point(log_height, index)
+    }
+
+    /// The points a query reaches in the bit-reversed LDE domains of every height up to
+    /// `2^log_max_height`, indexed by log2 height. `index` is the query's index into the largest
+    /// domain, and its low bits are dropped to index the smaller ones, as for matrices of
+    /// differing heights in one commitment.
+    pub fn query_points(&self, log_max_height: usize, index: usize) -> Vec<F> {
+        // Dropping a low bit of the index halves the subgroup, which squares its point.
+        let mut points =
+            iter::successors(Some(self.subgroup_point(log_max_height, index)), |point| {
+                Some(point.square())
+            })
+            .take(log_max_height + 1)
+            .map(|point| F::GENERATOR * point)
+            .collect_vec();
+        points.reverse();
+        points
+    }
+
+    /// The point at `index` of the bit-reversed subgroup of order `2^log_height`.
+    fn subgroup_point(&self, log_height: usize, index: usize) -> F {
+        // The generator of the smaller subgroup is the largest one's generator to the power of
+        // `2^(max_log_height - log_height)`.
+        let powers = &self.generator_powers[self.max_log_height() - log_height..];
+        let exponent = reverse_bits_len(index, log_height);
+        powers
+            .iter()
+            .enumerate()
+            .filter(|&(k, _)| exponent >> k & 1 == 1)
+            .fold(F::ONE, |acc, (_, &power)| acc * power)
+    }
+}
+
+/// A round's prover data, and the points to open each of its matrices at, as passed to
+/// [`Pcs::open`].
+pub type ProverOpeningRound<'a, Val, Challenge, InputMmcs> = (
+    &'a <InputMmcs as Mmcs<Val>>::ProverData<RowMajorMatrix<Val>>,
+    Vec<Vec<Challenge>>,
+);
+
+/// A round's commitment, and the domain of each of its matrices with their claimed evaluations,
+/// as passed to [`Pcs::verify`].
+pub type VerifierOpeningRound<Val, Challenge, InputMmcs> = (
+    <InputMmcs as Mmcs<Val>>::Commitment,
+    Vec<(
+        TwoAdicMultiplicativeCoset<Val>,
+        Vec<(Challenge, Vec<Challenge>)>,
+    )>,
+);
+
+/// Evaluate each matrix at its opening points, and combine the quotients `(p(X) - p(z)) / (X - z)`
+/// with powers of `alpha` into one reduced opening codeword per height, in bit-reversed order.
+///
+/// Returns the opened values, and the reduced openings sorted by height in descending order.
+pub fn reduce_openings<Val, Challenge, InputMmcs>(
+    mmcs: &InputMmcs,
+    log_blowup: usize,
+    rounds: &[ProverOpeningRound<'_, Val, Challenge, InputMmcs>],
+    alpha: Challenge,
+) -> (OpenedValues<Challenge>, Vec<Vec<Challenge>>)
+where
+    Val: TwoAdicField,
+    Challenge: ExtensionField<Val> + TwoAdicField,
+    InputMmcs: Mmcs<Val>,
+{
+    /*
+
+    A quick rundown of the optimizations in this function:
+    We are trying to compute sum_i alpha^i * (p(X) - y)/(X - z),
+    for each z an opening point, y = p(z). Each p(X) is given as evaluations in bit-reversed order
+    in the columns of the matrices. y is computed by barycentric interpolation.
+    X and p(X) are in the base field; alpha, y and z are in the extension.
+    The primary goal is to minimize extension multiplications.
+
+    - Instead of computing all alpha^i, we just compute alpha^i for i up to the largest width
+    of a matrix, then multiply by an "alpha offset" when accumulating.
+          a^0 x0 + a^1 x1 + a^2 x2 + a^3 x3 + ...
+        = a^0 ( a^0 x0 + a^1 x1 ) + a^2 ( a^0 x2 + a^1 x3 ) + ...
+        (see `alpha_pows`, `alpha_pow_offset`, `num_reduced`)
+
+    - For each unique point z, we precompute 1/(X-z) for the largest subgroup opened at this point.
+    Since we compute it in bit-reversed order, smaller subgroups can simply truncate the vector.
+        (see `inv_denoms`)
+
+    - Then, for each matrix (with columns p_i) and opening point z, we want:
+        for each row (corresponding to subgroup element X):
+            reduced[X] += alpha_offset * sum_i [ alpha^i * inv_denom[X] * (p_i[X] - y[i]) ]
+
+        We can factor out inv_denom, and expand what's left:
+            reduced[X] += alpha_offset * inv_denom[X] * sum_i [ alpha^i * p_i[X] - alpha^i * y[i] ]
+
+        And separate the sum:
+            reduced[X] += alpha_offset * inv_denom[X] * [ sum_i [ alpha^i * p_i[X] ] - sum_i [ alpha^i * y[i] ] ]
+
+        And now the last sum doesn't depend on X, so we can precompute that for the matrix, too.
+        So the hot loop (that depends on both X and i) is just:
+            sum_i [ alpha^i * p_i[X] ]
+
+        with alpha^i an extension, p_i[X] a base
+
+    */
+
+    let mats_and_points = rounds
+        .iter()
+        .map(|(data, points)| {
+            (
+                mmcs.get_matrices(data)
+                    .into_iter()
+                    .map(|m| m.as_view())
+                    .collect_vec(),
+                points,
+            )
+        })
+        .collect_vec();
+
+    // For each unique opening point z, we will find the largest degree bound
+    // for that point, and precompute 1/(z - X) for the largest subgroup (in bitrev order).
+    let inv_denoms = compute_inverse_denominators(&mats_and_points, Val::GENERATOR);
+
+    let mut all_opened_values: OpenedValues<Challenge> = vec![];
+
+    let mut reduced_openings: [_; 32] = core::array::from_fn(|_| None);
+    let mut num_reduced = [0; 32];
+
+    for (mats, points) in mats_and_points {
+        let opened_values_for_round = all_opened_values.pushed_mut(vec![]);
+        for (mat, points_for_mat) in izip!(mats, points) {
+            let log_height = log2_strict_usize(mat.height());
+            let reduced_opening_for_log_height = reduced_openings[log_height]
+                .get_or_insert_with(|| vec![Challenge::ZERO; mat.height()]);
+            debug_assert_eq!(reduced_opening_for_log_height.len(), mat.height());
+
+            let opened_values_for_mat = opened_values_for_round.pushed_mut(vec![]);
+            for &point in points_for_mat {
+                let _guard =
+                    info_span!("reduce matrix quotient", dims = %mat.dimensions()).entered();
+
+                // Use Barycentric interpolation to evaluate the matrix at the given point.
+                let ys = info_span!("compute opened values with Lagrange interpolation").in_scope(
+                    || {
+                        let h = mat.height() >> log_blowup;
+                        let (low_coset, _) = mat.split_rows(h);
+                        let mut inv_denoms = inv_denoms.get(&point).unwrap()[..h].to_vec();
+                        reverse_slice_index_bits(&mut inv_denoms);
+                        interpolate_coset(
+                            &BitReversalPerm::new_view(low_coset),
+                            Val::GENERATOR,
+                            point,
+                            Some(&inv_denoms),
+                        )
+                    },
+                );
+
+                let alpha_pow_offset = alpha.exp_u64(num_reduced[log_height] as u64);
+                let reduced_ys: Challenge = dot_product(alpha.powers(), ys.iter().copied());
+
+                info_span!("reduce rows").in_scope(|| {
+                    mat.dot_ext_powers(alpha)
+                        .zip(reduced_opening_for_log_height.par_iter_mut())
+                        // This might be longer, but zip will truncate to smaller subgroup
+                        // (which is ok because it's bitrev)
+                        .zip(inv_denoms.get(&point).unwrap().par_iter())
+                        .for_each(|((reduced_row, ro), &inv_denom)| {
+                            *ro += alpha_pow_offset * (reduced_ys - reduced_row) * inv_denom
+                        });
+                });
+
+                num_reduced[log_height] += mat.width();
+                opened_values_for_mat.push(ys);
+            }
+        }
+    }
+
+    let reduced_openings = reduced_openings.into_iter().rev().flatten().collect_vec();
+    (all_opened_values, reduced_openings)
+}
+
+/// Open every round's matrices at the rows of each query index into the largest LDE domain, with
+/// one multi-opening per round.
+pub fn open_input<Val, Challenge, InputMmcs>(
+    mmcs: &InputMmcs,
+    rounds: &[ProverOpeningRound<'_, Val, Challenge, InputMmcs>],
+    indices: &[usize],
+) -> Vec<MultiBatchOpening<Val, InputMmcs>>
+where
+    Val: Field,
+    InputMmcs: Mmcs<Val>,
+{
+    let log_global_max_height = rounds
+        .iter()
+        .map(|(data, _)| log2_strict_usize(mmcs.get_max_height(data)))
+        .max()
+        .unwrap();
+    rounds
+        .iter()
+        .map(|(data, _)| {
+            let log_max_height = log2_strict_usize(mmcs.get_max_height(data));
+            let bits_reduced = log_global_max_height - log_max_height;
+            let reduced_indices = indices
+                .iter()
+                .map(|index| index >> bits_reduced)
+                .collect_vec();
+            let (opened_values, opening_proof) = mmcs.open_multi_batch(&reduced_indices, data);
+            MultiBatchOpening {
+                opened_values,
+                opening_proof,
+            }
+        })
+        .collect()
+}
+
+/// Check the openings of every query made by [`open_input`] against the commitments, and recompute
+/// the reduced openings of [`reduce_openings`] at each queried point from them and the claimed
+/// evaluations. `lde_points` must reach the height of the largest LDE.
+///
+/// Returns the reduced opening of each log2 height, for each query.
+pub fn verify_input<Val, Challenge, InputMmcs>(
+    mmcs: &InputMmcs,
+    log_blowup: usize,
+    rounds: &[VerifierOpeningRound<Val, Challenge, InputMmcs>],
+    alpha: Challenge,
+    lde_points: &LdePoints<Val>,
+    indices: &[usize],
+    input_proof: &[MultiBatchOpening<Val, InputMmcs>],
+) -> Result<Vec<BTreeMap<usize, Challenge>>, InputMmcs::Error>
+where
+    Val: TwoAdicField,
+    Challenge: ExtensionField<Val>,
+    InputMmcs: Mmcs<Val>,
+{
+    let log_global_max_height = log_global_max_height(log_blowup, rounds);
+
+    for (batch_opening, (batch_commit, mats)) in izip!(input_proof, rounds) {
+        let (batch_dims, bits_reduced) =
+            batch_dims_and_bits_reduced(log_blowup, log_global_max_height, mats);
+        let reduced_indices = indices
+            .iter()
+            .map(|index| index >> bits_reduced)
+            .collect_vec();
+        mmcs.verify_multi_batch(
+            batch_commit,
+            &batch_dims,
+            &reduced_indices,
+            &batch_opening.opened_values,
+            &batch_opening.opening_proof,
+        )?;
+    }
+
+    Ok(indices
+        .iter()
+        .enumerate()
+        .map(|(query, &index)| {
+            let opened_values = input_proof
+                .iter()
+                .map(|batch_opening| batch_opening.opened_values[query].as_slice())
+                .collect_vec();
+            reduce_query_openings(
+                log_blowup,
+                log_global_max_height,
+                rounds,
+                alpha,
+                lde_points,
+                index,
+                &opened_values,
+            )
+        })
+        .collect())
+}
+
+/// The log2 height of the largest LDE of the rounds' matrices.
+fn log_global_max_height<Val, Challenge, InputMmcs>(
+    log_blowup: usize,
+    rounds: &[VerifierOpeningRound<Val, Challenge, InputMmcs>],
+) -> usize
+where
+    Val: TwoAdicField,
+    InputMmcs: Mmcs<Val>,
+{
+    rounds
+        .iter()
+        .flat_map(|(_, mats)| mats)
+        .map(|(domain, _)| log2_strict_usize(domain.size()) + log_blowup)
+        .max()
+        .unwrap_or(log_blowup)
+}
+
+/// The dimensions of a round's LDEs, for its MMCS, and the number of low bits to remove from a
+/// query index into the largest LDE of all rounds to get its index into the round's largest.
+fn batch_dims_and_bits_reduced<Val: TwoAdicField, Challenge>(
+    log_blowup: usize,
+    log_global_max_height: usize,
+    mats: &[(
+        TwoAdicMultiplicativeCoset<Val>,
+        Vec<(Challenge, Vec<Challenge>)>,
+    )],
+) -> (Vec<Dimensions>, usize) {
+    let batch_heights = mats
+        .iter()
+        .map(|(domain, _)| domain.size() << log_blowup)
+        .collect_vec();
+    let batch_dims = batch_heights
+        .iter()
+        // TODO: MMCS doesn't really need width; we put 0 for now.
+        .map(|&height| Dimensions { width: 0, height })
+        .collect_vec();
+
+    let batch_max_height = batch_heights.iter().max().expect("Empty batch?");
+    let log_batch_max_height = log2_strict_usize(*batch_max_height);
+    (batch_dims, log_global_max_height - log_batch_max_height)
+}
+
+/// Recompute the reduced openings of [`reduce_openings`] at the query `index`, from each round's
+/// opened rows there.
+fn reduce_query_openings<Val, Challenge, InputMmcs>(
+    log_blowup: usize,
+    log_global_max_height: usize,
+    rounds: &[VerifierOpeningRound<Val, Challenge, InputMmcs>],
+    alpha: Challenge,
+    lde_points: &LdePoints<Val>,
+    index: usize,
+    opened_values: &[&[Vec<Val>]],
+) -> BTreeMap<usize, Challenge>
+where
+    Val: TwoAdicField,
+    Challenge: ExtensionField<Val>,
+    InputMmcs: Mmcs<Val>,
+{
+    // The denominator `x - z` of each quotient, in order of round, matrix and opening point, for
+    // the queried point `x` of the matrix's LDE domain. They're inverted together. The points `x`
+    // are computed once per height, and shared by the matrices of that height.
+    let xs = lde_points.query_points(log_global_max_height, index);
+    let denominators = rounds
+        .iter()
+        .flat_map(|(_, mats)| mats)
+        .flat_map(|(mat_domain, mat_points_and_values)| {
+            let x = xs[log2_strict_usize(mat_domain.size()) + log_blowup];
+            mat_points_and_values.iter().map(move |&(z, _)| -z + x)
+        })
+        .collect_vec();
+
+    let inv_denominators = batch_multiplicative_inverse(&denominators);
+    let mut inv_denominators = inv_denominators.into_iter();
+
+    // log_height -> (alpha_pow, reduced_opening)
+    let mut reduced_openings = BTreeMap::<usize, (Challenge, Challenge)>::new();
+
+    for (batch_opened_values, (_, mats)) in izip!(opened_values, rounds) {
+        for (mat_opening, (mat_domain, mat_points_and_values)) in
+            izip!(batch_opened_values.iter(), mats)
+        {
+            let log_height = log2_strict_usize(mat_domain.size()) + log_blowup;
+
+            let (alpha_pow, ro) = reduced_openings
+                .entry(log_height)
+                .or_insert((Challenge::ONE, Challenge::ZERO));
+
+            for (_, ps_at_z) in mat_points_and_values {
+                let inv_denominator = inv_denominators.next().unwrap();
+                for (&p_at_x, &p_at_z) in izip!(mat_opening, ps_at_z) {
+                    let quotient = (-p_at_z + p_at_x) * inv_denominator;
+                    *ro += *alpha_pow * quotient;
+                    *alpha_pow *= alpha;
+                }
+            }
+        }
+    }
+
+    reduced_openings
+        .into_iter()
+        .map(|(log_height, (_alpha_pow, ro))| (log_height, ro))
+        .collect()
+}
+
+impl<Val, Dft, InputMmcs, FriMmcs> PcsCostModel for TwoAdicFriPcs<Val, Dft, InputMmcs, FriMmcs>
+where
+    InputMmcs: MmcsCostModel,
+    FriMmcs: MmcsCostModel,
+{
+    fn estimate_cost(&self, rounds: &[Vec<MatrixShape>]) -> PcsCost {
+        let log_blowup = self.fri.log_blowup;
+        let num_queries = self.fri.num_queries;
+        let fri_mmcs = &self.fri.mmcs;
+
+        let round_dims = rounds
+            .iter()
+            .map(|round| {
+                round
+                    .iter()
+                    .map(|shape| Dimensions {
+                        width: shape.width,
+                        height: 1 << (shape.log_degree + log_blowup),
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+        let log_heights = rounds
+            .iter()
+            .flatten()
+            .map(|shape| shape.log_degree + log_blowup)
+            .sorted_by(|l, r| r.cmp(l))
+            .dedup()
+            .collect_vec();
+
+        // Each query opens a row of every committed matrix, with one multi-opening per round.
+        let input_proof_bytes = round_dims
+            .iter()
+            .map(|dims| {
+                let width: usize = dims.iter().map(|dims| dims.width).sum();
+                num_queries * width * self.mmcs.value_bytes()
+                    + self.mmcs.multi_opening_proof_bytes(dims, num_queries)
+            })
+            .sum::<usize>();
+        let input_verify_hashes = round_dims
+            .iter()
+            .map(|dims| self.mmcs.multi_verify_cost(dims, num_queries))
+            .sum::<HashCount>();
+
+        // The commit phase folds the codeword down to the final polynomial, committing to each
+        // codeword as rows of the evaluations which fold together.
+        let mut log_height = log_heights.first().copied().unwrap_or(log_blowup);
+        let commit_phase_dims = self
+            .fri
+            .commit_phase_log_arities(&log_heights)
+            .into_iter()
+            .map(|log_arity| {
+                log_height -= log_arity;
+                [Dimensions {
+                    width: 1 << log_arity,
+                    height: 1 << log_height,
+                }]
+            })
+            .collect_vec();
+        let final_poly_len = 1 << (log_height - log_blowup);
+        let commit_phase_bytes = commit_phase_dims.len() * fri_mmcs.commitment_bytes()
+            + commit_phase_dims
+                .iter()
+                .map(|dims| {
+                    num_queries * (dims[0].width - 1) * fri_mmcs.value_bytes()
+                        + fri_mmcs.multi_opening_proof_bytes(dims, num_queries)
+                })
+                .sum::<usize>()
+            // The final polynomial and the proof-of-work witness.
+            + final_poly_len * fri_mmcs.value_bytes()
+            + size_of::<Val>();
+        let commit_phase_verify_hashes = commit_phase_dims
+            .iter()
+            .map(|dims| fri_mmcs.multi_verify_cost(dims, num_queries))
+            .sum::<HashCount>();
+
+        PcsCost {
+            commitment_bytes: self.mmcs.commitment_bytes(),
+            commit_hashes: round_dims
+                .iter()
+                .map(|dims| self.mmcs.commit_cost(dims))
+                .collect(),
+            input_proof_bytes,
+            commit_phase_bytes,
+            open_hashes: commit_phase_dims
+                .iter()
+                .map(|dims| fri_mmcs.commit_cost(dims))
+                .sum(),
+            verify_hashes: input_verify_hashes + commit_phase_verify_hashes,
+        }
     }
 }
 
@@ -1,13 +1,19 @@
 use alloc::vec;
 use alloc::vec::Vec;
+use core::iter;
 
 use itertools::{izip, Itertools};
 use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
 use p3_commit::Mmcs;
-use p3_field::{ExtensionField, Field};
+use p3_field::{ExtensionField, Field, TwoAdicField};
 use p3_matrix::Dimensions;
+use p3_util::reverse_bits_len;
 
-use crate::{CommitPhaseProofStep, FriConfig, FriGenericConfig, FriProof};
+use crate::proof::dedup_indices;
+use crate::{
+    CommitPhaseOpenings, CompressedCommitPhaseOpenings, CompressedFriProof, FriConfig,
+    FriGenericConfig, FriProof,
+};
 
 #[derive(Debug)]
 pub enum FriError<CommitMmcsErr, InputError> {
@@ -18,64 +24,299 @@ pub enum FriError<CommitMmcsErr, InputError> {
     InvalidPowWitness,
 }
 
+/// Verify a FRI proof for input codewords of the given log2 heights, sorted in descending order.
+///
+/// `open_input` checks the openings of the inputs at every query index, and returns each query's
+/// reduced openings, by log2 height in descending order.
 pub fn verify<G, Val, Challenge, M, Challenger>(
     g: &G,
     config: &FriConfig<M>,
+    log_heights: &[usize],
     proof: &FriProof<Challenge, M, Challenger::Witness, G::InputProof>,
     challenger: &mut Challenger,
-    open_input: impl Fn(usize, &G::InputProof) -> Result<Vec<(usize, Challenge)>, G::InputError>,
+    open_input: impl Fn(&[usize], &G::InputProof) -> Result<Vec<Vec<(usize, Challenge)>>, G::InputError>,
 ) -> Result<(), FriError<M::Error, G::InputError>>
 where
     Val: Field,
-    Challenge: ExtensionField<Val>,
+    Challenge: ExtensionField<Val> + TwoAdicField,
     M: Mmcs<Challenge>,
     Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<M::Commitment>,
     G: FriGenericConfig<Challenge>,
 {
-    let betas: Vec<Challenge> = proof
-        .commit_phase_commits
+    verify_rounds(
+        g,
+        config,
+        log_heights,
+        &proof.commit_phase_commits,
+        proof
+            .commit_phase_openings
+            .iter()
+            .map(RoundOpenings::Full)
+            .collect(),
+        &proof.final_poly,
+        proof.pow_witness,
+        challenger,
+        |indices| open_input(indices, &proof.input_proof),
+    )
+}
+
+/// Verify a compressed FRI proof, as [`verify`] verifies an uncompressed one.
+///
+/// `open_input` is given every query index, including repeated ones, and returns the reduced
+/// openings of each.
+pub fn verify_compressed<G, Val, Challenge, M, Challenger, InputProof>(
+    g: &G,
+    config: &FriConfig<M>,
+    log_heights: &[usize],
+    proof: &CompressedFriProof<Challenge, M, Challenger::Witness, InputProof>,
+    challenger: &mut Challenger,
+    open_input: impl Fn(&[usize], &InputProof) -> Result<Vec<Vec<(usize, Challenge)>>, G::InputError>,
+) -> Result<(), FriError<M::Error, G::InputError>>
+where
+    Val: Field,
+    Challenge: ExtensionField<Val> + TwoAdicField,
+    M: Mmcs<Challenge>,
+    Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<M::Commitment>,
+    G: FriGenericConfig<Challenge>,
+{
+    verify_rounds(
+        g,
+        config,
+        log_heights,
+        &proof.commit_phase_commits,
+        proof
+            .commit_phase_openings
+            .iter()
+            .map(RoundOpenings::Compressed)
+            .collect(),
+        &proof.final_poly,
+        proof.pow_witness,
+        challenger,
+        |indices| open_input(indices, &proof.input_proof),
+    )
+}
+
+/// Compress a FRI proof, given the challenger in the state [`verify`] would be passed it in.
+///
+/// This replays the verifier's transcript to find the query indices. `compress_input` is given
+/// them, including repeated ones, and returns the compressed input openings, or `None` if the
+/// input proof has the wrong shape.
+pub fn compress<G, Val, Challenge, M, Challenger, InputProof>(
+    g: &G,
+    config: &FriConfig<M>,
+    log_heights: &[usize],
+    proof: &FriProof<Challenge, M, Challenger::Witness, G::InputProof>,
+    challenger: &mut Challenger,
+    compress_input: impl FnOnce(&[usize], &G::InputProof) -> Option<InputProof>,
+) -> Result<
+    CompressedFriProof<Challenge, M, Challenger::Witness, InputProof>,
+    FriError<M::Error, G::InputError>,
+>
+where
+    Val: Field,
+    Challenge: ExtensionField<Val> + TwoAdicField,
+    M: Mmcs<Challenge>,
+    Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<M::Commitment>,
+    G: FriGenericConfig<Challenge>,
+{
+    let log_arities = config.commit_phase_log_arities(log_heights);
+    if proof.commit_phase_openings.len() != log_arities.len() {
+        return Err(FriError::InvalidProofShape);
+    }
+    let (_, indices) = sample_challenges(
+        g,
+        config,
+        log_heights,
+        &proof.commit_phase_commits,
+        &proof.final_poly,
+        proof.pow_witness,
+        challenger,
+    )?;
+
+    let input_proof =
+        compress_input(&indices, &proof.input_proof).ok_or(FriError::InvalidProofShape)?;
+
+    let mut indices = indices
+        .iter()
+        .map(|index| index >> g.extra_query_index_bits())
+        .collect_vec();
+    let commit_phase_openings = izip!(&log_arities, &proof.commit_phase_openings)
+        .map(|(&log_arity, openings)| {
+            let arity = 1 << log_arity;
+            if openings.sibling_values.len() != indices.len()
+                || openings
+                    .sibling_values
+                    .iter()
+                    .any(|sibling_values| sibling_values.len() != arity - 1)
+            {
+                return Err(FriError::InvalidProofShape);
+            }
+            let index_rows = indices.iter().map(|index| index >> log_arity).collect_vec();
+            let (slots, distinct_rows) = dedup_indices(&index_rows);
+
+            let mut queried = vec![vec![false; arity]; distinct_rows.len()];
+            let mut first_query = vec![None; distinct_rows.len()];
+            for (query, (&index, &slot)) in izip!(&indices, &slots).enumerate() {
+                queried[slot][index % arity] = true;
+                first_query[slot].get_or_insert(query);
+            }
+            let unqueried_values = izip!(queried, first_query)
+                .map(|(queried, query)| {
+                    // The first query's siblings are the row without its own location, which is
+                    // queried.
+                    let query = query.unwrap();
+                    let own = indices[query] % arity;
+                    let siblings = &openings.sibling_values[query];
+                    (0..arity)
+                        .filter(|&i| !queried[i])
+                        .map(|i| siblings[if i < own { i } else { i - 1 }])
+                        .collect()
+                })
+                .collect();
+
+            indices = index_rows;
+            Ok(CompressedCommitPhaseOpenings {
+                unqueried_values,
+                opening_proof: openings.opening_proof.clone(),
+            })
+        })
+        .collect::<Result<Vec<_>, FriError<M::Error, G::InputError>>>()?;
+
+    Ok(CompressedFriProof {
+        commit_phase_commits: proof.commit_phase_commits.clone(),
+        input_proof,
+        commit_phase_openings,
+        final_poly: proof.final_poly.clone(),
+        pow_witness: proof.pow_witness,
+    })
+}
+
+/// Observe the commit phase commitments and final polynomial, check the proof of work, and return
+/// the folding challenges and the query indices.
+fn sample_challenges<G, Val, Challenge, M, Challenger>(
+    g: &G,
+    config: &FriConfig<M>,
+    log_heights: &[usize],
+    commit_phase_commits: &[M::Commitment],
+    final_poly: &[Challenge],
+    pow_witness: Challenger::Witness,
+    challenger: &mut Challenger,
+) -> Result<(Vec<Challenge>, Vec<usize>), FriError<M::Error, G::InputError>>
+where
+    Val: Field,
+    Challenge: ExtensionField<Val> + TwoAdicField,
+    M: Mmcs<Challenge>,
+    Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<M::Commitment>,
+    G: FriGenericConfig<Challenge>,
+{
+    let log_arities = config.commit_phase_log_arities(log_heights);
+    let log_final_height = config.log_final_height(log_heights);
+    if commit_phase_commits.len() != log_arities.len()
+        || final_poly.len() != 1 << (log_final_height - config.log_blowup)
+    {
+        return Err(FriError::InvalidProofShape);
+    }
+
+    let betas: Vec<Challenge> = commit_phase_commits
         .iter()
         .map(|comm| {
             challenger.observe(comm.clone());
             challenger.sample_ext_element()
         })
         .collect();
-    challenger.observe_ext_element(proof.final_poly);
-
-    if proof.query_proofs.len() != config.num_queries {
-        return Err(FriError::InvalidProofShape);
+    for &coeff in final_poly {
+        challenger.observe_ext_element(coeff);
     }
 
     // Check PoW.
-    if !challenger.check_witness(config.proof_of_work_bits, proof.pow_witness) {
+    if !challenger.check_witness(config.proof_of_work_bits, pow_witness) {
         return Err(FriError::InvalidPowWitness);
     }
 
-    let log_max_height = proof.commit_phase_commits.len() + config.log_blowup;
+    let log_max_height = log_heights[0];
 
-    for qp in &proof.query_proofs {
-        let index = challenger.sample_bits(log_max_height + g.extra_query_index_bits());
-        let ro = open_input(index, &qp.input_proof).map_err(FriError::InputError)?;
+    let indices =
+        iter::repeat_with(|| challenger.sample_bits(log_max_height + g.extra_query_index_bits()))
+            .take(config.num_queries)
+            .collect_vec();
+    Ok((betas, indices))
+}
 
-        debug_assert!(
-            ro.iter().tuple_windows().all(|((l, _), (r, _))| l > r),
-            "reduced openings sorted by height descending"
-        );
+/// Verify the commit phase of a proof in either format, given the reduced openings of the inputs
+/// from `open_input`.
+#[allow(clippy::too_many_arguments)]
+fn verify_rounds<G, Val, Challenge, M, Challenger>(
+    g: &G,
+    config: &FriConfig<M>,
+    log_heights: &[usize],
+    commit_phase_commits: &[M::Commitment],
+    commit_phase_openings: Vec<RoundOpenings<'_, Challenge, M>>,
+    final_poly: &[Challenge],
+    pow_witness: Challenger::Witness,
+    challenger: &mut Challenger,
+    open_input: impl FnOnce(&[usize]) -> Result<Vec<Vec<(usize, Challenge)>>, G::InputError>,
+) -> Result<(), FriError<M::Error, G::InputError>>
+where
+    Val: Field,
+    Challenge: ExtensionField<Val> + TwoAdicField,
+    M: Mmcs<Challenge>,
+    Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<M::Commitment>,
+    G: FriGenericConfig<Challenge>,
+{
+    let log_arities = config.commit_phase_log_arities(log_heights);
+    let log_final_height = config.log_final_height(log_heights);
+    if commit_phase_openings.len() != log_arities.len() {
+        return Err(FriError::InvalidProofShape);
+    }
+    let (betas, indices) = sample_challenges(
+        g,
+        config,
+        log_heights,
+        commit_phase_commits,
+        final_poly,
+        pow_witness,
+        challenger,
+    )?;
+
+    let log_max_height = log_heights[0];
+
+    let reduced_openings = open_input(&indices).map_err(FriError::InputError)?;
+    if reduced_openings.len() != config.num_queries {
+        return Err(FriError::InvalidProofShape);
+    }
+    debug_assert!(
+        reduced_openings
+            .iter()
+            .all(|ro| ro.iter().tuple_windows().all(|((l, _), (r, _))| l > r)),
+        "reduced openings sorted by height descending"
+    );
+
+    let final_evals = verify_queries(
+        g,
+        config,
+        indices
+            .iter()
+            .map(|index| index >> g.extra_query_index_bits())
+            .collect(),
+        izip!(
+            &log_arities,
+            &betas,
+            commit_phase_commits,
+            commit_phase_openings
+        ),
+        reduced_openings,
+        log_max_height,
+    )?;
 
-        let folded_eval = verify_query(
-            g,
-            config,
-            index >> g.extra_query_index_bits(),
-            izip!(
-                &betas,
-                &proof.commit_phase_commits,
-                &qp.commit_phase_openings
-            ),
-            ro,
-            log_max_height,
-        )?;
-
-        if folded_eval != proof.final_poly {
+    for (final_index, folded_eval) in final_evals {
+        // The final codeword is over the subgroup, in bit-reversed order.
+        let x = Challenge::two_adic_generator(log_final_height)
+            .exp_u64(reverse_bits_len(final_index, log_final_height) as u64);
+        let final_eval = final_poly
+            .iter()
+            .rev()
+            .fold(Challenge::ZERO, |acc, &coeff| acc * x + coeff);
+        if folded_eval != final_eval {
             return Err(FriError::FinalPolyMismatch);
         }
     }
@@ -83,64 +324,197 @@ where
     Ok(())
 }
 
+/// The openings of one commit phase codeword, from a proof in either format.
+enum RoundOpenings<'a, F: Field, M: Mmcs<F>> {
+    Full(&'a CommitPhaseOpenings<F, M>),
+    Compressed(&'a CompressedCommitPhaseOpenings<F, M>),
+}
+
+impl<F: Field, M: Mmcs<F>> RoundOpenings<'_, F, M> {
+    fn opening_proof(&self) -> &M::MultiProof {
+        match self {
+            Self::Full(openings) => &openings.opening_proof,
+            Self::Compressed(openings) => &openings.opening_proof,
+        }
+    }
+
+    /// Each query's opened row, given the queries' indices into the codeword and their evaluations
+    /// there.
+    fn rows<InputError>(
+        &self,
+        indices: &[usize],
+        log_arity: usize,
+        evals: &[F],
+    ) -> Result<Vec<Vec<F>>, FriError<M::Error, InputError>> {
+        let arity = 1 << log_arity;
+        match self {
+            Self::Full(openings) => {
+                if openings.sibling_values.len() != indices.len()
+                    || openings
+                        .sibling_values
+                        .iter()
+                        .any(|sibling_values| sibling_values.len() != arity - 1)
+                {
+                    return Err(FriError::InvalidProofShape);
+                }
+                Ok(izip!(indices, evals, &openings.sibling_values)
+                    .map(|(index, &eval, sibling_values)| {
+                        let mut row = sibling_values.clone();
+                        row.insert(index % arity, eval);
+                        row
+                    })
+                    .collect())
+            }
+            Self::Compressed(openings) => {
+                let index_rows = indices.iter().map(|index| index >> log_arity).collect_vec();
+                let (slots, distinct_rows) = dedup_indices(&index_rows);
+                if openings.unqueried_values.len() != distinct_rows.len() {
+                    return Err(FriError::InvalidProofShape);
+                }
+
+                let mut rows = vec![vec![None; arity]; distinct_rows.len()];
+                for (index, &slot, &eval) in izip!(indices, &slots, evals) {
+                    match &mut rows[slot][index % arity] {
+                        // Queries at the same location can't disagree on its value.
+                        Some(other) if *other != eval => return Err(FriError::InvalidProofShape),
+                        entry => *entry = Some(eval),
+                    }
+                }
+                let rows = izip!(rows, &openings.unqueried_values)
+                    .map(|(row, unqueried_values)| {
+                        if row.iter().filter(|entry| entry.is_none()).count()
+                            != unqueried_values.len()
+                        {
+                            return Err(FriError::InvalidProofShape);
+                        }
+                        let mut unqueried_values = unqueried_values.iter();
+                        Ok(row
+                            .into_iter()
+                            .map(|entry| entry.unwrap_or_else(|| *unqueried_values.next().unwrap()))
+                            .collect_vec())
+                    })
+                    .collect::<Result<Vec<_>, FriError<M::Error, InputError>>>()?;
+                Ok(slots.into_iter().map(|slot| rows[slot].clone()).collect())
+            }
+        }
+    }
+}
+
 type CommitStep<'a, F, M> = (
+    &'a usize,
     &'a F,
     &'a <M as Mmcs<F>>::Commitment,
-    &'a CommitPhaseProofStep<F, M>,
+    RoundOpenings<'a, F, M>,
 );
 
-fn verify_query<'a, G, F, M>(
+/// Fold the reduced openings of every query down the commit phase together, checking each round's
+/// openings with one multi-opening, and return each query's index in and evaluation of the final
+/// codeword.
+fn verify_queries<'a, G, F, M>(
     g: &G,
     config: &FriConfig<M>,
-    mut index: usize,
+    mut indices: Vec<usize>,
     steps: impl Iterator<Item = CommitStep<'a, F, M>>,
-    reduced_openings: Vec<(usize, F)>,
+    reduced_openings: Vec<Vec<(usize, F)>>,
     log_max_height: usize,
-) -> Result<F, FriError<M::Error, G::InputError>>
+) -> Result<Vec<(usize, F)>, FriError<M::Error, G::InputError>>
 where
     F: Field,
     M: Mmcs<F> + 'a,
     G: FriGenericConfig<F>,
 {
-    let mut folded_eval = F::ZERO;
-    let mut ro_iter = reduced_openings.into_iter().peekable();
-
-    for (log_folded_height, (&beta, comm, opening)) in izip!((0..log_max_height).rev(), steps) {
-        if let Some((_, ro)) = ro_iter.next_if(|(lh, _)| *lh == log_folded_height + 1) {
-            folded_eval += ro;
-        }
+    let mut folded_evals = vec![F::ZERO; indices.len()];
+    let mut ro_iters = reduced_openings
+        .into_iter()
+        .map(|ro| ro.into_iter().peekable())
+        .collect_vec();
+    let mut log_height = log_max_height;
 
-        let index_sibling = index ^ 1;
-        let index_pair = index >> 1;
+    for (&log_arity, &beta, comm, openings) in steps {
+        let arity = 1 << log_arity;
+        let index_rows = indices.iter().map(|index| index >> log_arity).collect_vec();
+        let log_folded_height = log_height - log_arity;
 
-        let mut evals = vec![folded_eval; 2];
-        evals[index_sibling % 2] = opening.sibling_value;
+        for (folded_eval, ro_iter) in izip!(&mut folded_evals, &mut ro_iters) {
+            if let Some((_, ro)) = ro_iter.next_if(|(lh, _)| *lh == log_height) {
+                *folded_eval += ro;
+            }
+        }
+        let opened_rows = openings
+            .rows::<G::InputError>(&indices, log_arity, &folded_evals)?
+            .into_iter()
+            .map(|row| vec![row])
+            .collect_vec();
 
         let dims = &[Dimensions {
-            width: 2,
+            width: arity,
             height: 1 << log_folded_height,
         }];
         config
             .mmcs
-            .verify_batch(
+            .verify_multi_batch(
                 comm,
                 dims,
-                index_pair,
-                &[evals.clone()],
-                &opening.opening_proof,
+                &index_rows,
+                &opened_rows,
+                openings.opening_proof(),
             )
             .map_err(FriError::CommitPhaseMmcsError)?;
 
-        index = index_pair;
+        for (folded_eval, &index_row, mut evals) in
+            izip!(&mut folded_evals, &index_rows, opened_rows)
+        {
+            *folded_eval =
+                fold_row_by_arity(g, index_row, log_folded_height, beta, evals.pop().unwrap());
+        }
+        indices = index_rows;
+        log_height = log_folded_height;
+    }
 
-        folded_eval = g.fold_row(index, log_folded_height, beta, evals.into_iter());
+    for (folded_eval, ro_iter) in izip!(&mut folded_evals, &mut ro_iters) {
+        // The smallest input may be at the final height.
+        if let Some((_, ro)) = ro_iter.next_if(|(lh, _)| *lh == log_height) {
+            *folded_eval += ro;
+        }
+        debug_assert!(
+            ro_iter.next().is_none(),
+            "verifier reduced_openings were not in descending order?"
+        );
     }
+    debug_assert!(indices.iter().all(|&index| index < 1 << log_height));
 
-    debug_assert!(index < config.blowup(), "index was {}", index);
-    debug_assert!(
-        ro_iter.next().is_none(),
-        "verifier reduced_openings were not in descending order?"
-    );
+    Ok(izip!(indices, folded_evals).collect())
+}
 
-    Ok(folded_eval)
+/// Fold a row of `2^k` evaluations, by folding it in pairs `k` times with challenges `beta`,
+/// `beta^2`, `beta^4` and so on, as the prover folds the whole codeword.
+fn fold_row_by_arity<G, F>(
+    g: &G,
+    index: usize,
+    log_folded_height: usize,
+    beta: F,
+    mut evals: Vec<F>,
+) -> F
+where
+    F: Field,
+    G: FriGenericConfig<F>,
+{
+    let mut beta = beta;
+    while evals.len() > 1 {
+        let log_pairs = evals.len().ilog2() as usize - 1;
+        evals = evals
+            .chunks_exact(2)
+            .enumerate()
+            .map(|(i, pair)| {
+                g.fold_row(
+                    (index << log_pairs) + i,
+                    log_folded_height + log_pairs,
+                    beta,
+                    pair.iter().copied(),
+                )
+            })
+            .collect();
+        beta = beta.square();
+    }
+    evals[0]
 }
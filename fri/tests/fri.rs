@@ -1,56 +1,51 @@
 use core::cmp::Reverse;
 use std::marker::PhantomData;
 
-use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
-use p3_challenger::{CanSampleBits, DuplexChallenger, FieldChallenger};
-use p3_commit::ExtensionMmcs;
+use itertools::Itertools;
+use p3_challenger::{CanSampleBits, FieldChallenger};
 use p3_dft::{Radix2Dit, TwoAdicSubgroupDft};
-use p3_field::extension::BinomialExtensionField;
 use p3_field::{Field, FieldAlgebra};
-use p3_fri::{prover, verifier, FriConfig, TwoAdicFriGenericConfig};
+use p3_fri::{prover, verifier, FriConfig, TwoAdicFriGenericConfig, TwoAdicFriPcs};
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::util::reverse_matrix_index_bits;
 use p3_matrix::Matrix;
-use p3_merkle_tree::MerkleTreeMmcs;
-use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::testing::{
+    make_fri_config, make_mmcs, Challenge, ChallengeMmcs, Challenger, Perm, Val, ValMmcs,
+};
 use p3_util::log2_strict_usize;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha20Rng;
 
-type Val = BabyBear;
-type Challenge = BinomialExtensionField<Val, 4>;
-
-type Perm = Poseidon2BabyBear<16>;
-type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
-type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
-type ValMmcs =
-    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
-type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
-type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
 type MyFriConfig = FriConfig<ChallengeMmcs>;
 
-fn get_ldt_for_testing<R: Rng>(rng: &mut R) -> (Perm, MyFriConfig) {
+fn get_ldt_for_testing<R: Rng>(
+    rng: &mut R,
+    log_arity: usize,
+    log_final_poly_len: usize,
+) -> (Perm, MyFriConfig) {
     let perm = Perm::new_from_rng_128(rng);
-    let hash = MyHash::new(perm.clone());
-    let compress = MyCompress::new(perm.clone());
-    let mmcs = ChallengeMmcs::new(ValMmcs::new(hash, compress));
     let fri_config = FriConfig {
-        log_blowup: 1,
-        num_queries: 10,
-        proof_of_work_bits: 8,
-        mmcs,
+        log_arity,
+        log_final_poly_len,
+        ..make_fri_config(&perm, 1, 10, 8)
     };
     (perm, fri_config)
 }
 
-fn do_test_fri_ldt<R: Rng>(rng: &mut R) {
-    let (perm, fc) = get_ldt_for_testing(rng);
+fn do_test_fri_ldt<R: Rng>(
+    rng: &mut R,
+    log_arity: usize,
+    log_final_poly_len: usize,
+    log_degrees: &[usize],
+) {
+    let (perm, fc) = get_ldt_for_testing(rng, log_arity, log_final_poly_len);
     let dft = Radix2Dit::default();
 
     let shift = Val::GENERATOR;
 
-    let ldes: Vec<RowMajorMatrix<Val>> = (3..10)
-        .map(|deg_bits| {
+    let ldes: Vec<RowMajorMatrix<Val>> = log_degrees
+        .iter()
+        .map(|&deg_bits| {
             let evals = RowMajorMatrix::<Val>::rand_nonzero(rng, 1 << deg_bits, 16);
             let mut lde = dft.coset_lde_batch(evals, 1, shift);
             reverse_matrix_index_bits(&mut lde);
@@ -89,33 +84,44 @@ fn do_test_fri_ldt<R: Rng>(rng: &mut R) {
         let log_max_height = log2_strict_usize(input[0].len());
 
         let proof = prover::prove(
-            &TwoAdicFriGenericConfig::<Vec<(usize, Challenge)>, ()>(PhantomData),
+            &TwoAdicFriGenericConfig::<Vec<Vec<(usize, Challenge)>>, ()>(PhantomData),
             &fc,
             input.clone(),
             &mut chal,
-            |idx| {
+            |indices| {
                 // As our "input opening proof", just pass through the literal reduced openings.
-                let mut ro = vec![];
-                for v in &input {
-                    let log_height = log2_strict_usize(v.len());
-                    ro.push((log_height, v[idx >> (log_max_height - log_height)]));
-                }
-                ro.sort_by_key(|(lh, _)| Reverse(*lh));
-                ro
+                indices
+                    .iter()
+                    .map(|idx| {
+                        let mut ro = vec![];
+                        for v in &input {
+                            let log_height = log2_strict_usize(v.len());
+                            ro.push((log_height, v[idx >> (log_max_height - log_height)]));
+                        }
+                        ro.sort_by_key(|(lh, _)| Reverse(*lh));
+                        ro
+                    })
+                    .collect()
             },
         );
 
         (proof, chal.sample_bits(8))
     };
 
+    let log_heights = log_degrees
+        .iter()
+        .map(|&deg_bits| deg_bits + fc.log_blowup)
+        .sorted_by(|l, r| r.cmp(l))
+        .collect_vec();
     let mut v_challenger = Challenger::new(perm);
     let _alpha: Challenge = v_challenger.sample_ext_element();
     verifier::verify(
-        &TwoAdicFriGenericConfig::<Vec<(usize, Challenge)>, ()>(PhantomData),
+        &TwoAdicFriGenericConfig::<Vec<Vec<(usize, Challenge)>>, ()>(PhantomData),
         &fc,
+        &log_heights,
         &proof,
         &mut v_challenger,
-        |_index, proof| Ok(proof.clone()),
+        |_indices, proof| Ok(proof.clone()),
     )
     .unwrap();
 
@@ -131,6 +137,49 @@ fn test_fri_ldt() {
     // FRI is kind of flaky depending on indexing luck
     for i in 0..4 {
         let mut rng = ChaCha20Rng::seed_from_u64(i);
-        do_test_fri_ldt(&mut rng);
+        do_test_fri_ldt(&mut rng, 1, 0, &[3, 4, 5, 6, 7, 8, 9]);
+    }
+}
+
+#[test]
+fn test_fri_ldt_higher_arity() {
+    for i in 0..4 {
+        let mut rng = ChaCha20Rng::seed_from_u64(i);
+        // Folding by 8 has to stop short at the inputs of degrees 2^7 and 2^5.
+        do_test_fri_ldt(&mut rng, 3, 0, &[3, 5, 7, 10]);
+    }
+}
+
+#[test]
+fn test_fri_ldt_final_poly() {
+    for i in 0..4 {
+        let mut rng = ChaCha20Rng::seed_from_u64(i);
+        do_test_fri_ldt(&mut rng, 2, 3, &[5, 6, 9]);
+        // Folding stops at the smallest input, short of the final polynomial's length.
+        do_test_fri_ldt(&mut rng, 2, 4, &[3, 8]);
     }
 }
+
+#[test]
+fn test_commit_phase_log_arities() {
+    let (_, mut fc) = get_ldt_for_testing(&mut ChaCha20Rng::seed_from_u64(0), 3, 0);
+    // Heights include the blowup of 2, so the final polynomial defaults to a constant at height 1.
+    assert_eq!(fc.commit_phase_log_arities(&[11]), vec![3, 3, 3, 1]);
+    assert_eq!(fc.commit_phase_log_arities(&[11, 6, 4]), vec![3, 2, 2, 3]);
+    assert_eq!(fc.log_final_height(&[11, 6, 4]), 1);
+
+    fc.log_final_poly_len = 4;
+    assert_eq!(fc.commit_phase_log_arities(&[11]), vec![3, 3]);
+    assert_eq!(fc.log_final_height(&[11]), 5);
+    assert_eq!(fc.commit_phase_log_arities(&[11, 4, 1]), vec![3, 3, 1]);
+    assert_eq!(fc.log_final_height(&[11, 4, 1]), 4);
+    assert_eq!(fc.commit_phase_log_arities(&[3]), vec![]);
+}
+
+#[test]
+#[should_panic(expected = "FRI must fold by at least 2")]
+fn test_zero_arity_rejected() {
+    let (perm, fc) = get_ldt_for_testing(&mut ChaCha20Rng::seed_from_u64(0), 0, 0);
+    let mmcs: ValMmcs = make_mmcs(&perm);
+    TwoAdicFriPcs::<Val, Radix2Dit<Val>, _, _>::new(Radix2Dit::default(), mmcs, fc);
+}
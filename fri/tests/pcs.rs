@@ -1,14 +1,18 @@
 use itertools::{izip, Itertools};
-use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
-use p3_challenger::{CanObserve, DuplexChallenger, FieldChallenger};
-use p3_commit::{ExtensionMmcs, Pcs, PolynomialSpace};
+use p3_challenger::{CanObserve, FieldChallenger};
+use p3_commit::{
+    ConfigFingerprint, Pcs, PolynomialSpace, ProofFormatError, PROOF_FORMAT_VERSION, PROOF_MAGIC,
+};
 use p3_dft::Radix2DitParallel;
-use p3_field::extension::BinomialExtensionField;
-use p3_field::{ExtensionField, Field};
-use p3_fri::{FriConfig, TwoAdicFriPcs};
+use p3_field::{ExtensionField, Field, FieldAlgebra, TwoAdicField};
+#[allow(deprecated)]
+use p3_fri::ProofDecodingError;
+use p3_fri::{
+    CompressedTwoAdicFriProof, FriConfig, FriProof, FriVerifierInstance, LdePoints,
+    MultiBatchOpening, TwoAdicFriPcs, VerifierOpeningRound,
+};
 use p3_matrix::dense::RowMajorMatrix;
-use p3_merkle_tree::MerkleTreeMmcs;
-use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_util::reverse_bits_len;
 use rand::distributions::{Distribution, Standard};
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha20Rng;
@@ -151,36 +155,36 @@ macro_rules! make_tests_for_pcs {
 }
 
 mod babybear_fri_pcs {
-    use super::*;
-
-    type Val = BabyBear;
-    type Challenge = BinomialExtensionField<Val, 4>;
-
-    type Perm = Poseidon2BabyBear<16>;
-    type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
-    type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+    use p3_uni_stark::testing::{
+        make_fri_config, make_mmcs, Challenge, ChallengeMmcs, Challenger, Perm, Val, ValMmcs,
+    };
 
-    type ValMmcs =
-        MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
-    type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+    use super::*;
 
     type Dft = Radix2DitParallel<Val>;
-    type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
     type MyPcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
 
-    fn get_pcs(log_blowup: usize) -> (MyPcs, Challenger) {
-        let perm = Perm::new_from_rng_128(&mut seeded_rng());
-        let hash = MyHash::new(perm.clone());
-        let compress = MyCompress::new(perm.clone());
-
-        let val_mmcs = ValMmcs::new(hash, compress);
-        let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+    fn get_pcs(
+        log_blowup: usize,
+        log_arity: usize,
+        log_final_poly_len: usize,
+    ) -> (MyPcs, Challenger) {
+        get_pcs_with_cap_height(log_blowup, log_arity, log_final_poly_len, 0)
+    }
 
+    fn get_pcs_with_cap_height(
+        log_blowup: usize,
+        log_arity: usize,
+        log_final_poly_len: usize,
+        cap_height: usize,
+    ) -> (MyPcs, Challenger) {
+        let perm = Perm::new_from_rng_128(&mut seeded_rng());
+        let val_mmcs = make_mmcs(&perm).with_cap_height(cap_height);
         let fri_config = FriConfig {
-            log_blowup,
-            num_queries: 10,
-            proof_of_work_bits: 8,
-            mmcs: challenge_mmcs,
+            log_arity,
+            log_final_poly_len,
+            mmcs: ChallengeMmcs::new(val_mmcs.clone()),
+            ..make_fri_config(&perm, log_blowup, 10, 8)
         };
 
         let pcs = MyPcs::new(Dft::default(), val_mmcs, fri_config);
@@ -188,58 +192,292 @@ mod babybear_fri_pcs {
     }
 
     mod blowup_1 {
-        make_tests_for_pcs!(super::get_pcs(1));
+        make_tests_for_pcs!(super::get_pcs(1, 1, 0));
     }
+
     mod blowup_2 {
-        make_tests_for_pcs!(super::get_pcs(2));
+        make_tests_for_pcs!(super::get_pcs(2, 1, 0));
+    }
+    mod arity_4 {
+        make_tests_for_pcs!(super::get_pcs(1, 2, 0));
+    }
+    mod arity_16_final_poly {
+        make_tests_for_pcs!(super::get_pcs(2, 4, 3));
+    }
+    mod merkle_cap {
+        make_tests_for_pcs!(super::get_pcs_with_cap_height(1, 1, 0, 3));
     }
-}
 
-mod m31_fri_pcs {
-    use std::marker::PhantomData;
+    #[test]
+    fn lde_query_points() {
+        let lde_points = LdePoints::<Val>::new(8);
+        for index in [0, 1, 77, 200, 255] {
+            let points = lde_points.query_points(8, index);
+            for log_height in 0..=8 {
+                let reduced_index = index >> (8 - log_height);
+                let generator = Val::two_adic_generator(log_height);
+                let expected = Val::GENERATOR
+                    * generator.exp_u64(reverse_bits_len(reduced_index, log_height) as u64);
+                assert_eq!(lde_points.point(log_height, reduced_index), expected);
+                assert_eq!(points[log_height], expected);
+            }
+        }
+    }
 
-    use p3_challenger::{HashChallenger, SerializingChallenger32};
-    use p3_circle::CirclePcs;
-    use p3_keccak::Keccak256Hash;
-    use p3_mersenne_31::Mersenne31;
-    use p3_symmetric::{CompressionFunctionFromHasher, SerializingHasher32};
+    #[test]
+    fn verify_many() {
+        let (pcs, challenger) = get_pcs(1, 1, 0);
+        let mut rng = seeded_rng();
 
-    use super::*;
+        // Proofs of a few matrices each, with LDEs of different heights.
+        let instances = [vec![3, 5], vec![6], vec![4, 4, 2]]
+            .iter()
+            .map(|log_degrees| {
+                let domains_and_polys = log_degrees
+                    .iter()
+                    .map(|&log_degree| {
+                        let d = 1 << log_degree;
+                        (
+                            <MyPcs as Pcs<Challenge, Challenger>>::natural_domain_for_degree(
+                                &pcs, d,
+                            ),
+                            RowMajorMatrix::<Val>::rand(&mut rng, d, 4),
+                        )
+                    })
+                    .collect_vec();
+                let (commit, data) =
+                    <MyPcs as Pcs<Challenge, Challenger>>::commit(&pcs, domains_and_polys.clone());
+
+                let mut p_challenger = challenger.clone();
+                p_challenger.observe(commit.clone());
+                let zeta: Challenge = p_challenger.sample_ext_element();
+                let points = vec![vec![zeta]; log_degrees.len()];
+                let (opened_values, proof) = pcs.open(vec![(&data, points)], &mut p_challenger);
+
+                let claims = izip!(&domains_and_polys, &opened_values[0])
+                    .map(|((domain, _), values)| (*domain, vec![(zeta, values[0].clone())]))
+                    .collect_vec();
+                let mut v_challenger = challenger.clone();
+                v_challenger.observe(commit.clone());
+                let _zeta: Challenge = v_challenger.sample_ext_element();
+                (vec![(commit, claims)], proof, v_challenger)
+            })
+            .collect_vec();
+
+        let mut to_verify = instances
+            .iter()
+            .map(|(rounds, proof, challenger)| FriVerifierInstance {
+                rounds: rounds.clone(),
+                proof,
+                challenger: challenger.clone(),
+            })
+            .collect_vec();
+        // Claim a wrong value for the second proof.
+        to_verify[1].rounds[0].1[0].1[0].1[0] += Challenge::ONE;
+        let results = pcs.verify_many(to_verify);
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+        for ((rounds, proof, challenger), result) in izip!(instances, results) {
+            if result.is_ok() {
+                pcs.verify(rounds, &proof, &mut challenger.clone()).unwrap();
+            }
+        }
+    }
 
-    type Val = Mersenne31;
-    type Challenge = BinomialExtensionField<Mersenne31, 3>;
+    /// An opening proof of two small matrices, so that some queries repeat, with the rounds and
+    /// challenger to verify it with.
+    fn small_proof(
+        pcs: &MyPcs,
+        challenger: &Challenger,
+    ) -> (
+        Vec<VerifierOpeningRound<Val, Challenge, ValMmcs>>,
+        <MyPcs as Pcs<Challenge, Challenger>>::Proof,
+        Challenger,
+    ) {
+        let mut rng = seeded_rng();
+
+        let domains_and_polys = [4, 2]
+            .iter()
+            .map(|&log_degree| {
+                let d = 1 << log_degree;
+                (
+                    <MyPcs as Pcs<Challenge, Challenger>>::natural_domain_for_degree(pcs, d),
+                    RowMajorMatrix::<Val>::rand(&mut rng, d, 6),
+                )
+            })
+            .collect_vec();
+        let (commit, data) =
+            <MyPcs as Pcs<Challenge, Challenger>>::commit(pcs, domains_and_polys.clone());
+
+        let mut p_challenger = challenger.clone();
+        p_challenger.observe(commit.clone());
+        let zeta: Challenge = p_challenger.sample_ext_element();
+        let (opened_values, proof) =
+            pcs.open(vec![(&data, vec![vec![zeta]; 2])], &mut p_challenger);
+
+        let rounds = vec![(
+            commit.clone(),
+            izip!(&domains_and_polys, &opened_values[0])
+                .map(|((domain, _), values)| (*domain, vec![(zeta, values[0].clone())]))
+                .collect_vec(),
+        )];
+        let mut v_challenger = challenger.clone();
+        v_challenger.observe(commit);
+        let _zeta: Challenge = v_challenger.sample_ext_element();
+
+        (rounds, proof, v_challenger)
+    }
 
-    type ByteHash = Keccak256Hash;
-    type FieldHash = SerializingHasher32<ByteHash>;
+    #[test]
+    fn tampered_proof() {
+        let (pcs, challenger) = get_pcs(1, 1, 0);
+        let (rounds, proof, v_challenger) = small_proof(&pcs, &challenger);
+        let verify = |proof: &<MyPcs as Pcs<Challenge, Challenger>>::Proof| {
+            pcs.verify(rounds.clone(), proof, &mut v_challenger.clone())
+        };
+        verify(&proof).unwrap();
 
-    type MyCompress = CompressionFunctionFromHasher<ByteHash, 2, 32>;
+        // Tampering with any part of the proof is reported as an error, rather than a panic.
+        let mut wrong_final_poly = proof.clone();
+        wrong_final_poly.final_poly[0] += Challenge::ONE;
+        assert!(verify(&wrong_final_poly).is_err());
 
-    type ValMmcs = MerkleTreeMmcs<Val, u8, FieldHash, MyCompress, 32>;
+        let mut long_final_poly = proof.clone();
+        long_final_poly.final_poly.push(Challenge::ZERO);
+        assert!(verify(&long_final_poly).is_err());
 
-    type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+        let mut wrong_pow_witness = proof.clone();
+        wrong_pow_witness.pow_witness += Val::ONE;
+        assert!(verify(&wrong_pow_witness).is_err());
 
-    type Challenger = SerializingChallenger32<Val, HashChallenger<u8, ByteHash, 32>>;
+        let mut missing_commit = proof.clone();
+        missing_commit.commit_phase_commits.pop();
+        assert!(verify(&missing_commit).is_err());
 
-    type Pcs = CirclePcs<Val, ValMmcs, ChallengeMmcs>;
+        let mut missing_openings = proof;
+        missing_openings.commit_phase_openings.pop();
+        assert!(verify(&missing_openings).is_err());
+    }
 
-    fn get_pcs(log_blowup: usize) -> (Pcs, Challenger) {
-        let byte_hash = ByteHash {};
-        let field_hash = FieldHash::new(byte_hash);
-        let compress = MyCompress::new(byte_hash);
-        let val_mmcs = ValMmcs::new(field_hash, compress);
-        let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
-        let fri_config = FriConfig {
-            log_blowup,
-            num_queries: 10,
-            proof_of_work_bits: 8,
-            mmcs: challenge_mmcs,
-        };
-        let pcs = Pcs {
-            mmcs: val_mmcs,
-            fri_config,
-            _phantom: PhantomData,
-        };
-        (pcs, Challenger::from_hasher(vec![], byte_hash))
+    #[test]
+    fn compressed_proof() {
+        let (pcs, challenger) = get_pcs(1, 2, 0);
+        let (rounds, proof, v_challenger) = small_proof(&pcs, &challenger);
+
+        let compressed = pcs
+            .compress_proof(&rounds, &proof, &mut v_challenger.clone())
+            .unwrap();
+        pcs.verify(rounds.clone(), &proof, &mut v_challenger.clone())
+            .unwrap();
+        pcs.verify_compressed(rounds.clone(), &compressed, &mut v_challenger.clone())
+            .unwrap();
+
+        let fingerprint = pcs.fingerprint::<Challenge>();
+        let bytes = compressed.to_bytes(&fingerprint).unwrap();
+        assert!(bytes.len() < proof.to_bytes(&fingerprint).unwrap().len());
+        let decoded = CompressedTwoAdicFriProof::from_bytes(&fingerprint, &bytes).unwrap();
+        assert_eq!(decoded.to_bytes(&fingerprint).unwrap(), bytes);
+        pcs.verify_compressed(rounds.clone(), &decoded, &mut v_challenger.clone())
+            .unwrap();
+
+        // The unfingerprinted encoding is still readable.
+        #[allow(deprecated)]
+        {
+            let legacy_bytes = compressed.to_legacy_bytes().unwrap();
+            let decoded: CompressedTwoAdicFriProof<Val, Challenge, ValMmcs, ChallengeMmcs> =
+                CompressedTwoAdicFriProof::from_legacy_bytes(&legacy_bytes).unwrap();
+            assert_eq!(decoded.to_bytes(&fingerprint).unwrap(), bytes);
+
+            let mut wrong_version = legacy_bytes;
+            wrong_version[0] += 1;
+            assert!(matches!(
+                CompressedTwoAdicFriProof::<Val, Challenge, ValMmcs, ChallengeMmcs>::from_legacy_bytes(
+                    &wrong_version
+                ),
+                Err(ProofDecodingError::UnsupportedVersion(_))
+            ));
+        }
+
+        // A wrong claimed value is rejected in either format.
+        let mut wrong_rounds = rounds;
+        wrong_rounds[0].1[0].1[0].1[0] += Challenge::ONE;
+        assert!(pcs
+            .verify(wrong_rounds.clone(), &proof, &mut v_challenger.clone())
+            .is_err());
+        assert!(pcs
+            .verify_compressed(wrong_rounds, &compressed, &mut v_challenger)
+            .is_err());
+    }
+
+    #[test]
+    fn proof_encoding() {
+        type Proof = FriProof<Challenge, ChallengeMmcs, Val, Vec<MultiBatchOpening<Val, ValMmcs>>>;
+
+        let (pcs, challenger) = get_pcs(1, 1, 0);
+        let (rounds, proof, mut v_challenger) = small_proof(&pcs, &challenger);
+        let fingerprint = pcs.fingerprint::<Challenge>();
+        let bytes = proof.to_bytes(&fingerprint).unwrap();
+        assert_eq!(bytes[..PROOF_MAGIC.len()], PROOF_MAGIC);
+
+        let decoded = Proof::from_bytes(&fingerprint, &bytes).unwrap();
+        assert_eq!(decoded.to_bytes(&fingerprint).unwrap(), bytes);
+        pcs.verify(rounds, &decoded, &mut v_challenger).unwrap();
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] ^= 1;
+        assert!(matches!(
+            Proof::from_bytes(&fingerprint, &bad_magic),
+            Err(ProofFormatError::BadMagic)
+        ));
+
+        let mut bad_version = bytes.clone();
+        bad_version[PROOF_MAGIC.len()] = PROOF_FORMAT_VERSION + 1;
+        assert!(matches!(
+            Proof::from_bytes(&fingerprint, &bad_version),
+            Err(ProofFormatError::UnsupportedVersion(v)) if v == PROOF_FORMAT_VERSION + 1
+        ));
+
+        // A proof is rejected under a config with another challenge field, more queries, or
+        // another Merkle cap height.
+        let other_field = ConfigFingerprint::new::<Val, Val>(
+            fingerprint.parameters.clone(),
+            fingerprint.commitment_scheme.clone(),
+        );
+        let mut more_queries = fingerprint.clone();
+        more_queries.parameters[3] += 1;
+        let other_cap_height = get_pcs_with_cap_height(1, 1, 0, 3)
+            .0
+            .fingerprint::<Challenge>();
+        for other in [other_field, more_queries, other_cap_height] {
+            assert!(matches!(
+                Proof::from_bytes(&other, &bytes),
+                Err(ProofFormatError::ConfigMismatch { .. })
+            ));
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(
+            Proof::from_bytes(&fingerprint, &trailing),
+            Err(ProofFormatError::TrailingBytes)
+        ));
+        assert!(matches!(
+            Proof::from_bytes(&fingerprint, &bytes[..bytes.len() - 1]),
+            Err(ProofFormatError::Postcard(_))
+        ));
+    }
+}
+
+mod m31_fri_pcs {
+    use p3_uni_stark::testing::circle::{self, make_challenger, make_pcs, Challenger};
+
+    use super::*;
+
+    fn get_pcs(log_blowup: usize) -> (circle::Pcs, Challenger) {
+        (make_pcs(log_blowup, 10), make_challenger())
     }
 
     mod blowup_1 {
@@ -475,7 +475,9 @@ pub(crate) const fn to_goldilocks_array<const N: usize>(input: [u64; N]) -> [Gol
 
 #[cfg(test)]
 mod tests {
-    use p3_field_testing::{test_field, test_field_dft, test_two_adic_field};
+    use p3_field_testing::{
+        test_field, test_field_dft, test_field_subgroup_dft, test_two_adic_field,
+    };
 
     use super::*;
 
@@ -573,4 +575,18 @@ mod tests {
         crate::Goldilocks,
         p3_dft::Radix2DitParallel<crate::Goldilocks>
     );
+
+    test_field_subgroup_dft!(
+        mixed_radix,
+        crate::Goldilocks,
+        p3_dft::MixedRadixDft,
+        [1, 2, 3, 5, 6, 15, 17, 30, 32, 51, 85, 120]
+    );
+    // Orders with the prime factors 17 and 257 use Bluestein's algorithm.
+    test_field_subgroup_dft!(
+        bluestein,
+        crate::Goldilocks,
+        p3_dft::BluesteinDft<p3_dft::Radix2Dit<_>>,
+        [1, 15, 17, 34, 51, 85, 255, 257]
+    );
 }
@@ -18,6 +18,10 @@ impl<F> BaseAir<F> for KeccakAir {
     fn width(&self) -> usize {
         NUM_KECCAK_COLS
     }
+
+    fn compile_constraints(&self) -> bool {
+        true
+    }
 }
 
 impl<AB: AirBuilder> Air<AB> for KeccakAir {
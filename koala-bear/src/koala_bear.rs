@@ -103,7 +103,9 @@ impl BinomialExtensionData<4> for KoalaBearParameters {
 #[cfg(test)]
 mod tests {
     use p3_field::{PrimeField32, PrimeField64, TwoAdicField};
-    use p3_field_testing::{test_field, test_field_dft, test_two_adic_field};
+    use p3_field_testing::{
+        test_field, test_field_dft, test_field_subgroup_dft, test_two_adic_field,
+    };
 
     use super::*;
 
@@ -220,4 +222,18 @@ mod tests {
         crate::KoalaBear,
         p3_monty_31::dft::RecursiveDft<_>
     );
+
+    // The multiplicative group has order `2^24 * 127`.
+    test_field_subgroup_dft!(
+        mixed_radix,
+        crate::KoalaBear,
+        p3_dft::MixedRadixDft,
+        [1, 2, 8, 127, 254, 1016]
+    );
+    test_field_subgroup_dft!(
+        bluestein,
+        crate::KoalaBear,
+        p3_dft::BluesteinDft<p3_monty_31::dft::RecursiveDft<crate::KoalaBear>>,
+        [1, 2, 8, 127, 254, 1016]
+    );
 }
@@ -0,0 +1,13 @@
+//! Lookup arguments for AIRs, based on LogUp.
+
+#![no_std]
+
+extern crate alloc;
+
+mod logup;
+mod lookup;
+mod range_check;
+
+pub use logup::*;
+pub use lookup::*;
+pub use range_check::*;
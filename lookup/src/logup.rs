@@ -0,0 +1,217 @@
+use alloc::vec::Vec;
+
+use itertools::Itertools;
+use p3_air::{ExtensionBuilder, MultiTableAirBuilder, PairBuilder};
+use p3_field::{batch_multiplicative_inverse, ExtensionField, Field, FieldAlgebra};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+
+use crate::Lookup;
+
+/// A LogUp argument over a set of lookups within an AIR.
+///
+/// With challenges `alpha` and `beta`, each tuple `v` on bus `b` is fingerprinted as
+/// `alpha - (b + sum_i beta^(i + 1) v_i)`, and the argument checks that
+/// `sum_{looking} m / fingerprint = sum_{table} m / fingerprint` over all rows.
+///
+/// This occupies the first [`width`](Self::width) columns of the permutation trace and the first
+/// [`NUM_CHALLENGES`](Self::NUM_CHALLENGES) permutation challenges. There is one column per lookup,
+/// holding `m / fingerprint` for that row, followed by a running sum of these (signed) terms which
+/// must end at the table's [cumulative sum](MultiTableAirBuilder::cumulative_sum). That is zero
+/// when the AIR is proven on its own, while in a batch proof the sums of all tables must cancel.
+///
+/// An AIR using this argument must return [`running_sum_column`](Self::running_sum_column) from
+/// `BaseAir::cumulative_sum_column`, which the default only does if the permutation trace has no
+/// columns after this argument's.
+#[derive(Clone, Debug)]
+pub struct LogUp<F: Field> {
+    lookups: Vec<Lookup<F>>,
+}
+
+impl<F: Field> LogUp<F> {
+    pub const NUM_CHALLENGES: usize = 2;
+
+    pub fn new(lookups: Vec<Lookup<F>>) -> Self {
+        assert!(!lookups.is_empty(), "LogUp needs at least one lookup");
+        Self { lookups }
+    }
+
+    pub fn lookups(&self) -> &[Lookup<F>] {
+        &self.lookups
+    }
+
+    /// The number of (extension field) permutation trace columns used by this argument.
+    pub fn width(&self) -> usize {
+        self.lookups.len() + 1
+    }
+
+    /// Index of the running sum column within the permutation trace.
+    pub fn running_sum_column(&self) -> usize {
+        self.lookups.len()
+    }
+
+    /// Generate this argument's permutation trace columns.
+    pub fn generate_trace<EF: ExtensionField<F>>(
+        &self,
+        preprocessed: Option<&RowMajorMatrix<F>>,
+        main: &RowMajorMatrix<F>,
+        challenges: &[EF],
+    ) -> RowMajorMatrix<EF> {
+        let (alpha, beta) = (challenges[0], challenges[1]);
+        let height = main.height();
+        let num_lookups = self.lookups.len();
+
+        let mut denominators = Vec::with_capacity(height * num_lookups);
+        let mut multiplicities = Vec::with_capacity(height * num_lookups);
+        for i in 0..height {
+            let preprocessed_row = preprocessed.map_or_else(Vec::new, |p| p.row_slice(i).to_vec());
+            let main_row = main.row_slice(i);
+            for lookup in &self.lookups {
+                let values = lookup
+                    .values
+                    .iter()
+                    .map(|v| v.apply::<F, F>(&preprocessed_row, &main_row));
+                denominators.push(alpha - fingerprint(beta, lookup.bus, values));
+                multiplicities.push(
+                    lookup
+                        .multiplicity
+                        .apply::<F, F>(&preprocessed_row, &main_row),
+                );
+            }
+        }
+        let inverses = batch_multiplicative_inverse(&denominators);
+
+        let mut values = Vec::with_capacity(height * self.width());
+        let mut running_sum = EF::ZERO;
+        for (row_inverses, row_multiplicities) in inverses
+            .chunks_exact(num_lookups)
+            .zip(multiplicities.chunks_exact(num_lookups))
+        {
+            for ((&inverse, &multiplicity), lookup) in row_inverses
+                .iter()
+                .zip(row_multiplicities)
+                .zip(&self.lookups)
+            {
+                let term = inverse * multiplicity;
+                running_sum += lookup.sign(term);
+                values.push(term);
+            }
+            values.push(running_sum);
+        }
+        RowMajorMatrix::new(values, self.width())
+    }
+
+    /// Evaluate this argument's constraints.
+    pub fn eval<AB>(&self, builder: &mut AB)
+    where
+        AB: MultiTableAirBuilder<F = F> + PairBuilder,
+    {
+        let (alpha, beta): (AB::ExprEF, AB::ExprEF) = {
+            let challenges = builder.permutation_randomness();
+            (challenges[0].into(), challenges[1].into())
+        };
+
+        let preprocessed = builder.preprocessed();
+        let preprocessed_local = preprocessed.row_slice(0);
+        let main = builder.main();
+        let main_local = main.row_slice(0);
+        let permutation = builder.permutation();
+        let (permutation_local, permutation_next) =
+            (permutation.row_slice(0), permutation.row_slice(1));
+
+        let mut sum_local = AB::ExprEF::ZERO;
+        let mut sum_next = AB::ExprEF::ZERO;
+        for (k, lookup) in self.lookups.iter().enumerate() {
+            let values = lookup
+                .values
+                .iter()
+                .map(|v| v.apply::<AB::Expr, AB::Var>(&preprocessed_local, &main_local))
+                .collect_vec();
+            let multiplicity: AB::Expr =
+                lookup.multiplicity.apply(&preprocessed_local, &main_local);
+
+            let term_local: AB::ExprEF = permutation_local[k].into();
+            let term_next: AB::ExprEF = permutation_next[k].into();
+
+            // term * (alpha - fingerprint) = multiplicity
+            let denominator =
+                alpha.clone() - fingerprint(beta.clone(), lookup.bus, values.into_iter());
+            builder.label("logup term");
+            builder.assert_eq_ext(
+                term_local.clone() * denominator,
+                AB::ExprEF::from(multiplicity),
+            );
+
+            sum_local += lookup.sign(term_local);
+            sum_next += lookup.sign(term_next);
+        }
+
+        let running_sum_local: AB::ExprEF = permutation_local[self.running_sum_column()].into();
+        let running_sum_next: AB::ExprEF = permutation_next[self.running_sum_column()].into();
+
+        builder.label("logup running sum");
+        builder
+            .when_first_row()
+            .assert_eq_ext(running_sum_local.clone(), sum_local);
+        builder
+            .when_transition()
+            .assert_eq_ext(running_sum_next - running_sum_local.clone(), sum_next);
+        let cumulative_sum: AB::ExprEF = builder.cumulative_sum().into();
+        builder
+            .when_last_row()
+            .assert_eq_ext(running_sum_local, cumulative_sum);
+    }
+}
+
+/// Compute `bus + sum_i beta^(i + 1) v_i`.
+fn fingerprint<T, V>(beta: T, bus: usize, values: impl DoubleEndedIterator<Item = V>) -> T
+where
+    T: FieldAlgebra + From<V>,
+{
+    values
+        .rev()
+        .fold(T::ZERO, |acc, v| (acc + T::from(v)) * beta.clone())
+        + T::from_canonical_usize(bus)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use p3_air::BaseAir;
+    use p3_baby_bear::BabyBear;
+    use p3_field::extension::BinomialExtensionField;
+    use p3_field::FieldAlgebra;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_matrix::Matrix;
+    use rand::random;
+
+    use crate::RangeCheckAir;
+
+    type F = BabyBear;
+    type EF = BinomialExtensionField<F, 4>;
+
+    fn final_running_sum(air: &RangeCheckAir, main: &RowMajorMatrix<F>) -> EF {
+        let logup = air.logup::<F>();
+        let challenges = [random::<EF>(), random::<EF>()];
+        let preprocessed = BaseAir::<F>::preprocessed_trace(air).unwrap();
+        let trace = logup.generate_trace(Some(&preprocessed), main, &challenges);
+        trace.get(trace.height() - 1, logup.running_sum_column())
+    }
+
+    #[test]
+    fn running_sum_ends_at_zero() {
+        let air = RangeCheckAir::byte(2);
+        let values = (0..300).map(|i| (i * 7) % 256).collect::<Vec<_>>();
+        let main = air.generate_trace::<F>(&values);
+        assert_eq!(final_running_sum(&air, &main), EF::ZERO);
+    }
+
+    #[test]
+    fn tampered_multiplicity_unbalances_sum() {
+        let air = RangeCheckAir::byte(1);
+        let mut main = air.generate_trace::<F>(&[1, 2, 3]);
+        main.row_mut(5)[air.multiplicity_column()] += F::ONE;
+        assert_ne!(final_running_sum(&air, &main), EF::ZERO);
+    }
+}
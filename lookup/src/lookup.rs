@@ -0,0 +1,63 @@
+use alloc::vec::Vec;
+
+use p3_air::VirtualPairCol;
+use p3_field::{Field, FieldAlgebra};
+
+/// Which side of a lookup argument a [`Lookup`] is on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LookupKind {
+    /// Tuples which must appear in some table.
+    Looking,
+    /// Entries of a table, each with the number of times it is looked up.
+    Table,
+}
+
+/// A tuple of virtual columns taking part in a lookup argument, along with a multiplicity.
+///
+/// On each row, the tuple `values` is counted `multiplicity` times, either as being looked up or
+/// as being provided by a table, depending on `kind`. The argument is satisfied when, for every
+/// bus and tuple, the total looking count equals the total table count.
+///
+/// Lookups on different buses never match each other. Buses can span several tables of a batch
+/// proof, in which case a looking lookup sends tuples to whichever table receives them.
+#[derive(Clone, Debug)]
+pub struct Lookup<F: Field> {
+    pub values: Vec<VirtualPairCol<F>>,
+    pub multiplicity: VirtualPairCol<F>,
+    pub kind: LookupKind,
+    pub bus: usize,
+}
+
+impl<F: Field> Lookup<F> {
+    pub const fn looking(values: Vec<VirtualPairCol<F>>, multiplicity: VirtualPairCol<F>) -> Self {
+        Self {
+            values,
+            multiplicity,
+            kind: LookupKind::Looking,
+            bus: 0,
+        }
+    }
+
+    pub const fn table(values: Vec<VirtualPairCol<F>>, multiplicity: VirtualPairCol<F>) -> Self {
+        Self {
+            values,
+            multiplicity,
+            kind: LookupKind::Table,
+            bus: 0,
+        }
+    }
+
+    /// Move this lookup to the given bus; lookups are on bus 0 by default.
+    pub fn on_bus(mut self, bus: usize) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    /// The sign with which this lookup's terms enter the running sum.
+    pub(crate) fn sign<T: FieldAlgebra>(&self, x: T) -> T {
+        match self.kind {
+            LookupKind::Looking => x,
+            LookupKind::Table => -x,
+        }
+    }
+}
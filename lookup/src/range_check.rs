@@ -0,0 +1,156 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use p3_air::{Air, BaseAir, MultiTableAirBuilder, PairBuilder, PairCol, VirtualPairCol};
+use p3_field::{ExtensionField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+
+use crate::{LogUp, Lookup};
+
+/// An AIR which checks that every value in its `num_values` main columns lies in `0..2^bits`, by
+/// looking them up in a preprocessed table of all such values.
+///
+/// The trace has `2^bits` rows. The main trace holds the `num_values` value columns followed by a
+/// multiplicity column, counting how many times the corresponding table entry is looked up.
+///
+/// In a batch proof, other tables can also send values to be range checked over this AIR's bus,
+/// in which case `num_values` may be zero.
+#[derive(Clone, Copy, Debug)]
+pub struct RangeCheckAir {
+    bits: usize,
+    num_values: usize,
+    bus: usize,
+}
+
+impl RangeCheckAir {
+    pub const fn new(bits: usize, num_values: usize) -> Self {
+        Self {
+            bits,
+            num_values,
+            bus: 0,
+        }
+    }
+
+    /// Look up values on the given bus; the default is bus 0.
+    pub const fn with_bus(self, bus: usize) -> Self {
+        Self { bus, ..self }
+    }
+
+    /// Range checks values to `0..2^8`.
+    pub const fn byte(num_values: usize) -> Self {
+        Self::new(8, num_values)
+    }
+
+    /// Range checks values to `0..2^16`.
+    pub const fn u16(num_values: usize) -> Self {
+        Self::new(16, num_values)
+    }
+
+    pub const fn height(&self) -> usize {
+        1 << self.bits
+    }
+
+    pub const fn multiplicity_column(&self) -> usize {
+        self.num_values
+    }
+
+    pub fn logup<F: Field>(&self) -> LogUp<F> {
+        let mut lookups = (0..self.num_values)
+            .map(|i| {
+                Lookup::looking(vec![VirtualPairCol::single_main(i)], VirtualPairCol::ONE)
+                    .on_bus(self.bus)
+            })
+            .collect::<Vec<_>>();
+        lookups.push(
+            Lookup::table(
+                vec![VirtualPairCol::single(PairCol::Preprocessed(0))],
+                VirtualPairCol::single_main(self.multiplicity_column()),
+            )
+            .on_bus(self.bus),
+        );
+        LogUp::new(lookups)
+    }
+
+    /// Generate a trace checking the given values, padded with zeros.
+    ///
+    /// Panics if there are more than `num_values * 2^bits` values, or any is out of range.
+    pub fn generate_trace<F: Field>(&self, values: &[u32]) -> RowMajorMatrix<F> {
+        self.generate_trace_with_external(values, &[])
+    }
+
+    /// Like [`generate_trace`](Self::generate_trace), but the multiplicities also count `external`
+    /// values, which other tables send to this one over its bus.
+    pub fn generate_trace_with_external<F: Field>(
+        &self,
+        values: &[u32],
+        external: &[u32],
+    ) -> RowMajorMatrix<F> {
+        let height = self.height();
+        let width = self.num_values + 1;
+        assert!(
+            values.len() <= self.num_values * height,
+            "too many values to range check"
+        );
+
+        let mut multiplicities = vec![0u32; height];
+        let mut trace = RowMajorMatrix::new(F::zero_vec(width * height), width);
+        for (i, row) in trace.rows_mut().enumerate() {
+            for (j, cell) in row[..self.num_values].iter_mut().enumerate() {
+                let value = values.get(i * self.num_values + j).copied().unwrap_or(0);
+                assert!((value as usize) < height, "value {value} out of range");
+                multiplicities[value as usize] += 1;
+                *cell = F::from_canonical_u32(value);
+            }
+        }
+        for &value in external {
+            assert!((value as usize) < height, "value {value} out of range");
+            multiplicities[value as usize] += 1;
+        }
+        for (row, multiplicity) in trace.rows_mut().zip(multiplicities) {
+            row[self.num_values] = F::from_canonical_u32(multiplicity);
+        }
+        trace
+    }
+}
+
+impl<F: Field> BaseAir<F> for RangeCheckAir {
+    fn width(&self) -> usize {
+        self.num_values + 1
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        Some(RowMajorMatrix::new_col(
+            (0..self.height()).map(F::from_canonical_usize).collect(),
+        ))
+    }
+
+    fn permutation_width(&self) -> usize {
+        self.logup::<F>().width()
+    }
+
+    fn num_permutation_challenges(&self) -> usize {
+        LogUp::<F>::NUM_CHALLENGES
+    }
+
+    fn cumulative_sum_column(&self) -> usize {
+        self.logup::<F>().running_sum_column()
+    }
+
+    fn generate_permutation_trace<EF>(
+        &self,
+        preprocessed: Option<&RowMajorMatrix<F>>,
+        main: &RowMajorMatrix<F>,
+        challenges: &[EF],
+    ) -> Option<RowMajorMatrix<EF>>
+    where
+        EF: ExtensionField<F>,
+    {
+        Some(self.logup().generate_trace(preprocessed, main, challenges))
+    }
+}
+
+impl<AB: MultiTableAirBuilder + PairBuilder> Air<AB> for RangeCheckAir {
+    fn eval(&self, builder: &mut AB) {
+        self.logup::<AB::F>().eval(builder);
+    }
+}
@@ -0,0 +1,202 @@
+use p3_air::{
+    Air, BaseAir, ExtensionBuilder, MultiTableAirBuilder, PairBuilder, PermutationAirBuilder,
+    VirtualPairCol,
+};
+use p3_field::{ExtensionField, Field, FieldAlgebra};
+use p3_lookup::{LogUp, Lookup, RangeCheckAir};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_uni_stark::testing::{make_config, Challenger, Val};
+#[cfg(debug_assertions)]
+use p3_uni_stark::ProverError;
+#[cfg(not(debug_assertions))]
+use p3_uni_stark::VerificationError;
+use p3_uni_stark::{prove_batch, verify_batch};
+use rand::{thread_rng, Rng};
+
+/// A table with a single main column, each of whose values is sent over bus 0 to be range checked
+/// by another table.
+pub struct SenderAir;
+
+impl SenderAir {
+    fn logup<F: Field>(&self) -> LogUp<F> {
+        LogUp::new(vec![Lookup::looking(
+            vec![VirtualPairCol::single_main(0)],
+            VirtualPairCol::ONE,
+        )])
+    }
+}
+
+/// The tables of our test machine.
+pub enum Chip {
+    Sender(SenderAir),
+    /// A sender whose permutation trace has one more column after the LogUp columns, holding the
+    /// first permutation challenge.
+    WideSender(SenderAir),
+    Range(RangeCheckAir),
+}
+
+impl<F: Field> BaseAir<F> for Chip {
+    fn width(&self) -> usize {
+        match self {
+            Self::Sender(_) | Self::WideSender(_) => 1,
+            Self::Range(air) => BaseAir::<F>::width(air),
+        }
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        match self {
+            Self::Sender(_) | Self::WideSender(_) => None,
+            Self::Range(air) => air.preprocessed_trace(),
+        }
+    }
+
+    fn permutation_width(&self) -> usize {
+        match self {
+            Self::Sender(air) => air.logup::<F>().width(),
+            Self::WideSender(air) => air.logup::<F>().width() + 1,
+            Self::Range(air) => BaseAir::<F>::permutation_width(air),
+        }
+    }
+
+    fn num_permutation_challenges(&self) -> usize {
+        LogUp::<F>::NUM_CHALLENGES
+    }
+
+    fn cumulative_sum_column(&self) -> usize {
+        match self {
+            Self::Sender(air) | Self::WideSender(air) => air.logup::<F>().running_sum_column(),
+            Self::Range(air) => BaseAir::<F>::cumulative_sum_column(air),
+        }
+    }
+
+    fn generate_permutation_trace<EF>(
+        &self,
+        preprocessed: Option<&RowMajorMatrix<F>>,
+        main: &RowMajorMatrix<F>,
+        challenges: &[EF],
+    ) -> Option<RowMajorMatrix<EF>>
+    where
+        EF: ExtensionField<F>,
+    {
+        match self {
+            Self::Sender(air) => Some(air.logup().generate_trace(preprocessed, main, challenges)),
+            Self::WideSender(air) => {
+                let logup = air.logup::<F>();
+                let trace = logup.generate_trace(preprocessed, main, challenges);
+                let values = trace
+                    .values
+                    .chunks_exact(logup.width())
+                    .flat_map(|row| row.iter().copied().chain([challenges[0]]))
+                    .collect();
+                Some(RowMajorMatrix::new(values, logup.width() + 1))
+            }
+            Self::Range(air) => air.generate_permutation_trace(preprocessed, main, challenges),
+        }
+    }
+}
+
+impl<AB: MultiTableAirBuilder + PairBuilder> Air<AB> for Chip {
+    fn eval(&self, builder: &mut AB) {
+        match self {
+            Self::Sender(air) => air.logup::<AB::F>().eval(builder),
+            Self::WideSender(air) => {
+                let logup = air.logup::<AB::F>();
+                logup.eval(builder);
+                let challenge: AB::ExprEF = builder.permutation_randomness()[0].into();
+                let extra: AB::ExprEF = builder.permutation().row_slice(0)[logup.width()].into();
+                builder.assert_eq_ext(extra, challenge);
+            }
+            Self::Range(air) => air.eval(builder),
+        }
+    }
+}
+
+/// Generate traces for a sender of height `2^log_height` and a byte range check table receiving
+/// its values. If `dropped` is set, the range check table doesn't count one of the sent values.
+fn generate_traces(
+    range_check: &RangeCheckAir,
+    log_height: usize,
+    dropped: bool,
+) -> Vec<RowMajorMatrix<Val>> {
+    let mut rng = thread_rng();
+    let values = (0..1 << log_height)
+        .map(|_| rng.gen_range(0..256))
+        .collect::<Vec<u32>>();
+    let sender =
+        RowMajorMatrix::new_col(values.iter().map(|&v| Val::from_canonical_u32(v)).collect());
+    let received = if dropped { &values[1..] } else { &values[..] };
+    let range = range_check.generate_trace_with_external(&[], received);
+    vec![sender, range]
+}
+
+#[test]
+fn test_balanced_bus() {
+    let (config, perm) = make_config(1);
+    let range_check = RangeCheckAir::byte(0);
+    let chips = [Chip::Sender(SenderAir), Chip::Range(range_check)];
+    let traces = generate_traces(&range_check, 4, false);
+    let public_values = vec![vec![]; chips.len()];
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove_batch(&config, &chips, &mut challenger, traces, &public_values)
+        .expect("proving failed");
+
+    let mut challenger = Challenger::new(perm);
+    verify_batch(&config, &chips, &mut challenger, &proof, &public_values)
+        .expect("verification failed");
+}
+
+#[test]
+fn test_balanced_bus_wide_permutation_trace() {
+    // The sender's cumulative sum isn't in the last column of its permutation trace.
+    let (config, perm) = make_config(1);
+    let range_check = RangeCheckAir::byte(0);
+    let chips = [Chip::WideSender(SenderAir), Chip::Range(range_check)];
+    let traces = generate_traces(&range_check, 4, false);
+    let public_values = vec![vec![]; chips.len()];
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove_batch(&config, &chips, &mut challenger, traces, &public_values)
+        .expect("proving failed");
+
+    let mut challenger = Challenger::new(perm);
+    verify_batch(&config, &chips, &mut challenger, &proof, &public_values)
+        .expect("verification failed");
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_unbalanced_bus() {
+    let (config, perm) = make_config(1);
+    let range_check = RangeCheckAir::byte(0);
+    let chips = [Chip::Sender(SenderAir), Chip::Range(range_check)];
+    let traces = generate_traces(&range_check, 4, true);
+    let public_values = vec![vec![]; chips.len()];
+
+    let mut challenger = Challenger::new(perm);
+    assert!(matches!(
+        prove_batch(&config, &chips, &mut challenger, traces, &public_values),
+        Err(ProverError::NonzeroCumulativeSum)
+    ));
+}
+
+#[cfg(not(debug_assertions))]
+#[test]
+fn test_unbalanced_bus() {
+    let (config, perm) = make_config(1);
+    let range_check = RangeCheckAir::byte(0);
+    let chips = [Chip::Sender(SenderAir), Chip::Range(range_check)];
+    let traces = generate_traces(&range_check, 4, true);
+    let public_values = vec![vec![]; chips.len()];
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove_batch(&config, &chips, &mut challenger, traces, &public_values)
+        .expect("proving failed");
+
+    let mut challenger = Challenger::new(perm);
+    assert!(matches!(
+        verify_batch(&config, &chips, &mut challenger, &proof, &public_values),
+        Err(VerificationError::NonzeroCumulativeSum)
+    ));
+}
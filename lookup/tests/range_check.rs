@@ -0,0 +1,75 @@
+use p3_field::FieldAlgebra;
+use p3_lookup::RangeCheckAir;
+use p3_uni_stark::testing::{make_config, Challenger, Val};
+#[cfg(debug_assertions)]
+use p3_uni_stark::ProverError;
+use p3_uni_stark::{prove, prove_with_key, setup, verify_with_key};
+use rand::{thread_rng, Rng};
+
+fn random_bytes(n: usize) -> Vec<u32> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| rng.gen_range(0..256)).collect()
+}
+
+#[test]
+fn test_byte_range_check() {
+    let (config, perm) = make_config(1);
+    let air = RangeCheckAir::byte(3);
+    let (pk, vk) = setup(&config, &air).expect("setup failed");
+    let trace = air.generate_trace::<Val>(&random_bytes(500));
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove_with_key(&config, &pk, &air, &mut challenger, trace, &vec![])
+        .expect("proving failed");
+
+    let mut challenger = Challenger::new(perm);
+    verify_with_key(&config, &vk, &air, &mut challenger, &proof, &vec![])
+        .expect("verification failed");
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_tampered_multiplicity() {
+    let (config, perm) = make_config(1);
+    let air = RangeCheckAir::byte(1);
+    let mut trace = air.generate_trace::<Val>(&random_bytes(100));
+    trace.row_mut(7)[air.multiplicity_column()] += Val::ONE;
+
+    let mut challenger = Challenger::new(perm);
+    assert!(matches!(
+        prove(&config, &air, &mut challenger, trace, &vec![]),
+        Err(ProverError::ConstraintFailure { .. })
+    ));
+}
+
+#[cfg(not(debug_assertions))]
+#[test]
+fn test_tampered_multiplicity() {
+    let (config, perm) = make_config(1);
+    let air = RangeCheckAir::byte(1);
+    let (_, vk) = setup(&config, &air).expect("setup failed");
+    let mut trace = air.generate_trace::<Val>(&random_bytes(100));
+    trace.row_mut(7)[air.multiplicity_column()] += Val::ONE;
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut challenger, trace, &vec![]).expect("proving failed");
+
+    let mut challenger = Challenger::new(perm);
+    assert!(verify_with_key(&config, &vk, &air, &mut challenger, &proof, &vec![]).is_err());
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_out_of_range_value() {
+    let (config, perm) = make_config(1);
+    let air = RangeCheckAir::byte(1);
+    let mut trace = air.generate_trace::<Val>(&random_bytes(100));
+    // Replace a looked up byte with an out of range value, keeping multiplicities unchanged.
+    trace.row_mut(3)[0] = Val::from_canonical_u32(256);
+
+    let mut challenger = Challenger::new(perm);
+    assert!(matches!(
+        prove(&config, &air, &mut challenger, trace, &vec![]),
+        Err(ProverError::ConstraintFailure { .. })
+    ));
+}
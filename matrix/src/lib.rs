@@ -3,6 +3,8 @@
 #![no_std]
 
 extern crate alloc;
+#[cfg(feature = "mmap")]
+extern crate std;
 
 use alloc::vec::Vec;
 use core::fmt::{Debug, Display, Formatter};
@@ -22,6 +24,8 @@ pub mod bitrev;
 pub mod dense;
 pub mod extension;
 pub mod horizontally_truncated;
+#[cfg(feature = "mmap")]
+pub mod mmap;
 pub mod mul;
 pub mod row_index_mapped;
 pub mod sparse;
@@ -0,0 +1,257 @@
+//! Matrices backed by memory-mapped files, for data which doesn't fit in memory.
+
+use alloc::format;
+use alloc::vec::Vec;
+use core::borrow::{Borrow, BorrowMut};
+use core::fmt::{Debug, Formatter};
+use core::marker::PhantomData;
+use core::mem::{align_of, size_of, ManuallyDrop};
+use core::ops::{Deref, DerefMut};
+use core::slice;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use memmap2::MmapMut;
+
+use crate::dense::{DenseMatrix, DenseStorage};
+
+/// A dense matrix whose values are stored in a memory-mapped file.
+pub type MmapMatrix<T> = DenseMatrix<T, MmapBuffer<T>>;
+
+/// A buffer of `T`s stored in a memory-mapped file.
+///
+/// The operating system pages the file in and out as it's accessed, so the buffer can be much
+/// larger than memory. Values are stored in their in-memory representation, so a file can only be
+/// read back by the same build on the same platform.
+pub struct MmapBuffer<T> {
+    mmap: ManuallyDrop<MmapMut>,
+    len: usize,
+    /// A file to delete once the buffer is dropped.
+    temp_path: Option<PathBuf>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Copy> MmapBuffer<T> {
+    /// Create a buffer of `len` copies of `value` in a new file at `path`, replacing any existing
+    /// file there.
+    pub fn create(path: impl AsRef<Path>, len: usize, value: T) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        Self::fill_file(&file, len, value)
+    }
+
+    /// Create a buffer of `len` copies of `value` in a new file in `dir`, which is deleted when the
+    /// buffer is dropped.
+    pub fn temp_in(dir: impl AsRef<Path>, len: usize, value: T) -> io::Result<Self> {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let path = dir
+            .as_ref()
+            .join(format!("p3-mmap-{}-{id}", std::process::id()));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        match Self::fill_file(&file, len, value) {
+            Ok(mut buffer) => {
+                buffer.temp_path = Some(path);
+                Ok(buffer)
+            }
+            Err(err) => {
+                let _ = fs::remove_file(&path);
+                Err(err)
+            }
+        }
+    }
+
+    /// Map an existing file of `T`s, such as one created by [`MmapBuffer::create`].
+    ///
+    /// # Safety
+    ///
+    /// The file must hold valid values of `T`, for instance by having been written by a buffer of
+    /// `T`s from the same build, and mustn't be modified by anything else while it's mapped.
+    pub unsafe fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let mmap = MmapMut::map_mut(&file)?;
+        if size_of::<T>() == 0 || mmap.len() % size_of::<T>() != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "file size isn't a multiple of the value size",
+            ));
+        }
+        let len = mmap.len() / size_of::<T>();
+        Self::from_mmap(mmap, len)
+    }
+
+    fn fill_file(file: &File, len: usize, value: T) -> io::Result<Self> {
+        let bytes = len
+            .checked_mul(size_of::<T>())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "buffer too large"))?;
+        file.set_len(bytes as u64)?;
+        // SAFETY: The file was just created or truncated by us, and nothing else has it mapped.
+        let mmap = unsafe { MmapMut::map_mut(file)? };
+        let mut buffer = Self::from_mmap(mmap, len)?;
+        let ptr = buffer.mmap.as_mut_ptr().cast::<T>();
+        for i in 0..len {
+            // SAFETY: The mapping is aligned and holds `len` values of `T`. We write rather than
+            // assign, since the bytes aren't yet a valid `T`.
+            unsafe { ptr.add(i).write(value) };
+        }
+        Ok(buffer)
+    }
+}
+
+impl<T> MmapBuffer<T> {
+    fn from_mmap(mmap: MmapMut, len: usize) -> io::Result<Self> {
+        if mmap.as_ptr() as usize % align_of::<T>() != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "mapping isn't aligned for the value type",
+            ));
+        }
+        Ok(Self {
+            mmap: ManuallyDrop::new(mmap),
+            len,
+            temp_path: None,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Write any changes to the file.
+    pub fn flush(&self) -> io::Result<()> {
+        self.mmap.flush()
+    }
+}
+
+impl<T> Drop for MmapBuffer<T> {
+    fn drop(&mut self) {
+        // Unmap before deleting the file, which some platforms don't allow while it's mapped.
+        // SAFETY: The mapping isn't used again.
+        unsafe { ManuallyDrop::drop(&mut self.mmap) };
+        if let Some(path) = &self.temp_path {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+impl<T> Deref for MmapBuffer<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        // SAFETY: The mapping is aligned, and holds `len` valid values of `T`.
+        unsafe { slice::from_raw_parts(self.mmap.as_ptr().cast(), self.len) }
+    }
+}
+
+impl<T> DerefMut for MmapBuffer<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        // SAFETY: The mapping is aligned, and holds `len` valid values of `T`.
+        unsafe { slice::from_raw_parts_mut(self.mmap.as_mut_ptr().cast(), self.len) }
+    }
+}
+
+impl<T> Borrow<[T]> for MmapBuffer<T> {
+    fn borrow(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T> BorrowMut<[T]> for MmapBuffer<T> {
+    fn borrow_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+impl<T: Clone + Send + Sync> DenseStorage<T> for MmapBuffer<T> {
+    fn to_vec(self) -> Vec<T> {
+        <[T]>::to_vec(&self)
+    }
+}
+
+impl<T> Debug for MmapBuffer<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("MmapBuffer")
+            .field("len", &self.len)
+            .field("temp_path", &self.temp_path)
+            .finish()
+    }
+}
+
+impl<T: Copy + Send + Sync> MmapMatrix<T> {
+    /// Create a `width` by `height` matrix filled with `value`, in a new file at `path`.
+    pub fn create(
+        path: impl AsRef<Path>,
+        width: usize,
+        height: usize,
+        value: T,
+    ) -> io::Result<Self> {
+        Ok(Self::new(
+            MmapBuffer::create(path, width * height, value)?,
+            width,
+        ))
+    }
+
+    /// Create a `width` by `height` matrix filled with `value`, in a new file in `dir` which is
+    /// deleted when the matrix is dropped.
+    pub fn temp_in(
+        dir: impl AsRef<Path>,
+        width: usize,
+        height: usize,
+        value: T,
+    ) -> io::Result<Self> {
+        Ok(Self::new(
+            MmapBuffer::temp_in(dir, width * height, value)?,
+            width,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+
+    use p3_baby_bear::BabyBear;
+    use p3_field::FieldAlgebra;
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::dense::RowMajorMatrix;
+    use crate::Matrix;
+
+    type F = BabyBear;
+
+    #[test]
+    fn write_and_reopen() {
+        let mat = RowMajorMatrix::<F>::rand(&mut thread_rng(), 32, 5);
+        let path = temp_dir().join(format!("p3-mmap-test-{}", std::process::id()));
+
+        let mut mapped = MmapMatrix::create(&path, mat.width(), mat.height(), F::ZERO).unwrap();
+        assert!(mapped.values.iter().all(|&x| x == F::ZERO));
+        mapped.copy_from(&mat);
+        mapped.values.flush().unwrap();
+        drop(mapped);
+
+        // SAFETY: The file was written by a buffer of `F`s above.
+        let reopened = MmapMatrix::new(unsafe { MmapBuffer::<F>::open(&path) }.unwrap(), 5);
+        assert_eq!(reopened.dimensions(), mat.dimensions());
+        assert_eq!(reopened.to_row_major_matrix(), mat);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn temp_file_is_deleted() {
+        let buffer = MmapBuffer::temp_in(temp_dir(), 100, F::ONE).unwrap();
+        let path = buffer.temp_path.clone().unwrap();
+        assert!(path.exists());
+        assert!(buffer.iter().all(|&x| x == F::ONE));
+        drop(buffer);
+        assert!(!path.exists());
+    }
+}
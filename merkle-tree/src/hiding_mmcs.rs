@@ -2,12 +2,12 @@ use alloc::vec::Vec;
 use core::cell::RefCell;
 
 use itertools::Itertools;
-use p3_commit::Mmcs;
+use p3_commit::{HashCount, Mmcs, MmcsCostModel};
 use p3_field::PackedValue;
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::stack::HorizontalPair;
 use p3_matrix::{Dimensions, Matrix};
-use p3_symmetric::{CryptographicHasher, Hash, PseudoCompressionFunction};
+use p3_symmetric::{CryptographicHasher, MerkleCap, PseudoCompressionFunction};
 use rand::distributions::{Distribution, Standard};
 use rand::Rng;
 use serde::de::DeserializeOwned;
@@ -51,6 +51,15 @@ impl<P, PW, H, C, R, const DIGEST_ELEMS: usize, const SALT_ELEMS: usize>
             rng: rng.into(),
         }
     }
+
+    /// Commit to the layer `cap_height` below the root instead of the root; see
+    /// [`MerkleTreeMmcs::with_cap_height`].
+    pub fn with_cap_height(self, cap_height: usize) -> Self {
+        Self {
+            inner: self.inner.with_cap_height(cap_height),
+            ..self
+        }
+    }
 }
 
 impl<P, PW, H, C, R, const DIGEST_ELEMS: usize, const SALT_ELEMS: usize> Mmcs<P::Value>
@@ -72,11 +81,18 @@ where
 {
     type ProverData<M> =
         MerkleTree<P::Value, PW::Value, HorizontalPair<M, RowMajorMatrix<P::Value>>, DIGEST_ELEMS>;
-    type Commitment = Hash<P::Value, PW::Value, DIGEST_ELEMS>;
+    type Commitment = MerkleCap<P::Value, PW::Value, DIGEST_ELEMS>;
     /// The first item is salts; the second is the usual Merkle proof (sibling digests).
     type Proof = (Vec<Vec<P::Value>>, Vec<[PW::Value; DIGEST_ELEMS]>);
+    /// The first item is the salts of each index's openings; the second is the deduplicated
+    /// sibling digests, as for `MerkleTreeMmcs`.
+    type MultiProof = (Vec<Vec<Vec<P::Value>>>, Vec<[PW::Value; DIGEST_ELEMS]>);
     type Error = MerkleTreeError;
 
+    fn parameters(&self) -> Vec<usize> {
+        self.inner.parameters()
+    }
+
     fn commit<M: Matrix<P::Value>>(
         &self,
         inputs: Vec<M>,
@@ -101,12 +117,19 @@ where
         (Vec<Vec<P::Value>>, Vec<[PW::Value; DIGEST_ELEMS]>),
     ) {
         let (salted_openings, siblings) = self.inner.open_batch(index, prover_data);
-        let (openings, salts): (Vec<_>, Vec<_>) = salted_openings
+        let (openings, salts) = unsalt::<_, SALT_ELEMS>(salted_openings);
+        (openings, (salts, siblings))
+    }
+
+    fn open_multi_batch<M: Matrix<P::Value>>(
+        &self,
+        indices: &[usize],
+        prover_data: &Self::ProverData<M>,
+    ) -> (Vec<Vec<Vec<P::Value>>>, Self::MultiProof) {
+        let (salted_openings, siblings) = self.inner.open_multi_batch(indices, prover_data);
+        let (openings, salts) = salted_openings
             .into_iter()
-            .map(|row| {
-                let (a, b) = row.split_at(row.len() - SALT_ELEMS);
-                (a.to_vec(), b.to_vec())
-            })
+            .map(unsalt::<_, SALT_ELEMS>)
             .unzip();
         (openings, (salts, siblings))
     }
@@ -128,15 +151,106 @@ where
     ) -> Result<(), Self::Error> {
         let (salts, siblings) = proof;
 
+        let opened_salted_values = salt(opened_values, salts);
+
+        self.inner
+            .verify_batch(commit, dimensions, index, &opened_salted_values, siblings)
+    }
+
+    fn verify_multi_batch(
+        &self,
+        commit: &Self::Commitment,
+        dimensions: &[Dimensions],
+        indices: &[usize],
+        opened_values: &[Vec<Vec<P::Value>>],
+        proof: &Self::MultiProof,
+    ) -> Result<(), Self::Error> {
+        let (salts, siblings) = proof;
+
         let opened_salted_values = opened_values
             .iter()
-            .zip(salts.iter())
-            .map(|(opened, salt)| opened.iter().chain(salt.iter()).copied().collect_vec())
+            .zip(salts)
+            .map(|(opened, salts)| salt(opened, salts))
             .collect_vec();
 
         self.inner
-            .verify_batch(commit, dimensions, index, &opened_salted_values, siblings)
+            .verify_multi_batch(commit, dimensions, indices, &opened_salted_values, siblings)
+    }
+}
+
+/// Split opened salted rows into the rows and their salts.
+fn unsalt<T: Clone, const SALT_ELEMS: usize>(
+    salted_rows: Vec<Vec<T>>,
+) -> (Vec<Vec<T>>, Vec<Vec<T>>) {
+    salted_rows
+        .into_iter()
+        .map(|row| {
+            let (a, b) = row.split_at(row.len() - SALT_ELEMS);
+            (a.to_vec(), b.to_vec())
+        })
+        .unzip()
+}
+
+/// Append salts to opened rows.
+fn salt<T: Copy>(rows: &[Vec<T>], salts: &[Vec<T>]) -> Vec<Vec<T>> {
+    rows.iter()
+        .zip(salts)
+        .map(|(opened, salt)| opened.iter().chain(salt).copied().collect_vec())
+        .collect_vec()
+}
+
+impl<P, PW, H, C, R, const DIGEST_ELEMS: usize, const SALT_ELEMS: usize> MmcsCostModel
+    for MerkleTreeHidingMmcs<P, PW, H, C, R, DIGEST_ELEMS, SALT_ELEMS>
+where
+    P: PackedValue,
+    PW: PackedValue,
+{
+    fn commitment_bytes(&self) -> usize {
+        self.inner.commitment_bytes()
+    }
+
+    fn value_bytes(&self) -> usize {
+        self.inner.value_bytes()
+    }
+
+    fn opening_proof_bytes(&self, dimensions: &[Dimensions]) -> usize {
+        let salt_bytes = dimensions.len() * SALT_ELEMS * self.value_bytes();
+        salt_bytes + self.inner.opening_proof_bytes(dimensions)
+    }
+
+    fn multi_opening_proof_bytes(&self, dimensions: &[Dimensions], num_indices: usize) -> usize {
+        let salt_bytes = num_indices * dimensions.len() * SALT_ELEMS * self.value_bytes();
+        salt_bytes
+            + self
+                .inner
+                .multi_opening_proof_bytes(dimensions, num_indices)
+    }
+
+    fn commit_cost(&self, dimensions: &[Dimensions]) -> HashCount {
+        self.inner
+            .commit_cost(&salted_dimensions::<SALT_ELEMS>(dimensions))
+    }
+
+    fn verify_cost(&self, dimensions: &[Dimensions]) -> HashCount {
+        self.inner
+            .verify_cost(&salted_dimensions::<SALT_ELEMS>(dimensions))
     }
+
+    fn multi_verify_cost(&self, dimensions: &[Dimensions], num_indices: usize) -> HashCount {
+        self.inner
+            .multi_verify_cost(&salted_dimensions::<SALT_ELEMS>(dimensions), num_indices)
+    }
+}
+
+/// The dimensions of some matrices once salts have been appended to each row.
+fn salted_dimensions<const SALT_ELEMS: usize>(dimensions: &[Dimensions]) -> Vec<Dimensions> {
+    dimensions
+        .iter()
+        .map(|dims| Dimensions {
+            width: dims.width + SALT_ELEMS,
+            height: dims.height,
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -208,4 +322,29 @@ mod tests {
         let (opened_values, proof) = mmcs.open_batch(17, &prover_data);
         mmcs.verify_batch(&commit, &dims, 17, &opened_values, &proof)
     }
+
+    #[test]
+    fn multi_batch() -> Result<(), MerkleTreeError> {
+        let mut rng = thread_rng();
+        let perm = Perm::new_from_rng_128(&mut rng);
+        let mmcs = MyMmcs::new(
+            MyHash::new(perm.clone()),
+            MyCompress::new(perm),
+            thread_rng(),
+        );
+
+        let mats = vec![
+            RowMajorMatrix::<F>::rand(&mut rng, 32, 3),
+            RowMajorMatrix::<F>::rand(&mut rng, 8, 2),
+        ];
+        let dims = mats.iter().map(|m| m.dimensions()).collect_vec();
+
+        let (commit, prover_data) = mmcs.commit(mats);
+        let indices = [17, 3, 16, 17];
+        let (opened_values, proof) = mmcs.open_multi_batch(&indices, &prover_data);
+        for (&index, opened_values) in indices.iter().zip(&opened_values) {
+            assert_eq!(&mmcs.open_batch(index, &prover_data).0, opened_values);
+        }
+        mmcs.verify_multi_batch(&commit, &dims, &indices, &opened_values, &proof)
+    }
 }
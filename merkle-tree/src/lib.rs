@@ -1,6 +1,8 @@
 #![no_std]
 
 extern crate alloc;
+#[cfg(feature = "mmap")]
+extern crate std;
 
 mod hiding_mmcs;
 mod merkle_tree;
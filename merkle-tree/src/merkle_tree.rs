@@ -3,13 +3,19 @@ use alloc::vec::Vec;
 use core::array;
 use core::cmp::Reverse;
 use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "mmap")]
+use std::path::PathBuf;
 
 use itertools::Itertools;
 use p3_field::PackedValue;
+#[cfg(feature = "mmap")]
+use p3_matrix::mmap::MmapBuffer;
 use p3_matrix::Matrix;
 use p3_maybe_rayon::prelude::*;
-use p3_symmetric::{CryptographicHasher, Hash, PseudoCompressionFunction};
-use serde::{Deserialize, Serialize};
+use p3_symmetric::{CryptographicHasher, Hash, MerkleCap, PseudoCompressionFunction};
+use p3_util::log2_ceil_usize;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use tracing::instrument;
 
 /// A binary Merkle tree for packed data. It has leaves of type `F` and digests of type
@@ -24,18 +30,173 @@ pub struct MerkleTree<F, W, M, const DIGEST_ELEMS: usize> {
     #[serde(bound(serialize = "[W; DIGEST_ELEMS]: Serialize"))]
     // Enable deserialization for this type whenever the underlying array type supports it (len 1-32).
     #[serde(bound(deserialize = "[W; DIGEST_ELEMS]: Deserialize<'de>"))]
-    pub(crate) digest_layers: Vec<Vec<[W; DIGEST_ELEMS]>>,
+    pub(crate) digest_layers: Vec<DigestLayer<[W; DIGEST_ELEMS]>>,
     _phantom: PhantomData<F>,
 }
 
+/// A layer of digests of a `MerkleTree`, kept in memory or, with the `mmap` feature, in a
+/// memory-mapped file. Either way, it derefs to a slice of the digests.
+///
+/// A tree serializes its layers as plain sequences, and deserializes them into memory.
+#[derive(Debug)]
+pub enum DigestLayer<D> {
+    Memory(Vec<D>),
+    #[cfg(feature = "mmap")]
+    Mapped(MmapBuffer<D>),
+}
+
+impl<D> Deref for DigestLayer<D> {
+    type Target = [D];
+
+    fn deref(&self) -> &[D] {
+        match self {
+            Self::Memory(digests) => digests,
+            #[cfg(feature = "mmap")]
+            Self::Mapped(digests) => digests,
+        }
+    }
+}
+
+impl<D> DerefMut for DigestLayer<D> {
+    fn deref_mut(&mut self) -> &mut [D] {
+        match self {
+            Self::Memory(digests) => digests,
+            #[cfg(feature = "mmap")]
+            Self::Mapped(digests) => digests,
+        }
+    }
+}
+
+impl<D: Serialize> Serialize for DigestLayer<D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, D: Deserialize<'de>> Deserialize<'de> for DigestLayer<D> {
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        Vec::deserialize(deserializer).map(Self::Memory)
+    }
+}
+
+/// The error building a `MerkleTree` with `MerkleTreeOptions` can fail with. With the `mmap`
+/// feature, this is the I/O error from creating a digest layer's file. Without it, no digests are
+/// stored on disk, so building can't fail.
+#[cfg(feature = "mmap")]
+pub type MerkleTreeBuildError = std::io::Error;
+/// The error building a `MerkleTree` with `MerkleTreeOptions` can fail with. With the `mmap`
+/// feature, this is the I/O error from creating a digest layer's file. Without it, no digests are
+/// stored on disk, so building can't fail.
+#[cfg(not(feature = "mmap"))]
+pub type MerkleTreeBuildError = core::convert::Infallible;
+
+/// Options for building a `MerkleTree` over matrices too large to hash in memory, such as an
+/// `MmapMatrix` of a large trace's LDE computed with `coset_lde_batch_chunked`.
+///
+/// These are used by committing with `MerkleTreeMmcs::commit_with_options` directly. PCSs such as
+/// `TwoAdicFriPcs` commit through `Mmcs::commit`, which builds the tree in memory.
+#[derive(Clone, Debug, Default)]
+pub struct MerkleTreeOptions {
+    chunk_rows: Option<usize>,
+    #[cfg(feature = "mmap")]
+    digest_dir: Option<PathBuf>,
+}
+
+impl MerkleTreeOptions {
+    /// Digest layers shorter than this are always kept in memory, as they're small, and mapping
+    /// each to its own file would only cost time.
+    #[cfg(feature = "mmap")]
+    const MIN_MAPPED_LAYER_LEN: usize = 1 << 12;
+
+    /// Hash `chunk_rows` rows of the matrices at a time, rather than all of them at once, so the
+    /// rows being hashed in parallel are close together. When the matrices are stored on disk,
+    /// only around a chunk of each needs to be paged in at any one time.
+    ///
+    /// `chunk_rows` is rounded up to a multiple of the packing width.
+    pub fn with_chunk_rows(self, chunk_rows: usize) -> Self {
+        assert!(chunk_rows > 0, "chunk size must be positive");
+        Self {
+            chunk_rows: Some(chunk_rows),
+            ..self
+        }
+    }
+
+    /// Keep the digest layers in files in `dir`, which are deleted when the tree is dropped.
+    #[cfg(feature = "mmap")]
+    pub fn with_digest_dir(self, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            digest_dir: Some(dir.into()),
+            ..self
+        }
+    }
+
+    fn alloc_layer<D: Copy>(
+        &self,
+        len: usize,
+        default: D,
+    ) -> Result<DigestLayer<D>, MerkleTreeBuildError> {
+        #[cfg(feature = "mmap")]
+        if let Some(dir) = &self.digest_dir {
+            if len >= Self::MIN_MAPPED_LAYER_LEN {
+                return MmapBuffer::temp_in(dir, len, default).map(DigestLayer::Mapped);
+            }
+        }
+        Ok(DigestLayer::Memory(vec![default; len]))
+    }
+
+    /// Call `f` on each group of `width` consecutive digests, with the index of its first one, in
+    /// parallel within each chunk of rows.
+    fn for_each_packed<D: Send>(
+        &self,
+        digests: &mut [D],
+        width: usize,
+        f: impl Fn(usize, &mut [D]) + Sync,
+    ) {
+        let chunk_len = self
+            .chunk_rows
+            .map_or(digests.len(), |rows| rows.next_multiple_of(width))
+            .max(width);
+        for (chunk_index, chunk) in digests.chunks_mut(chunk_len).enumerate() {
+            chunk
+                .par_chunks_exact_mut(width)
+                .enumerate()
+                .for_each(|(i, packed)| f(chunk_index * chunk_len + i * width, packed));
+        }
+    }
+}
+
 impl<F: Clone + Send + Sync, W: Clone, M: Matrix<F>, const DIGEST_ELEMS: usize>
     MerkleTree<F, W, M, DIGEST_ELEMS>
 {
     /// Matrix heights need not be powers of two. However, if the heights of two given matrices
     /// round up to the same power of two, they must be equal.
+    pub fn new<P, PW, H, C>(h: &H, c: &C, leaves: Vec<M>) -> Self
+    where
+        P: PackedValue<Value = F>,
+        PW: PackedValue<Value = W>,
+        H: CryptographicHasher<F, [W; DIGEST_ELEMS]>,
+        H: CryptographicHasher<P, [PW; DIGEST_ELEMS]>,
+        H: Sync,
+        C: PseudoCompressionFunction<[W; DIGEST_ELEMS], 2>,
+        C: PseudoCompressionFunction<[PW; DIGEST_ELEMS], 2>,
+        C: Sync,
+    {
+        Self::new_with_options::<P, PW, H, C>(h, c, leaves, &MerkleTreeOptions::default())
+            .expect("the default options keep the digests in memory")
+    }
+
+    /// Build a tree as `new` does, hashing the leaves and storing the digests as `options` say.
+    /// The tree is the same whatever the options.
+    ///
+    /// Fails if a digest layer's file can't be created.
     #[instrument(name = "build merkle tree", level = "debug", skip_all,
                  fields(dimensions = alloc::format!("{:?}", leaves.iter().map(|l| l.dimensions()).collect::<Vec<_>>())))]
-    pub fn new<P, PW, H, C>(h: &H, c: &C, leaves: Vec<M>) -> Self
+    pub fn new_with_options<P, PW, H, C>(
+        h: &H,
+        c: &C,
+        leaves: Vec<M>,
+        options: &MerkleTreeOptions,
+    ) -> Result<Self, MerkleTreeBuildError>
     where
         P: PackedValue<Value = F>,
         PW: PackedValue<Value = W>,
@@ -74,9 +235,10 @@ impl<F: Clone + Send + Sync, W: Clone, M: Matrix<F>, const DIGEST_ELEMS: usize>
         let mut digest_layers = vec![first_digest_layer::<P, PW, H, M, DIGEST_ELEMS>(
             h,
             tallest_matrices,
-        )];
+            options,
+        )?];
         loop {
-            let prev_layer = digest_layers.last().unwrap().as_slice();
+            let prev_layer: &[_] = digest_layers.last().unwrap();
             if prev_layer.len() == 1 {
                 break;
             }
@@ -92,15 +254,16 @@ impl<F: Clone + Send + Sync, W: Clone, M: Matrix<F>, const DIGEST_ELEMS: usize>
                 matrices_to_inject,
                 h,
                 c,
-            );
+                options,
+            )?;
             digest_layers.push(next_digests);
         }
 
-        Self {
+        Ok(Self {
             leaves,
             digest_layers,
             _phantom: PhantomData,
-        }
+        })
     }
 
     #[must_use]
@@ -110,13 +273,39 @@ impl<F: Clone + Send + Sync, W: Clone, M: Matrix<F>, const DIGEST_ELEMS: usize>
     {
         self.digest_layers.last().unwrap()[0].into()
     }
+
+    /// The digests of the layer `cap_height` below the root, padded with default digests to
+    /// `2^cap_height`. The cap is lowered to the height of the smallest matrix if that's shorter,
+    /// so that every matrix is committed to below it; see [`effective_cap_height`].
+    #[must_use]
+    pub fn cap(&self, cap_height: usize) -> MerkleCap<F, W, DIGEST_ELEMS>
+    where
+        W: Copy + Default,
+    {
+        let log_max_height = self.digest_layers.len() - 1;
+        let cap_height = effective_cap_height(cap_height, self.leaves.iter().map(|m| m.height()));
+        let mut cap = self.digest_layers[log_max_height - cap_height].to_vec();
+        cap.resize(1 << cap_height, [W::default(); DIGEST_ELEMS]);
+        MerkleCap::new(cap)
+    }
+}
+
+/// The height of the cap committing to matrices of the given heights. This is `cap_height`, unless
+/// the smallest matrix is injected into the tree above it, in which case the cap is at that
+/// matrix's layer instead.
+pub fn effective_cap_height(cap_height: usize, heights: impl IntoIterator<Item = usize>) -> usize {
+    heights
+        .into_iter()
+        .map(log2_ceil_usize)
+        .fold(cap_height, usize::min)
 }
 
 #[instrument(name = "first digest layer", level = "debug", skip_all)]
 fn first_digest_layer<P, PW, H, M, const DIGEST_ELEMS: usize>(
     h: &H,
     tallest_matrices: Vec<&M>,
-) -> Vec<[PW::Value; DIGEST_ELEMS]>
+    options: &MerkleTreeOptions,
+) -> Result<DigestLayer<[PW::Value; DIGEST_ELEMS]>, MerkleTreeBuildError>
 where
     P: PackedValue,
     PW: PackedValue,
@@ -135,13 +324,12 @@ where
     };
 
     let default_digest: [PW::Value; DIGEST_ELEMS] = [PW::Value::default(); DIGEST_ELEMS];
-    let mut digests = vec![default_digest; max_height_padded];
+    let mut digests = options.alloc_layer(max_height_padded, default_digest)?;
 
-    digests[0..max_height]
-        .par_chunks_exact_mut(width)
-        .enumerate()
-        .for_each(|(i, digests_chunk)| {
-            let first_row = i * width;
+    options.for_each_packed(
+        &mut digests[0..max_height],
+        width,
+        |first_row, digests_chunk| {
             let packed_digest: [PW; DIGEST_ELEMS] = h.hash_iter(
                 tallest_matrices
                     .iter()
@@ -150,7 +338,8 @@ where
             for (dst, src) in digests_chunk.iter_mut().zip(unpack_array(packed_digest)) {
                 *dst = src;
             }
-        });
+        },
+    );
 
     // If our packing width did not divide max_height, fall back to single-threaded scalar code
     // for the last bit.
@@ -160,7 +349,7 @@ where
     }
 
     // Everything has been initialized so we can safely cast.
-    digests
+    Ok(digests)
 }
 
 /// Compress `n` digests from the previous layer into `n/2` digests, while potentially mixing in
@@ -170,7 +359,8 @@ fn compress_and_inject<P, PW, H, C, M, const DIGEST_ELEMS: usize>(
     matrices_to_inject: Vec<&M>,
     h: &H,
     c: &C,
-) -> Vec<[PW::Value; DIGEST_ELEMS]>
+    options: &MerkleTreeOptions,
+) -> Result<DigestLayer<[PW::Value; DIGEST_ELEMS]>, MerkleTreeBuildError>
 where
     P: PackedValue,
     PW: PackedValue,
@@ -183,7 +373,7 @@ where
     M: Matrix<P::Value>,
 {
     if matrices_to_inject.is_empty() {
-        return compress::<PW, C, DIGEST_ELEMS>(prev_layer, c);
+        return compress::<PW, C, DIGEST_ELEMS>(prev_layer, c, options);
     }
 
     let width = PW::WIDTH;
@@ -196,12 +386,11 @@ where
     };
 
     let default_digest: [PW::Value; DIGEST_ELEMS] = [PW::Value::default(); DIGEST_ELEMS];
-    let mut next_digests = vec![default_digest; next_len_padded];
-    next_digests[0..next_len]
-        .par_chunks_exact_mut(width)
-        .enumerate()
-        .for_each(|(i, digests_chunk)| {
-            let first_row = i * width;
+    let mut next_digests = options.alloc_layer(next_len_padded, default_digest)?;
+    options.for_each_packed(
+        &mut next_digests[0..next_len],
+        width,
+        |first_row, digests_chunk| {
             let left = array::from_fn(|j| PW::from_fn(|k| prev_layer[2 * (first_row + k)][j]));
             let right = array::from_fn(|j| PW::from_fn(|k| prev_layer[2 * (first_row + k) + 1][j]));
             let mut packed_digest = c.compress([left, right]);
@@ -214,7 +403,8 @@ where
             for (dst, src) in digests_chunk.iter_mut().zip(unpack_array(packed_digest)) {
                 *dst = src;
             }
-        });
+        },
+    );
 
     // If our packing width did not divide next_len, fall back to single-threaded scalar code
     // for the last bit.
@@ -236,14 +426,15 @@ where
         next_digests[i] = c.compress([digest, default_digest]);
     }
 
-    next_digests
+    Ok(next_digests)
 }
 
 /// Compress `n` digests from the previous layer into `n/2` digests.
 fn compress<P, C, const DIGEST_ELEMS: usize>(
     prev_layer: &[[P::Value; DIGEST_ELEMS]],
     c: &C,
-) -> Vec<[P::Value; DIGEST_ELEMS]>
+    options: &MerkleTreeOptions,
+) -> Result<DigestLayer<[P::Value; DIGEST_ELEMS]>, MerkleTreeBuildError>
 where
     P: PackedValue,
     C: PseudoCompressionFunction<[P::Value; DIGEST_ELEMS], 2>,
@@ -260,20 +451,20 @@ where
     let next_len = prev_layer.len() / 2;
 
     let default_digest: [P::Value; DIGEST_ELEMS] = [P::Value::default(); DIGEST_ELEMS];
-    let mut next_digests = vec![default_digest; next_len_padded];
+    let mut next_digests = options.alloc_layer(next_len_padded, default_digest)?;
 
-    next_digests[0..next_len]
-        .par_chunks_exact_mut(width)
-        .enumerate()
-        .for_each(|(i, digests_chunk)| {
-            let first_row = i * width;
+    options.for_each_packed(
+        &mut next_digests[0..next_len],
+        width,
+        |first_row, digests_chunk| {
             let left = array::from_fn(|j| P::from_fn(|k| prev_layer[2 * (first_row + k)][j]));
             let right = array::from_fn(|j| P::from_fn(|k| prev_layer[2 * (first_row + k) + 1][j]));
             let packed_digest = c.compress([left, right]);
             for (dst, src) in digests_chunk.iter_mut().zip(unpack_array(packed_digest)) {
                 *dst = src;
             }
-        });
+        },
+    );
 
     // If our packing width did not divide next_len, fall back to single-threaded scalar code
     // for the last bit.
@@ -284,7 +475,7 @@ where
     }
 
     // Everything has been initialized so we can safely cast.
-    next_digests
+    Ok(next_digests)
 }
 
 /// Converts a packed array `[P; N]` into its underlying `P::WIDTH` scalar arrays.
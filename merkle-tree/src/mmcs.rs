@@ -1,20 +1,32 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::cmp::Reverse;
 use core::marker::PhantomData;
+use core::mem::size_of;
 
 use itertools::Itertools;
-use p3_commit::Mmcs;
+use p3_commit::{HashCount, Mmcs, MmcsCostModel};
 use p3_field::PackedValue;
 use p3_matrix::{Dimensions, Matrix};
-use p3_symmetric::{CryptographicHasher, Hash, PseudoCompressionFunction};
+use p3_symmetric::{CryptographicHasher, MerkleCap, PseudoCompressionFunction};
 use p3_util::log2_ceil_usize;
 use serde::{Deserialize, Serialize};
 
-use crate::MerkleTree;
-use crate::MerkleTreeError::{RootMismatch, WrongBatchSize, WrongHeight};
+use crate::MerkleTreeError::{
+    InconsistentOpenings, RootMismatch, WrongBatchSize, WrongCapSize, WrongHeight,
+};
+use crate::{effective_cap_height, MerkleTree, MerkleTreeBuildError, MerkleTreeOptions};
 
 /// A vector commitment scheme backed by a `MerkleTree`.
 ///
+/// The commitment is a `MerkleCap` of `2^cap_height` digests, and opening proofs stop below it.
+/// The cap height is 0 by default, so the commitment is just the root.
+///
+/// A multi-batch opening proof is the sibling digests which the verifier can't compute from the
+/// opened rows, layer by layer from the leaves up, and from left to right within each layer. So
+/// queries of nearby rows share the upper parts of their paths.
+///
 /// Generics:
 /// - `P`: a leaf value
 /// - `PW`: an element of a digest
@@ -24,6 +36,7 @@ use crate::MerkleTreeError::{RootMismatch, WrongBatchSize, WrongHeight};
 pub struct MerkleTreeMmcs<P, PW, H, C, const DIGEST_ELEMS: usize> {
     hash: H,
     compress: C,
+    cap_height: usize,
     _phantom: PhantomData<(P, PW)>,
 }
 
@@ -35,7 +48,13 @@ pub enum MerkleTreeError {
         max_height: usize,
         num_siblings: usize,
     },
+    WrongCapSize {
+        cap_height: usize,
+        cap_len: usize,
+    },
     RootMismatch,
+    /// A multi-batch opening opened different values for the same row.
+    InconsistentOpenings,
 }
 
 impl<P, PW, H, C, const DIGEST_ELEMS: usize> MerkleTreeMmcs<P, PW, H, C, DIGEST_ELEMS> {
@@ -43,9 +62,224 @@ impl<P, PW, H, C, const DIGEST_ELEMS: usize> MerkleTreeMmcs<P, PW, H, C, DIGEST_
         Self {
             hash,
             compress,
+            cap_height: 0,
             _phantom: PhantomData,
         }
     }
+
+    /// Commit to the layer `cap_height` below the root instead of the root, shortening every
+    /// opening proof by `cap_height` digests.
+    pub fn with_cap_height(self, cap_height: usize) -> Self {
+        Self { cap_height, ..self }
+    }
+
+    pub const fn cap_height(&self) -> usize {
+        self.cap_height
+    }
+}
+
+/// The digests of a layer of a Merkle tree known to the verifier of a multi-batch opening.
+type KnownDigests<W, const DIGEST_ELEMS: usize> = BTreeMap<usize, [W; DIGEST_ELEMS]>;
+
+impl<P, PW, H, C, const DIGEST_ELEMS: usize> MerkleTreeMmcs<P, PW, H, C, DIGEST_ELEMS>
+where
+    P: PackedValue,
+    PW: PackedValue,
+    H: CryptographicHasher<P::Value, [PW::Value; DIGEST_ELEMS]>,
+    C: PseudoCompressionFunction<[PW::Value; DIGEST_ELEMS], 2>,
+    PW::Value: Eq,
+{
+    /// Verify a multi-batch opening, and recover the authentication path of each index, as
+    /// `open_batch` would have given it.
+    ///
+    /// This is for verifiers which check each path separately, such as recursive verifiers whose
+    /// shape can't depend on which queried paths overlap.
+    pub fn multi_batch_paths(
+        &self,
+        commit: &MerkleCap<P::Value, PW::Value, DIGEST_ELEMS>,
+        dimensions: &[Dimensions],
+        indices: &[usize],
+        opened_values: &[Vec<Vec<P::Value>>],
+        proof: &[[PW::Value; DIGEST_ELEMS]],
+    ) -> Result<Vec<Vec<[PW::Value; DIGEST_ELEMS]>>, MerkleTreeError> {
+        let layers =
+            self.verify_multi_batch_layers(commit, dimensions, indices, opened_values, proof)?;
+        Ok(indices
+            .iter()
+            .map(|&index| {
+                layers
+                    .iter()
+                    .enumerate()
+                    .map(|(i, layer)| layer[&((index >> i) ^ 1)])
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Verify a multi-batch opening, returning the digests the verifier knows at each layer below
+    /// the cap: those computed on the queried paths, and their siblings from the proof.
+    fn verify_multi_batch_layers(
+        &self,
+        commit: &MerkleCap<P::Value, PW::Value, DIGEST_ELEMS>,
+        dimensions: &[Dimensions],
+        indices: &[usize],
+        opened_values: &[Vec<Vec<P::Value>>],
+        proof: &[[PW::Value; DIGEST_ELEMS]],
+    ) -> Result<Vec<KnownDigests<PW::Value, DIGEST_ELEMS>>, MerkleTreeError> {
+        // Check that the openings have the correct shape.
+        if opened_values.len() != indices.len()
+            || opened_values
+                .iter()
+                .any(|rows| rows.len() != dimensions.len())
+        {
+            return Err(WrongBatchSize);
+        }
+        let Some(max_height) = dimensions.iter().map(|dims| dims.height).max() else {
+            return Err(WrongBatchSize);
+        };
+        let log_max_height = log2_ceil_usize(max_height);
+        let cap_height =
+            effective_cap_height(self.cap_height, dimensions.iter().map(|dims| dims.height));
+        if commit.len() != 1 << cap_height {
+            return Err(WrongCapSize {
+                cap_height,
+                cap_len: commit.len(),
+            });
+        }
+        let num_layers = log_max_height - cap_height;
+
+        // The matrices hashed into each layer, in the order they're hashed. The cap is never above
+        // the smallest matrix, so every matrix is hashed in below it.
+        let mut injected = vec![vec![]; num_layers + 1];
+        for (i, dims) in dimensions
+            .iter()
+            .enumerate()
+            .sorted_by_key(|(_, dims)| Reverse(dims.height))
+        {
+            injected[log_max_height - log2_ceil_usize(dims.height)].push(i);
+        }
+        let hash_rows = |query: usize, matrices: &[usize]| -> [PW::Value; DIGEST_ELEMS] {
+            self.hash
+                .hash_iter_slices(matrices.iter().map(|&i| opened_values[query][i].as_slice()))
+        };
+        // Once two queries' paths meet, only one of them is followed up the tree, so they must
+        // agree on every row injected from that layer on.
+        let check_consistent = |query: usize, other: usize, layer: usize| {
+            if injected[layer..]
+                .iter()
+                .flatten()
+                .all(|&i| opened_values[query][i] == opened_values[other][i])
+            {
+                Ok(())
+            } else {
+                Err(InconsistentOpenings)
+            }
+        };
+
+        let mut nodes: BTreeMap<usize, (usize, [PW::Value; DIGEST_ELEMS])> = BTreeMap::new();
+        for (query, &index) in indices.iter().enumerate() {
+            if let Some(&(other, _)) = nodes.get(&index) {
+                check_consistent(query, other, 0)?;
+            } else {
+                nodes.insert(index, (query, hash_rows(query, &injected[0])));
+            }
+        }
+
+        let mut siblings = proof.iter();
+        let mut layers = Vec::with_capacity(num_layers);
+        for layer in 0..num_layers {
+            let mut known: KnownDigests<PW::Value, DIGEST_ELEMS> = nodes
+                .iter()
+                .map(|(&index, &(_, digest))| (index, digest))
+                .collect();
+            let mut parents = BTreeMap::new();
+            let mut nodes_iter = nodes.into_iter().peekable();
+            while let Some((index, (query, digest))) = nodes_iter.next() {
+                // A left node's sibling may be on another queried path; otherwise it's next in the
+                // proof.
+                let sibling = match nodes_iter.next_if(|&(i, _)| i == index ^ 1) {
+                    Some((_, (other, sibling))) => {
+                        check_consistent(query, other, layer + 1)?;
+                        sibling
+                    }
+                    None => {
+                        let &sibling = siblings.next().ok_or(WrongHeight {
+                            max_height,
+                            num_siblings: proof.len(),
+                        })?;
+                        known.insert(index ^ 1, sibling);
+                        sibling
+                    }
+                };
+                let (left, right) = if index & 1 == 0 {
+                    (digest, sibling)
+                } else {
+                    (sibling, digest)
+                };
+                let mut parent = self.compress.compress([left, right]);
+                if !injected[layer + 1].is_empty() {
+                    parent = self
+                        .compress
+                        .compress([parent, hash_rows(query, &injected[layer + 1])]);
+                }
+                parents.insert(index >> 1, (query, parent));
+            }
+            layers.push(known);
+            nodes = parents;
+        }
+        if siblings.next().is_some() {
+            return Err(WrongHeight {
+                max_height,
+                num_siblings: proof.len(),
+            });
+        }
+
+        // The paths end at the cap's digests above the opened leaves.
+        for (index, (_, digest)) in nodes {
+            if commit.roots().get(index) != Some(&digest) {
+                return Err(RootMismatch);
+            }
+        }
+        Ok(layers)
+    }
+}
+
+impl<P, PW, H, C, const DIGEST_ELEMS: usize> MerkleTreeMmcs<P, PW, H, C, DIGEST_ELEMS>
+where
+    P: PackedValue,
+    PW: PackedValue,
+    H: CryptographicHasher<P::Value, [PW::Value; DIGEST_ELEMS]>,
+    H: CryptographicHasher<P, [PW; DIGEST_ELEMS]>,
+    H: Sync,
+    C: PseudoCompressionFunction<[PW::Value; DIGEST_ELEMS], 2>,
+    C: PseudoCompressionFunction<[PW; DIGEST_ELEMS], 2>,
+    C: Sync,
+{
+    /// Commit to `inputs` as `commit` does, but build the tree as `options` say, e.g. hashing in
+    /// chunks and keeping the digest layers on disk. The commitment and prover data are the same
+    /// as `commit` gives, and are opened in the same way.
+    ///
+    /// Fails if a digest layer's file can't be created.
+    pub fn commit_with_options<M: Matrix<P::Value>>(
+        &self,
+        inputs: Vec<M>,
+        options: &MerkleTreeOptions,
+    ) -> Result<
+        (
+            MerkleCap<P::Value, PW::Value, DIGEST_ELEMS>,
+            MerkleTree<P::Value, PW::Value, M, DIGEST_ELEMS>,
+        ),
+        MerkleTreeBuildError,
+    > {
+        let tree = MerkleTree::new_with_options::<P, PW, H, C>(
+            &self.hash,
+            &self.compress,
+            inputs,
+            options,
+        )?;
+        let cap = tree.cap(self.cap_height);
+        Ok((cap, tree))
+    }
 }
 
 impl<P, PW, H, C, const DIGEST_ELEMS: usize> Mmcs<P::Value>
@@ -63,17 +297,22 @@ where
     [PW::Value; DIGEST_ELEMS]: Serialize + for<'de> Deserialize<'de>,
 {
     type ProverData<M> = MerkleTree<P::Value, PW::Value, M, DIGEST_ELEMS>;
-    type Commitment = Hash<P::Value, PW::Value, DIGEST_ELEMS>;
+    type Commitment = MerkleCap<P::Value, PW::Value, DIGEST_ELEMS>;
     type Proof = Vec<[PW::Value; DIGEST_ELEMS]>;
+    type MultiProof = Vec<[PW::Value; DIGEST_ELEMS]>;
     type Error = MerkleTreeError;
 
+    fn parameters(&self) -> Vec<usize> {
+        vec![self.cap_height]
+    }
+
     fn commit<M: Matrix<P::Value>>(
         &self,
         inputs: Vec<M>,
     ) -> (Self::Commitment, Self::ProverData<M>) {
         let tree = MerkleTree::new::<P, PW, H, C>(&self.hash, &self.compress, inputs);
-        let root = tree.root();
-        (root, tree)
+        let cap = tree.cap(self.cap_height);
+        (cap, tree)
     }
 
     fn open_batch<M: Matrix<P::Value>>(
@@ -81,27 +320,52 @@ where
         index: usize,
         prover_data: &MerkleTree<P::Value, PW::Value, M, DIGEST_ELEMS>,
     ) -> (Vec<Vec<P::Value>>, Vec<[PW::Value; DIGEST_ELEMS]>) {
-        let max_height = self.get_max_height(prover_data);
-        let log_max_height = log2_ceil_usize(max_height);
+        let log_max_height = log2_ceil_usize(self.get_max_height(prover_data));
+        let openings = open_rows(prover_data, index);
 
-        let openings = prover_data
-            .leaves
-            .iter()
-            .map(|matrix| {
-                let log2_height = log2_ceil_usize(matrix.height());
-                let bits_reduced = log_max_height - log2_height;
-                let reduced_index = index >> bits_reduced;
-                matrix.row(reduced_index).collect()
-            })
-            .collect_vec();
-
-        let proof: Vec<_> = (0..log_max_height)
+        let cap_height = effective_cap_height(
+            self.cap_height,
+            prover_data.leaves.iter().map(|m| m.height()),
+        );
+        let proof: Vec<_> = (0..log_max_height - cap_height)
             .map(|i| prover_data.digest_layers[i][(index >> i) ^ 1])
             .collect();
 
         (openings, proof)
     }
 
+    fn open_multi_batch<M: Matrix<P::Value>>(
+        &self,
+        indices: &[usize],
+        prover_data: &MerkleTree<P::Value, PW::Value, M, DIGEST_ELEMS>,
+    ) -> (Vec<Vec<Vec<P::Value>>>, Vec<[PW::Value; DIGEST_ELEMS]>) {
+        let log_max_height = log2_ceil_usize(self.get_max_height(prover_data));
+        let openings = indices
+            .iter()
+            .map(|&index| open_rows(prover_data, index))
+            .collect();
+
+        let cap_height = effective_cap_height(
+            self.cap_height,
+            prover_data.leaves.iter().map(|m| m.height()),
+        );
+        // Each layer's queried nodes, in order. Siblings which are themselves queried nodes are
+        // computed by the verifier, so only the others are sent.
+        let mut nodes = indices.iter().copied().sorted().dedup().collect_vec();
+        let mut proof = vec![];
+        for layer in &prover_data.digest_layers[..log_max_height - cap_height] {
+            let mut nodes_iter = nodes.iter().copied().peekable();
+            while let Some(index) = nodes_iter.next() {
+                if nodes_iter.next_if_eq(&(index ^ 1)).is_none() {
+                    proof.push(layer[index ^ 1]);
+                }
+            }
+            nodes = nodes.into_iter().map(|index| index >> 1).dedup().collect();
+        }
+
+        (openings, proof)
+    }
+
     fn get_matrices<'a, M: Matrix<P::Value>>(
         &self,
         prover_data: &'a Self::ProverData<M>,
@@ -132,7 +396,15 @@ where
         // TODO: Disabled for now, CirclePcs sometimes passes a height that's off by 1 bit.
         let max_height = dimensions.iter().map(|dim| dim.height).max().unwrap();
         let log_max_height = log2_ceil_usize(max_height);
-        if proof.len() != log_max_height {
+        let cap_height =
+            effective_cap_height(self.cap_height, dimensions.iter().map(|dims| dims.height));
+        if commit.len() != 1 << cap_height {
+            return Err(WrongCapSize {
+                cap_height,
+                cap_len: commit.len(),
+            });
+        }
+        if proof.len() != log_max_height - cap_height {
             return Err(WrongHeight {
                 max_height,
                 num_siblings: proof.len(),
@@ -186,12 +458,171 @@ where
             }
         }
 
-        if commit == &root {
+        // The path ends at the cap's digest above the opened leaf.
+        if commit.roots().get(index) == Some(&root) {
             Ok(())
         } else {
             Err(RootMismatch)
         }
     }
+
+    fn verify_multi_batch(
+        &self,
+        commit: &Self::Commitment,
+        dimensions: &[Dimensions],
+        indices: &[usize],
+        opened_values: &[Vec<Vec<P::Value>>],
+        proof: &Self::MultiProof,
+    ) -> Result<(), Self::Error> {
+        self.verify_multi_batch_layers(commit, dimensions, indices, opened_values, proof)
+            .map(|_| ())
+    }
+}
+
+/// The rows of each committed matrix at `index` of the tallest.
+fn open_rows<F, W, M, const DIGEST_ELEMS: usize>(
+    tree: &MerkleTree<F, W, M, DIGEST_ELEMS>,
+    index: usize,
+) -> Vec<Vec<F>>
+where
+    F: Clone + Send + Sync,
+    M: Matrix<F>,
+{
+    let log_max_height = log2_ceil_usize(tree.leaves.iter().map(|m| m.height()).max().unwrap());
+    tree.leaves
+        .iter()
+        .map(|matrix| {
+            let log2_height = log2_ceil_usize(matrix.height());
+            let bits_reduced = log_max_height - log2_height;
+            let reduced_index = index >> bits_reduced;
+            matrix.row(reduced_index).collect()
+        })
+        .collect()
+}
+
+impl<P, PW, H, C, const DIGEST_ELEMS: usize> MmcsCostModel
+    for MerkleTreeMmcs<P, PW, H, C, DIGEST_ELEMS>
+where
+    P: PackedValue,
+    PW: PackedValue,
+{
+    fn commitment_bytes(&self) -> usize {
+        (1 << self.cap_height) * size_of::<[PW::Value; DIGEST_ELEMS]>()
+    }
+
+    fn value_bytes(&self) -> usize {
+        size_of::<P::Value>()
+    }
+
+    fn opening_proof_bytes(&self, dimensions: &[Dimensions]) -> usize {
+        let max_height = dimensions.iter().map(|dims| dims.height).max().unwrap_or(1);
+        let cap_height =
+            effective_cap_height(self.cap_height, dimensions.iter().map(|dims| dims.height));
+        (log2_ceil_usize(max_height) - cap_height) * size_of::<[PW::Value; DIGEST_ELEMS]>()
+    }
+
+    fn commit_cost(&self, dimensions: &[Dimensions]) -> HashCount {
+        let layers = injected_layers(dimensions);
+        let Some(&(max_height, _)) = layers.first() else {
+            return HashCount::default();
+        };
+        // Every row is hashed. Each layer after the first compresses pairs of digests from the
+        // layer above, and again to inject the digests of any matrices of its height.
+        HashCount {
+            hashes: layers.iter().map(|&(height, _)| height).sum(),
+            hashed_elements: layers.iter().map(|&(height, width)| height * width).sum(),
+            compressions: max_height - 1
+                + layers[1..].iter().map(|&(height, _)| height).sum::<usize>(),
+        }
+    }
+
+    fn verify_cost(&self, dimensions: &[Dimensions]) -> HashCount {
+        let layers = injected_layers(dimensions);
+        let Some(&(max_height, _)) = layers.first() else {
+            return HashCount::default();
+        };
+        // Every matrix is injected below the cap, whose digest the path ends at.
+        let cap_height =
+            effective_cap_height(self.cap_height, dimensions.iter().map(|dims| dims.height));
+        HashCount {
+            hashes: layers.len(),
+            hashed_elements: layers.iter().map(|&(_, width)| width).sum(),
+            compressions: log2_ceil_usize(max_height) - cap_height + layers.len() - 1,
+        }
+    }
+
+    fn multi_opening_proof_bytes(&self, dimensions: &[Dimensions], num_indices: usize) -> usize {
+        let max_height = dimensions.iter().map(|dims| dims.height).max().unwrap_or(1);
+        let cap_height =
+            effective_cap_height(self.cap_height, dimensions.iter().map(|dims| dims.height));
+        // A layer's siblings are sent unless they're queried nodes themselves, so there's one for
+        // each queried node of the layer above, less one for each pair of queried nodes.
+        let num_siblings: f64 = (0..log2_ceil_usize(max_height) - cap_height)
+            .map(|i| {
+                let num_nodes = max_height.next_power_of_two() >> i;
+                2.0 * expected_distinct(num_nodes / 2, num_indices)
+                    - expected_distinct(num_nodes, num_indices)
+            })
+            .sum();
+        round(num_siblings) * size_of::<[PW::Value; DIGEST_ELEMS]>()
+    }
+
+    fn multi_verify_cost(&self, dimensions: &[Dimensions], num_indices: usize) -> HashCount {
+        let layers = injected_layers(dimensions);
+        let Some(&(max_height, _)) = layers.first() else {
+            return HashCount::default();
+        };
+        let cap_height =
+            effective_cap_height(self.cap_height, dimensions.iter().map(|dims| dims.height));
+        // Each queried node is computed once, however many queries share it.
+        let parents: f64 = (1..=log2_ceil_usize(max_height) - cap_height)
+            .map(|i| expected_distinct(max_height >> i, num_indices))
+            .sum();
+        let injections: f64 = layers[1..]
+            .iter()
+            .map(|&(height, _)| expected_distinct(height, num_indices))
+            .sum();
+        HashCount {
+            hashes: round(
+                layers
+                    .iter()
+                    .map(|&(height, _)| expected_distinct(height, num_indices))
+                    .sum(),
+            ),
+            hashed_elements: round(
+                layers
+                    .iter()
+                    .map(|&(height, width)| expected_distinct(height, num_indices) * width as f64)
+                    .sum(),
+            ),
+            compressions: round(parents + injections),
+        }
+    }
+}
+
+/// The expected number of distinct nodes among `num_indices` chosen uniformly at random from
+/// `num_nodes`.
+fn expected_distinct(num_nodes: usize, num_indices: usize) -> f64 {
+    let num_nodes = num_nodes as f64;
+    let p_missed = (0..num_indices).fold(1.0, |p, _| p * (1.0 - 1.0 / num_nodes));
+    num_nodes * (1.0 - p_missed)
+}
+
+fn round(x: f64) -> usize {
+    (x + 0.5) as usize
+}
+
+/// The heights, rounded up to powers of two, at which matrices are hashed into a Merkle tree, and
+/// the total width of the matrices at each, tallest first.
+pub(crate) fn injected_layers(dimensions: &[Dimensions]) -> Vec<(usize, usize)> {
+    dimensions
+        .iter()
+        .map(|dims| (dims.height.next_power_of_two(), dims.width))
+        .sorted_by_key(|&(height, _)| Reverse(height))
+        .chunk_by(|&(height, _)| height)
+        .into_iter()
+        .map(|(height, group)| (height, group.map(|(_, width)| width).sum()))
+        .collect()
 }
 
 #[cfg(test)]
@@ -200,7 +631,7 @@ mod tests {
 
     use itertools::Itertools;
     use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
-    use p3_commit::Mmcs;
+    use p3_commit::{HashCount, Mmcs, MmcsCostModel};
     use p3_field::{Field, FieldAlgebra};
     use p3_matrix::dense::RowMajorMatrix;
     use p3_matrix::{Dimensions, Matrix};
@@ -210,6 +641,7 @@ mod tests {
     use rand::thread_rng;
 
     use super::MerkleTreeMmcs;
+    use crate::MerkleTreeOptions;
 
     type F = BabyBear;
 
@@ -558,4 +990,250 @@ mod tests {
         mmcs.verify_batch(&commit, &dims, 17, &opened_values, &proof)
             .expect("expected verification to succeed");
     }
+
+    #[test]
+    fn cap() {
+        let mut rng = thread_rng();
+        let perm = Perm::new_from_rng_128(&mut rng);
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm);
+        let mmcs = MyMmcs::new(hash, compress.clone());
+        let capped_mmcs = mmcs.clone().with_cap_height(2);
+
+        let mats = (0..3)
+            .map(|_| RowMajorMatrix::<F>::rand(&mut rng, 16, 4))
+            .collect_vec();
+        let dims = mats.iter().map(|m| m.dimensions()).collect_vec();
+
+        let (root, _) = mmcs.commit(mats.clone());
+        let (cap, prover_data) = capped_mmcs.commit(mats);
+        assert_eq!(cap.len(), 4);
+
+        // The cap compresses to the root.
+        let cap_digests = cap.roots();
+        let expected_root = compress.compress([
+            compress.compress([cap_digests[0], cap_digests[1]]),
+            compress.compress([cap_digests[2], cap_digests[3]]),
+        ]);
+        assert_eq!(root, expected_root);
+
+        let (opened_values, proof) = capped_mmcs.open_batch(9, &prover_data);
+        assert_eq!(proof.len(), 2);
+        capped_mmcs
+            .verify_batch(&cap, &dims, 9, &opened_values, &proof)
+            .expect("expected verification to succeed");
+
+        // A proof against the root doesn't verify against the cap, and vice versa.
+        let (_, full_proof) = mmcs.open_batch(9, &prover_data);
+        assert_eq!(full_proof.len(), 4);
+        assert_eq!(&full_proof[..2], &proof[..]);
+        capped_mmcs
+            .verify_batch(&cap, &dims, 9, &opened_values, &full_proof)
+            .expect_err("expected verification to fail");
+        capped_mmcs
+            .verify_batch(&root, &dims, 9, &opened_values, &proof)
+            .expect_err("expected verification to fail");
+    }
+
+    #[test]
+    fn cap_lowered_to_smallest_matrix() {
+        let mut rng = thread_rng();
+        let perm = Perm::new_from_rng_128(&mut rng);
+        let mmcs = MyMmcs::new(MyHash::new(perm.clone()), MyCompress::new(perm)).with_cap_height(3);
+
+        // The 2-row matrix is injected 1 layer below the root, so the cap can't be any lower.
+        let mats = vec![
+            RowMajorMatrix::<F>::rand(&mut rng, 32, 2),
+            RowMajorMatrix::<F>::rand(&mut rng, 2, 3),
+        ];
+        let dims = mats.iter().map(|m| m.dimensions()).collect_vec();
+
+        let (cap, prover_data) = mmcs.commit(mats);
+        assert_eq!(cap.len(), 2);
+        let (opened_values, proof) = mmcs.open_batch(21, &prover_data);
+        assert_eq!(proof.len(), 4);
+        mmcs.verify_batch(&cap, &dims, 21, &opened_values, &proof)
+            .expect("expected verification to succeed");
+    }
+
+    #[test]
+    fn multi_batch() {
+        let mut rng = thread_rng();
+        let perm = Perm::new_from_rng_128(&mut rng);
+        let mmcs = MyMmcs::new(MyHash::new(perm.clone()), MyCompress::new(perm));
+
+        let mats = vec![
+            RowMajorMatrix::<F>::rand(&mut rng, 64, 3),
+            RowMajorMatrix::<F>::rand(&mut rng, 32, 2),
+            RowMajorMatrix::<F>::rand(&mut rng, 4, 5),
+        ];
+        let dims = mats.iter().map(|m| m.dimensions()).collect_vec();
+        let (commit, prover_data) = mmcs.commit(mats);
+
+        // Repeated indices, siblings, and indices whose paths meet at various heights.
+        let indices = [37, 5, 36, 37, 62, 4, 0];
+        let (opened_values, proof) = mmcs.open_multi_batch(&indices, &prover_data);
+        mmcs.verify_multi_batch(&commit, &dims, &indices, &opened_values, &proof)
+            .expect("expected verification to succeed");
+
+        // The openings and recovered paths are those of the individual openings, and the shared
+        // siblings are only sent once.
+        let paths = mmcs
+            .multi_batch_paths(&commit, &dims, &indices, &opened_values, &proof)
+            .expect("expected verification to succeed");
+        for ((&index, opened_values), path) in indices.iter().zip(&opened_values).zip(&paths) {
+            assert_eq!(
+                mmcs.open_batch(index, &prover_data),
+                (opened_values.clone(), path.clone())
+            );
+        }
+        assert!(proof.len() < paths.iter().map(|path| path.len()).sum());
+
+        // Dropping or adding a sibling fails.
+        mmcs.verify_multi_batch(
+            &commit,
+            &dims,
+            &indices,
+            &opened_values,
+            &proof[1..].to_vec(),
+        )
+        .expect_err("expected verification to fail");
+        let mut long_proof = proof.clone();
+        long_proof.push(proof[0]);
+        mmcs.verify_multi_batch(&commit, &dims, &indices, &opened_values, &long_proof)
+            .expect_err("expected verification to fail");
+    }
+
+    #[test]
+    fn multi_batch_inconsistent_openings_fail() {
+        let mut rng = thread_rng();
+        let perm = Perm::new_from_rng_128(&mut rng);
+        let mmcs = MyMmcs::new(MyHash::new(perm.clone()), MyCompress::new(perm)).with_cap_height(1);
+
+        let mats = vec![
+            RowMajorMatrix::<F>::rand(&mut rng, 16, 2),
+            RowMajorMatrix::<F>::rand(&mut rng, 4, 2),
+        ];
+        let dims = mats.iter().map(|m| m.dimensions()).collect_vec();
+        let (commit, prover_data) = mmcs.commit(mats);
+
+        // Rows 8 and 10 share their row of the short matrix, which is only hashed once, on the
+        // path of row 8, so the other opening of it must be checked separately.
+        let indices = [8, 10];
+        let (mut opened_values, proof) = mmcs.open_multi_batch(&indices, &prover_data);
+        mmcs.verify_multi_batch(&commit, &dims, &indices, &opened_values, &proof)
+            .expect("expected verification to succeed");
+        assert_eq!(opened_values[0][1], opened_values[1][1]);
+        opened_values[1][1][0] += F::ONE;
+        mmcs.verify_multi_batch(&commit, &dims, &indices, &opened_values, &proof)
+            .expect_err("expected verification to fail");
+    }
+
+    #[test]
+    fn cost_model() {
+        let perm = Perm::new_from_rng_128(&mut thread_rng());
+        let mmcs = MyMmcs::new(MyHash::new(perm.clone()), MyCompress::new(perm));
+        let dimensions = [
+            Dimensions {
+                width: 3,
+                height: 2,
+            },
+            Dimensions {
+                width: 4,
+                height: 8,
+            },
+        ];
+
+        // The 8 rows of the tall matrix are hashed and compressed up to the root, and the 2 rows of
+        // the short matrix are hashed and compressed into the layer of height 2.
+        assert_eq!(
+            mmcs.commit_cost(&dimensions),
+            HashCount {
+                hashes: 10,
+                hashed_elements: 38,
+                compressions: 9,
+            }
+        );
+        assert_eq!(
+            mmcs.verify_cost(&dimensions),
+            HashCount {
+                hashes: 2,
+                hashed_elements: 7,
+                compressions: 4,
+            }
+        );
+        assert_eq!(mmcs.commitment_bytes(), 32);
+        assert_eq!(mmcs.opening_proof_bytes(&dimensions), 3 * 32);
+
+        // A single index costs the same as an ordinary opening, while two can share a sibling.
+        assert_eq!(mmcs.multi_opening_proof_bytes(&dimensions, 1), 3 * 32);
+        assert_eq!(
+            mmcs.multi_verify_cost(&dimensions, 1),
+            mmcs.verify_cost(&dimensions)
+        );
+        assert!(mmcs.multi_opening_proof_bytes(&dimensions, 2) < 2 * 3 * 32);
+    }
+
+    #[test]
+    fn commit_with_options() {
+        let mut rng = thread_rng();
+        let perm = Perm::new_from_rng_128(&mut rng);
+        let mmcs = MyMmcs::new(MyHash::new(perm.clone()), MyCompress::new(perm));
+
+        let mats = vec![
+            RowMajorMatrix::<F>::rand(&mut rng, 77, 3),
+            RowMajorMatrix::<F>::rand(&mut rng, 37, 2),
+            RowMajorMatrix::<F>::rand(&mut rng, 5, 4),
+        ];
+        let (commit, prover_data) = mmcs.commit(mats.clone());
+
+        // Chunks which do and don't divide the heights give the same tree.
+        for chunk_rows in [1, 5, 16, 1000] {
+            let options = MerkleTreeOptions::default().with_chunk_rows(chunk_rows);
+            let (chunked_commit, chunked_data) = mmcs
+                .commit_with_options(mats.clone(), &options)
+                .expect("in-memory commitments don't fail");
+            assert_eq!(chunked_commit, commit);
+            for index in [0, 36, 76] {
+                assert_eq!(
+                    mmcs.open_batch(index, &chunked_data),
+                    mmcs.open_batch(index, &prover_data)
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn commit_out_of_core() {
+        use p3_matrix::mmap::MmapMatrix;
+        use std::env::temp_dir;
+
+        let mut rng = thread_rng();
+        let perm = Perm::new_from_rng_128(&mut rng);
+        let mmcs = MyMmcs::new(MyHash::new(perm.clone()), MyCompress::new(perm));
+
+        // Tall enough that the lower digest layers are stored on disk.
+        let mat = RowMajorMatrix::<F>::rand(&mut rng, 1 << 14, 3);
+        let dims = [mat.dimensions()];
+        let (commit, _) = mmcs.commit(vec![mat.clone()]);
+
+        let mut mapped = MmapMatrix::temp_in(temp_dir(), 3, 1 << 14, F::ZERO).unwrap();
+        mapped.copy_from(&mat);
+        let options = MerkleTreeOptions::default()
+            .with_chunk_rows(1000)
+            .with_digest_dir(temp_dir());
+        let (mapped_commit, prover_data) = mmcs
+            .commit_with_options(vec![mapped], &options)
+            .expect("failed to create the digest layer files");
+        assert_eq!(mapped_commit, commit);
+
+        let (opened_values, proof) = mmcs.open_batch(12345, &prover_data);
+        mmcs.verify_batch(&commit, &dims, 12345, &opened_values, &proof)
+            .expect("expected verification to succeed");
+
+        // A digest directory which doesn't exist gives an error rather than a panic.
+        let options = MerkleTreeOptions::default().with_digest_dir(temp_dir().join("p3-missing"));
+        assert!(mmcs.commit_with_options(vec![mat], &options).is_err());
+    }
 }
@@ -433,7 +433,7 @@ pub const fn to_mersenne31_array<const N: usize>(input: [u32; N]) -> [Mersenne31
 #[cfg(test)]
 mod tests {
     use p3_field::{Field, FieldAlgebra, PrimeField32};
-    use p3_field_testing::test_field;
+    use p3_field_testing::{test_field, test_field_subgroup_dft};
 
     use crate::Mersenne31;
 
@@ -489,4 +489,12 @@ mod tests {
     }
 
     test_field!(crate::Mersenne31);
+
+    // The multiplicative group has order `2 * 3^2 * 7 * 11 * 31 * 151 * 331`.
+    test_field_subgroup_dft!(
+        mixed_radix,
+        crate::Mersenne31,
+        p3_dft::MixedRadixDft,
+        [1, 2, 3, 6, 7, 9, 11, 14, 18, 21, 31, 63, 99, 151]
+    );
 }
@@ -105,6 +105,8 @@ fn main() -> Result<(), impl Debug> {
 
     let fri_config = FriConfig {
         log_blowup: 1, // TODO: Should this be 3? Why is it working?
+        log_arity: 1,
+        log_final_poly_len: 0,
         num_queries: 100,
         proof_of_work_bits: 16,
         mmcs: challenge_mmcs,
@@ -116,7 +118,7 @@ fn main() -> Result<(), impl Debug> {
     let config = MyConfig::new(pcs);
 
     let mut challenger = Challenger::from_hasher(vec![], byte_hash);
-    let proof = prove(&config, &air, &mut challenger, trace, &vec![]);
+    let proof = prove(&config, &air, &mut challenger, trace, &vec![]).expect("proving failed");
 
     let mut challenger = Challenger::from_hasher(vec![], byte_hash);
     verify(&config, &air, &mut challenger, &proof, &vec![])
@@ -103,8 +103,11 @@ fn main() -> Result<(), impl Debug> {
 
     let dft = Dft::default();
 
+    // Zero-knowledge mode doubles the quotient degree, so needs a larger blowup.
     let fri_config = FriConfig {
-        log_blowup: 1,
+        log_blowup: 2,
+        log_arity: 1,
+        log_final_poly_len: 0,
         num_queries: 100,
         proof_of_work_bits: 16,
         mmcs: challenge_mmcs,
@@ -116,7 +119,7 @@ fn main() -> Result<(), impl Debug> {
     let config = MyConfig::new(pcs);
 
     let mut challenger = Challenger::from_hasher(vec![], byte_hash);
-    let proof = prove(&config, &air, &mut challenger, trace, &vec![]);
+    let proof = prove(&config, &air, &mut challenger, trace, &vec![]).expect("proving failed");
 
     let mut challenger = Challenger::from_hasher(vec![], byte_hash);
     verify(&config, &air, &mut challenger, &proof, &vec![])
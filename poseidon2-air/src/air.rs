@@ -74,6 +74,10 @@ impl<
     fn width(&self) -> usize {
         num_cols::<WIDTH, SBOX_DEGREE, SBOX_REGISTERS, HALF_FULL_ROUNDS, PARTIAL_ROUNDS>()
     }
+
+    fn compile_constraints(&self) -> bool {
+        true
+    }
 }
 
 pub(crate) fn eval<
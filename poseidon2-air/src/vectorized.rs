@@ -201,6 +201,10 @@ impl<
     fn width(&self) -> usize {
         self.air.width() * VECTOR_LEN
     }
+
+    fn compile_constraints(&self) -> bool {
+        true
+    }
 }
 
 impl<
@@ -0,0 +1,475 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use p3_air::{
+    Air, AirBuilderWithPublicValues, BaseAir, MultiTableAirBuilder, PairBuilder, VirtualPairCol,
+};
+use p3_field::{ExtensionField, Field, PrimeField};
+use p3_lookup::{LogUp, Lookup};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_poseidon2::GenericPoseidon2LinearLayers;
+use p3_poseidon2_air::{generate_trace_rows, num_cols, Poseidon2Air, RoundConstants};
+
+use crate::{ArithmeticGate, Circuit, Target, WIDTH};
+
+// Preprocessed columns of the arithmetic table. Each row is a gate, or a witness, whose output is
+// written to the `out` column. Gates read their inputs from the `a`, `b`, and `c` columns.
+const A_ID: usize = 0;
+const B_ID: usize = 1;
+const C_ID: usize = 2;
+const OUT_ID: usize = 3;
+/// Whether `a`, `b`, and `c` are read, followed by the number of times the output is read.
+const A_READ: usize = 4;
+const OUT_MULT: usize = 7;
+const Q_MUL: usize = 8;
+const Q_A: usize = 9;
+const Q_B: usize = 10;
+const Q_C: usize = 11;
+const Q_CONST: usize = 12;
+const Q_OUT: usize = 13;
+/// One selector per public value, flagging the row of the public input exposing it.
+const PUBLIC_SELECTORS: usize = 14;
+
+// Main columns of the arithmetic table.
+const A: usize = 0;
+const B: usize = 1;
+const C: usize = 2;
+const OUT: usize = 3;
+
+// Preprocessed columns of the Poseidon2 table: the wires read and written by each permutation,
+// the number of times each output is read, and whether the row is a gate rather than padding.
+const IN_IDS: usize = 0;
+const OUT_IDS: usize = WIDTH;
+const OUT_MULTS: usize = 2 * WIDTH;
+const ACTIVE: usize = 3 * WIDTH;
+
+/// The tables proving a [`Circuit`] with the batch prover: an arithmetic table, with one row per
+/// arithmetic gate or witness, and a Poseidon2 table with one row per permutation.
+///
+/// Every wire is sent over a LogUp bus as an `(id, value)` pair by the row writing it, as many
+/// times as it's read, and received by each row reading it, which connects the gates. The layout
+/// of the tables is fixed by the circuit's wiring in their preprocessed traces, so a verifying key
+/// is specific to a circuit, but not to the values of its wires.
+pub enum RecursionAir<
+    F: Field,
+    LinearLayers,
+    const SBOX_DEGREE: u64,
+    const SBOX_REGISTERS: usize,
+    const HALF_FULL_ROUNDS: usize,
+    const PARTIAL_ROUNDS: usize,
+> {
+    Arithmetic(ArithmeticTable<F>),
+    Poseidon2(
+        Poseidon2Table<
+            F,
+            LinearLayers,
+            SBOX_DEGREE,
+            SBOX_REGISTERS,
+            HALF_FULL_ROUNDS,
+            PARTIAL_ROUNDS,
+        >,
+    ),
+}
+
+/// The table of arithmetic gates and witnesses, which checks that
+/// `q_out * out = q_mul * a * b + q_a * a + q_b * b + q_c * c + q_const` on each row.
+pub struct ArithmeticTable<F: Field> {
+    preprocessed: RowMajorMatrix<F>,
+    num_public_values: usize,
+    logup: LogUp<F>,
+}
+
+/// The table of Poseidon2 permutations, each checked by a [`Poseidon2Air`] row.
+pub struct Poseidon2Table<
+    F: Field,
+    LinearLayers,
+    const SBOX_DEGREE: u64,
+    const SBOX_REGISTERS: usize,
+    const HALF_FULL_ROUNDS: usize,
+    const PARTIAL_ROUNDS: usize,
+> {
+    air: Poseidon2Air<
+        F,
+        LinearLayers,
+        WIDTH,
+        SBOX_DEGREE,
+        SBOX_REGISTERS,
+        HALF_FULL_ROUNDS,
+        PARTIAL_ROUNDS,
+    >,
+    constants: RoundConstants<F, WIDTH, HALF_FULL_ROUNDS, PARTIAL_ROUNDS>,
+    preprocessed: RowMajorMatrix<F>,
+    logup: LogUp<F>,
+}
+
+/// The rows of the arithmetic table, in order: the public inputs, the other witnesses, then the
+/// gates.
+fn arithmetic_rows<F: Field>(circuit: &Circuit<F>) -> impl Iterator<Item = ArithmeticRow<'_, F>> {
+    circuit
+        .public_inputs
+        .iter()
+        .chain(&circuit.witnesses)
+        .map(|&target| ArithmeticRow::Witness(target))
+        .chain(circuit.arithmetic_gates.iter().map(ArithmeticRow::Gate))
+}
+
+enum ArithmeticRow<'a, F> {
+    Witness(Target),
+    Gate(&'a ArithmeticGate<F>),
+}
+
+/// The number of times each wire is read, by either kind of gate.
+fn read_counts<F: Field>(circuit: &Circuit<F>) -> Vec<usize> {
+    let mut counts = vec![0; circuit.values.len()];
+    let gate_inputs = circuit
+        .arithmetic_gates
+        .iter()
+        .flat_map(|gate| gate.inputs.iter().flatten());
+    let permutation_inputs = circuit.poseidon2_gates.iter().flat_map(|gate| &gate.inputs);
+    for target in gate_inputs.chain(permutation_inputs) {
+        counts[target.index()] += 1;
+    }
+    counts
+}
+
+impl<
+        F: PrimeField,
+        LinearLayers: GenericPoseidon2LinearLayers<F, WIDTH>,
+        const SBOX_DEGREE: u64,
+        const SBOX_REGISTERS: usize,
+        const HALF_FULL_ROUNDS: usize,
+        const PARTIAL_ROUNDS: usize,
+    > RecursionAir<F, LinearLayers, SBOX_DEGREE, SBOX_REGISTERS, HALF_FULL_ROUNDS, PARTIAL_ROUNDS>
+{
+    /// The tables for proving `circuit`, whose Poseidon2 gates must use the given round constants.
+    pub fn tables(
+        circuit: &Circuit<F>,
+        constants: RoundConstants<F, WIDTH, HALF_FULL_ROUNDS, PARTIAL_ROUNDS>,
+    ) -> Vec<Self> {
+        let reads = read_counts(circuit);
+        vec![
+            Self::Arithmetic(ArithmeticTable::new(circuit, &reads)),
+            Self::Poseidon2(Poseidon2Table::new(circuit, &reads, constants)),
+        ]
+    }
+
+    /// Generate this table's main trace from the values of the circuit's wires.
+    pub fn generate_trace(&self, circuit: &Circuit<F>) -> RowMajorMatrix<F> {
+        match self {
+            Self::Arithmetic(table) => table.generate_trace(circuit),
+            Self::Poseidon2(table) => table.generate_trace(circuit),
+        }
+    }
+
+    /// This table's public values: those of the circuit for the arithmetic table, and none for the
+    /// Poseidon2 table.
+    pub fn public_values(&self, circuit: &Circuit<F>) -> Vec<F> {
+        match self {
+            Self::Arithmetic(_) => circuit.public_values(),
+            Self::Poseidon2(_) => vec![],
+        }
+    }
+}
+
+impl<F: Field> ArithmeticTable<F> {
+    fn new(circuit: &Circuit<F>, reads: &[usize]) -> Self {
+        let num_public_values = circuit.public_inputs.len();
+        let width = PUBLIC_SELECTORS + num_public_values;
+        let num_rows = arithmetic_rows(circuit).count().next_power_of_two();
+
+        let mut values = F::zero_vec(num_rows * width);
+        for (i, (row, values)) in arithmetic_rows(circuit)
+            .zip(values.chunks_exact_mut(width))
+            .enumerate()
+        {
+            let output = match row {
+                ArithmeticRow::Witness(target) => Some(target),
+                ArithmeticRow::Gate(gate) => {
+                    for (j, input) in gate.inputs.iter().enumerate() {
+                        if let Some(input) = input {
+                            values[A_ID + j] = F::from_canonical_usize(input.index());
+                            values[A_READ + j] = F::ONE;
+                        }
+                    }
+                    values[Q_MUL] = gate.mul;
+                    values[Q_A..=Q_C].copy_from_slice(&gate.linear);
+                    values[Q_CONST] = gate.constant;
+                    values[Q_OUT] = F::from_bool(gate.output.is_some());
+                    gate.output
+                }
+            };
+            if let Some(output) = output {
+                values[OUT_ID] = F::from_canonical_usize(output.index());
+                values[OUT_MULT] = F::from_canonical_usize(reads[output.index()]);
+            }
+            if i < num_public_values {
+                values[PUBLIC_SELECTORS + i] = F::ONE;
+            }
+        }
+
+        let id_and_value = |id, value| {
+            vec![
+                VirtualPairCol::single_preprocessed(id),
+                VirtualPairCol::single_main(value),
+            ]
+        };
+        let logup = LogUp::new(vec![
+            Lookup::looking(
+                id_and_value(A_ID, A),
+                VirtualPairCol::single_preprocessed(A_READ),
+            ),
+            Lookup::looking(
+                id_and_value(B_ID, B),
+                VirtualPairCol::single_preprocessed(A_READ + 1),
+            ),
+            Lookup::looking(
+                id_and_value(C_ID, C),
+                VirtualPairCol::single_preprocessed(A_READ + 2),
+            ),
+            Lookup::table(
+                id_and_value(OUT_ID, OUT),
+                VirtualPairCol::single_preprocessed(OUT_MULT),
+            ),
+        ]);
+
+        Self {
+            preprocessed: RowMajorMatrix::new(values, width),
+            num_public_values,
+            logup,
+        }
+    }
+
+    fn generate_trace(&self, circuit: &Circuit<F>) -> RowMajorMatrix<F> {
+        let num_rows = self.preprocessed.height();
+        let mut values = F::zero_vec(num_rows * 4);
+        for (row, values) in arithmetic_rows(circuit).zip(values.chunks_exact_mut(4)) {
+            match row {
+                ArithmeticRow::Witness(target) => values[OUT] = circuit.value(target),
+                ArithmeticRow::Gate(gate) => {
+                    for (j, input) in gate.inputs.iter().enumerate() {
+                        if let Some(input) = input {
+                            values[A + j] = circuit.value(*input);
+                        }
+                    }
+                    if let Some(output) = gate.output {
+                        values[OUT] = circuit.value(output);
+                    }
+                }
+            }
+        }
+        RowMajorMatrix::new(values, 4)
+    }
+
+    fn eval<AB>(&self, builder: &mut AB)
+    where
+        AB: MultiTableAirBuilder<F = F> + PairBuilder + AirBuilderWithPublicValues,
+    {
+        let preprocessed = builder.preprocessed();
+        let preprocessed = preprocessed.row_slice(0);
+        let main = builder.main();
+        let main = main.row_slice(0);
+        let (a, b, c, out) = (main[A], main[B], main[C], main[OUT]);
+
+        builder.label("arithmetic gate");
+        builder.assert_eq(
+            preprocessed[Q_OUT] * out,
+            preprocessed[Q_MUL] * a * b
+                + preprocessed[Q_A] * a
+                + preprocessed[Q_B] * b
+                + preprocessed[Q_C] * c
+                + preprocessed[Q_CONST],
+        );
+
+        builder.label("public input");
+        let public_values = builder.public_values().to_vec();
+        for (k, &public_value) in public_values.iter().enumerate() {
+            let selector: AB::Expr = preprocessed[PUBLIC_SELECTORS + k].into();
+            builder.assert_zero(selector * (out.into() - public_value.into()));
+        }
+
+        drop(preprocessed);
+        drop(main);
+        self.logup.eval(builder);
+    }
+}
+
+impl<
+        F: PrimeField,
+        LinearLayers: GenericPoseidon2LinearLayers<F, WIDTH>,
+        const SBOX_DEGREE: u64,
+        const SBOX_REGISTERS: usize,
+        const HALF_FULL_ROUNDS: usize,
+        const PARTIAL_ROUNDS: usize,
+    >
+    Poseidon2Table<F, LinearLayers, SBOX_DEGREE, SBOX_REGISTERS, HALF_FULL_ROUNDS, PARTIAL_ROUNDS>
+{
+    const NUM_COLS: usize =
+        num_cols::<WIDTH, SBOX_DEGREE, SBOX_REGISTERS, HALF_FULL_ROUNDS, PARTIAL_ROUNDS>();
+
+    fn new(
+        circuit: &Circuit<F>,
+        reads: &[usize],
+        constants: RoundConstants<F, WIDTH, HALF_FULL_ROUNDS, PARTIAL_ROUNDS>,
+    ) -> Self {
+        let width = ACTIVE + 1;
+        let num_rows = circuit.poseidon2_gates.len().next_power_of_two();
+
+        let mut values = F::zero_vec(num_rows * width);
+        for (gate, values) in circuit
+            .poseidon2_gates
+            .iter()
+            .zip(values.chunks_exact_mut(width))
+        {
+            for k in 0..WIDTH {
+                values[IN_IDS + k] = F::from_canonical_usize(gate.inputs[k].index());
+                values[OUT_IDS + k] = F::from_canonical_usize(gate.outputs[k].index());
+                values[OUT_MULTS + k] = F::from_canonical_usize(reads[gate.outputs[k].index()]);
+            }
+            values[ACTIVE] = F::ONE;
+        }
+
+        // The permutation's inputs follow the `export` column, and its outputs are the state after
+        // the last full round, which ends the row.
+        let outputs = Self::NUM_COLS - WIDTH;
+        let lookups = (0..WIDTH)
+            .map(|k| {
+                Lookup::looking(
+                    vec![
+                        VirtualPairCol::single_preprocessed(IN_IDS + k),
+                        VirtualPairCol::single_main(1 + k),
+                    ],
+                    VirtualPairCol::single_preprocessed(ACTIVE),
+                )
+            })
+            .chain((0..WIDTH).map(|k| {
+                Lookup::table(
+                    vec![
+                        VirtualPairCol::single_preprocessed(OUT_IDS + k),
+                        VirtualPairCol::single_main(outputs + k),
+                    ],
+                    VirtualPairCol::single_preprocessed(OUT_MULTS + k),
+                )
+            }))
+            .collect();
+
+        Self {
+            air: Poseidon2Air::new(constants.clone()),
+            constants,
+            preprocessed: RowMajorMatrix::new(values, width),
+            logup: LogUp::new(lookups),
+        }
+    }
+
+    fn generate_trace(&self, circuit: &Circuit<F>) -> RowMajorMatrix<F> {
+        let mut inputs = circuit
+            .poseidon2_gates
+            .iter()
+            .map(|gate| gate.inputs.map(|t| circuit.value(t)))
+            .collect::<Vec<_>>();
+        inputs.resize(self.preprocessed.height(), [F::ZERO; WIDTH]);
+        generate_trace_rows::<
+            F,
+            LinearLayers,
+            WIDTH,
+            SBOX_DEGREE,
+            SBOX_REGISTERS,
+            HALF_FULL_ROUNDS,
+            PARTIAL_ROUNDS,
+        >(inputs, &self.constants)
+    }
+}
+
+impl<
+        F: Field,
+        LinearLayers: Sync,
+        const SBOX_DEGREE: u64,
+        const SBOX_REGISTERS: usize,
+        const HALF_FULL_ROUNDS: usize,
+        const PARTIAL_ROUNDS: usize,
+    > BaseAir<F>
+    for RecursionAir<F, LinearLayers, SBOX_DEGREE, SBOX_REGISTERS, HALF_FULL_ROUNDS, PARTIAL_ROUNDS>
+{
+    fn width(&self) -> usize {
+        match self {
+            Self::Arithmetic(_) => 4,
+            Self::Poseidon2(table) => table.air.width(),
+        }
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        match self {
+            Self::Arithmetic(table) => Some(table.preprocessed.clone()),
+            Self::Poseidon2(table) => Some(table.preprocessed.clone()),
+        }
+    }
+
+    fn permutation_width(&self) -> usize {
+        match self {
+            Self::Arithmetic(table) => table.logup.width(),
+            Self::Poseidon2(table) => table.logup.width(),
+        }
+    }
+
+    fn num_permutation_challenges(&self) -> usize {
+        LogUp::<F>::NUM_CHALLENGES
+    }
+
+    fn cumulative_sum_column(&self) -> usize {
+        match self {
+            Self::Arithmetic(table) => table.logup.running_sum_column(),
+            Self::Poseidon2(table) => table.logup.running_sum_column(),
+        }
+    }
+
+    fn generate_permutation_trace<EF>(
+        &self,
+        preprocessed: Option<&RowMajorMatrix<F>>,
+        main: &RowMajorMatrix<F>,
+        challenges: &[EF],
+    ) -> Option<RowMajorMatrix<EF>>
+    where
+        EF: ExtensionField<F>,
+    {
+        let logup = match self {
+            Self::Arithmetic(table) => &table.logup,
+            Self::Poseidon2(table) => &table.logup,
+        };
+        Some(logup.generate_trace(preprocessed, main, challenges))
+    }
+
+    fn num_public_values(&self) -> usize {
+        match self {
+            Self::Arithmetic(table) => table.num_public_values,
+            Self::Poseidon2(_) => 0,
+        }
+    }
+}
+
+impl<
+        AB: MultiTableAirBuilder + PairBuilder + AirBuilderWithPublicValues,
+        LinearLayers: GenericPoseidon2LinearLayers<AB::Expr, WIDTH>,
+        const SBOX_DEGREE: u64,
+        const SBOX_REGISTERS: usize,
+        const HALF_FULL_ROUNDS: usize,
+        const PARTIAL_ROUNDS: usize,
+    > Air<AB>
+    for RecursionAir<
+        AB::F,
+        LinearLayers,
+        SBOX_DEGREE,
+        SBOX_REGISTERS,
+        HALF_FULL_ROUNDS,
+        PARTIAL_ROUNDS,
+    >
+{
+    fn eval(&self, builder: &mut AB) {
+        match self {
+            Self::Arithmetic(table) => table.eval(builder),
+            Self::Poseidon2(table) => {
+                table.air.eval(builder);
+                table.logup.eval(builder);
+            }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use p3_symmetric::Permutation;
+
+use crate::{CircuitBuilder, ExtTarget, RecursionField, Target, RATE, WIDTH};
+
+/// The in-circuit counterpart of a `DuplexChallenger` with width [`WIDTH`] and rate [`RATE`].
+///
+/// Observing and sampling values in the same order as a native challenger produces the same
+/// challenges, as wires of the circuit.
+#[derive(Clone, Debug)]
+pub struct DuplexChallengerTargets {
+    sponge_state: [Target; WIDTH],
+    input_buffer: Vec<Target>,
+    output_buffer: Vec<Target>,
+}
+
+impl DuplexChallengerTargets {
+    pub fn new<F, P>(builder: &mut CircuitBuilder<F, P>) -> Self
+    where
+        F: RecursionField,
+        P: Permutation<[F; WIDTH]>,
+    {
+        Self {
+            sponge_state: [builder.zero(); WIDTH],
+            input_buffer: vec![],
+            output_buffer: vec![],
+        }
+    }
+
+    fn duplexing<F, P>(&mut self, builder: &mut CircuitBuilder<F, P>)
+    where
+        F: RecursionField,
+        P: Permutation<[F; WIDTH]>,
+    {
+        assert!(self.input_buffer.len() <= RATE);
+
+        for (i, value) in self.input_buffer.drain(..).enumerate() {
+            self.sponge_state[i] = value;
+        }
+        self.sponge_state = builder.permute(self.sponge_state);
+
+        self.output_buffer.clear();
+        self.output_buffer.extend(&self.sponge_state[..RATE]);
+    }
+
+    pub fn observe<F, P>(&mut self, builder: &mut CircuitBuilder<F, P>, value: Target)
+    where
+        F: RecursionField,
+        P: Permutation<[F; WIDTH]>,
+    {
+        self.output_buffer.clear();
+        self.input_buffer.push(value);
+        if self.input_buffer.len() == RATE {
+            self.duplexing(builder);
+        }
+    }
+
+    pub fn observe_slice<F, P>(&mut self, builder: &mut CircuitBuilder<F, P>, values: &[Target])
+    where
+        F: RecursionField,
+        P: Permutation<[F; WIDTH]>,
+    {
+        for &value in values {
+            self.observe(builder, value);
+        }
+    }
+
+    pub fn observe_ext<F, P>(&mut self, builder: &mut CircuitBuilder<F, P>, value: ExtTarget)
+    where
+        F: RecursionField,
+        P: Permutation<[F; WIDTH]>,
+    {
+        self.observe_slice(builder, &value.0);
+    }
+
+    pub fn sample<F, P>(&mut self, builder: &mut CircuitBuilder<F, P>) -> Target
+    where
+        F: RecursionField,
+        P: Permutation<[F; WIDTH]>,
+    {
+        if !self.input_buffer.is_empty() || self.output_buffer.is_empty() {
+            self.duplexing(builder);
+        }
+        self.output_buffer
+            .pop()
+            .expect("Output buffer should be non-empty")
+    }
+
+    pub fn sample_ext<F, P>(&mut self, builder: &mut CircuitBuilder<F, P>) -> ExtTarget
+    where
+        F: RecursionField,
+        P: Permutation<[F; WIDTH]>,
+    {
+        ExtTarget(core::array::from_fn(|_| self.sample(builder)))
+    }
+
+    /// Sample a random `bits`-bit integer, returned as its bits, least significant first.
+    pub fn sample_bits<F, P>(
+        &mut self,
+        builder: &mut CircuitBuilder<F, P>,
+        bits: usize,
+    ) -> Vec<Target>
+    where
+        F: RecursionField,
+        P: Permutation<[F; WIDTH]>,
+    {
+        let sample = self.sample(builder);
+        let mut sample_bits = builder.split_bits(sample);
+        sample_bits.truncate(bits);
+        sample_bits
+    }
+
+    /// Assert that `witness` is a valid proof-of-work witness for `bits` bits.
+    pub fn check_witness<F, P>(
+        &mut self,
+        builder: &mut CircuitBuilder<F, P>,
+        bits: usize,
+        witness: Target,
+    ) where
+        F: RecursionField,
+        P: Permutation<[F; WIDTH]>,
+    {
+        self.observe(builder, witness);
+        for bit in self.sample_bits(builder, bits) {
+            builder.assert_zero(bit);
+        }
+    }
+}
@@ -0,0 +1,601 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use hashbrown::HashMap;
+use itertools::Itertools;
+use p3_field::extension::{BinomialExtensionField, HasTwoAdicBionmialExtension};
+use p3_field::{Field, FieldAlgebra, FieldExtensionAlgebra, PrimeField64, TwoAdicField};
+use p3_symmetric::Permutation;
+use p3_util::log2_ceil_u64;
+
+/// The width of the Poseidon2 permutation used by recursive circuits.
+pub const WIDTH: usize = 16;
+
+/// The rate of the sponge and the duplex challenger built from the permutation.
+pub const RATE: usize = 8;
+
+/// The number of field elements in a Merkle digest.
+pub const DIGEST_ELEMS: usize = 8;
+
+/// The degree of the binomial extension field which challenges are drawn from.
+pub const EXT_DEGREE: usize = 4;
+
+/// A field which circuits verifying proofs over it can be built for: a two-adic field of order
+/// `2^n - 2^s + 1`, where `s` is the two-adicity, with a binomial extension of degree
+/// [`EXT_DEGREE`]. BabyBear and KoalaBear are such fields.
+pub trait RecursionField:
+    PrimeField64 + TwoAdicField + HasTwoAdicBionmialExtension<EXT_DEGREE>
+{
+}
+
+impl<F: PrimeField64 + TwoAdicField + HasTwoAdicBionmialExtension<EXT_DEGREE>> RecursionField
+    for F
+{
+}
+
+/// A wire of a [`Circuit`], carrying one base field value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Target(usize);
+
+impl Target {
+    pub const fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// The wires carrying the coefficients of an extension field element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExtTarget(pub [Target; EXT_DEGREE]);
+
+/// A gate computing `mul * a * b + linear[0] * a + linear[1] * b + linear[2] * c + constant`.
+///
+/// The result is written to `output`, or if there is none, asserted to be zero. An input is `None`
+/// when no term of the gate uses it.
+#[derive(Clone, Debug)]
+pub struct ArithmeticGate<F> {
+    pub inputs: [Option<Target>; 3],
+    pub mul: F,
+    pub linear: [F; 3],
+    pub constant: F,
+    pub output: Option<Target>,
+}
+
+impl<F: Field> ArithmeticGate<F> {
+    /// Evaluate the gate's expression, given the values of its inputs (zero for unused inputs).
+    pub fn eval(&self, [a, b, c]: [F; 3]) -> F {
+        self.mul * a * b
+            + self.linear[0] * a
+            + self.linear[1] * b
+            + self.linear[2] * c
+            + self.constant
+    }
+}
+
+/// A Poseidon2 permutation of the values on `inputs`, whose result is written to `outputs`.
+#[derive(Clone, Debug)]
+pub struct Poseidon2Gate {
+    pub inputs: [Target; WIDTH],
+    pub outputs: [Target; WIDTH],
+}
+
+/// A circuit, along with an assignment of values to its wires.
+///
+/// Every wire is written exactly once: by an arithmetic gate, by a Poseidon2 gate, or as a witness,
+/// which the prover is free to choose. Public inputs are witnesses whose values become the public
+/// values of the proof. The wiring only depends on how the circuit was built, so building it again
+/// with other values gives the same circuit with a different assignment.
+#[derive(Clone, Debug)]
+pub struct Circuit<F> {
+    pub values: Vec<F>,
+    pub public_inputs: Vec<Target>,
+    /// Witnesses other than the public inputs.
+    pub witnesses: Vec<Target>,
+    pub arithmetic_gates: Vec<ArithmeticGate<F>>,
+    pub poseidon2_gates: Vec<Poseidon2Gate>,
+}
+
+impl<F: Field> Circuit<F> {
+    pub fn value(&self, target: Target) -> F {
+        self.values[target.0]
+    }
+
+    pub fn public_values(&self) -> Vec<F> {
+        self.public_inputs.iter().map(|&t| self.value(t)).collect()
+    }
+
+    /// The indices of the arithmetic gates whose outputs are inconsistent with their inputs, or
+    /// whose asserted expressions are nonzero. The circuit can be proven iff this is empty.
+    pub fn unsatisfied_gates(&self) -> Vec<usize> {
+        self.arithmetic_gates
+            .iter()
+            .positions(|gate| {
+                let inputs = gate.inputs.map(|t| t.map_or(F::ZERO, |t| self.value(t)));
+                let output = gate.output.map_or(F::ZERO, |t| self.value(t));
+                gate.eval(inputs) != output
+            })
+            .collect()
+    }
+}
+
+/// Builds a [`Circuit`], computing the values of its wires along the way.
+///
+/// Gates reading wires whose values are known constants are simplified, so for instance extension
+/// field operations on base field elements only cost as much as the base field operations.
+pub struct CircuitBuilder<F, P> {
+    permutation: P,
+    circuit: Circuit<F>,
+    constants: HashMap<F, Target>,
+    constant_values: HashMap<Target, F>,
+}
+
+impl<F: Field, P: Permutation<[F; WIDTH]>> CircuitBuilder<F, P> {
+    /// Create a builder whose Poseidon2 gates are evaluated with `permutation`.
+    ///
+    /// The permutation must agree with the round constants of the Poseidon2 table the circuit is
+    /// proven with, or the proof will fail.
+    pub fn new(permutation: P) -> Self {
+        Self {
+            permutation,
+            circuit: Circuit {
+                values: vec![],
+                public_inputs: vec![],
+                witnesses: vec![],
+                arithmetic_gates: vec![],
+                poseidon2_gates: vec![],
+            },
+            constants: HashMap::new(),
+            constant_values: HashMap::new(),
+        }
+    }
+
+    pub fn build(self) -> Circuit<F> {
+        self.circuit
+    }
+
+    pub fn value(&self, target: Target) -> F {
+        self.circuit.value(target)
+    }
+
+    /// The permutation the builder evaluates Poseidon2 gates with.
+    pub const fn permutation(&self) -> &P {
+        &self.permutation
+    }
+
+    fn alloc(&mut self, value: F) -> Target {
+        self.circuit.values.push(value);
+        Target(self.circuit.values.len() - 1)
+    }
+
+    /// A wire which the prover may set to any value; here it is set to `value`.
+    pub fn witness(&mut self, value: F) -> Target {
+        let target = self.alloc(value);
+        self.circuit.witnesses.push(target);
+        target
+    }
+
+    /// A witness whose value is exposed as a public value of the proof.
+    pub fn public_input(&mut self, value: F) -> Target {
+        let target = self.alloc(value);
+        self.circuit.public_inputs.push(target);
+        target
+    }
+
+    pub fn constant(&mut self, value: F) -> Target {
+        if let Some(&target) = self.constants.get(&value) {
+            return target;
+        }
+        let target = self
+            .add_gate([None; 3], F::ZERO, [F::ZERO; 3], value, false)
+            .unwrap();
+        self.constants.insert(value, target);
+        self.constant_values.insert(target, value);
+        target
+    }
+
+    pub fn zero(&mut self) -> Target {
+        self.constant(F::ZERO)
+    }
+
+    pub fn one(&mut self) -> Target {
+        self.constant(F::ONE)
+    }
+
+    /// The value of `target` if it is a constant.
+    pub fn constant_value(&self, target: Target) -> Option<F> {
+        self.constant_values.get(&target).copied()
+    }
+
+    fn add_gate(
+        &mut self,
+        inputs: [Option<Target>; 3],
+        mul: F,
+        linear: [F; 3],
+        constant: F,
+        assert_zero: bool,
+    ) -> Option<Target> {
+        let mut gate = ArithmeticGate {
+            inputs,
+            mul,
+            linear,
+            constant,
+            output: None,
+        };
+        if !assert_zero {
+            let value = gate.eval(inputs.map(|t| t.map_or(F::ZERO, |t| self.value(t))));
+            gate.output = Some(self.alloc(value));
+        }
+        let output = gate.output;
+        self.circuit.arithmetic_gates.push(gate);
+        output
+    }
+
+    /// Compute `sum_i k_i x_i y_i + sum_j l_j z_j + constant` for the given `(k_i, x_i, y_i)`
+    /// products and `(l_j, z_j)` terms, or assert that it's zero, using one gate per product and
+    /// one per pair of terms.
+    fn emit_sum(
+        &mut self,
+        products: &[(F, Target, Target)],
+        terms: &[(F, Target)],
+        mut constant: F,
+        assert_zero: bool,
+    ) -> Option<Target> {
+        let mut linear = vec![];
+        let mut quadratic = vec![];
+        for &(k, x, y) in products.iter().filter(|(k, _, _)| !k.is_zero()) {
+            match (self.constant_value(x), self.constant_value(y)) {
+                (Some(x), Some(y)) => constant += k * x * y,
+                (Some(x), None) => linear.push((k * x, y)),
+                (None, Some(y)) => linear.push((k * y, x)),
+                (None, None) => quadratic.push((k, x, y)),
+            }
+        }
+        for &(k, z) in terms.iter().filter(|(k, _)| !k.is_zero()) {
+            match self.constant_value(z) {
+                Some(z) => constant += k * z,
+                None => linear.push((k, z)),
+            }
+        }
+        linear.retain(|(k, _)| !k.is_zero());
+
+        if !assert_zero && quadratic.is_empty() {
+            match linear[..] {
+                [] => return Some(self.constant(constant)),
+                [(k, z)] if k == F::ONE && constant.is_zero() => return Some(z),
+                _ => {}
+            }
+        }
+
+        // The first gate takes up to three terms, and later ones two terms along with the sum so
+        // far, which is passed through the `c` input.
+        let mut linear_chunks = vec![];
+        let mut rest = &linear[..];
+        if quadratic.is_empty() {
+            let (first, tail) = rest.split_at(rest.len().min(3));
+            linear_chunks.push(first);
+            rest = tail;
+        }
+        linear_chunks.extend(rest.chunks(2));
+        let num_gates = quadratic.len() + linear_chunks.len();
+
+        let mut acc: Option<Target> = None;
+        for i in 0..num_gates {
+            let is_last = i == num_gates - 1;
+            let gate_constant = if i == 0 { constant } else { F::ZERO };
+            let carry = acc.map_or(F::ZERO, |_| F::ONE);
+            let (inputs, mul, coefficients) = if let Some(&(k, x, y)) = quadratic.get(i) {
+                ([Some(x), Some(y), acc], k, [F::ZERO, F::ZERO, carry])
+            } else {
+                let chunk = linear_chunks[i - quadratic.len()];
+                let mut inputs = [None; 3];
+                let mut coefficients = [F::ZERO; 3];
+                for (j, &(k, z)) in chunk.iter().enumerate() {
+                    inputs[j] = Some(z);
+                    coefficients[j] = k;
+                }
+                if acc.is_some() {
+                    inputs[2] = acc;
+                    coefficients[2] = F::ONE;
+                }
+                (inputs, F::ZERO, coefficients)
+            };
+            acc = self.add_gate(
+                inputs,
+                mul,
+                coefficients,
+                gate_constant,
+                assert_zero && is_last,
+            );
+        }
+        acc
+    }
+
+    /// Compute `sum_i k_i x_i y_i + sum_j l_j z_j + constant`.
+    pub fn sum(
+        &mut self,
+        products: &[(F, Target, Target)],
+        terms: &[(F, Target)],
+        constant: F,
+    ) -> Target {
+        self.emit_sum(products, terms, constant, false).unwrap()
+    }
+
+    /// Assert that `sum_i k_i x_i y_i + sum_j l_j z_j + constant` is zero.
+    pub fn assert_sum_zero(
+        &mut self,
+        products: &[(F, Target, Target)],
+        terms: &[(F, Target)],
+        constant: F,
+    ) {
+        self.emit_sum(products, terms, constant, true);
+    }
+
+    pub fn add(&mut self, x: Target, y: Target) -> Target {
+        self.sum(&[], &[(F::ONE, x), (F::ONE, y)], F::ZERO)
+    }
+
+    pub fn sub(&mut self, x: Target, y: Target) -> Target {
+        self.sum(&[], &[(F::ONE, x), (F::NEG_ONE, y)], F::ZERO)
+    }
+
+    pub fn mul(&mut self, x: Target, y: Target) -> Target {
+        self.sum(&[(F::ONE, x, y)], &[], F::ZERO)
+    }
+
+    /// Compute `x * y + z`.
+    pub fn mul_add(&mut self, x: Target, y: Target, z: Target) -> Target {
+        self.sum(&[(F::ONE, x, y)], &[(F::ONE, z)], F::ZERO)
+    }
+
+    /// Compute `x` if `bit` is one, or `y` if it is zero.
+    pub fn select(&mut self, bit: Target, x: Target, y: Target) -> Target {
+        self.sum(
+            &[(F::ONE, bit, x), (F::NEG_ONE, bit, y)],
+            &[(F::ONE, y)],
+            F::ZERO,
+        )
+    }
+
+    /// Compute the inverse of `x`, which must be nonzero.
+    pub fn inverse(&mut self, x: Target) -> Target {
+        if let Some(x) = self.constant_value(x) {
+            return self.constant(x.inverse());
+        }
+        let inverse = self.value(x).try_inverse().unwrap_or(F::ZERO);
+        let inverse = self.witness(inverse);
+        self.assert_sum_zero(&[(F::ONE, x, inverse)], &[], F::NEG_ONE);
+        inverse
+    }
+
+    pub fn assert_zero(&mut self, x: Target) {
+        self.assert_sum_zero(&[], &[(F::ONE, x)], F::ZERO);
+    }
+
+    pub fn assert_eq(&mut self, x: Target, y: Target) {
+        self.assert_sum_zero(&[], &[(F::ONE, x), (F::NEG_ONE, y)], F::ZERO);
+    }
+
+    pub fn assert_bool(&mut self, x: Target) {
+        self.assert_sum_zero(&[(F::ONE, x, x)], &[(F::NEG_ONE, x)], F::ZERO);
+    }
+
+    /// Apply the Poseidon2 permutation to `inputs`.
+    pub fn permute(&mut self, inputs: [Target; WIDTH]) -> [Target; WIDTH] {
+        let values = self.permutation.permute(inputs.map(|t| self.value(t)));
+        let outputs = values.map(|value| self.alloc(value));
+        self.circuit
+            .poseidon2_gates
+            .push(Poseidon2Gate { inputs, outputs });
+        outputs
+    }
+}
+
+impl<F: RecursionField, P: Permutation<[F; WIDTH]>> CircuitBuilder<F, P> {
+    /// Decompose `x` into the bits of its canonical representative, least significant first.
+    ///
+    /// A decomposition is canonical iff it doesn't exceed `p - 1`, whose top `n - s` bits are ones
+    /// and whose low `s` bits are zeros.
+    pub fn split_bits(&mut self, x: Target) -> Vec<Target> {
+        let num_bits = log2_ceil_u64(F::ORDER_U64) as usize;
+        let two_adicity = F::TWO_ADICITY;
+        assert_eq!(
+            F::ORDER_U64,
+            (1 << num_bits) - (1 << two_adicity) + 1,
+            "unsupported field order"
+        );
+
+        let value = self.value(x).as_canonical_u64();
+        let bits = (0..num_bits)
+            .map(|i| {
+                let bit = self.witness(F::from_bool(value >> i & 1 == 1));
+                self.assert_bool(bit);
+                bit
+            })
+            .collect_vec();
+
+        let powers = F::TWO.powers().take(num_bits).collect_vec();
+        let low_terms = (0..two_adicity).map(|i| (powers[i], bits[i])).collect_vec();
+        let low = self.sum(&[], &low_terms, F::ZERO);
+        let mut terms = vec![(F::ONE, low), (F::NEG_ONE, x)];
+        terms.extend((two_adicity..num_bits).map(|i| (powers[i], bits[i])));
+        self.assert_sum_zero(&[], &terms, F::ZERO);
+
+        let high_bits_all_one = bits[two_adicity + 1..]
+            .iter()
+            .fold(bits[two_adicity], |acc, &bit| self.mul(acc, bit));
+        self.assert_sum_zero(&[(F::ONE, high_bits_all_one, low)], &[], F::ZERO);
+        bits
+    }
+
+    /// Compute `base^e` for the exponent `e` given by `bits`, least significant first.
+    pub fn exp_const_base_bits(&mut self, base: F, bits: &[Target]) -> Target {
+        let mut acc = self.one();
+        let mut power = base;
+        for &bit in bits {
+            // acc * (1 + bit * (power - 1))
+            acc = self.sum(&[(power - F::ONE, acc, bit)], &[(F::ONE, acc)], F::ZERO);
+            power = power.square();
+        }
+        acc
+    }
+
+    pub fn ext_value(&self, x: ExtTarget) -> BinomialExtensionField<F, EXT_DEGREE> {
+        BinomialExtensionField::from_base_fn(|i| self.value(x.0[i]))
+    }
+
+    pub fn ext_constant<EF: FieldExtensionAlgebra<F>>(&mut self, value: EF) -> ExtTarget {
+        assert_eq!(EF::D, EXT_DEGREE);
+        ExtTarget(core::array::from_fn(|i| {
+            self.constant(value.as_base_slice()[i])
+        }))
+    }
+
+    pub fn ext_witness<EF: FieldExtensionAlgebra<F>>(&mut self, value: EF) -> ExtTarget {
+        assert_eq!(EF::D, EXT_DEGREE);
+        ExtTarget(core::array::from_fn(|i| {
+            self.witness(value.as_base_slice()[i])
+        }))
+    }
+
+    pub fn ext_zero(&mut self) -> ExtTarget {
+        ExtTarget([self.zero(); EXT_DEGREE])
+    }
+
+    pub fn ext_one(&mut self) -> ExtTarget {
+        let zero = self.zero();
+        let mut limbs = [zero; EXT_DEGREE];
+        limbs[0] = self.one();
+        ExtTarget(limbs)
+    }
+
+    /// Embed a base field element into the extension field.
+    pub fn ext_from_base(&mut self, x: Target) -> ExtTarget {
+        let zero = self.zero();
+        let mut limbs = [zero; EXT_DEGREE];
+        limbs[0] = x;
+        ExtTarget(limbs)
+    }
+
+    pub fn ext_add(&mut self, x: ExtTarget, y: ExtTarget) -> ExtTarget {
+        ExtTarget(core::array::from_fn(|i| self.add(x.0[i], y.0[i])))
+    }
+
+    pub fn ext_sub(&mut self, x: ExtTarget, y: ExtTarget) -> ExtTarget {
+        ExtTarget(core::array::from_fn(|i| self.sub(x.0[i], y.0[i])))
+    }
+
+    pub fn ext_neg(&mut self, x: ExtTarget) -> ExtTarget {
+        ExtTarget(x.0.map(|x| self.sum(&[], &[(F::NEG_ONE, x)], F::ZERO)))
+    }
+
+    /// The products making up each coefficient of `x * y`.
+    fn ext_mul_products(x: ExtTarget, y: ExtTarget) -> [Vec<(F, Target, Target)>; EXT_DEGREE] {
+        let mut products: [Vec<_>; EXT_DEGREE] = Default::default();
+        for i in 0..EXT_DEGREE {
+            for j in 0..EXT_DEGREE {
+                let (k, coefficient) = if i + j < EXT_DEGREE {
+                    (i + j, F::ONE)
+                } else {
+                    (i + j - EXT_DEGREE, F::W)
+                };
+                products[k].push((coefficient, x.0[i], y.0[j]));
+            }
+        }
+        products
+    }
+
+    pub fn ext_mul(&mut self, x: ExtTarget, y: ExtTarget) -> ExtTarget {
+        let products = Self::ext_mul_products(x, y);
+        ExtTarget(core::array::from_fn(|k| {
+            self.sum(&products[k], &[], F::ZERO)
+        }))
+    }
+
+    /// Compute `x * y + z`.
+    pub fn ext_mul_add(&mut self, x: ExtTarget, y: ExtTarget, z: ExtTarget) -> ExtTarget {
+        let products = Self::ext_mul_products(x, y);
+        ExtTarget(core::array::from_fn(|k| {
+            self.sum(&products[k], &[(F::ONE, z.0[k])], F::ZERO)
+        }))
+    }
+
+    /// Compute `sum_i xs[i] * ys[i] + z`.
+    pub fn ext_inner_product(
+        &mut self,
+        xs: &[ExtTarget],
+        ys: &[ExtTarget],
+        z: ExtTarget,
+    ) -> ExtTarget {
+        let mut products: [Vec<_>; EXT_DEGREE] = Default::default();
+        for (&x, &y) in xs.iter().zip_eq(ys) {
+            for (all, new) in products.iter_mut().zip(Self::ext_mul_products(x, y)) {
+                all.extend(new);
+            }
+        }
+        ExtTarget(core::array::from_fn(|k| {
+            self.sum(&products[k], &[(F::ONE, z.0[k])], F::ZERO)
+        }))
+    }
+
+    /// Compute `sum_i xs[i] * ys[i] + z`, where the `ys` are base field elements.
+    pub fn ext_base_inner_product(
+        &mut self,
+        xs: &[ExtTarget],
+        ys: &[Target],
+        z: ExtTarget,
+    ) -> ExtTarget {
+        ExtTarget(core::array::from_fn(|k| {
+            let products = xs
+                .iter()
+                .zip_eq(ys)
+                .map(|(x, &y)| (F::ONE, x.0[k], y))
+                .collect_vec();
+            self.sum(&products, &[(F::ONE, z.0[k])], F::ZERO)
+        }))
+    }
+
+    /// Multiply an extension field element by a base field element.
+    pub fn ext_mul_base(&mut self, x: ExtTarget, y: Target) -> ExtTarget {
+        ExtTarget(x.0.map(|x| self.mul(x, y)))
+    }
+
+    pub fn ext_square(&mut self, x: ExtTarget) -> ExtTarget {
+        self.ext_mul(x, x)
+    }
+
+    /// Compute `x^(2^power_log)`.
+    pub fn ext_exp_power_of_2(&mut self, x: ExtTarget, power_log: usize) -> ExtTarget {
+        (0..power_log).fold(x, |acc, _| self.ext_square(acc))
+    }
+
+    /// Compute `x` if `bit` is one, or `y` if it is zero.
+    pub fn ext_select(&mut self, bit: Target, x: ExtTarget, y: ExtTarget) -> ExtTarget {
+        ExtTarget(core::array::from_fn(|i| self.select(bit, x.0[i], y.0[i])))
+    }
+
+    /// Compute `x / y`, where `y` must be nonzero.
+    pub fn ext_div(&mut self, x: ExtTarget, y: ExtTarget) -> ExtTarget {
+        let quotient = self
+            .ext_value(y)
+            .try_inverse()
+            .map_or(BinomialExtensionField::ZERO, |inverse| {
+                self.ext_value(x) * inverse
+            });
+        let quotient = self.ext_witness(quotient);
+        let products = Self::ext_mul_products(quotient, y);
+        for (k, products) in products.iter().enumerate() {
+            self.assert_sum_zero(products, &[(F::NEG_ONE, x.0[k])], F::ZERO);
+        }
+        quotient
+    }
+
+    pub fn ext_inverse(&mut self, x: ExtTarget) -> ExtTarget {
+        let one = self.ext_one();
+        self.ext_div(one, x)
+    }
+
+    pub fn assert_ext_eq(&mut self, x: ExtTarget, y: ExtTarget) {
+        for (x, y) in x.0.into_iter().zip(y.0) {
+            self.assert_eq(x, y);
+        }
+    }
+}
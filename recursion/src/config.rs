@@ -0,0 +1,36 @@
+use alloc::vec::Vec;
+
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::Field;
+use p3_fri::{FriProof, MultiBatchOpening, TwoAdicFriPcs};
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use p3_uni_stark::StarkConfig;
+
+use crate::{DIGEST_ELEMS, EXT_DEGREE, RATE, WIDTH};
+
+pub type InnerHash<P> = PaddingFreeSponge<P, WIDTH, RATE, DIGEST_ELEMS>;
+pub type InnerCompress<P> = TruncatedPermutation<P, 2, DIGEST_ELEMS, WIDTH>;
+pub type InnerValMmcs<F, P> = MerkleTreeMmcs<
+    <F as Field>::Packing,
+    <F as Field>::Packing,
+    InnerHash<P>,
+    InnerCompress<P>,
+    DIGEST_ELEMS,
+>;
+pub type InnerChallenge<F> = BinomialExtensionField<F, EXT_DEGREE>;
+pub type InnerChallengeMmcs<F, P> = ExtensionMmcs<F, InnerChallenge<F>, InnerValMmcs<F, P>>;
+pub type InnerPcs<F, P, Dft> = TwoAdicFriPcs<F, Dft, InnerValMmcs<F, P>, InnerChallengeMmcs<F, P>>;
+pub type InnerChallenger<F, P> = DuplexChallenger<F, P, WIDTH, RATE>;
+/// The configuration of the proofs which can be verified in a circuit over `F`, whose hashes and
+/// challenger use the circuit's Poseidon2 permutation `P`.
+pub type InnerConfig<F, P, Dft> =
+    StarkConfig<InnerPcs<F, P, Dft>, InnerChallenge<F>, InnerChallenger<F, P>>;
+pub type InnerFriProof<F, P> = FriProof<
+    InnerChallenge<F>,
+    InnerChallengeMmcs<F, P>,
+    F,
+    Vec<MultiBatchOpening<F, InnerValMmcs<F, P>>>,
+>;
@@ -0,0 +1,680 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::{izip, Itertools};
+use p3_field::PrimeField64;
+use p3_fri::FriConfig;
+use p3_matrix::Dimensions;
+use p3_symmetric::{CryptographicPermutation, MerkleCap, Permutation};
+use p3_util::{log2_strict_usize, reverse_bits_len};
+
+use crate::{
+    merkle, CircuitBuilder, DuplexChallengerTargets, ExtTarget, InnerCompress, InnerFriProof,
+    InnerHash, InnerValMmcs, RecursionError, RecursionField, Target, DIGEST_ELEMS, EXT_DEGREE,
+    WIDTH,
+};
+
+/// A committed batch's opened rows at every query index, along with the multi-proof for them.
+///
+/// The circuit checks a separate Merkle path for each query, so that its shape doesn't depend on
+/// which paths overlap. The multi-proof is kept natively, and `verify_fri` recovers the paths
+/// from it once the query indices are known.
+#[derive(Clone, Debug)]
+pub struct MultiBatchOpeningTargets<F> {
+    /// For each query, the opened row of each matrix.
+    pub opened_values: Vec<Vec<Vec<Target>>>,
+    pub opening_proof: Vec<[F; DIGEST_ELEMS]>,
+}
+
+/// For every query, the values which fold together with a commit phase codeword's queried value,
+/// along with the multi-proof for them.
+#[derive(Clone, Debug)]
+pub struct CommitPhaseOpeningsTargets<F> {
+    pub sibling_values: Vec<Vec<ExtTarget>>,
+    pub opening_proof: Vec<[F; DIGEST_ELEMS]>,
+}
+
+/// The witness wires holding a FRI proof of a `TwoAdicFriPcs` opening.
+#[derive(Clone, Debug)]
+pub struct FriProofTargets<F> {
+    pub commit_phase_commits: Vec<[Target; DIGEST_ELEMS]>,
+    pub input_proof: Vec<MultiBatchOpeningTargets<F>>,
+    pub commit_phase_openings: Vec<CommitPhaseOpeningsTargets<F>>,
+    pub final_poly: Vec<ExtTarget>,
+    pub pow_witness: Target,
+}
+
+impl<F: RecursionField> FriProofTargets<F> {
+    /// Add the values of `proof` to the circuit as witnesses.
+    pub fn new<P>(
+        builder: &mut CircuitBuilder<F, P>,
+        proof: &InnerFriProof<F, P>,
+    ) -> Result<Self, RecursionError>
+    where
+        P: CryptographicPermutation<[F; WIDTH]> + CryptographicPermutation<[F::Packing; WIDTH]>,
+    {
+        let commit_phase_commits = proof
+            .commit_phase_commits
+            .iter()
+            .map(|commit| merkle::cap_root(commit).map(|root| root.map(|x| builder.witness(x))))
+            .collect::<Result<_, _>>()?;
+        let input_proof = proof
+            .input_proof
+            .iter()
+            .map(|multi_opening| MultiBatchOpeningTargets {
+                opened_values: multi_opening
+                    .opened_values
+                    .iter()
+                    .map(|rows| {
+                        rows.iter()
+                            .map(|row| row.iter().map(|&x| builder.witness(x)).collect())
+                            .collect()
+                    })
+                    .collect(),
+                opening_proof: multi_opening.opening_proof.clone(),
+            })
+            .collect();
+        let commit_phase_openings = proof
+            .commit_phase_openings
+            .iter()
+            .map(|openings| CommitPhaseOpeningsTargets {
+                sibling_values: openings
+                    .sibling_values
+                    .iter()
+                    .map(|values| {
+                        values
+                            .iter()
+                            .map(|&value| builder.ext_witness(value))
+                            .collect()
+                    })
+                    .collect(),
+                opening_proof: openings.opening_proof.clone(),
+            })
+            .collect();
+        Ok(Self {
+            commit_phase_commits,
+            input_proof,
+            commit_phase_openings,
+            final_poly: proof
+                .final_poly
+                .iter()
+                .map(|&coeff| builder.ext_witness(coeff))
+                .collect(),
+            pow_witness: builder.witness(proof.pow_witness),
+        })
+    }
+}
+
+/// A committed batch of matrices to be opened, as passed to `Pcs::verify`.
+#[derive(Clone, Debug)]
+pub struct OpeningRound {
+    pub commit: [Target; DIGEST_ELEMS],
+    /// For each matrix, the log2 size of its domain, and the points it's opened at.
+    pub matrices: Vec<(usize, Vec<OpenedPoint>)>,
+}
+
+/// A point a matrix is opened at, along with the claimed values of each column there.
+pub type OpenedPoint = (ExtTarget, Vec<ExtTarget>);
+
+/// The columns opened at one point, among the matrices of one height.
+///
+/// The contribution of each such group to the reduced opening at that height is
+/// `sum_j alpha^(offset_j) (p_j(x) - p_j(z)) / (x - z)`, and since the denominator is shared, we
+/// only divide once for the whole group.
+struct OpeningGroup {
+    log_height: usize,
+    point: ExtTarget,
+    /// The `alpha` power for each column, with its (round, matrix, column) position.
+    columns: Vec<(ExtTarget, (usize, usize, usize))>,
+    /// `-sum_j alpha^(offset_j) p_j(z)`.
+    neg_reduced_at_point: ExtTarget,
+}
+
+/// Assert that `proof` is a valid `TwoAdicFriPcs` opening proof for `rounds`, mirroring
+/// `TwoAdicFriPcs::verify`.
+pub fn verify_fri<F, P, M>(
+    builder: &mut CircuitBuilder<F, P>,
+    challenger: &mut DuplexChallengerTargets,
+    config: &FriConfig<M>,
+    rounds: &[OpeningRound],
+    proof: &FriProofTargets<F>,
+) -> Result<(), RecursionError>
+where
+    F: RecursionField,
+    P: CryptographicPermutation<[F; WIDTH]> + CryptographicPermutation<[F::Packing; WIDTH]>,
+{
+    let log_heights = rounds
+        .iter()
+        .flat_map(|round| {
+            round
+                .matrices
+                .iter()
+                .map(|(log_size, _)| log_size + config.log_blowup)
+        })
+        .sorted_by(|l, r| r.cmp(l))
+        .dedup()
+        .collect_vec();
+    let log_max_height = *log_heights
+        .first()
+        .ok_or(RecursionError::InvalidProofShape)?;
+    let log_arities = config.commit_phase_log_arities(&log_heights);
+    let log_final_height = config.log_final_height(&log_heights);
+    if proof.commit_phase_commits.len() != log_arities.len()
+        || proof.commit_phase_openings.len() != log_arities.len()
+        || proof.input_proof.len() != rounds.len()
+        || proof.final_poly.len() != 1 << (log_final_height - config.log_blowup)
+    {
+        return Err(RecursionError::InvalidProofShape);
+    }
+
+    let alpha = challenger.sample_ext(builder);
+    let betas = proof
+        .commit_phase_commits
+        .iter()
+        .map(|commit| {
+            challenger.observe_slice(builder, commit);
+            challenger.sample_ext(builder)
+        })
+        .collect_vec();
+    for &coeff in &proof.final_poly {
+        challenger.observe_ext(builder, coeff);
+    }
+
+    challenger.check_witness(builder, config.proof_of_work_bits, proof.pow_witness);
+
+    let index_bits = (0..config.num_queries)
+        .map(|_| challenger.sample_bits(builder, log_max_height))
+        .collect_vec();
+    let indices = index_bits
+        .iter()
+        .map(|bits| index_value(builder, bits))
+        .collect_vec();
+
+    for (multi_opening, round) in proof.input_proof.iter().zip(rounds) {
+        if multi_opening.opened_values.len() != indices.len() {
+            return Err(RecursionError::InvalidProofShape);
+        }
+        let mut query_openings = Vec::with_capacity(indices.len());
+        for rows in &multi_opening.opened_values {
+            if rows.len() != round.matrices.len() {
+                return Err(RecursionError::InvalidProofShape);
+            }
+            let openings = izip!(&round.matrices, rows)
+                .map(|((log_size, points), row)| {
+                    if points.iter().any(|(_, values)| values.len() != row.len()) {
+                        return Err(RecursionError::InvalidProofShape);
+                    }
+                    Ok((log_size + config.log_blowup, row.clone()))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            query_openings.push(openings);
+        }
+        let log_batch_max_height = round
+            .matrices
+            .iter()
+            .map(|(log_size, _)| log_size + config.log_blowup)
+            .max()
+            .unwrap_or(0);
+        let bits_reduced = log_max_height - log_batch_max_height;
+
+        let dimensions = round
+            .matrices
+            .iter()
+            .map(|(log_size, _)| Dimensions {
+                width: 0,
+                height: 1 << (log_size + config.log_blowup),
+            })
+            .collect_vec();
+        let batch_indices = indices
+            .iter()
+            .map(|index| index >> bits_reduced)
+            .collect_vec();
+        let paths = witness_paths(
+            builder,
+            &round.commit,
+            &dimensions,
+            &batch_indices,
+            &multi_opening.opened_values,
+            &multi_opening.opening_proof,
+        )?;
+        for (openings, bits, path) in izip!(&query_openings, &index_bits, &paths) {
+            merkle::verify_batch(
+                builder,
+                &round.commit,
+                openings,
+                &bits[bits_reduced..],
+                path,
+            )?;
+        }
+    }
+
+    let groups = opening_groups(builder, config.log_blowup, rounds, alpha);
+
+    let reduced_openings = (0..indices.len())
+        .map(|query| {
+            // The point each matrix of a given height is evaluated at, in the LDE coset.
+            let mut xs = BTreeMap::new();
+            let mut reduced_openings = BTreeMap::<usize, ExtTarget>::new();
+            for group in &groups {
+                let log_height = group.log_height;
+                let x = *xs.entry(log_height).or_insert_with(|| {
+                    let bits_reduced = log_max_height - log_height;
+                    let reversed_bits = index_bits[query][bits_reduced..]
+                        .iter()
+                        .rev()
+                        .copied()
+                        .collect_vec();
+                    let power = builder
+                        .exp_const_base_bits(F::two_adic_generator(log_height), &reversed_bits);
+                    builder.sum(&[], &[(F::GENERATOR, power)], F::ZERO)
+                });
+
+                let (alpha_powers, positions): (Vec<_>, Vec<_>) =
+                    group.columns.iter().copied().unzip();
+                let values = positions
+                    .into_iter()
+                    .map(|(round, matrix, column)| {
+                        proof.input_proof[round].opened_values[query][matrix][column]
+                    })
+                    .collect_vec();
+                let numerator = builder.ext_base_inner_product(
+                    &alpha_powers,
+                    &values,
+                    group.neg_reduced_at_point,
+                );
+                let x = builder.ext_from_base(x);
+                let denominator = builder.ext_sub(x, group.point);
+                let quotient = builder.ext_div(numerator, denominator);
+
+                let reduced_opening = match reduced_openings.get(&log_height) {
+                    Some(&reduced_opening) => builder.ext_add(reduced_opening, quotient),
+                    None => quotient,
+                };
+                reduced_openings.insert(log_height, reduced_opening);
+            }
+
+            // As natively, a reduced opening at the blowup height would come from a trace of
+            // height 1, and isn't checked against any commit phase commitment.
+            reduced_openings.remove(&config.log_blowup);
+            reduced_openings
+        })
+        .collect_vec();
+
+    let folded_evals = verify_commit_phase(
+        builder,
+        &index_bits,
+        &indices,
+        izip!(
+            &log_arities,
+            &betas,
+            &proof.commit_phase_commits,
+            &proof.commit_phase_openings
+        ),
+        reduced_openings,
+        log_max_height,
+    )?;
+
+    for (bits, folded_eval) in izip!(&index_bits, folded_evals) {
+        // The final codeword is over the subgroup, in bit-reversed order.
+        let reversed_bits = bits[log_max_height - log_final_height..]
+            .iter()
+            .rev()
+            .copied()
+            .collect_vec();
+        let x =
+            builder.exp_const_base_bits(F::two_adic_generator(log_final_height), &reversed_bits);
+        let zero = builder.ext_zero();
+        let final_eval = proof.final_poly.iter().rev().fold(zero, |acc, &coeff| {
+            let acc = builder.ext_mul_base(acc, x);
+            builder.ext_add(acc, coeff)
+        });
+        builder.assert_ext_eq(folded_eval, final_eval);
+    }
+
+    Ok(())
+}
+
+/// The value of an index from its bits, least significant first.
+fn index_value<F, P>(builder: &CircuitBuilder<F, P>, bits: &[Target]) -> usize
+where
+    F: RecursionField,
+    P: Permutation<[F; WIDTH]>,
+{
+    bits.iter().rev().fold(0, |index, &bit| {
+        (index << 1) | builder.value(bit).as_canonical_u64() as usize
+    })
+}
+
+/// Recover the Merkle path of each index from a multi-proof, and add them to the circuit as
+/// witnesses.
+///
+/// The multi-proof is verified natively here, but that's only so the paths can be recovered; the
+/// circuit checks each of them against `commit` itself.
+fn witness_paths<F, P>(
+    builder: &mut CircuitBuilder<F, P>,
+    commit: &[Target; DIGEST_ELEMS],
+    dimensions: &[Dimensions],
+    indices: &[usize],
+    opened_values: &[Vec<Vec<Target>>],
+    proof: &[[F; DIGEST_ELEMS]],
+) -> Result<Vec<Vec<[Target; DIGEST_ELEMS]>>, RecursionError>
+where
+    F: RecursionField,
+    P: CryptographicPermutation<[F; WIDTH]> + CryptographicPermutation<[F::Packing; WIDTH]>,
+{
+    let permutation = builder.permutation().clone();
+    let mmcs = InnerValMmcs::<F, P>::new(
+        InnerHash::new(permutation.clone()),
+        InnerCompress::new(permutation),
+    );
+    let commit = MerkleCap::from(commit.map(|target| builder.value(target)));
+    let opened_values: Vec<Vec<Vec<F>>> = opened_values
+        .iter()
+        .map(|rows| {
+            rows.iter()
+                .map(|row| row.iter().map(|&target| builder.value(target)).collect())
+                .collect()
+        })
+        .collect();
+    let paths = mmcs
+        .multi_batch_paths(&commit, dimensions, indices, &opened_values, proof)
+        .map_err(|_| RecursionError::InvalidOpeningProof)?;
+    Ok(paths
+        .into_iter()
+        .map(|path| {
+            path.into_iter()
+                .map(|sibling| sibling.map(|x| builder.witness(x)))
+                .collect()
+        })
+        .collect())
+}
+
+/// Group the opened columns of `rounds` by height and point, assigning each its power of `alpha`.
+fn opening_groups<F, P>(
+    builder: &mut CircuitBuilder<F, P>,
+    log_blowup: usize,
+    rounds: &[OpeningRound],
+    alpha: ExtTarget,
+) -> Vec<OpeningGroup>
+where
+    F: RecursionField,
+    P: Permutation<[F; WIDTH]>,
+{
+    // Natively, each height has its own running power of alpha, which goes through the columns of
+    // each point of each matrix in order.
+    let mut num_columns = BTreeMap::<usize, usize>::new();
+    for round in rounds {
+        for (log_size, points) in &round.matrices {
+            let count = num_columns.entry(log_size + log_blowup).or_insert(0);
+            *count += points.iter().map(|(_, values)| values.len()).sum::<usize>();
+        }
+    }
+    let max_columns = num_columns.values().copied().max().unwrap_or(0);
+    let mut alpha_powers = Vec::with_capacity(max_columns);
+    let mut power = builder.ext_one();
+    for _ in 0..max_columns {
+        alpha_powers.push(power);
+        power = builder.ext_mul(power, alpha);
+    }
+
+    let mut groups = Vec::<OpeningGroup>::new();
+    let mut values_at_point = Vec::<Vec<ExtTarget>>::new();
+    num_columns.clear();
+    for (round_index, round) in rounds.iter().enumerate() {
+        for (matrix_index, (log_size, points)) in round.matrices.iter().enumerate() {
+            let log_height = log_size + log_blowup;
+            for &(point, ref values) in points {
+                let group_index = groups
+                    .iter()
+                    .position(|g| g.log_height == log_height && g.point == point)
+                    .unwrap_or_else(|| {
+                        groups.push(OpeningGroup {
+                            log_height,
+                            point,
+                            columns: Vec::new(),
+                            neg_reduced_at_point: point,
+                        });
+                        values_at_point.push(Vec::new());
+                        groups.len() - 1
+                    });
+                for (column, &value) in values.iter().enumerate() {
+                    let count = num_columns.entry(log_height).or_insert(0);
+                    groups[group_index]
+                        .columns
+                        .push((alpha_powers[*count], (round_index, matrix_index, column)));
+                    values_at_point[group_index].push(value);
+                    *count += 1;
+                }
+            }
+        }
+    }
+
+    for (group, values) in groups.iter_mut().zip(values_at_point) {
+        let alpha_powers = group.columns.iter().map(|(a, _)| *a).collect_vec();
+        let zero = builder.ext_zero();
+        let reduced_at_point = builder.ext_inner_product(&alpha_powers, &values, zero);
+        group.neg_reduced_at_point = builder.ext_neg(reduced_at_point);
+    }
+    groups
+}
+
+type CommitStep<'a, F> = (
+    &'a usize,
+    &'a ExtTarget,
+    &'a [Target; DIGEST_ELEMS],
+    &'a CommitPhaseOpeningsTargets<F>,
+);
+
+/// Fold the reduced openings of every query down the commit phase codewords, checking each
+/// step's openings, and return the final folded evaluations.
+fn verify_commit_phase<'a, F, P>(
+    builder: &mut CircuitBuilder<F, P>,
+    index_bits: &[Vec<Target>],
+    indices: &[usize],
+    steps: impl Iterator<Item = CommitStep<'a, F>>,
+    reduced_openings: Vec<BTreeMap<usize, ExtTarget>>,
+    log_max_height: usize,
+) -> Result<Vec<ExtTarget>, RecursionError>
+where
+    F: RecursionField,
+    P: CryptographicPermutation<[F; WIDTH]> + CryptographicPermutation<[F::Packing; WIDTH]>,
+{
+    let zero = builder.ext_zero();
+    let mut folded_evals = vec![zero; indices.len()];
+    let mut ro_iters = reduced_openings
+        .into_iter()
+        .map(|ro| ro.into_iter().rev().peekable())
+        .collect_vec();
+    let mut log_height = log_max_height;
+    let mut indices = indices.to_vec();
+    let mut index_bits = index_bits.iter().map(Vec::as_slice).collect_vec();
+
+    for (&log_arity, &beta, commit, openings) in steps {
+        if openings.sibling_values.len() != indices.len()
+            || openings
+                .sibling_values
+                .iter()
+                .any(|sibling_values| sibling_values.len() != (1 << log_arity) - 1)
+        {
+            return Err(RecursionError::InvalidProofShape);
+        }
+
+        // The low bits of each index give the queried value's position in its row.
+        let mut rows = Vec::with_capacity(indices.len());
+        for (folded_eval, ro_iter, bits, sibling_values) in izip!(
+            &mut folded_evals,
+            &mut ro_iters,
+            &index_bits,
+            &openings.sibling_values
+        ) {
+            if let Some((_, ro)) = ro_iter.next_if(|(lh, _)| *lh == log_height) {
+                *folded_eval = builder.ext_add(*folded_eval, ro);
+            }
+            rows.push(insert_at(
+                builder,
+                *folded_eval,
+                sibling_values,
+                &bits[..log_arity],
+            ));
+        }
+
+        // As natively, the commit phase MMCS commits to extension elements as their base field
+        // coefficients.
+        let log_folded_height = log_height - log_arity;
+        let dimensions = [Dimensions {
+            width: EXT_DEGREE << log_arity,
+            height: 1 << log_folded_height,
+        }];
+        let index_rows = indices.iter().map(|index| index >> log_arity).collect_vec();
+        let leaves = rows
+            .iter()
+            .map(|evals| vec![evals.iter().flat_map(|eval| eval.0).collect_vec()])
+            .collect_vec();
+        let paths = witness_paths(
+            builder,
+            commit,
+            &dimensions,
+            &index_rows,
+            &leaves,
+            &openings.opening_proof,
+        )?;
+
+        for (folded_eval, bits, evals, mut leaf, path) in
+            izip!(&mut folded_evals, &mut index_bits, rows, leaves, paths)
+        {
+            let all_bits = *bits;
+            let row_bits = &all_bits[log_arity..];
+            merkle::verify_batch(
+                builder,
+                commit,
+                &[(log_folded_height, leaf.pop().unwrap())],
+                row_bits,
+                &path,
+            )?;
+            *folded_eval = fold_row(builder, evals, row_bits, beta);
+            *bits = row_bits;
+        }
+        indices = index_rows;
+        log_height = log_folded_height;
+    }
+
+    for (folded_eval, ro_iter) in izip!(&mut folded_evals, &mut ro_iters) {
+        // The smallest input may be at the final height.
+        if let Some((_, ro)) = ro_iter.next_if(|(lh, _)| *lh == log_height) {
+            *folded_eval = builder.ext_add(*folded_eval, ro);
+        }
+        if ro_iter.next().is_some() {
+            return Err(RecursionError::InvalidProofShape);
+        }
+    }
+    Ok(folded_evals)
+}
+
+/// Insert `value` into `siblings` at the position given by `bits`, least significant first.
+fn insert_at<F, P>(
+    builder: &mut CircuitBuilder<F, P>,
+    value: ExtTarget,
+    siblings: &[ExtTarget],
+    bits: &[Target],
+) -> Vec<ExtTarget>
+where
+    F: RecursionField,
+    P: Permutation<[F; WIDTH]>,
+{
+    let Some((&top_bit, low_bits)) = bits.split_last() else {
+        return vec![value];
+    };
+    let half = 1 << low_bits.len();
+    let mut if_zero = insert_at(builder, value, &siblings[..half - 1], low_bits);
+    if_zero.extend_from_slice(&siblings[half - 1..]);
+    let mut if_one = siblings[..half].to_vec();
+    if_one.extend(insert_at(builder, value, &siblings[half..], low_bits));
+    izip!(if_one, if_zero)
+        .map(|(x, y)| {
+            if x == y {
+                x
+            } else {
+                builder.ext_select(top_bit, x, y)
+            }
+        })
+        .collect()
+}
+
+/// Fold a queried row of `2^k` evaluations as natively, by folding in pairs `k` times with
+/// challenges `beta`, `beta^2`, `beta^4` and so on. `row_bits` are the bits of the row's index in
+/// the folded codeword, least significant first.
+fn fold_row<F, P>(
+    builder: &mut CircuitBuilder<F, P>,
+    mut evals: Vec<ExtTarget>,
+    row_bits: &[Target],
+    beta: ExtTarget,
+) -> ExtTarget
+where
+    F: RecursionField,
+    P: Permutation<[F; WIDTH]>,
+{
+    let log_folded_height = row_bits.len();
+    let log_arity = log2_strict_usize(evals.len());
+    let mut beta = beta;
+
+    // Pair `i` of a level with `2^p` pairs is at index `(row << p) + i` of that level's folded
+    // codeword, and interpolates the points `s` and `-s` for `s = g^rev(index)`, with `g` of order
+    // `2^(log_folded_height + p + 1)`. Reversed, the row's bits come first, so
+    //     s = g^rev(row) * w^rev(i),
+    // for `w` of order `2^(p + 1)`, where `g^rev(row)` squares from one level to the next.
+    let reversed_bits = row_bits.iter().rev().copied().collect_vec();
+    let mut row_power = builder.exp_const_base_bits(
+        F::two_adic_generator(log_folded_height + log_arity),
+        &reversed_bits,
+    );
+    for log_pairs in (0..log_arity).rev() {
+        let inverse_row_power = builder.inverse(row_power);
+        evals = evals
+            .chunks_exact(2)
+            .enumerate()
+            .map(|(i, pair)| {
+                let shift = F::two_adic_generator(log_pairs + 1)
+                    .exp_u64(reverse_bits_len(i, log_pairs) as u64);
+                let s = builder.sum(&[], &[(shift, row_power)], F::ZERO);
+                let inverse = builder.sum(
+                    &[],
+                    &[((-F::TWO * shift).inverse(), inverse_row_power)],
+                    F::ZERO,
+                );
+                fold_pair(builder, pair[0], pair[1], s, inverse, beta)
+            })
+            .collect();
+        row_power = builder.mul(row_power, row_power);
+        beta = builder.ext_square(beta);
+    }
+    evals[0]
+}
+
+/// Interpolate the pair `e0` and `e1` at `s` and `-s`, and evaluate at `beta`:
+///     e0 + (beta - s) (e1 - e0) / (-2 s)
+/// given `inverse = 1 / (-2 s)`.
+fn fold_pair<F, P>(
+    builder: &mut CircuitBuilder<F, P>,
+    e0: ExtTarget,
+    e1: ExtTarget,
+    s: Target,
+    inverse: Target,
+    beta: ExtTarget,
+) -> ExtTarget
+where
+    F: RecursionField,
+    P: Permutation<[F; WIDTH]>,
+{
+    let s = builder.ext_from_base(s);
+    let beta_minus_s = builder.ext_sub(beta, s);
+    let difference = builder.ext_sub(e1, e0);
+    let slope = builder.ext_mul(beta_minus_s, difference);
+    ExtTarget(core::array::from_fn(|k| {
+        builder.sum(
+            &[(F::ONE, slope.0[k], inverse)],
+            &[(F::ONE, e0.0[k])],
+            F::ZERO,
+        )
+    }))
+}
@@ -0,0 +1,39 @@
+//! Recursive verification of uni-stark proofs.
+//!
+//! A [`CircuitBuilder`] builds a circuit of arithmetic and Poseidon2 gates, and [`verify_stark`]
+//! adds the checks of `p3_uni_stark::verify` to it: the Fiat-Shamir transcript, the Merkle paths and
+//! folding of the FRI opening proof, and the constraints at the out-of-domain point. The circuit is
+//! then proven with the tables of [`RecursionAir`].
+
+#![no_std]
+
+extern crate alloc;
+
+mod air;
+mod challenger;
+mod circuit;
+mod config;
+mod fri;
+pub mod merkle;
+mod stark;
+
+pub use air::*;
+pub use challenger::*;
+pub use circuit::*;
+pub use config::*;
+pub use fri::*;
+pub use stark::*;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RecursionError {
+    /// The proof doesn't have the shape expected for the AIR and configuration.
+    InvalidProofShape,
+    /// The AIR uses features which can't be verified in a circuit, such as permutation arguments.
+    UnsupportedAir,
+    /// A commitment is a Merkle cap of more than one digest. The circuit only verifies Merkle
+    /// paths up to the root.
+    UnsupportedMerkleCap,
+    /// A Merkle multi-proof doesn't verify, so the path of each query, which the circuit checks
+    /// separately, can't be recovered from it.
+    InvalidOpeningProof,
+}
@@ -0,0 +1,120 @@
+use alloc::vec::Vec;
+
+use itertools::Itertools;
+use p3_symmetric::{MerkleCap, Permutation};
+
+use crate::{CircuitBuilder, RecursionError, RecursionField, Target, DIGEST_ELEMS, RATE, WIDTH};
+
+/// The root of a `MerkleTreeMmcs` commitment, which must be a cap of height 0.
+pub fn cap_root<F: Copy>(
+    commit: &MerkleCap<F, F, DIGEST_ELEMS>,
+) -> Result<[F; DIGEST_ELEMS], RecursionError> {
+    commit
+        .root()
+        .map(Into::into)
+        .ok_or(RecursionError::UnsupportedMerkleCap)
+}
+
+/// Hash `inputs` with a `PaddingFreeSponge` over the circuit's permutation.
+pub fn hash_iter<F, P>(
+    builder: &mut CircuitBuilder<F, P>,
+    inputs: &[Target],
+) -> [Target; DIGEST_ELEMS]
+where
+    F: RecursionField,
+    P: Permutation<[F; WIDTH]>,
+{
+    let mut state = [builder.zero(); WIDTH];
+    for chunk in inputs.chunks(RATE) {
+        state[..chunk.len()].copy_from_slice(chunk);
+        state = builder.permute(state);
+    }
+    state[..DIGEST_ELEMS].try_into().unwrap()
+}
+
+/// Compress two digests with a `TruncatedPermutation` over the circuit's permutation.
+pub fn compress<F, P>(
+    builder: &mut CircuitBuilder<F, P>,
+    left: [Target; DIGEST_ELEMS],
+    right: [Target; DIGEST_ELEMS],
+) -> [Target; DIGEST_ELEMS]
+where
+    F: RecursionField,
+    P: Permutation<[F; WIDTH]>,
+{
+    let mut state = [builder.zero(); WIDTH];
+    state[..DIGEST_ELEMS].copy_from_slice(&left);
+    state[DIGEST_ELEMS..2 * DIGEST_ELEMS].copy_from_slice(&right);
+    builder.permute(state)[..DIGEST_ELEMS].try_into().unwrap()
+}
+
+/// Assert that the rows of a batch of matrices opened at some index are consistent with a
+/// `MerkleTreeMmcs` commitment.
+///
+/// `openings` holds the log2 height and opened row of each matrix, and `index_bits` the bits of the
+/// index into the tallest matrix, least significant first. As in the native verifier, matrices of
+/// equal height are hashed together, in the order given.
+pub fn verify_batch<F, P>(
+    builder: &mut CircuitBuilder<F, P>,
+    commit: &[Target; DIGEST_ELEMS],
+    openings: &[(usize, Vec<Target>)],
+    index_bits: &[Target],
+    proof: &[[Target; DIGEST_ELEMS]],
+) -> Result<(), RecursionError>
+where
+    F: RecursionField,
+    P: Permutation<[F; WIDTH]>,
+{
+    let mut heights_tallest_first = openings
+        .iter()
+        .sorted_by_key(|(log_height, _)| core::cmp::Reverse(*log_height))
+        .peekable();
+    let Some(&&(mut log_height, _)) = heights_tallest_first.peek() else {
+        return Err(RecursionError::InvalidProofShape);
+    };
+    if proof.len() != log_height || index_bits.len() < log_height {
+        return Err(RecursionError::InvalidProofShape);
+    }
+
+    let leaves = heights_tallest_first
+        .peeking_take_while(|(h, _)| *h == log_height)
+        .flat_map(|(_, row)| row.iter().copied())
+        .collect_vec();
+    let mut root = hash_iter(builder, &leaves);
+
+    for (&sibling, &bit) in proof.iter().zip(index_bits) {
+        // If the bit is set, the current node is the right child.
+        let left: [Target; DIGEST_ELEMS] =
+            core::array::from_fn(|i| builder.select(bit, sibling[i], root[i]));
+        let right = core::array::from_fn(|i| {
+            builder.sum(
+                &[],
+                &[
+                    (F::ONE, root[i]),
+                    (F::ONE, sibling[i]),
+                    (F::NEG_ONE, left[i]),
+                ],
+                F::ZERO,
+            )
+        });
+        root = compress(builder, left, right);
+        log_height -= 1;
+
+        if heights_tallest_first
+            .peek()
+            .is_some_and(|(h, _)| *h == log_height)
+        {
+            let leaves = heights_tallest_first
+                .peeking_take_while(|(h, _)| *h == log_height)
+                .flat_map(|(_, row)| row.iter().copied())
+                .collect_vec();
+            let digest = hash_iter(builder, &leaves);
+            root = compress(builder, root, digest);
+        }
+    }
+
+    for (&root, &commit) in root.iter().zip(commit) {
+        builder.assert_eq(root, commit);
+    }
+    Ok(())
+}
@@ -0,0 +1,357 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use hashbrown::HashMap;
+use itertools::Itertools;
+use p3_air::Air;
+use p3_commit::{Pcs, PolynomialSpace};
+use p3_dft::TwoAdicSubgroupDft;
+use p3_symmetric::CryptographicPermutation;
+use p3_uni_stark::{
+    get_log_quotient_degree, get_symbolic_constraints, Entry, Proof, StarkGenericConfig,
+    StarkVerifyingKey, SymbolicAirBuilder, SymbolicExpression,
+};
+
+use crate::merkle::cap_root;
+use crate::{
+    verify_fri, CircuitBuilder, DuplexChallengerTargets, ExtTarget, FriProofTargets,
+    InnerChallenge, InnerChallenger, InnerConfig, InnerPcs, OpeningRound, RecursionError,
+    RecursionField, Target, DIGEST_ELEMS, EXT_DEGREE, WIDTH,
+};
+
+/// The Lagrange selectors of the trace domain, evaluated at the out-of-domain point.
+struct SelectorTargets {
+    is_first_row: ExtTarget,
+    is_last_row: ExtTarget,
+    is_transition: ExtTarget,
+}
+
+/// The opened values of the main and preprocessed traces at `zeta` and `zeta_next`.
+struct OpenedValuesTargets<'a> {
+    preprocessed_local: &'a [ExtTarget],
+    preprocessed_next: &'a [ExtTarget],
+    trace_local: &'a [ExtTarget],
+    trace_next: &'a [ExtTarget],
+    public_values: &'a [Target],
+}
+
+/// Assert that `proof` is a valid proof of `air` with the given public values, mirroring
+/// `p3_uni_stark::verify_with_key`.
+///
+/// The resulting circuit only depends on `config`, `verifying_key`, `air`, and the shape of
+/// `proof`, i.e. its degree and the number of FRI queries, so it can verify any proof of that
+/// shape. The proof itself is added to the circuit as witnesses.
+///
+/// AIRs with permutation arguments aren't supported, and result in
+/// [`RecursionError::UnsupportedAir`].
+pub fn verify_stark<F, P, Dft, A>(
+    builder: &mut CircuitBuilder<F, P>,
+    config: &InnerConfig<F, P, Dft>,
+    verifying_key: &StarkVerifyingKey<InnerConfig<F, P, Dft>>,
+    air: &A,
+    proof: &Proof<InnerConfig<F, P, Dft>>,
+    public_values: &[Target],
+) -> Result<(), RecursionError>
+where
+    F: RecursionField,
+    P: CryptographicPermutation<[F; WIDTH]> + CryptographicPermutation<[F::Packing; WIDTH]>,
+    Dft: TwoAdicSubgroupDft<F>,
+    A: Air<SymbolicAirBuilder<F>>,
+{
+    if air.permutation_width() > 0 || proof.commitments().permutation().is_some() {
+        return Err(RecursionError::UnsupportedAir);
+    }
+
+    let degree_bits = proof.degree_bits();
+    let preprocessed_width = verifying_key.preprocessed_width();
+    if verifying_key
+        .degree_bits()
+        .is_some_and(|bits| bits != degree_bits)
+        || public_values.len() != air.num_public_values()
+    {
+        return Err(RecursionError::InvalidProofShape);
+    }
+
+    let log_quotient_degree =
+        get_log_quotient_degree::<F, A>(air, preprocessed_width, public_values.len(), false);
+    let quotient_degree = 1 << log_quotient_degree;
+
+    let pcs = config.pcs();
+    let trace_domain =
+        <InnerPcs<F, P, Dft> as Pcs<InnerChallenge<F>, InnerChallenger<F, P>>>::natural_domain_for_degree(
+            pcs,
+            1 << degree_bits,
+        );
+    let quotient_domain =
+        trace_domain.create_disjoint_domain(1 << (degree_bits + log_quotient_degree));
+    let quotient_chunks_domains = quotient_domain.split_domains(quotient_degree);
+
+    let opened_values = proof.opened_values();
+    let air_width = air.width();
+    if opened_values.preprocessed_local().len() != preprocessed_width
+        || opened_values.preprocessed_next().len() != preprocessed_width
+        || opened_values.trace_local().len() != air_width
+        || opened_values.trace_next().len() != air_width
+        || opened_values.quotient_chunks().len() != quotient_degree
+        || opened_values
+            .quotient_chunks()
+            .iter()
+            .any(|chunk| chunk.len() != EXT_DEGREE)
+    {
+        return Err(RecursionError::InvalidProofShape);
+    }
+
+    // Add the proof to the circuit.
+    let ext_witnesses = |builder: &mut CircuitBuilder<F, P>, values: &[_]| {
+        values
+            .iter()
+            .map(|&value| builder.ext_witness(value))
+            .collect_vec()
+    };
+    let preprocessed_local = ext_witnesses(builder, opened_values.preprocessed_local());
+    let preprocessed_next = ext_witnesses(builder, opened_values.preprocessed_next());
+    let trace_local = ext_witnesses(builder, opened_values.trace_local());
+    let trace_next = ext_witnesses(builder, opened_values.trace_next());
+    let quotient_chunks = opened_values
+        .quotient_chunks()
+        .iter()
+        .map(|chunk| ext_witnesses(builder, chunk))
+        .collect_vec();
+    let digest_witness = |builder: &mut CircuitBuilder<F, P>, digest: [F; DIGEST_ELEMS]| {
+        digest.map(|x| builder.witness(x))
+    };
+    let trace_commit = digest_witness(builder, cap_root(proof.commitments().trace())?);
+    let quotient_commit = digest_witness(builder, cap_root(proof.commitments().quotient_chunks())?);
+    let preprocessed_commit = verifying_key
+        .preprocessed_commitment()
+        .map(|commit| cap_root(commit).map(|root| root.map(|x| builder.constant(x))))
+        .transpose()?;
+    let fri_proof = FriProofTargets::new(builder, proof.opening_proof())?;
+
+    // Replay the transcript.
+    let mut challenger = DuplexChallengerTargets::new(builder);
+    let instance_data = verifying_key
+        .instance_data()
+        .into_iter()
+        .map(|x| builder.constant(x))
+        .collect::<Vec<_>>();
+    challenger.observe_slice(builder, &instance_data);
+    if let Some(preprocessed_commit) = &preprocessed_commit {
+        challenger.observe_slice(builder, preprocessed_commit);
+    }
+    let degree_bits_target = builder.constant(F::from_canonical_usize(degree_bits));
+    challenger.observe(builder, degree_bits_target);
+    challenger.observe_slice(builder, &trace_commit);
+    challenger.observe_slice(builder, public_values);
+    let alpha = challenger.sample_ext(builder);
+    challenger.observe_slice(builder, &quotient_commit);
+    let zeta = challenger.sample_ext(builder);
+    let zeta_next = ext_scale(builder, zeta, F::two_adic_generator(degree_bits));
+
+    let mut rounds = vec![
+        OpeningRound {
+            commit: trace_commit,
+            matrices: vec![(
+                degree_bits,
+                vec![(zeta, trace_local.clone()), (zeta_next, trace_next.clone())],
+            )],
+        },
+        OpeningRound {
+            commit: quotient_commit,
+            matrices: quotient_chunks_domains
+                .iter()
+                .zip(&quotient_chunks)
+                .map(|(domain, values)| (domain.log_n, vec![(zeta, values.clone())]))
+                .collect(),
+        },
+    ];
+    if let Some(preprocessed_commit) = preprocessed_commit {
+        rounds.push(OpeningRound {
+            commit: preprocessed_commit,
+            matrices: vec![(
+                degree_bits,
+                vec![
+                    (zeta, preprocessed_local.clone()),
+                    (zeta_next, preprocessed_next.clone()),
+                ],
+            )],
+        });
+    }
+    verify_fri(
+        builder,
+        &mut challenger,
+        pcs.fri_config(),
+        &rounds,
+        &fri_proof,
+    )?;
+
+    // Recompose the quotient at zeta from its chunks.
+    let zeta_pow_chunk_size = builder.ext_exp_power_of_2(zeta, degree_bits);
+    let zps_at_zeta = quotient_chunks_domains
+        .iter()
+        .map(|domain| {
+            // (zeta / shift)^n - 1
+            let scale = domain.shift.inverse().exp_power_of_2(domain.log_n);
+            let scaled = ext_scale(builder, zeta_pow_chunk_size, scale);
+            let mut limbs = scaled.0;
+            limbs[0] = builder.sum(&[], &[(F::ONE, limbs[0])], F::NEG_ONE);
+            ExtTarget(limbs)
+        })
+        .collect_vec();
+    let zps = quotient_chunks_domains
+        .iter()
+        .enumerate()
+        .map(|(i, domain)| {
+            let mut zp = builder.ext_one();
+            let mut scale = F::ONE;
+            for (j, other_domain) in quotient_chunks_domains.iter().enumerate() {
+                if j != i {
+                    zp = builder.ext_mul(zp, zps_at_zeta[j]);
+                    scale *= other_domain.zp_at_point(domain.first_point()).inverse();
+                }
+            }
+            ext_scale(builder, zp, scale)
+        })
+        .collect_vec();
+    let chunk_values = quotient_chunks
+        .iter()
+        .map(|chunk| {
+            // sum_e X^e chunk[e]
+            ExtTarget(core::array::from_fn(|k| {
+                let terms = (0..EXT_DEGREE)
+                    .map(|e| {
+                        if e <= k {
+                            (F::ONE, chunk[e].0[k - e])
+                        } else {
+                            (F::W, chunk[e].0[k + EXT_DEGREE - e])
+                        }
+                    })
+                    .collect_vec();
+                builder.sum(&[], &terms, F::ZERO)
+            }))
+        })
+        .collect_vec();
+    let zero = builder.ext_zero();
+    let quotient = builder.ext_inner_product(&zps, &chunk_values, zero);
+
+    // Evaluate the constraints at zeta.
+    let z_h = {
+        let zeta_pow_n = builder.ext_exp_power_of_2(zeta, degree_bits);
+        let mut limbs = zeta_pow_n.0;
+        limbs[0] = builder.sum(&[], &[(F::ONE, limbs[0])], F::NEG_ONE);
+        ExtTarget(limbs)
+    };
+    let zeta_minus = |builder: &mut CircuitBuilder<F, P>, x: F| {
+        let mut limbs = zeta.0;
+        limbs[0] = builder.sum(&[], &[(F::ONE, limbs[0])], -x);
+        ExtTarget(limbs)
+    };
+    let zeta_minus_one = zeta_minus(builder, F::ONE);
+    let is_transition = zeta_minus(builder, F::two_adic_generator(degree_bits).inverse());
+    let selectors = SelectorTargets {
+        is_first_row: builder.ext_div(z_h, zeta_minus_one),
+        is_last_row: builder.ext_div(z_h, is_transition),
+        is_transition,
+    };
+    let opened_values = OpenedValuesTargets {
+        preprocessed_local: &preprocessed_local,
+        preprocessed_next: &preprocessed_next,
+        trace_local: &trace_local,
+        trace_next: &trace_next,
+        public_values,
+    };
+
+    let constraints = get_symbolic_constraints(air, preprocessed_width, public_values.len());
+    let mut memo = HashMap::new();
+    let mut folded_constraints = builder.ext_zero();
+    for constraint in &constraints {
+        let value = eval_symbolic(builder, &mut memo, &selectors, &opened_values, constraint)?;
+        folded_constraints = builder.ext_mul_add(folded_constraints, alpha, value);
+    }
+
+    // Finally, check that
+    //     folded_constraints(zeta) = quotient(zeta) Z_H(zeta)
+    let expected = builder.ext_mul(quotient, z_h);
+    builder.assert_ext_eq(folded_constraints, expected);
+
+    Ok(())
+}
+
+/// Multiply an extension field element by a base field constant.
+fn ext_scale<F, P>(builder: &mut CircuitBuilder<F, P>, x: ExtTarget, scale: F) -> ExtTarget
+where
+    F: RecursionField,
+    P: CryptographicPermutation<[F; WIDTH]>,
+{
+    ExtTarget(x.0.map(|x| builder.sum(&[], &[(scale, x)], F::ZERO)))
+}
+
+/// Evaluate a symbolic constraint at the out-of-domain point, reusing the results for shared
+/// subexpressions.
+fn eval_symbolic<F, P>(
+    builder: &mut CircuitBuilder<F, P>,
+    memo: &mut HashMap<*const SymbolicExpression<F>, ExtTarget>,
+    selectors: &SelectorTargets,
+    opened_values: &OpenedValuesTargets<'_>,
+    expression: &SymbolicExpression<F>,
+) -> Result<ExtTarget, RecursionError>
+where
+    F: RecursionField,
+    P: CryptographicPermutation<[F; WIDTH]>,
+{
+    let key = expression as *const _;
+    if let Some(&value) = memo.get(&key) {
+        return Ok(value);
+    }
+
+    let mut eval = |builder: &mut CircuitBuilder<F, P>, x: &SymbolicExpression<F>| {
+        eval_symbolic(builder, memo, selectors, opened_values, x)
+    };
+    let value = match expression {
+        SymbolicExpression::Variable(v) => match v.entry {
+            Entry::Preprocessed { offset } => {
+                let row = [
+                    opened_values.preprocessed_local,
+                    opened_values.preprocessed_next,
+                ];
+                row[offset][v.index]
+            }
+            Entry::Main { offset } => {
+                let row = [opened_values.trace_local, opened_values.trace_next];
+                row[offset][v.index]
+            }
+            Entry::Public => builder.ext_from_base(opened_values.public_values[v.index]),
+            Entry::Permutation { .. } | Entry::Challenge | Entry::CumulativeSum => {
+                return Err(RecursionError::UnsupportedAir)
+            }
+        },
+        SymbolicExpression::IsFirstRow => selectors.is_first_row,
+        SymbolicExpression::IsLastRow => selectors.is_last_row,
+        SymbolicExpression::IsTransition => selectors.is_transition,
+        SymbolicExpression::Constant(c) => {
+            let c = builder.constant(*c);
+            builder.ext_from_base(c)
+        }
+        SymbolicExpression::Add { x, y, .. } => {
+            let x = eval(builder, x)?;
+            let y = eval(builder, y)?;
+            builder.ext_add(x, y)
+        }
+        SymbolicExpression::Sub { x, y, .. } => {
+            let x = eval(builder, x)?;
+            let y = eval(builder, y)?;
+            builder.ext_sub(x, y)
+        }
+        SymbolicExpression::Neg { x, .. } => {
+            let x = eval(builder, x)?;
+            builder.ext_neg(x)
+        }
+        SymbolicExpression::Mul { x, y, .. } => {
+            let x = eval(builder, x)?;
+            let y = eval(builder, y)?;
+            builder.ext_mul(x, y)
+        }
+    };
+    memo.insert(key, value);
+    Ok(value)
+}
@@ -0,0 +1,434 @@
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir, PairBuilder};
+use p3_baby_bear::{BabyBear, GenericPoseidon2LinearLayersBabyBear, Poseidon2BabyBear};
+use p3_challenger::{CanObserve, CanSample, CanSampleBits, FieldChallenger};
+use p3_dft::Radix2DitParallel;
+use p3_field::{Field, FieldAlgebra, PrimeField64};
+use p3_fri::FriConfig;
+use p3_koala_bear::{GenericPoseidon2LinearLayersKoalaBear, KoalaBear, Poseidon2KoalaBear};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_poseidon2::ExternalLayerConstants;
+use p3_poseidon2_air::RoundConstants;
+use p3_recursion::{
+    verify_stark, Circuit, CircuitBuilder, DuplexChallengerTargets, InnerChallenge,
+    InnerChallenger, InnerConfig, RecursionAir, RecursionError, RecursionField, WIDTH,
+};
+use p3_symmetric::CryptographicPermutation;
+use p3_uni_stark::testing::{make_config_with_fri, make_fri_config};
+use p3_uni_stark::{
+    check_all_constraints, prove_batch, prove_with_key, setup, verify_batch, verify_with_key,
+    DebugConstraintBuilder, Proof, ProverConstraintFolder, StarkGenericConfig, StarkVerifyingKey,
+    SymbolicAirBuilder, Val, VerifierConstraintFolder,
+};
+use rand::distributions::{Distribution, Standard};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// A Fibonacci AIR whose initial values are selected by a preprocessed column, so that proofs of it
+/// open all three kinds of commitments.
+pub struct FibonacciAir {
+    log_height: usize,
+}
+
+impl<F: Field> BaseAir<F> for FibonacciAir {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        let mut values = F::zero_vec(1 << self.log_height);
+        values[0] = F::ONE;
+        Some(RowMajorMatrix::new_col(values))
+    }
+
+    fn num_public_values(&self) -> usize {
+        3
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues + PairBuilder> Air<AB> for FibonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        let preprocessed = builder.preprocessed();
+        let main = builder.main();
+        let pis = builder.public_values();
+        let (a, b, x) = (pis[0], pis[1], pis[2]);
+
+        let is_first = preprocessed.row_slice(0)[0];
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+
+        builder.assert_zero(is_first * (local[0] - a.into()));
+        builder.assert_zero(is_first * (local[1] - b.into()));
+
+        let mut when_transition = builder.when_transition();
+        when_transition.assert_eq(local[1], next[0]);
+        when_transition.assert_eq(local[0] + local[1], next[1]);
+
+        builder.when_last_row().assert_eq(local[1], x);
+    }
+}
+
+impl FibonacciAir {
+    fn generate_trace<F: Field>(&self, a: u64, b: u64) -> (RowMajorMatrix<F>, Vec<F>) {
+        let mut values = vec![F::from_canonical_u64(a), F::from_canonical_u64(b)];
+        for i in 1..1 << self.log_height {
+            values.push(values[2 * i - 1]);
+            values.push(values[2 * i - 2] + values[2 * i - 1]);
+        }
+        let result = *values.last().unwrap();
+        let public_values = vec![F::from_canonical_u64(a), F::from_canonical_u64(b), result];
+        (RowMajorMatrix::new(values, 2), public_values)
+    }
+}
+
+type Dft<F> = Radix2DitParallel<F>;
+type Config<F, P> = InnerConfig<F, P, Dft<F>>;
+
+type BabyBearAir = RecursionAir<BabyBear, GenericPoseidon2LinearLayersBabyBear, 7, 1, 4, 13>;
+type KoalaBearAir = RecursionAir<KoalaBear, GenericPoseidon2LinearLayersKoalaBear, 3, 0, 4, 20>;
+
+/// A permutation with random round constants, along with the same constants for the Poseidon2
+/// table.
+fn make_permutation<F, P, const HALF_FULL_ROUNDS: usize, const PARTIAL_ROUNDS: usize>(
+    rng: &mut impl Rng,
+    new: fn(ExternalLayerConstants<F, WIDTH>, Vec<F>) -> P,
+) -> (
+    P,
+    RoundConstants<F, WIDTH, HALF_FULL_ROUNDS, PARTIAL_ROUNDS>,
+)
+where
+    F: Field,
+    Standard: Distribution<F> + Distribution<[F; WIDTH]>,
+{
+    let external = ExternalLayerConstants::new_from_rng(2 * HALF_FULL_ROUNDS, rng);
+    let internal: Vec<F> = rng.sample_iter(Standard).take(PARTIAL_ROUNDS).collect();
+    let constants = RoundConstants::new(
+        external.get_initial_constants().clone().try_into().unwrap(),
+        internal.clone().try_into().unwrap(),
+        external
+            .get_terminal_constants()
+            .clone()
+            .try_into()
+            .unwrap(),
+    );
+    (new(external, internal), constants)
+}
+
+fn make_config<F, P>(perm: &P, num_queries: usize) -> Config<F, P>
+where
+    F: RecursionField,
+    P: CryptographicPermutation<[F; WIDTH]> + CryptographicPermutation<[F::Packing; WIDTH]>,
+{
+    make_folding_config(perm, num_queries, 1, 0)
+}
+
+fn make_folding_config<F, P>(
+    perm: &P,
+    num_queries: usize,
+    log_arity: usize,
+    log_final_poly_len: usize,
+) -> Config<F, P>
+where
+    F: RecursionField,
+    P: CryptographicPermutation<[F; WIDTH]> + CryptographicPermutation<[F::Packing; WIDTH]>,
+{
+    let fri_config = FriConfig {
+        log_arity,
+        log_final_poly_len,
+        ..make_fri_config(perm, 1, num_queries, 8)
+    };
+    make_config_with_fri(perm, fri_config)
+}
+
+/// Prove the Fibonacci AIR, checking the proof natively.
+#[allow(clippy::type_complexity)]
+fn prove_fibonacci<F, P>(
+    config: &Config<F, P>,
+    perm: &P,
+    air: &FibonacciAir,
+    a: u64,
+    b: u64,
+) -> (StarkVerifyingKey<Config<F, P>>, Proof<Config<F, P>>, Vec<F>)
+where
+    F: RecursionField,
+    P: CryptographicPermutation<[F; WIDTH]> + CryptographicPermutation<[F::Packing; WIDTH]>,
+{
+    let (proving_key, verifying_key) = setup(config, air).expect("setup failed");
+    let (trace, public_values) = air.generate_trace(a, b);
+    let mut challenger = InnerChallenger::new(perm.clone());
+    let proof = prove_with_key(
+        config,
+        &proving_key,
+        air,
+        &mut challenger,
+        trace,
+        &public_values,
+    )
+    .expect("proving failed");
+    let mut challenger = InnerChallenger::new(perm.clone());
+    verify_with_key(
+        config,
+        &verifying_key,
+        air,
+        &mut challenger,
+        &proof,
+        &public_values,
+    )
+    .expect("native verification failed");
+    (verifying_key, proof, public_values)
+}
+
+/// Build a circuit verifying `proof`, exposing its public values.
+fn build_circuit<F, P>(
+    perm: &P,
+    config: &Config<F, P>,
+    verifying_key: &StarkVerifyingKey<Config<F, P>>,
+    air: &FibonacciAir,
+    proof: &Proof<Config<F, P>>,
+    public_values: &[F],
+) -> Result<Circuit<F>, RecursionError>
+where
+    F: RecursionField,
+    P: CryptographicPermutation<[F; WIDTH]> + CryptographicPermutation<[F::Packing; WIDTH]>,
+{
+    let mut builder = CircuitBuilder::new(perm.clone());
+    let public_values = public_values
+        .iter()
+        .map(|&x| builder.public_input(x))
+        .collect::<Vec<_>>();
+    verify_stark(
+        &mut builder,
+        config,
+        verifying_key,
+        air,
+        proof,
+        &public_values,
+    )?;
+    Ok(builder.build())
+}
+
+/// Prove the given traces of the recursion tables, and verify that proof.
+fn prove_tables<SC, A>(
+    config: &SC,
+    challenger: &SC::Challenger,
+    tables: &[A],
+    traces: Vec<RowMajorMatrix<Val<SC>>>,
+    public_values: &[Vec<Val<SC>>],
+) where
+    SC: StarkGenericConfig,
+    SC::Challenger: Clone,
+    A: Air<SymbolicAirBuilder<Val<SC>>>
+        + for<'a> Air<DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>
+        + for<'a> Air<ProverConstraintFolder<'a, SC>>
+        + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+{
+    let proof = prove_batch(
+        config,
+        tables,
+        &mut challenger.clone(),
+        traces,
+        public_values,
+    )
+    .expect("proving the circuit failed");
+    verify_batch(
+        config,
+        tables,
+        &mut challenger.clone(),
+        &proof,
+        public_values,
+    )
+    .expect("verifying the circuit's proof failed");
+}
+
+#[test]
+fn test_recursive_verification_baby_bear() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let (perm, constants) = make_permutation(&mut rng, Poseidon2BabyBear::<WIDTH>::new);
+    let config = make_config(&perm, 4);
+    let air = FibonacciAir { log_height: 3 };
+    let (verifying_key, proof, public_values) = prove_fibonacci(&config, &perm, &air, 0, 1);
+
+    let circuit = build_circuit(&perm, &config, &verifying_key, &air, &proof, &public_values)
+        .expect("building the circuit failed");
+    assert_eq!(circuit.unsatisfied_gates(), Vec::<usize>::new());
+    assert_eq!(circuit.public_values(), public_values);
+
+    let tables = BabyBearAir::tables(&circuit, constants);
+    let traces = tables
+        .iter()
+        .map(|table| table.generate_trace(&circuit))
+        .collect::<Vec<_>>();
+    let public_values = tables
+        .iter()
+        .map(|table| table.public_values(&circuit))
+        .collect::<Vec<_>>();
+    prove_tables(
+        &config,
+        &InnerChallenger::new(perm),
+        &tables,
+        traces,
+        &public_values,
+    );
+}
+
+#[test]
+fn test_recursive_verification_koala_bear() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let (perm, constants) = make_permutation(&mut rng, Poseidon2KoalaBear::<WIDTH>::new);
+    let config = make_config(&perm, 4);
+    let air = FibonacciAir { log_height: 4 };
+    let (verifying_key, proof, public_values) = prove_fibonacci(&config, &perm, &air, 2, 3);
+
+    let circuit = build_circuit(&perm, &config, &verifying_key, &air, &proof, &public_values)
+        .expect("building the circuit failed");
+    assert_eq!(circuit.unsatisfied_gates(), Vec::<usize>::new());
+
+    let tables = KoalaBearAir::tables(&circuit, constants);
+    let traces = tables
+        .iter()
+        .map(|table| table.generate_trace(&circuit))
+        .collect::<Vec<_>>();
+    let public_values = tables
+        .iter()
+        .map(|table| table.public_values(&circuit))
+        .collect::<Vec<_>>();
+    prove_tables(
+        &config,
+        &InnerChallenger::new(perm),
+        &tables,
+        traces,
+        &public_values,
+    );
+}
+
+#[test]
+fn test_circuit_independent_of_proof() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let (perm, constants) = make_permutation(&mut rng, Poseidon2BabyBear::<WIDTH>::new);
+    let config = make_config(&perm, 2);
+    let air = FibonacciAir { log_height: 3 };
+
+    let preprocessed_traces = [(0, 1), (5, 8)].map(|(a, b)| {
+        let (verifying_key, proof, public_values) = prove_fibonacci(&config, &perm, &air, a, b);
+        let circuit = build_circuit(&perm, &config, &verifying_key, &air, &proof, &public_values)
+            .expect("building the circuit failed");
+        assert_eq!(circuit.unsatisfied_gates(), Vec::<usize>::new());
+        BabyBearAir::tables(&circuit, constants.clone())
+            .iter()
+            .map(|table| table.preprocessed_trace())
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(preprocessed_traces[0], preprocessed_traces[1]);
+}
+
+#[test]
+fn test_wrong_public_values_unsatisfiable() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let (perm, constants) = make_permutation(&mut rng, Poseidon2BabyBear::<WIDTH>::new);
+    let config = make_config(&perm, 2);
+    let air = FibonacciAir { log_height: 3 };
+    let (verifying_key, proof, mut public_values) = prove_fibonacci(&config, &perm, &air, 0, 1);
+    public_values[2] += BabyBear::ONE;
+
+    let circuit = build_circuit(&perm, &config, &verifying_key, &air, &proof, &public_values)
+        .expect("building the circuit failed");
+    assert!(!circuit.unsatisfied_gates().is_empty());
+
+    // The tables catch the unsatisfied gates.
+    let tables = BabyBearAir::tables(&circuit, constants);
+    let table = &tables[0];
+    let preprocessed = table.preprocessed_trace();
+    let main = table.generate_trace(&circuit);
+    let challenges: [InnerChallenge<BabyBear>; 2] = rng.gen();
+    let permutation = table
+        .generate_permutation_trace(preprocessed.as_ref(), &main, &challenges)
+        .unwrap();
+    let cumulative_sum = *permutation.values.last().unwrap();
+    let report = check_all_constraints(
+        table,
+        preprocessed.as_ref(),
+        &main,
+        Some(&permutation),
+        &challenges,
+        cumulative_sum,
+        &table.public_values(&circuit),
+    )
+    .unwrap();
+    assert!(report
+        .failures
+        .iter()
+        .any(|failure| failure.label == Some("arithmetic gate")));
+}
+
+#[test]
+fn test_recursive_verification_higher_arity() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let (perm, _) = make_permutation::<_, _, 4, 13>(&mut rng, Poseidon2BabyBear::<WIDTH>::new);
+    let air = FibonacciAir { log_height: 6 };
+
+    let num_poseidon2_gates = [(1, 0), (3, 2)].map(|(log_arity, log_final_poly_len)| {
+        let config = make_folding_config(&perm, 4, log_arity, log_final_poly_len);
+        let (verifying_key, proof, public_values) = prove_fibonacci(&config, &perm, &air, 0, 1);
+        let circuit = build_circuit(&perm, &config, &verifying_key, &air, &proof, &public_values)
+            .expect("building the circuit failed");
+        assert_eq!(circuit.unsatisfied_gates(), Vec::<usize>::new());
+        circuit.poseidon2_gates.len()
+    });
+    // Folding by 8 down to a polynomial of degree 4 opens fewer, wider commit phase rows.
+    assert!(num_poseidon2_gates[1] < num_poseidon2_gates[0]);
+}
+
+#[test]
+fn test_wrong_shape() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let (perm, _) = make_permutation::<_, _, 4, 13>(&mut rng, Poseidon2BabyBear::<WIDTH>::new);
+    let config = make_config(&perm, 2);
+    let air = FibonacciAir { log_height: 3 };
+    let (verifying_key, proof, public_values) = prove_fibonacci(&config, &perm, &air, 0, 1);
+
+    let other_config = make_config(&perm, 3);
+    assert_eq!(
+        build_circuit(
+            &perm,
+            &other_config,
+            &verifying_key,
+            &air,
+            &proof,
+            &public_values
+        )
+        .err(),
+        Some(RecursionError::InvalidProofShape)
+    );
+}
+
+#[test]
+fn test_challenger_matches_native() {
+    let mut rng = StdRng::seed_from_u64(6);
+    let (perm, _) = make_permutation::<_, _, 4, 13>(&mut rng, Poseidon2BabyBear::<WIDTH>::new);
+    let mut native = InnerChallenger::new(perm.clone());
+    let mut builder = CircuitBuilder::new(perm);
+    let mut challenger = DuplexChallengerTargets::new(&mut builder);
+
+    for num_observed in [3, 8, 0, 13] {
+        for _ in 0..num_observed {
+            let value: BabyBear = rng.gen();
+            native.observe(value);
+            let target = builder.witness(value);
+            challenger.observe(&mut builder, target);
+        }
+        let native_sample: InnerChallenge<BabyBear> = native.sample();
+        let sample = challenger.sample_ext(&mut builder);
+        assert_eq!(builder.ext_value(sample), native_sample);
+
+        let native_bits = native.sample_bits(10);
+        let bits = challenger.sample_bits(&mut builder, 10);
+        let value = bits.iter().rev().fold(0, |acc, &bit| {
+            2 * acc + builder.value(bit).as_canonical_u64() as usize
+        });
+        assert_eq!(value, native_bits);
+    }
+    let native_ext: InnerChallenge<BabyBear> = native.sample_ext_element();
+    let ext = challenger.sample_ext(&mut builder);
+    assert_eq!(builder.ext_value(ext), native_ext);
+
+    assert!(builder.build().unsatisfied_gates().is_empty());
+}
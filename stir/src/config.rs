@@ -0,0 +1,95 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(Debug)]
+pub struct StirConfig<M> {
+    pub log_blowup: usize,
+    /// The log2 of the number of evaluations folded into one in each round.
+    pub log_folding_factor: usize,
+    /// The log2 of the number of coefficients of the final polynomial, which is sent in the clear
+    /// once the codeword has been folded down to its degree.
+    pub log_final_poly_len: usize,
+    /// The conjectured soundness, in bits, which the queries and proof-of-work of every round must
+    /// reach. See [`num_queries`](Self::num_queries).
+    pub security_bits: usize,
+    /// The proof-of-work bits ground before each round's queries.
+    pub proof_of_work_bits: usize,
+    pub mmcs: M,
+}
+
+impl<M> StirConfig<M> {
+    pub const fn blowup(&self) -> usize {
+        1 << self.log_blowup
+    }
+
+    pub const fn folding_factor(&self) -> usize {
+        1 << self.log_folding_factor
+    }
+
+    pub const fn final_poly_len(&self) -> usize {
+        1 << self.log_final_poly_len
+    }
+
+    /// The parameters which affect STIR proofs, for binding into a transcript. See
+    /// [`Pcs::parameters`](p3_commit::Pcs::parameters).
+    pub fn parameters(&self) -> Vec<usize> {
+        vec![
+            self.log_blowup,
+            self.log_folding_factor,
+            self.log_final_poly_len,
+            self.security_bits,
+            self.proof_of_work_bits,
+        ]
+    }
+
+    /// The log2 factor each round folds by, for an initial polynomial of degree less than
+    /// `2^log_degree`.
+    ///
+    /// Every round but the last commits to its folded polynomial, and folds by `folding_factor()`.
+    /// The last round folds by less where that would pass `final_poly_len()` coefficients, and
+    /// doesn't fold at all if the polynomial is already that small.
+    pub fn log_folding_factors(&self, log_degree: usize) -> Vec<usize> {
+        assert!(self.log_folding_factor > 0, "STIR must fold by at least 2");
+        let log_final_degree = self.log_final_poly_len.min(log_degree);
+        let mut log_degree = log_degree;
+        let mut log_folding_factors = vec![];
+        while log_degree > log_final_degree {
+            let log_folding_factor = self.log_folding_factor.min(log_degree - log_final_degree);
+            log_folding_factors.push(log_folding_factor);
+            log_degree -= log_folding_factor;
+        }
+        if log_folding_factors.is_empty() {
+            log_folding_factors.push(0);
+        }
+        log_folding_factors
+    }
+
+    /// The log2 inverse rate of the codeword each round starts from, for an initial polynomial of
+    /// degree less than `2^log_degree`. A round folding by `2^k` halves the domain, so the next
+    /// round's rate is `2^(k - 1)` times lower.
+    pub fn log_inv_rates(&self, log_degree: usize) -> Vec<usize> {
+        self.log_folding_factors(log_degree)
+            .into_iter()
+            .scan(self.log_blowup, |log_inv_rate, log_folding_factor| {
+                let round_log_inv_rate = *log_inv_rate;
+                *log_inv_rate += log_folding_factor.saturating_sub(1);
+                Some(round_log_inv_rate)
+            })
+            .collect()
+    }
+
+    /// The number of queries made in each round, for an initial polynomial of degree less than
+    /// `2^log_degree`.
+    ///
+    /// Under the conjecture that each query of a codeword of rate `ρ` has an error of at most `ρ`,
+    /// as in [`FriConfig::conjectured_soundness_bits`](p3_fri::FriConfig::conjectured_soundness_bits),
+    /// each round makes enough queries to reach `security_bits` together with its proof-of-work.
+    pub fn num_queries(&self, log_degree: usize) -> Vec<usize> {
+        assert!(self.log_blowup > 0, "STIR needs a blowup of at least 2");
+        let query_bits = self.security_bits.saturating_sub(self.proof_of_work_bits);
+        self.log_inv_rates(log_degree)
+            .into_iter()
+            .map(|log_inv_rate| query_bits.div_ceil(log_inv_rate).max(1))
+            .collect()
+    }
+}
@@ -0,0 +1,20 @@
+//! An implementation of the [STIR](https://eprint.iacr.org/2024/390) low-degree test (LDT).
+//!
+//! Like FRI, STIR folds a codeword down to a small polynomial. But where FRI's domains shrink with
+//! the degree, each STIR round only halves its domain, so the rate drops from round to round and
+//! later rounds need fewer queries. [`TwoAdicStirPcs`] uses it in place of FRI in a PCS.
+
+#![no_std]
+
+extern crate alloc;
+
+mod config;
+mod proof;
+pub mod prover;
+mod two_adic_pcs;
+mod utils;
+pub mod verifier;
+
+pub use config::*;
+pub use proof::*;
+pub use two_adic_pcs::*;
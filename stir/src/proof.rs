@@ -0,0 +1,50 @@
+use alloc::vec::Vec;
+
+use p3_commit::Mmcs;
+use p3_field::Field;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound(
+    serialize = "Witness: Serialize, InputProof: Serialize",
+    deserialize = "Witness: Deserialize<'de>, InputProof: Deserialize<'de>"
+))]
+pub struct StirProof<F: Field, M: Mmcs<F>, Witness, InputProof> {
+    /// The commitment to the first round's codeword, the random combination of the inputs.
+    pub initial_commitment: M::Commitment,
+    /// A proof for each round but the last, which commits to its folded polynomial.
+    pub round_proofs: Vec<RoundProof<F, M, Witness>>,
+    /// The coefficients of the polynomial the last round folds down to, lowest degree first.
+    pub final_poly: Vec<F>,
+    pub final_pow_witness: Witness,
+    /// The openings of the commitment the last round's codeword is computed from, at its queries.
+    pub final_queries: QueryOpenings<F, M>,
+    /// The openings of the inputs at the first round's queries.
+    pub input_proof: InputProof,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound(
+    serialize = "Witness: Serialize",
+    deserialize = "Witness: Deserialize<'de>"
+))]
+pub struct RoundProof<F: Field, M: Mmcs<F>, Witness> {
+    /// The commitment to the folded polynomial's evaluations over the next round's domain.
+    pub commitment: M::Commitment,
+    /// The folded polynomial's evaluation at the out-of-domain point.
+    pub ood_answer: F,
+    pub pow_witness: Witness,
+    /// The openings of the commitment this round's codeword is computed from, at its queries.
+    pub queries: QueryOpenings<F, M>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(bound = "")]
+pub struct QueryOpenings<F: Field, M: Mmcs<F>> {
+    /// For each query, the committed evaluations which fold into its point of the folded domain,
+    /// in order.
+    pub values: Vec<Vec<F>>,
+
+    /// One opening proof for the rows of every query.
+    pub opening_proof: M::MultiProof,
+}
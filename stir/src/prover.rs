@@ -0,0 +1,246 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter;
+
+use itertools::{izip, Itertools};
+use p3_challenger::{CanObserve, CanSampleBits, FieldChallenger, GrindingChallenger};
+use p3_commit::Mmcs;
+use p3_dft::{Radix2Dit, TwoAdicSubgroupDft};
+use p3_field::{ExtensionField, Field, TwoAdicField};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_maybe_rayon::prelude::*;
+use p3_util::{log2_strict_usize, reverse_slice_index_bits};
+use tracing::{info_span, instrument};
+
+use crate::utils::{
+    coset_point, coset_points, eval_poly, fiber_points, fold_row, DegreeCorrectedQuotient,
+};
+use crate::{QueryOpenings, RoundProof, StirConfig, StirProof};
+
+/// Prove that the input codewords, given in bit-reversed order and sorted by height in descending
+/// order, are close to codewords of polynomials of degree less than their height over the blowup.
+///
+/// Smaller inputs are lifted to the largest domain, i.e. each codeword of `f` is replaced by one of
+/// `f(x^(2^j))`, before all inputs are combined with powers of a random challenge. As in FRI, the
+/// folding formulas absorb the LDE's coset shift, so the first round's domain is a subgroup.
+#[instrument(name = "STIR prover", skip_all)]
+pub fn prove<Val, Challenge, M, Challenger, InputProof>(
+    config: &StirConfig<M>,
+    inputs: Vec<Vec<Challenge>>,
+    challenger: &mut Challenger,
+    open_input: impl Fn(&[usize]) -> InputProof,
+) -> StirProof<Challenge, M, Challenger::Witness, InputProof>
+where
+    Val: TwoAdicField,
+    Challenge: ExtensionField<Val> + TwoAdicField,
+    M: Mmcs<Challenge>,
+    Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<M::Commitment>,
+{
+    // check sorted descending
+    assert!(inputs
+        .iter()
+        .tuple_windows()
+        .all(|(l, r)| l.len() >= r.len()));
+
+    let mut log_height = log2_strict_usize(inputs[0].len());
+    let mut log_degree = log_height - config.log_blowup;
+    let log_folding_factors = config.log_folding_factors(log_degree);
+    let num_queries = config.num_queries(log_degree);
+    let dft = Radix2Dit::default();
+
+    let gamma: Challenge = challenger.sample_ext_element();
+    let mut codeword = info_span!("combine inputs").in_scope(|| {
+        let mut combined = vec![Challenge::ZERO; 1 << log_height];
+        for (input, gamma_pow) in izip!(inputs, gamma.powers()) {
+            // In bit-reversed order, lifting repeats each evaluation.
+            let bits_reduced = log_height - log2_strict_usize(input.len());
+            combined
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(i, c)| *c += gamma_pow * input[i >> bits_reduced]);
+        }
+        combined
+    });
+
+    let (initial_commitment, mut data) = config.mmcs.commit_matrix(RowMajorMatrix::new(
+        codeword.clone(),
+        1 << log_folding_factors[0],
+    ));
+    challenger.observe(initial_commitment.clone());
+
+    let mut shift = Challenge::ONE;
+    let mut round_proofs = vec![];
+    let mut input_proof = None;
+
+    for (round, (&log_folding_factor, &num_queries)) in
+        izip!(&log_folding_factors, &num_queries).enumerate()
+    {
+        let _guard = info_span!("round", round).entered();
+        let r_fold: Challenge = challenger.sample_ext_element();
+        let log_folded_height = log_height - log_folding_factor;
+        let folded_shift = shift.exp_power_of_2(log_folding_factor);
+        let folded = codeword
+            .par_chunks_exact(1 << log_folding_factor)
+            .enumerate()
+            .map(|(row, values)| {
+                let points = fiber_points(shift, log_height, log_folding_factor, row);
+                fold_row(&points, values, r_fold)
+            })
+            .collect::<Vec<_>>();
+
+        // Interpolate the folded polynomial from its evaluations over the folded domain.
+        let log_folded_degree = log_degree - log_folding_factor;
+        let mut folded_evals = folded.clone();
+        reverse_slice_index_bits(&mut folded_evals);
+        let mut folded_poly = dft.coset_idft(folded_evals, folded_shift);
+        assert!(
+            folded_poly[1 << log_folded_degree..]
+                .iter()
+                .all(|c| c.is_zero()),
+            "folded polynomial has too high a degree"
+        );
+        folded_poly.truncate(1 << log_folded_degree);
+
+        let Some(&next_log_folding_factor) = log_folding_factors.get(round + 1) else {
+            for &coeff in &folded_poly {
+                challenger.observe_ext_element(coeff);
+            }
+            let final_pow_witness = challenger.grind(config.proof_of_work_bits);
+            let (final_queries, _) = answer_queries(
+                config,
+                &data,
+                log_height,
+                log_folding_factor,
+                num_queries,
+                round == 0,
+                challenger,
+                |indices| input_proof = Some(open_input(indices)),
+            );
+            return StirProof {
+                initial_commitment,
+                round_proofs,
+                final_poly: folded_poly,
+                final_pow_witness,
+                final_queries,
+                input_proof: input_proof.expect("the first round opens the inputs"),
+            };
+        };
+
+        // Evaluate the folded polynomial over the next domain. It's half the size of this round's,
+        // so the rate drops, and a coset disjoint from the folded domain, so that the quotient
+        // below is defined over it.
+        let next_shift = folded_shift * Challenge::from_base(Val::GENERATOR);
+        let next_log_height = log_height - 1;
+        let mut next_poly = folded_poly.clone();
+        next_poly.resize(1 << next_log_height, Challenge::ZERO);
+        let mut next_evals = dft.coset_dft(next_poly, next_shift);
+        reverse_slice_index_bits(&mut next_evals);
+        let (commitment, next_data) = config.mmcs.commit_matrix(RowMajorMatrix::new(
+            next_evals,
+            1 << next_log_folding_factor,
+        ));
+        challenger.observe(commitment.clone());
+
+        let r_out: Challenge = challenger.sample_ext_element();
+        let ood_answer = eval_poly(&folded_poly, r_out);
+        challenger.observe_ext_element(ood_answer);
+        let r_comb: Challenge = challenger.sample_ext_element();
+
+        let pow_witness = challenger.grind(config.proof_of_work_bits);
+        let (queries, rows) = answer_queries(
+            config,
+            &data,
+            log_height,
+            log_folding_factor,
+            num_queries,
+            round == 0,
+            challenger,
+            |indices| input_proof = Some(open_input(indices)),
+        );
+
+        // The next round's codeword is the quotient of the folded polynomial by its evaluations at
+        // the out-of-domain point and at the queried points of the folded domain.
+        let (points, answers): (Vec<_>, Vec<_>) = iter::once((r_out, ood_answer))
+            .chain(rows.into_iter().sorted().dedup().map(|row| {
+                (
+                    coset_point(folded_shift, log_folded_height, row),
+                    folded[row],
+                )
+            }))
+            .unzip();
+        let quotient = DegreeCorrectedQuotient::new(points, answers, r_comb);
+        // We passed ownership of the evaluations to the MMCS, so get a reference to them.
+        let next_evals = config.mmcs.get_matrices(&next_data).pop().unwrap();
+        codeword = info_span!("compute quotient").in_scope(|| {
+            coset_points(next_shift, next_log_height)
+                .into_par_iter()
+                .zip(next_evals.values.par_iter())
+                .map(|(x, &g_x)| quotient.eval(x, g_x))
+                .collect()
+        });
+
+        round_proofs.push(RoundProof {
+            commitment,
+            ood_answer,
+            pow_witness,
+            queries,
+        });
+        data = next_data;
+        shift = next_shift;
+        log_height = next_log_height;
+        log_degree = log_folded_degree;
+    }
+
+    unreachable!("the last round returns the proof")
+}
+
+/// Sample a round's queries and open the committed codeword at all of them at once, returning the
+/// openings and the queried rows.
+///
+/// The first round's queries are points of its domain, whose inputs are opened with `open_input`,
+/// while later rounds only query rows.
+#[allow(clippy::too_many_arguments)]
+fn answer_queries<F, M, Challenger>(
+    config: &StirConfig<M>,
+    data: &M::ProverData<RowMajorMatrix<F>>,
+    log_height: usize,
+    log_folding_factor: usize,
+    num_queries: usize,
+    is_first_round: bool,
+    challenger: &mut Challenger,
+    mut open_input: impl FnMut(&[usize]),
+) -> (QueryOpenings<F, M>, Vec<usize>)
+where
+    F: Field,
+    M: Mmcs<F>,
+    Challenger: CanSampleBits<usize>,
+{
+    let rows = if is_first_round {
+        let indices = iter::repeat_with(|| challenger.sample_bits(log_height))
+            .take(num_queries)
+            .collect_vec();
+        open_input(&indices);
+        indices
+            .into_iter()
+            .map(|index| index >> log_folding_factor)
+            .collect_vec()
+    } else {
+        iter::repeat_with(|| challenger.sample_bits(log_height - log_folding_factor))
+            .take(num_queries)
+            .collect_vec()
+    };
+
+    let (opened_rows, opening_proof) = config.mmcs.open_multi_batch(&rows, data);
+    let values = opened_rows
+        .into_iter()
+        .map(|mut opened_rows| {
+            assert_eq!(opened_rows.len(), 1);
+            opened_rows.pop().unwrap()
+        })
+        .collect();
+    let openings = QueryOpenings {
+        values,
+        opening_proof,
+    };
+    (openings, rows)
+}
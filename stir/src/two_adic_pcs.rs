@@ -0,0 +1,288 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::mem::size_of;
+
+use itertools::{izip, Itertools};
+use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
+use p3_commit::{
+    HashCount, MatrixShape, Mmcs, MmcsCostModel, OpenedValues, Pcs, PcsCost, PcsCostModel,
+    PolynomialSpace, TwoAdicMultiplicativeCoset,
+};
+use p3_dft::TwoAdicSubgroupDft;
+use p3_field::{ExtensionField, TwoAdicField};
+use p3_fri::{open_input, reduce_openings, verify_input, LdePoints, MultiBatchOpening};
+use p3_matrix::bitrev::BitReversableMatrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::{Dimensions, Matrix};
+use p3_util::log2_strict_usize;
+
+use crate::verifier::{self, StirError};
+use crate::{prover, StirConfig, StirProof};
+
+/// A PCS which commits to LDEs like [`TwoAdicFriPcs`](p3_fri::TwoAdicFriPcs), and opens them by
+/// running STIR on their reduced openings rather than FRI.
+#[derive(Debug)]
+pub struct TwoAdicStirPcs<Val, Dft, InputMmcs, StirMmcs> {
+    dft: Dft,
+    mmcs: InputMmcs,
+    stir: StirConfig<StirMmcs>,
+    _phantom: PhantomData<Val>,
+}
+
+impl<Val, Dft, InputMmcs, StirMmcs> TwoAdicStirPcs<Val, Dft, InputMmcs, StirMmcs> {
+    pub const fn new(dft: Dft, mmcs: InputMmcs, stir: StirConfig<StirMmcs>) -> Self {
+        Self {
+            dft,
+            mmcs,
+            stir,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub const fn stir_config(&self) -> &StirConfig<StirMmcs> {
+        &self.stir
+    }
+}
+
+impl<Val, Dft, InputMmcs, StirMmcs, Challenge, Challenger> Pcs<Challenge, Challenger>
+    for TwoAdicStirPcs<Val, Dft, InputMmcs, StirMmcs>
+where
+    Val: TwoAdicField,
+    Dft: TwoAdicSubgroupDft<Val>,
+    InputMmcs: Mmcs<Val>,
+    StirMmcs: Mmcs<Challenge>,
+    Challenge: TwoAdicField + ExtensionField<Val>,
+    Challenger:
+        FieldChallenger<Val> + CanObserve<StirMmcs::Commitment> + GrindingChallenger<Witness = Val>,
+{
+    type Domain = TwoAdicMultiplicativeCoset<Val>;
+    type Commitment = InputMmcs::Commitment;
+    type ProverData = InputMmcs::ProverData<RowMajorMatrix<Val>>;
+    type Proof = StirProof<Challenge, StirMmcs, Val, Vec<MultiBatchOpening<Val, InputMmcs>>>;
+    type Error = StirError<StirMmcs::Error, InputMmcs::Error>;
+
+    fn natural_domain_for_degree(&self, degree: usize) -> Self::Domain {
+        let log_n = log2_strict_usize(degree);
+        TwoAdicMultiplicativeCoset {
+            log_n,
+            shift: Val::ONE,
+        }
+    }
+
+    fn parameters(&self) -> Vec<usize> {
+        let mut parameters = self.stir.parameters();
+        parameters.extend(self.mmcs.parameters());
+        parameters.extend(self.stir.mmcs.parameters());
+        parameters
+    }
+
+    fn log_blowup(&self) -> Option<usize> {
+        Some(self.stir.log_blowup)
+    }
+
+    fn commit(
+        &self,
+        evaluations: Vec<(Self::Domain, RowMajorMatrix<Val>)>,
+    ) -> (Self::Commitment, Self::ProverData) {
+        let ldes: Vec<_> = evaluations
+            .into_iter()
+            .map(|(domain, evals)| {
+                assert_eq!(domain.size(), evals.height());
+                let shift = Val::GENERATOR / domain.shift;
+                // Commit to the bit-reversed LDE.
+                self.dft
+                    .coset_lde_batch(evals, self.stir.log_blowup, shift)
+                    .bit_reverse_rows()
+                    .to_row_major_matrix()
+            })
+            .collect();
+
+        self.mmcs.commit(ldes)
+    }
+
+    fn get_evaluations_on_domain<'a>(
+        &self,
+        prover_data: &'a Self::ProverData,
+        idx: usize,
+        domain: Self::Domain,
+    ) -> impl Matrix<Val> + 'a {
+        assert_eq!(domain.shift, Val::GENERATOR);
+        let lde = self.mmcs.get_matrices(prover_data)[idx];
+        assert!(lde.height() >= domain.size());
+        lde.split_rows(domain.size()).0.bit_reverse_rows()
+    }
+
+    fn open(
+        &self,
+        // For each round,
+        rounds: Vec<(
+            &Self::ProverData,
+            // for each matrix,
+            Vec<
+                // points to open
+                Vec<Challenge>,
+            >,
+        )>,
+        challenger: &mut Challenger,
+    ) -> (OpenedValues<Challenge>, Self::Proof) {
+        // Batch combination challenge
+        let alpha: Challenge = challenger.sample_ext_element();
+
+        let (all_opened_values, stir_input) =
+            reduce_openings(&self.mmcs, self.stir.log_blowup, &rounds, alpha);
+
+        let stir_proof = prover::prove(&self.stir, stir_input, challenger, |indices| {
+            open_input(&self.mmcs, &rounds, indices)
+        });
+
+        (all_opened_values, stir_proof)
+    }
+
+    fn verify(
+        &self,
+        // For each round:
+        rounds: Vec<(
+            Self::Commitment,
+            // for each matrix:
+            Vec<(
+                // its domain,
+                Self::Domain,
+                // for each point:
+                Vec<(
+                    // the point,
+                    Challenge,
+                    // values at the point
+                    Vec<Challenge>,
+                )>,
+            )>,
+        )>,
+        proof: &Self::Proof,
+        challenger: &mut Challenger,
+    ) -> Result<(), Self::Error> {
+        // Batch combination challenge
+        let alpha: Challenge = challenger.sample_ext_element();
+
+        let log_heights = rounds
+            .iter()
+            .flat_map(|(_, mats)| {
+                mats.iter()
+                    .map(|(domain, _)| log2_strict_usize(domain.size()) + self.stir.log_blowup)
+            })
+            .sorted_by(|l, r| r.cmp(l))
+            .dedup()
+            .collect_vec();
+        let lde_points = LdePoints::new(log_heights[0]);
+
+        verifier::verify(
+            &self.stir,
+            &log_heights,
+            proof,
+            challenger,
+            |indices, input_proof| {
+                let reduced_openings = verify_input(
+                    &self.mmcs,
+                    self.stir.log_blowup,
+                    &rounds,
+                    alpha,
+                    &lde_points,
+                    indices,
+                    input_proof,
+                )?;
+                // Return reduced openings descending by log_height.
+                Ok(reduced_openings
+                    .into_iter()
+                    .map(|reduced_openings| reduced_openings.into_iter().rev().collect())
+                    .collect())
+            },
+        )
+    }
+}
+
+impl<Val, Dft, InputMmcs, StirMmcs> PcsCostModel for TwoAdicStirPcs<Val, Dft, InputMmcs, StirMmcs>
+where
+    InputMmcs: MmcsCostModel,
+    StirMmcs: MmcsCostModel,
+{
+    fn estimate_cost(&self, rounds: &[Vec<MatrixShape>]) -> PcsCost {
+        let log_blowup = self.stir.log_blowup;
+        let stir_mmcs = &self.stir.mmcs;
+
+        let round_dims = rounds
+            .iter()
+            .map(|round| {
+                round
+                    .iter()
+                    .map(|shape| Dimensions {
+                        width: shape.width,
+                        height: 1 << (shape.log_degree + log_blowup),
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+        let log_degree = rounds
+            .iter()
+            .flatten()
+            .map(|shape| shape.log_degree)
+            .max()
+            .unwrap_or(0);
+        let log_folding_factors = self.stir.log_folding_factors(log_degree);
+        let num_queries = self.stir.num_queries(log_degree);
+
+        // The first round's queries open a row of every committed matrix, with one multi-opening
+        // per round.
+        let input_proof_bytes = round_dims
+            .iter()
+            .map(|dims| {
+                let width: usize = dims.iter().map(|dims| dims.width).sum();
+                num_queries[0] * width * self.mmcs.value_bytes()
+                    + self.mmcs.multi_opening_proof_bytes(dims, num_queries[0])
+            })
+            .sum::<usize>();
+        let input_verify_hashes = round_dims
+            .iter()
+            .map(|dims| self.mmcs.multi_verify_cost(dims, num_queries[0]))
+            .sum::<HashCount>();
+
+        // Each round opens the codeword committed by the one before, in rows which fold together.
+        // The domain halves from round to round.
+        let log_max_height = log_degree + log_blowup;
+        let committed_dims = izip!(0.., &log_folding_factors)
+            .map(|(round, &log_folding_factor)| {
+                [Dimensions {
+                    width: 1 << log_folding_factor,
+                    height: 1 << (log_max_height - round - log_folding_factor),
+                }]
+            })
+            .collect_vec();
+        let num_rounds = log_folding_factors.len() - 1;
+        let final_poly_len = 1 << (log_degree - log_folding_factors.iter().sum::<usize>());
+        let commit_phase_bytes = (num_rounds + 1) * stir_mmcs.commitment_bytes()
+            + izip!(&committed_dims, &num_queries)
+                .map(|(dims, &num_queries)| {
+                    num_queries * dims[0].width * stir_mmcs.value_bytes()
+                        + stir_mmcs.multi_opening_proof_bytes(dims, num_queries)
+                })
+                .sum::<usize>()
+            // The out-of-domain answers and the final polynomial, and the proof-of-work witnesses.
+            + (num_rounds + final_poly_len) * stir_mmcs.value_bytes()
+            + (num_rounds + 1) * size_of::<Val>();
+        let commit_phase_verify_hashes = izip!(&committed_dims, &num_queries)
+            .map(|(dims, &num_queries)| stir_mmcs.multi_verify_cost(dims, num_queries))
+            .sum::<HashCount>();
+
+        PcsCost {
+            commitment_bytes: self.mmcs.commitment_bytes(),
+            commit_hashes: round_dims
+                .iter()
+                .map(|dims| self.mmcs.commit_cost(dims))
+                .collect(),
+            input_proof_bytes,
+            commit_phase_bytes,
+            open_hashes: committed_dims
+                .iter()
+                .map(|dims| stir_mmcs.commit_cost(dims))
+                .sum(),
+            verify_hashes: input_verify_hashes + commit_phase_verify_hashes,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use alloc::vec::Vec;
+
+use itertools::{izip, Itertools};
+use p3_field::{batch_multiplicative_inverse, Field, TwoAdicField};
+use p3_util::{reverse_bits_len, reverse_slice_index_bits};
+
+/// The points of the coset `shift <g>` of size `2^log_size`, in bit-reversed order.
+pub(crate) fn coset_points<F: TwoAdicField>(shift: F, log_size: usize) -> Vec<F> {
+    let mut points = F::two_adic_generator(log_size)
+        .shifted_powers(shift)
+        .take(1 << log_size)
+        .collect_vec();
+    reverse_slice_index_bits(&mut points);
+    points
+}
+
+/// The point at `index` of the coset `shift <g>` of size `2^log_size`, in bit-reversed order.
+pub(crate) fn coset_point<F: TwoAdicField>(shift: F, log_size: usize, index: usize) -> F {
+    shift * F::two_adic_generator(log_size).exp_u64(reverse_bits_len(index, log_size) as u64)
+}
+
+/// The points of row `row` of a codeword over the coset `shift <g>` of size `2^log_height`,
+/// committed in rows of `2^log_folding_factor` evaluations.
+///
+/// In bit-reversed order, these are the `2^log_folding_factor`th roots of the point at `row` of
+/// the folded domain, so they fold together.
+pub(crate) fn fiber_points<F: TwoAdicField>(
+    shift: F,
+    log_height: usize,
+    log_folding_factor: usize,
+    row: usize,
+) -> Vec<F> {
+    let first = shift
+        * F::two_adic_generator(log_height)
+            .exp_u64(reverse_bits_len(row, log_height - log_folding_factor) as u64);
+    coset_points(first, log_folding_factor)
+}
+
+/// Fold the evaluations of a polynomial `f(x) = sum_i x^i f_i(x^k)` over the `k`th roots of `y`
+/// into the evaluation of `sum_i r^i f_i` at `y`, which is that of their interpolant at `r`.
+pub(crate) fn fold_row<F: Field>(points: &[F], values: &[F], r: F) -> F {
+    if let Some(i) = points.iter().position(|&x| x == r) {
+        return values[i];
+    }
+    let k = points.len();
+    let y = points[0].exp_u64(k as u64);
+    let inv_diffs = batch_multiplicative_inverse(&points.iter().map(|&x| r - x).collect_vec());
+    // The barycentric formula, with weights `x / (k y)` for the roots `x` of `X^k - y`.
+    let sum: F = izip!(points, values, inv_diffs)
+        .map(|(&x, &v, inv_diff)| x * v * inv_diff)
+        .sum();
+    (r.exp_u64(k as u64) - y) * (y * F::from_canonical_usize(k)).inverse() * sum
+}
+
+/// Evaluate the polynomial with the given coefficients, lowest degree first, at `x`.
+pub(crate) fn eval_poly<F: Field>(coeffs: &[F], x: F) -> F {
+    coeffs
+        .iter()
+        .rev()
+        .fold(F::ZERO, |acc, &coeff| acc * x + coeff)
+}
+
+/// The codeword a round hands on to the next, computed from the folded polynomial `g`.
+///
+/// Having checked that `g` takes the values `a_j` at the points `z_j`, the next round tests the
+/// quotient `(g - A) / V`, where `A` interpolates the `a_j` and `V` vanishes on the `z_j`. This has
+/// degree `n` less than `g`, for `n` points, so it's multiplied by `1 + rx + ... + (rx)^n` for a
+/// random `r` to correct its degree.
+pub(crate) struct DegreeCorrectedQuotient<F> {
+    points: Vec<F>,
+    answers: Vec<F>,
+    /// The barycentric weights `1 / prod_{j != i} (z_i - z_j)` of the points.
+    weights: Vec<F>,
+    r: F,
+}
+
+impl<F: Field> DegreeCorrectedQuotient<F> {
+    /// The quotient for `g` taking the `answers` at the distinct `points`, with degree correction
+    /// challenge `r`.
+    pub(crate) fn new(points: Vec<F>, answers: Vec<F>, r: F) -> Self {
+        let weights = batch_multiplicative_inverse(
+            &points
+                .iter()
+                .enumerate()
+                .map(|(i, &z_i)| {
+                    points
+                        .iter()
+                        .enumerate()
+                        .filter(|&(j, _)| j != i)
+                        .map(|(_, &z_j)| z_i - z_j)
+                        .product()
+                })
+                .collect_vec(),
+        );
+        Self {
+            points,
+            answers,
+            weights,
+            r,
+        }
+    }
+
+    /// Evaluate the quotient at `x`, which isn't one of the points, given `g(x)`.
+    pub(crate) fn eval(&self, x: F, g_x: F) -> F {
+        let inv_diffs =
+            batch_multiplicative_inverse(&self.points.iter().map(|&z| x - z).collect_vec());
+        let inv_vanishing: F = inv_diffs.iter().copied().product();
+        // `A(x) / V(x)`, by the barycentric formula.
+        let interpolant_over_vanishing: F = izip!(&self.answers, &self.weights, inv_diffs)
+            .map(|(&a, &w, inv_diff)| a * w * inv_diff)
+            .sum();
+        (g_x * inv_vanishing - interpolant_over_vanishing) * self.degree_correction(x)
+    }
+
+    /// `1 + rx + ... + (rx)^n`, for `n` points.
+    fn degree_correction(&self, x: F) -> F {
+        let rx = self.r * x;
+        let num_terms = self.points.len() + 1;
+        if rx == F::ONE {
+            F::from_canonical_usize(num_terms)
+        } else {
+            (F::ONE - rx.exp_u64(num_terms as u64)) / (F::ONE - rx)
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter;
+
+use itertools::{izip, Itertools};
+use p3_challenger::{CanObserve, FieldChallenger, GrindingChallenger};
+use p3_commit::Mmcs;
+use p3_field::{ExtensionField, TwoAdicField};
+use p3_matrix::Dimensions;
+
+use crate::utils::{coset_point, eval_poly, fiber_points, fold_row, DegreeCorrectedQuotient};
+use crate::{StirConfig, StirProof};
+
+#[derive(Debug)]
+pub enum StirError<CommitMmcsErr, InputError> {
+    InvalidProofShape,
+    CommitMmcsError(CommitMmcsErr),
+    InputError(InputError),
+    /// The initial codeword doesn't match the combination of the inputs at a query.
+    InputMismatch,
+    FinalPolyMismatch,
+    InvalidPowWitness,
+}
+
+/// Verify a STIR proof for input codewords of the given log2 heights, sorted in descending order.
+///
+/// `open_input` checks the inputs' openings at the first round's queries of the largest domain,
+/// and returns, for each query, their evaluations there by log2 height, in the same order.
+pub fn verify<Val, Challenge, M, Challenger, InputProof, InputError>(
+    config: &StirConfig<M>,
+    log_heights: &[usize],
+    proof: &StirProof<Challenge, M, Challenger::Witness, InputProof>,
+    challenger: &mut Challenger,
+    open_input: impl Fn(&[usize], &InputProof) -> Result<Vec<Vec<(usize, Challenge)>>, InputError>,
+) -> Result<(), StirError<M::Error, InputError>>
+where
+    Val: TwoAdicField,
+    Challenge: ExtensionField<Val> + TwoAdicField,
+    M: Mmcs<Challenge>,
+    Challenger: FieldChallenger<Val> + GrindingChallenger + CanObserve<M::Commitment>,
+{
+    let mut log_height = log_heights[0];
+    let log_degree = log_height - config.log_blowup;
+    let log_folding_factors = config.log_folding_factors(log_degree);
+    let num_queries = config.num_queries(log_degree);
+    let log_final_degree = log_degree - log_folding_factors.iter().sum::<usize>();
+    if proof.round_proofs.len() != log_folding_factors.len() - 1
+        || proof.final_poly.len() != 1 << log_final_degree
+    {
+        return Err(StirError::InvalidProofShape);
+    }
+
+    let gamma: Challenge = challenger.sample_ext_element();
+    challenger.observe(proof.initial_commitment.clone());
+
+    let mut commitment = &proof.initial_commitment;
+    let mut shift = Challenge::ONE;
+    // The first round's codeword is committed directly, while later rounds' are computed from the
+    // previous round's folded polynomial.
+    let mut quotient: Option<DegreeCorrectedQuotient<Challenge>> = None;
+
+    for (round, (&log_folding_factor, &num_queries)) in
+        izip!(&log_folding_factors, &num_queries).enumerate()
+    {
+        let r_fold: Challenge = challenger.sample_ext_element();
+        let log_folded_height = log_height - log_folding_factor;
+        let folded_shift = shift.exp_power_of_2(log_folding_factor);
+
+        let round_proof = proof.round_proofs.get(round);
+        let (pow_witness, openings) = match round_proof {
+            Some(round_proof) => (round_proof.pow_witness, &round_proof.queries),
+            None => (proof.final_pow_witness, &proof.final_queries),
+        };
+        let next_round_challenges = match round_proof {
+            Some(round_proof) => {
+                challenger.observe(round_proof.commitment.clone());
+                let r_out: Challenge = challenger.sample_ext_element();
+                challenger.observe_ext_element(round_proof.ood_answer);
+                let r_comb: Challenge = challenger.sample_ext_element();
+                Some((round_proof, r_out, r_comb))
+            }
+            None => {
+                for &coeff in &proof.final_poly {
+                    challenger.observe_ext_element(coeff);
+                }
+                None
+            }
+        };
+
+        if openings.values.len() != num_queries
+            || openings
+                .values
+                .iter()
+                .any(|values| values.len() != 1 << log_folding_factor)
+        {
+            return Err(StirError::InvalidProofShape);
+        }
+
+        // Check PoW.
+        if !challenger.check_witness(config.proof_of_work_bits, pow_witness) {
+            return Err(StirError::InvalidPowWitness);
+        }
+
+        let dims = &[Dimensions {
+            width: 1 << log_folding_factor,
+            height: 1 << log_folded_height,
+        }];
+        let rows = if round == 0 {
+            let indices = iter::repeat_with(|| challenger.sample_bits(log_height))
+                .take(num_queries)
+                .collect_vec();
+            let reduced_openings =
+                open_input(&indices, &proof.input_proof).map_err(StirError::InputError)?;
+            if reduced_openings.len() != num_queries {
+                return Err(StirError::InvalidProofShape);
+            }
+            for (&index, ro, values) in izip!(&indices, reduced_openings, &openings.values) {
+                debug_assert!(
+                    izip!(&ro, log_heights).all(|((l, _), r)| l == r),
+                    "reduced openings sorted by height descending"
+                );
+                let combined: Challenge = izip!(ro, gamma.powers())
+                    .map(|((_, ro), gamma_pow)| gamma_pow * ro)
+                    .sum();
+                if values[index % (1 << log_folding_factor)] != combined {
+                    return Err(StirError::InputMismatch);
+                }
+            }
+            indices
+                .into_iter()
+                .map(|index| index >> log_folding_factor)
+                .collect_vec()
+        } else {
+            iter::repeat_with(|| challenger.sample_bits(log_folded_height))
+                .take(num_queries)
+                .collect_vec()
+        };
+
+        let opened_rows = openings
+            .values
+            .iter()
+            .map(|values| vec![values.clone()])
+            .collect_vec();
+        config
+            .mmcs
+            .verify_multi_batch(
+                commitment,
+                dims,
+                &rows,
+                &opened_rows,
+                &openings.opening_proof,
+            )
+            .map_err(StirError::CommitMmcsError)?;
+
+        let folded_evals = izip!(rows, &openings.values)
+            .map(|(row, values)| {
+                let points = fiber_points(shift, log_height, log_folding_factor, row);
+                let evals = match &quotient {
+                    Some(quotient) => izip!(&points, values)
+                        .map(|(&x, &g_x)| quotient.eval(x, g_x))
+                        .collect(),
+                    None => values.clone(),
+                };
+                (row, fold_row(&points, &evals, r_fold))
+            })
+            .collect_vec();
+
+        let Some((round_proof, r_out, r_comb)) = next_round_challenges else {
+            for (row, folded_eval) in folded_evals {
+                let y = coset_point(folded_shift, log_folded_height, row);
+                if eval_poly(&proof.final_poly, y) != folded_eval {
+                    return Err(StirError::FinalPolyMismatch);
+                }
+            }
+            return Ok(());
+        };
+
+        let (points, answers): (Vec<_>, Vec<_>) = iter::once((r_out, round_proof.ood_answer))
+            .chain(
+                folded_evals
+                    .into_iter()
+                    .sorted_by_key(|&(row, _)| row)
+                    .dedup_by(|(l, _), (r, _)| l == r)
+                    .map(|(row, folded_eval)| {
+                        (
+                            coset_point(folded_shift, log_folded_height, row),
+                            folded_eval,
+                        )
+                    }),
+            )
+            .unzip();
+        quotient = Some(DegreeCorrectedQuotient::new(points, answers, r_comb));
+        commitment = &round_proof.commitment;
+        shift = folded_shift * Challenge::from_base(Val::GENERATOR);
+        log_height -= 1;
+    }
+
+    unreachable!("the last round returns")
+}
@@ -0,0 +1,242 @@
+use itertools::{izip, Itertools};
+use p3_challenger::{CanObserve, FieldChallenger};
+use p3_commit::{MatrixShape, Pcs, PcsCostModel, PolynomialSpace};
+use p3_dft::Radix2DitParallel;
+use p3_field::{ExtensionField, Field};
+use p3_fri::TwoAdicFriPcs;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_stir::{StirConfig, TwoAdicStirPcs};
+use p3_uni_stark::testing::{
+    make_fri_config, make_mmcs, Challenge, ChallengeMmcs, Challenger, Perm, Val, ValMmcs,
+};
+use rand::distributions::{Distribution, Standard};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use serde::Serialize;
+
+fn seeded_rng() -> impl Rng {
+    ChaCha20Rng::seed_from_u64(0)
+}
+
+/// Commit to random polynomials of the given log2 degrees, open them at a random point, and check
+/// the opening proof, returning it.
+fn do_test_pcs<Val, Challenge, Challenger, P>(
+    (pcs, challenger): &(P, Challenger),
+    log_degrees_by_round: &[&[usize]],
+) -> P::Proof
+where
+    P: Pcs<Challenge, Challenger>,
+    P::Domain: PolynomialSpace<Val = Val>,
+    Val: Field,
+    Standard: Distribution<Val>,
+    Challenge: ExtensionField<Val>,
+    Challenger: Clone + CanObserve<P::Commitment> + FieldChallenger<Val>,
+{
+    let num_rounds = log_degrees_by_round.len();
+    let mut rng = seeded_rng();
+
+    let mut p_challenger = challenger.clone();
+
+    let domains_and_polys_by_round = log_degrees_by_round
+        .iter()
+        .map(|log_degrees| {
+            log_degrees
+                .iter()
+                .map(|&log_degree| {
+                    let d = 1 << log_degree;
+                    // random width 5-15
+                    let width = 5 + rng.gen_range(0..=10);
+                    (
+                        pcs.natural_domain_for_degree(d),
+                        RowMajorMatrix::<Val>::rand(&mut rng, d, width),
+                    )
+                })
+                .collect_vec()
+        })
+        .collect_vec();
+
+    let (commits_by_round, data_by_round): (Vec<_>, Vec<_>) = domains_and_polys_by_round
+        .iter()
+        .map(|domains_and_polys| pcs.commit(domains_and_polys.clone()))
+        .unzip();
+    p_challenger.observe_slice(&commits_by_round);
+
+    let zeta: Challenge = p_challenger.sample_ext_element();
+
+    let points_by_round = log_degrees_by_round
+        .iter()
+        .map(|log_degrees| vec![vec![zeta]; log_degrees.len()])
+        .collect_vec();
+    let data_and_points = data_by_round.iter().zip(points_by_round).collect();
+    let (opening_by_round, proof) = pcs.open(data_and_points, &mut p_challenger);
+    assert_eq!(opening_by_round.len(), num_rounds);
+
+    // Verify the proof.
+    let mut v_challenger = challenger.clone();
+    v_challenger.observe_slice(&commits_by_round);
+    let verifier_zeta: Challenge = v_challenger.sample_ext_element();
+    assert_eq!(verifier_zeta, zeta);
+
+    let commits_and_claims_by_round = izip!(
+        commits_by_round,
+        domains_and_polys_by_round,
+        opening_by_round
+    )
+    .map(|(commit, domains_and_polys, openings)| {
+        let claims = domains_and_polys
+            .iter()
+            .zip(openings)
+            .map(|((domain, _), mat_openings)| (*domain, vec![(zeta, mat_openings[0].clone())]))
+            .collect_vec();
+        (commit, claims)
+    })
+    .collect_vec();
+
+    pcs.verify(commits_and_claims_by_round, &proof, &mut v_challenger)
+        .unwrap();
+    proof
+}
+
+// Set it up so we create tests inside a module for each pcs, so we get nice error reports
+// specific to a failing PCS.
+macro_rules! make_tests_for_pcs {
+    ($p:expr) => {
+        #[test]
+        fn single() {
+            let p = $p;
+            for i in 3..6 {
+                $crate::do_test_pcs(&p, &[&[i]]);
+            }
+        }
+
+        #[test]
+        fn many_equal() {
+            let p = $p;
+            for i in 5..8 {
+                $crate::do_test_pcs(&p, &[&[i; 5]]);
+            }
+        }
+
+        #[test]
+        fn many_different() {
+            let p = $p;
+            for i in 3..8 {
+                let degrees = (3..3 + i).collect::<Vec<_>>();
+                $crate::do_test_pcs(&p, &[&degrees]);
+            }
+        }
+
+        #[test]
+        fn multiple_rounds() {
+            let p = $p;
+            $crate::do_test_pcs(&p, &[&[3], &[3]]);
+            $crate::do_test_pcs(&p, &[&[3], &[2]]);
+            $crate::do_test_pcs(&p, &[&[2], &[3]]);
+            $crate::do_test_pcs(&p, &[&[4, 2], &[4, 2]]);
+            $crate::do_test_pcs(&p, &[&[2], &[3, 3]]);
+        }
+    };
+}
+
+type Dft = Radix2DitParallel<Val>;
+type MyStirPcs = TwoAdicStirPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+type MyFriPcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
+
+fn get_mmcs() -> (Perm, ValMmcs) {
+    let perm = Perm::new_from_rng_128(&mut seeded_rng());
+    let val_mmcs = make_mmcs(&perm);
+    (perm, val_mmcs)
+}
+
+fn get_stir_pcs(
+    log_blowup: usize,
+    log_folding_factor: usize,
+    log_final_poly_len: usize,
+    security_bits: usize,
+) -> (MyStirPcs, Challenger) {
+    let (perm, val_mmcs) = get_mmcs();
+    let stir_config = StirConfig {
+        log_blowup,
+        log_folding_factor,
+        log_final_poly_len,
+        security_bits,
+        proof_of_work_bits: 8,
+        mmcs: ChallengeMmcs::new(val_mmcs.clone()),
+    };
+    let pcs = MyStirPcs::new(Dft::default(), val_mmcs, stir_config);
+    (pcs, Challenger::new(perm))
+}
+
+fn get_fri_pcs(log_blowup: usize, num_queries: usize) -> (MyFriPcs, Challenger) {
+    let perm = Perm::new_from_rng_128(&mut seeded_rng());
+    let fri_config = make_fri_config(&perm, log_blowup, num_queries, 8);
+    let pcs = MyFriPcs::new(Dft::default(), make_mmcs(&perm), fri_config);
+    (pcs, Challenger::new(perm))
+}
+
+mod folding_factor_4 {
+    make_tests_for_pcs!(super::get_stir_pcs(1, 2, 0, 40));
+}
+mod folding_factor_16_final_poly {
+    make_tests_for_pcs!(super::get_stir_pcs(2, 4, 2, 40));
+}
+
+fn proof_bytes(proof: &impl Serialize) -> usize {
+    postcard::to_allocvec(proof)
+        .expect("unable to serialize proof")
+        .len()
+}
+
+#[test]
+fn test_proof_size_against_fri() {
+    // Both reach 100 bits of conjectured soundness with 8 bits of proof-of-work, FRI with 92
+    // queries, and STIR with 92 queries in its first round and fewer in later ones.
+    let fri = get_fri_pcs(1, 92);
+    assert_eq!(fri.0.fri_config().conjectured_soundness_bits(), 100);
+    let stir = get_stir_pcs(1, 4, 0, 100);
+    assert_eq!(stir.0.stir_config().num_queries(12), vec![92, 23, 14]);
+
+    let fri_proof = do_test_pcs(&fri, &[&[12]]);
+    let stir_proof = do_test_pcs(&stir, &[&[12]]);
+    let (fri_bytes, stir_bytes) = (proof_bytes(&fri_proof), proof_bytes(&stir_proof));
+    // At this size, many of each round's queries share the upper parts of their Merkle paths,
+    // which narrows the gap, as FRI makes all of its queries in every round.
+    assert!(
+        stir_bytes < fri_bytes,
+        "STIR proof of {stir_bytes} bytes, FRI proof of {fri_bytes} bytes"
+    );
+
+    // Postcard encodes field elements as varints of up to 5 bytes, rather than the 4 bytes the
+    // estimate assumes, and adds length prefixes.
+    let shape = [vec![MatrixShape {
+        log_degree: 12,
+        width: 10,
+        num_points: 1,
+    }]];
+    let stir_cost = stir.0.estimate_cost(&shape);
+    let estimate = stir_cost.input_proof_bytes + stir_cost.commit_phase_bytes;
+    assert!(estimate <= stir_bytes);
+    assert!(stir_bytes <= estimate * 5 / 4 + 1024);
+}
+
+#[test]
+fn test_estimated_cost_against_fri() {
+    let fri = get_fri_pcs(1, 92).0;
+    let stir = get_stir_pcs(1, 4, 0, 100).0;
+    let shape = [vec![MatrixShape {
+        log_degree: 20,
+        width: 100,
+        num_points: 2,
+    }]];
+    let fri_cost = fri.estimate_cost(&shape);
+    let stir_cost = stir.estimate_cost(&shape);
+
+    // Committing to the inputs costs the same, as do the first round's queries of them.
+    assert_eq!(stir_cost.commit_hashes, fri_cost.commit_hashes);
+    assert_eq!(stir_cost.input_proof_bytes, fri_cost.input_proof_bytes);
+
+    // But STIR's later rounds need fewer queries, and it commits to fewer, smaller codewords.
+    assert!(stir_cost.commit_phase_bytes * 3 < fri_cost.commit_phase_bytes);
+    assert!(stir_cost.verify_hashes.compressions < fri_cost.verify_hashes.compressions);
+    assert!(stir_cost.open_hashes.hashes < fri_cost.open_hashes.hashes);
+}
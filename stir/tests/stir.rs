@@ -0,0 +1,223 @@
+use core::cmp::Reverse;
+
+use itertools::Itertools;
+use p3_challenger::{CanSampleBits, FieldChallenger};
+use p3_dft::{Radix2Dit, TwoAdicSubgroupDft};
+use p3_field::{Field, FieldAlgebra};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::util::reverse_matrix_index_bits;
+use p3_matrix::Matrix;
+use p3_stir::verifier::StirError;
+use p3_stir::{prover, verifier, StirConfig, StirProof};
+use p3_uni_stark::testing::{make_mmcs, Challenge, ChallengeMmcs, Challenger, Perm, Val};
+use p3_util::log2_strict_usize;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+type MyStirConfig = StirConfig<ChallengeMmcs>;
+type InputProof = Vec<Vec<(usize, Challenge)>>;
+type MyStirProof = StirProof<Challenge, ChallengeMmcs, Val, InputProof>;
+
+fn get_ldt_for_testing<R: Rng>(
+    rng: &mut R,
+    log_folding_factor: usize,
+    log_final_poly_len: usize,
+) -> (Perm, MyStirConfig) {
+    let perm = Perm::new_from_rng_128(rng);
+    let mmcs = ChallengeMmcs::new(make_mmcs(&perm));
+    let stir_config = StirConfig {
+        log_blowup: 1,
+        log_folding_factor,
+        log_final_poly_len,
+        security_bits: 24,
+        proof_of_work_bits: 8,
+        mmcs,
+    };
+    (perm, stir_config)
+}
+
+/// Prove that random polynomials of the given log2 degrees have low degree, returning the proof,
+/// the input codewords' log2 heights, and a sample of the prover's transcript afterwards.
+fn prove_ldt<R: Rng>(
+    rng: &mut R,
+    perm: &Perm,
+    config: &MyStirConfig,
+    log_degrees: &[usize],
+) -> (MyStirProof, Vec<usize>, usize) {
+    let dft = Radix2Dit::default();
+    let shift = Val::GENERATOR;
+
+    let ldes: Vec<RowMajorMatrix<Val>> = log_degrees
+        .iter()
+        .map(|&deg_bits| {
+            let evals = RowMajorMatrix::<Val>::rand_nonzero(rng, 1 << deg_bits, 16);
+            let mut lde = dft.coset_lde_batch(evals, config.log_blowup, shift);
+            reverse_matrix_index_bits(&mut lde);
+            lde
+        })
+        .collect();
+
+    let mut chal = Challenger::new(perm.clone());
+    let alpha: Challenge = chal.sample_ext_element();
+
+    let input: Vec<Vec<Challenge>> = ldes
+        .iter()
+        .map(|m| log2_strict_usize(m.height()))
+        .sorted_by_key(|&log_height| Reverse(log_height))
+        .dedup()
+        .map(|log_height| {
+            let matrices_with_log_height = ldes
+                .iter()
+                .filter(|m| log2_strict_usize(m.height()) == log_height)
+                .collect_vec();
+            (0..(1 << log_height))
+                .map(|r| {
+                    alpha
+                        .powers()
+                        .zip(matrices_with_log_height.iter().flat_map(|m| m.row(r)))
+                        .map(|(alpha_pow, v)| alpha_pow * v)
+                        .sum()
+                })
+                .collect()
+        })
+        .collect();
+    let log_heights = input
+        .iter()
+        .map(|v| log2_strict_usize(v.len()))
+        .collect_vec();
+    let log_max_height = log_heights[0];
+
+    let proof = prover::prove(config, input.clone(), &mut chal, |indices| {
+        // As our "input opening proof", just pass through the literal reduced openings.
+        indices
+            .iter()
+            .map(|idx| {
+                input
+                    .iter()
+                    .map(|v| {
+                        let log_height = log2_strict_usize(v.len());
+                        (log_height, v[idx >> (log_max_height - log_height)])
+                    })
+                    .collect()
+            })
+            .collect()
+    });
+
+    (proof, log_heights, chal.sample_bits(8))
+}
+
+fn verify_ldt(
+    perm: &Perm,
+    config: &MyStirConfig,
+    log_heights: &[usize],
+    proof: &MyStirProof,
+) -> Result<usize, StirError<<ChallengeMmcs as p3_commit::Mmcs<Challenge>>::Error, ()>> {
+    let mut v_challenger = Challenger::new(perm.clone());
+    let _alpha: Challenge = v_challenger.sample_ext_element();
+    verifier::verify(
+        config,
+        log_heights,
+        proof,
+        &mut v_challenger,
+        |_indices, proof: &InputProof| Ok(proof.clone()),
+    )?;
+    Ok(v_challenger.sample_bits(8))
+}
+
+fn do_test_stir_ldt<R: Rng>(
+    rng: &mut R,
+    log_folding_factor: usize,
+    log_final_poly_len: usize,
+    log_degrees: &[usize],
+) {
+    let (perm, config) = get_ldt_for_testing(rng, log_folding_factor, log_final_poly_len);
+    let (proof, log_heights, p_sample) = prove_ldt(rng, &perm, &config, log_degrees);
+
+    let log_degree = log_heights[0] - config.log_blowup;
+    let log_folding_factors = config.log_folding_factors(log_degree);
+    assert_eq!(proof.round_proofs.len(), log_folding_factors.len() - 1);
+    assert_eq!(
+        proof.final_poly.len(),
+        1 << (log_degree - log_folding_factors.iter().sum::<usize>())
+    );
+
+    let v_sample = verify_ldt(&perm, &config, &log_heights, &proof).unwrap();
+    assert_eq!(
+        p_sample, v_sample,
+        "prover and verifier transcript have same state after STIR"
+    );
+}
+
+#[test]
+fn test_stir_ldt() {
+    for i in 0..4 {
+        let mut rng = ChaCha20Rng::seed_from_u64(i);
+        do_test_stir_ldt(&mut rng, 2, 0, &[3, 4, 5, 6, 7, 8, 9]);
+    }
+}
+
+#[test]
+fn test_stir_ldt_higher_folding_factor() {
+    for i in 0..4 {
+        let mut rng = ChaCha20Rng::seed_from_u64(i);
+        // The last round folds by 4 rather than 16, down to a constant.
+        do_test_stir_ldt(&mut rng, 4, 0, &[3, 5, 7, 10]);
+    }
+}
+
+#[test]
+fn test_stir_ldt_final_poly() {
+    for i in 0..4 {
+        let mut rng = ChaCha20Rng::seed_from_u64(i);
+        do_test_stir_ldt(&mut rng, 3, 2, &[5, 6, 11]);
+        // The polynomials are already small enough, so the only round doesn't fold.
+        do_test_stir_ldt(&mut rng, 2, 4, &[3, 4]);
+    }
+}
+
+#[test]
+fn test_stir_rejects_tampered_proofs() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let (perm, config) = get_ldt_for_testing(&mut rng, 2, 0);
+    let (proof, log_heights, _) = prove_ldt(&mut rng, &perm, &config, &[6, 8]);
+    assert!(verify_ldt(&perm, &config, &log_heights, &proof).is_ok());
+
+    let mut bad_input = proof.clone();
+    bad_input.input_proof[0][0].1 += Challenge::ONE;
+    assert!(matches!(
+        verify_ldt(&perm, &config, &log_heights, &bad_input),
+        Err(StirError::InputMismatch)
+    ));
+
+    let mut bad_ood_answer = proof.clone();
+    bad_ood_answer.round_proofs[0].ood_answer += Challenge::ONE;
+    assert!(verify_ldt(&perm, &config, &log_heights, &bad_ood_answer).is_err());
+
+    let mut bad_final_poly = proof.clone();
+    bad_final_poly.final_poly[0] += Challenge::ONE;
+    assert!(verify_ldt(&perm, &config, &log_heights, &bad_final_poly).is_err());
+
+    // Claiming a higher degree changes the number of rounds.
+    assert!(matches!(
+        verify_ldt(&perm, &config, &[10, 7], &proof),
+        Err(StirError::InvalidProofShape)
+    ));
+}
+
+#[test]
+fn test_round_parameters() {
+    let (_, mut config) = get_ldt_for_testing(&mut ChaCha20Rng::seed_from_u64(0), 4, 0);
+    config.security_bits = 100;
+    config.proof_of_work_bits = 16;
+
+    // Each round folds the degree by 16 while halving the domain, so the rate drops by 8.
+    assert_eq!(config.log_folding_factors(20), vec![4, 4, 4, 4, 4]);
+    assert_eq!(config.log_inv_rates(20), vec![1, 4, 7, 10, 13]);
+    assert_eq!(config.num_queries(20), vec![84, 21, 12, 9, 7]);
+    assert_eq!(config.log_folding_factors(10), vec![4, 4, 2]);
+
+    config.log_final_poly_len = 3;
+    assert_eq!(config.log_folding_factors(10), vec![4, 3]);
+    assert_eq!(config.log_inv_rates(10), vec![1, 4]);
+    assert_eq!(config.log_folding_factors(2), vec![0]);
+}
@@ -0,0 +1,20 @@
+//! A generic implementation of the sumcheck protocol, for sums over the boolean hypercube of sums
+//! of products of multilinear polynomials.
+//!
+//! Multilinear polynomials in `n` variables are given by their evaluations over the hypercube
+//! `{0, 1}^n`, where index `i` holds the evaluation at the point whose `j`th coordinate is bit `j`
+//! of `i`, as in [`MultilinearPcs`](https://docs.rs/p3-commit). The sumcheck reduces a claim about
+//! the sum to a claim about the polynomial's value at a random point, which the caller checks
+//! against openings of the multilinear polynomials there.
+
+#![no_std]
+
+extern crate alloc;
+
+mod poly;
+mod proof;
+pub mod prover;
+pub mod verifier;
+
+pub use poly::*;
+pub use proof::*;
@@ -0,0 +1,49 @@
+use alloc::vec::Vec;
+
+use p3_field::Field;
+
+/// A term of a [`SumOfProducts`]: a coefficient times a product of multilinear polynomials.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Product<EF> {
+    pub coeff: EF,
+    /// The indices of the multilinear polynomials in the product, which may repeat.
+    pub factors: Vec<usize>,
+}
+
+/// A polynomial of the form `sum_i coeff_i prod_{j in factors_i} p_j(x)`, for multilinear
+/// polynomials `p_j`. Only its shape is described here; the prover holds the `p_j` themselves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SumOfProducts<EF> {
+    /// The number of multilinear polynomials.
+    pub num_mles: usize,
+    pub terms: Vec<Product<EF>>,
+}
+
+impl<EF: Field> SumOfProducts<EF> {
+    pub fn new(num_mles: usize, terms: Vec<Product<EF>>) -> Self {
+        assert!(
+            terms.iter().flat_map(|t| &t.factors).all(|&j| j < num_mles),
+            "factor out of range"
+        );
+        Self { num_mles, terms }
+    }
+
+    /// The degree of the polynomial in each variable, which is the degree of the sumcheck's round
+    /// polynomials.
+    pub fn degree(&self) -> usize {
+        self.terms
+            .iter()
+            .map(|t| t.factors.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Evaluate the polynomial, given the evaluations of the multilinear polynomials at a point.
+    pub fn evaluate(&self, mle_evals: &[EF]) -> EF {
+        assert_eq!(mle_evals.len(), self.num_mles);
+        self.terms
+            .iter()
+            .map(|t| t.coeff * t.factors.iter().map(|&j| mle_evals[j]).product::<EF>())
+            .sum()
+    }
+}
@@ -0,0 +1,49 @@
+use alloc::vec::Vec;
+
+use p3_field::Field;
+use serde::{Deserialize, Serialize};
+
+/// The prover's message in one round of the sumcheck: the round polynomial, given by its
+/// evaluations at `0, 1, ..., d` for the degree `d` of the summed polynomial.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct RoundPoly<F: Field> {
+    pub evals: Vec<F>,
+}
+
+impl<F: Field> RoundPoly<F> {
+    /// Evaluate the round polynomial at `x`, by Lagrange interpolation over `0, 1, ..., d`.
+    pub fn evaluate(&self, x: F) -> F {
+        let nodes = (0..self.evals.len())
+            .map(F::from_canonical_usize)
+            .collect::<Vec<_>>();
+        self.evals
+            .iter()
+            .zip(&nodes)
+            .map(|(&eval, &node)| {
+                let (num, denom) = nodes
+                    .iter()
+                    .filter(|&&other| other != node)
+                    .fold((F::ONE, F::ONE), |(num, denom), &other| {
+                        (num * (x - other), denom * (node - other))
+                    });
+                eval * num / denom
+            })
+            .sum()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct SumcheckProof<F: Field> {
+    /// The round polynomials, one per variable.
+    pub round_polys: Vec<RoundPoly<F>>,
+}
+
+/// The claim a sumcheck reduces to: that the summed polynomial takes `value` at `point`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvaluationClaim<F> {
+    /// The point, with one coordinate per variable, in the order of the variables.
+    pub point: Vec<F>,
+    pub value: F,
+}
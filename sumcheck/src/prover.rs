@@ -0,0 +1,236 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::{izip, Itertools};
+use p3_challenger::FieldChallenger;
+use p3_field::{ExtensionField, Field, FieldAlgebra, FieldExtensionAlgebra, PackedValue};
+use p3_maybe_rayon::prelude::*;
+use p3_util::log2_strict_usize;
+use tracing::instrument;
+
+use crate::{EvaluationClaim, RoundPoly, SumOfProducts, SumcheckProof};
+
+/// Prove the sum over the hypercube of `poly`, where `mles` holds the evaluations of its
+/// multilinear polynomials.
+///
+/// Each round observes the round polynomial and samples a challenge, which fixes the last
+/// remaining variable. The claimed sum itself isn't observed, so the caller should bind it to the
+/// transcript if it isn't already. Returns the proof, the claim it reduces to, and the evaluations
+/// of the multilinear polynomials at the claim's point.
+#[instrument(name = "sumcheck prover", skip_all)]
+pub fn prove<F, EF, Challenger>(
+    poly: &SumOfProducts<EF>,
+    mles: Vec<Vec<F>>,
+    challenger: &mut Challenger,
+) -> (SumcheckProof<EF>, EvaluationClaim<EF>, Vec<EF>)
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    Challenger: FieldChallenger<F>,
+{
+    assert_eq!(mles.len(), poly.num_mles);
+    let num_vars = log2_strict_usize(mles[0].len());
+    assert!(mles.iter().all(|mle| mle.len() == 1 << num_vars));
+    let degree = poly.degree();
+
+    let mut tables = Tables::Base(mles);
+    let mut round_polys = Vec::with_capacity(num_vars);
+    let mut point = Vec::with_capacity(num_vars);
+
+    for _ in 0..num_vars {
+        let sums = tables.term_sums(poly, degree);
+        let evals = (0..=degree)
+            .map(|t| {
+                izip!(&poly.terms, sums.chunks_exact(degree + 1))
+                    .map(|(term, sums)| term.coeff * sums[t])
+                    .sum()
+            })
+            .collect_vec();
+        for &eval in &evals {
+            challenger.observe_ext_element(eval);
+        }
+        round_polys.push(RoundPoly { evals });
+
+        let r: EF = challenger.sample_ext_element();
+        tables = tables.fix_last_variable(r);
+        point.push(r);
+    }
+
+    // The last variable was fixed first.
+    point.reverse();
+    let mle_evals = tables.into_evals();
+    let claim = EvaluationClaim {
+        point,
+        value: poly.evaluate(&mle_evals),
+    };
+    (SumcheckProof { round_polys }, claim, mle_evals)
+}
+
+/// The evaluations of the multilinear polynomials over the hypercube of the remaining variables.
+///
+/// They start in the base field, and move to the extension field once a variable is fixed. They're
+/// kept packed while each half of a table fills at least one packed value.
+enum Tables<F: Field, EF: ExtensionField<F>> {
+    Base(Vec<Vec<F>>),
+    Packed(Vec<Vec<EF::ExtensionPacking>>),
+    Scalar(Vec<Vec<EF>>),
+}
+
+impl<F: Field, EF: ExtensionField<F>> Tables<F, EF> {
+    /// For each term of `poly`, the sum over the hypercube of the other variables of the term's
+    /// product, with the last variable set to each of `0, 1, ..., degree`.
+    fn term_sums(&self, poly: &SumOfProducts<EF>, degree: usize) -> Vec<EF> {
+        match self {
+            Self::Base(tables) if tables[0].len() >= 2 * F::Packing::WIDTH => {
+                let tables = tables
+                    .iter()
+                    .map(|table| F::Packing::pack_slice(table))
+                    .collect_vec();
+                term_sums(poly, &tables, degree)
+                    .into_iter()
+                    .map(|sum| EF::from_base(sum.as_slice().iter().copied().sum()))
+                    .collect()
+            }
+            Self::Base(tables) => {
+                let tables = tables.iter().map(Vec::as_slice).collect_vec();
+                term_sums(poly, &tables, degree)
+                    .into_iter()
+                    .map(EF::from_base)
+                    .collect()
+            }
+            Self::Packed(tables) => {
+                let tables = tables.iter().map(Vec::as_slice).collect_vec();
+                term_sums(poly, &tables, degree)
+                    .into_iter()
+                    .map(|sum| {
+                        EF::from_base_fn(|i| {
+                            sum.as_base_slice()[i].as_slice().iter().copied().sum()
+                        })
+                    })
+                    .collect()
+            }
+            Self::Scalar(tables) => {
+                let tables = tables.iter().map(Vec::as_slice).collect_vec();
+                term_sums(poly, &tables, degree)
+            }
+        }
+    }
+
+    /// Fix the last variable to `r`, halving each table.
+    fn fix_last_variable(self, r: EF) -> Self {
+        let width = F::Packing::WIDTH;
+        match self {
+            Self::Base(tables) if tables[0].len() >= 4 * width => {
+                let r = EF::ExtensionPacking::from_f(r);
+                Self::Packed(
+                    tables
+                        .iter()
+                        .map(|table| {
+                            let (lo, hi) =
+                                F::Packing::pack_slice(table).split_at(table.len() / (2 * width));
+                            lo.par_iter()
+                                .zip(hi)
+                                .map(|(&lo, &hi)| r * (hi - lo) + lo)
+                                .collect()
+                        })
+                        .collect(),
+                )
+            }
+            Self::Base(tables) => Self::Scalar(
+                tables
+                    .iter()
+                    .map(|table| {
+                        let (lo, hi) = table.split_at(table.len() / 2);
+                        lo.iter()
+                            .zip(hi)
+                            .map(|(&lo, &hi)| r * (hi - lo) + lo)
+                            .collect()
+                    })
+                    .collect(),
+            ),
+            Self::Packed(tables) => {
+                let r = EF::ExtensionPacking::from_f(r);
+                let tables = tables
+                    .into_iter()
+                    .map(|table| {
+                        let (lo, hi) = table.split_at(table.len() / 2);
+                        lo.par_iter()
+                            .zip(hi)
+                            .map(|(&lo, &hi)| r * (hi - lo) + lo)
+                            .collect::<Vec<_>>()
+                    })
+                    .collect_vec();
+                if tables[0].len() >= 2 {
+                    Self::Packed(tables)
+                } else {
+                    Self::Scalar(tables.iter().map(|table| unpack::<F, EF>(table)).collect())
+                }
+            }
+            Self::Scalar(tables) => Self::Scalar(
+                tables
+                    .iter()
+                    .map(|table| {
+                        let (lo, hi) = table.split_at(table.len() / 2);
+                        lo.iter()
+                            .zip(hi)
+                            .map(|(&lo, &hi)| r * (hi - lo) + lo)
+                            .collect()
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The evaluations once every variable is fixed.
+    fn into_evals(self) -> Vec<EF> {
+        match self {
+            Self::Base(tables) => tables.iter().map(|table| EF::from_base(table[0])).collect(),
+            Self::Packed(_) => unreachable!("packed tables have at least two packed values"),
+            Self::Scalar(tables) => tables.iter().map(|table| table[0]).collect(),
+        }
+    }
+}
+
+/// For each term of `poly`, the sum over `i` of the term's product of `lo_j + t (hi_j - lo_j)`,
+/// where `lo_j` and `hi_j` are the `i`th values in the two halves of table `j`, for each `t` in
+/// `0, 1, ..., degree`. Returned term by term.
+fn term_sums<EF, A>(poly: &SumOfProducts<EF>, tables: &[&[A]], degree: usize) -> Vec<A>
+where
+    EF: Field,
+    A: FieldAlgebra + Copy + Send + Sync,
+{
+    let half = tables[0].len() / 2;
+    let ts = (0..=degree).map(A::from_canonical_usize).collect_vec();
+    (0..half).into_par_iter().par_fold_reduce(
+        || vec![A::ZERO; poly.terms.len() * (degree + 1)],
+        |mut acc, i| {
+            for (term, acc) in izip!(&poly.terms, acc.chunks_exact_mut(degree + 1)) {
+                for (acc, &t) in izip!(acc, &ts) {
+                    *acc += term
+                        .factors
+                        .iter()
+                        .map(|&j| {
+                            let (lo, hi) = (tables[j][i], tables[j][half + i]);
+                            lo + (hi - lo) * t
+                        })
+                        .product::<A>();
+                }
+            }
+            acc
+        },
+        |mut acc, other| {
+            izip!(&mut acc, other).for_each(|(acc, other)| *acc += other);
+            acc
+        },
+    )
+}
+
+fn unpack<F: Field, EF: ExtensionField<F>>(packed: &[EF::ExtensionPacking]) -> Vec<EF> {
+    packed
+        .iter()
+        .flat_map(|p| {
+            (0..F::Packing::WIDTH)
+                .map(move |lane| EF::from_base_fn(|i| p.as_base_slice()[i].as_slice()[lane]))
+        })
+        .collect()
+}
@@ -0,0 +1,65 @@
+use alloc::vec::Vec;
+
+use p3_challenger::FieldChallenger;
+use p3_field::{ExtensionField, Field};
+
+use crate::{EvaluationClaim, SumOfProducts, SumcheckProof};
+
+#[derive(Debug)]
+pub enum SumcheckError {
+    InvalidProofShape,
+    /// A round polynomial doesn't sum to the previous round's claim over `{0, 1}`.
+    SumMismatch,
+}
+
+/// Verify a sumcheck proof that `poly` sums to `sum` over the hypercube in `num_vars` variables.
+///
+/// Returns the claim the sum reduces to, which the caller must check, for instance by opening the
+/// multilinear polynomials at its point and comparing [`SumOfProducts::evaluate`] to its value.
+pub fn verify<F, EF, Challenger>(
+    poly: &SumOfProducts<EF>,
+    num_vars: usize,
+    sum: EF,
+    proof: &SumcheckProof<EF>,
+    challenger: &mut Challenger,
+) -> Result<EvaluationClaim<EF>, SumcheckError>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    Challenger: FieldChallenger<F>,
+{
+    let degree = poly.degree();
+    if proof.round_polys.len() != num_vars
+        || proof
+            .round_polys
+            .iter()
+            .any(|round_poly| round_poly.evals.len() != degree + 1)
+    {
+        return Err(SumcheckError::InvalidProofShape);
+    }
+
+    let mut value = sum;
+    let mut point = Vec::with_capacity(num_vars);
+    for round_poly in &proof.round_polys {
+        if round_poly.evals[0]
+            + round_poly
+                .evals
+                .get(1)
+                .copied()
+                .unwrap_or(round_poly.evals[0])
+            != value
+        {
+            return Err(SumcheckError::SumMismatch);
+        }
+        for &eval in &round_poly.evals {
+            challenger.observe_ext_element(eval);
+        }
+        let r: EF = challenger.sample_ext_element();
+        value = round_poly.evaluate(r);
+        point.push(r);
+    }
+
+    // The last variable was fixed first.
+    point.reverse();
+    Ok(EvaluationClaim { point, value })
+}
@@ -0,0 +1,151 @@
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::{CanSampleBits, DuplexChallenger};
+use p3_field::extension::BinomialExtensionField;
+use p3_field::FieldAlgebra;
+use p3_sumcheck::verifier::SumcheckError;
+use p3_sumcheck::{prover, verifier, Product, SumOfProducts, SumcheckProof};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+type Val = BabyBear;
+type Challenge = BinomialExtensionField<Val, 4>;
+type Perm = Poseidon2BabyBear<16>;
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+
+/// `3 p0 p1 p2 + p3 + 5 p0 p3 p3`, of degree 3.
+fn example_poly() -> SumOfProducts<Challenge> {
+    let product = |coeff: u32, factors: Vec<usize>| Product {
+        coeff: Challenge::from_canonical_u32(coeff),
+        factors,
+    };
+    SumOfProducts::new(
+        4,
+        vec![
+            product(3, vec![0, 1, 2]),
+            product(1, vec![3]),
+            product(5, vec![0, 3, 3]),
+        ],
+    )
+}
+
+/// Evaluate a multilinear polynomial at `point` by fixing one variable at a time.
+fn eval_mle(evals: &[Val], point: &[Challenge]) -> Challenge {
+    let mut evals: Vec<Challenge> = evals.iter().map(|&v| v.into()).collect();
+    for &z in point {
+        evals = evals
+            .chunks_exact(2)
+            .map(|pair| pair[0] + z * (pair[1] - pair[0]))
+            .collect();
+    }
+    evals[0]
+}
+
+fn hypercube_sum(poly: &SumOfProducts<Challenge>, mles: &[Vec<Val>]) -> Challenge {
+    (0..mles[0].len())
+        .map(|i| poly.evaluate(&mles.iter().map(|mle| mle[i].into()).collect::<Vec<_>>()))
+        .sum()
+}
+
+fn prove<R: Rng>(
+    rng: &mut R,
+    perm: &Perm,
+    num_vars: usize,
+) -> (SumcheckProof<Challenge>, Challenge, usize) {
+    let poly = example_poly();
+    let mles: Vec<Vec<Val>> = (0..poly.num_mles)
+        .map(|_| (0..1 << num_vars).map(|_| rng.gen()).collect())
+        .collect();
+    let sum = hypercube_sum(&poly, &mles);
+
+    let mut challenger = Challenger::new(perm.clone());
+    let (proof, claim, mle_evals) = prover::prove(&poly, mles.clone(), &mut challenger);
+    assert_eq!(claim.point.len(), num_vars);
+    for (mle, &eval) in mles.iter().zip(&mle_evals) {
+        assert_eq!(eval_mle(mle, &claim.point), eval);
+    }
+    assert_eq!(claim.value, poly.evaluate(&mle_evals));
+
+    (proof, sum, challenger.sample_bits(8))
+}
+
+fn verify(
+    perm: &Perm,
+    num_vars: usize,
+    sum: Challenge,
+    proof: &SumcheckProof<Challenge>,
+) -> Result<usize, SumcheckError> {
+    let mut challenger = Challenger::new(perm.clone());
+    verifier::verify(&example_poly(), num_vars, sum, proof, &mut challenger)?;
+    Ok(challenger.sample_bits(8))
+}
+
+#[test]
+fn test_sumcheck() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let perm = Perm::new_from_rng_128(&mut rng);
+    // Small tables are never packed, while large ones are packed for the first few rounds.
+    for num_vars in [0, 1, 2, 3, 6, 12] {
+        let (proof, sum, p_sample) = prove(&mut rng, &perm, num_vars);
+        let v_sample = verify(&perm, num_vars, sum, &proof).unwrap();
+        assert_eq!(
+            p_sample, v_sample,
+            "prover and verifier transcript have same state after sumcheck"
+        );
+    }
+}
+
+#[test]
+fn test_sumcheck_claim_matches_prover() {
+    let mut rng = ChaCha20Rng::seed_from_u64(1);
+    let perm = Perm::new_from_rng_128(&mut rng);
+    let poly = example_poly();
+    let mles: Vec<Vec<Val>> = (0..poly.num_mles)
+        .map(|_| (0..1 << 8).map(|_| rng.gen()).collect())
+        .collect();
+    let sum = hypercube_sum(&poly, &mles);
+
+    let (proof, p_claim, _) = prover::prove(&poly, mles, &mut Challenger::new(perm.clone()));
+    let v_claim =
+        verifier::verify(&poly, 8, sum, &proof, &mut Challenger::new(perm.clone())).unwrap();
+    assert_eq!(p_claim, v_claim);
+}
+
+#[test]
+fn test_sumcheck_rejects_wrong_sum() {
+    let mut rng = ChaCha20Rng::seed_from_u64(2);
+    let perm = Perm::new_from_rng_128(&mut rng);
+    let (proof, sum, _) = prove(&mut rng, &perm, 5);
+    assert!(matches!(
+        verify(&perm, 5, sum + Challenge::ONE, &proof),
+        Err(SumcheckError::SumMismatch)
+    ));
+
+    let mut bad_proof = proof.clone();
+    bad_proof.round_polys[2].evals[1] += Challenge::ONE;
+    assert!(matches!(
+        verify(&perm, 5, sum, &bad_proof),
+        Err(SumcheckError::SumMismatch)
+    ));
+
+    let mut bad_proof = proof.clone();
+    bad_proof.round_polys[0].evals.pop();
+    assert!(matches!(
+        verify(&perm, 5, sum, &bad_proof),
+        Err(SumcheckError::InvalidProofShape)
+    ));
+    assert!(matches!(
+        verify(&perm, 4, sum, &proof),
+        Err(SumcheckError::InvalidProofShape)
+    ));
+}
+
+#[test]
+fn test_sumcheck_proof_serialization() {
+    let mut rng = ChaCha20Rng::seed_from_u64(3);
+    let perm = Perm::new_from_rng_128(&mut rng);
+    let (proof, sum, _) = prove(&mut rng, &perm, 4);
+    let bytes = postcard::to_allocvec(&proof).unwrap();
+    let decoded: SumcheckProof<Challenge> = postcard::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded, proof);
+    assert!(verify(&perm, 4, sum, &decoded).is_ok());
+}
@@ -7,6 +7,7 @@ extern crate alloc;
 mod compression;
 mod hash;
 mod hasher;
+mod merkle_cap;
 mod permutation;
 mod serializing_hasher;
 mod sponge;
@@ -14,6 +15,7 @@ mod sponge;
 pub use compression::*;
 pub use hash::*;
 pub use hasher::*;
+pub use merkle_cap::*;
 pub use permutation::*;
 pub use serializing_hasher::*;
 pub use sponge::*;
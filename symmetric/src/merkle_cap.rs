@@ -0,0 +1,101 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Hash;
+
+/// The `2^k` digests of a Merkle tree's layer at height `k` below the root, committing to the
+/// tree in place of its root.
+///
+/// Authentication paths against a cap stop below it, so they're `k` digests shorter than paths to
+/// the root. A cap of height 0 is just the root.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(serialize = "[W; DIGEST_ELEMS]: Serialize"))]
+#[serde(bound(deserialize = "[W; DIGEST_ELEMS]: Deserialize<'de>"))]
+pub struct MerkleCap<F, W, const DIGEST_ELEMS: usize> {
+    cap: Vec<[W; DIGEST_ELEMS]>,
+    _marker: PhantomData<F>,
+}
+
+impl<F, W, const DIGEST_ELEMS: usize> MerkleCap<F, W, DIGEST_ELEMS> {
+    /// Panics if the number of digests isn't a power of two.
+    pub fn new(cap: Vec<[W; DIGEST_ELEMS]>) -> Self {
+        assert!(
+            cap.len().is_power_of_two(),
+            "a Merkle cap must have a power of two digests"
+        );
+        Self {
+            cap,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The log2 number of digests in the cap. This is usually the cap height the tree was
+    /// committed with, unless the tree was shorter.
+    ///
+    /// A deserialized cap may not have a power of two digests, in which case this rounds down.
+    pub fn height(&self) -> usize {
+        self.cap.len().max(1).ilog2() as usize
+    }
+
+    pub fn len(&self) -> usize {
+        self.cap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cap.is_empty()
+    }
+
+    /// The digests of the cap, from left to right.
+    pub fn roots(&self) -> &[[W; DIGEST_ELEMS]] {
+        &self.cap
+    }
+
+    /// The root of the tree, if this is a cap of height 0.
+    pub fn root(&self) -> Option<Hash<F, W, DIGEST_ELEMS>>
+    where
+        W: Copy,
+    {
+        match self.cap.as_slice() {
+            &[root] => Some(root.into()),
+            _ => None,
+        }
+    }
+}
+
+impl<F, W, const DIGEST_ELEMS: usize> From<Hash<F, W, DIGEST_ELEMS>>
+    for MerkleCap<F, W, DIGEST_ELEMS>
+{
+    fn from(root: Hash<F, W, DIGEST_ELEMS>) -> Self {
+        Self::new(vec![root.into()])
+    }
+}
+
+impl<F, W, const DIGEST_ELEMS: usize> From<[W; DIGEST_ELEMS]> for MerkleCap<F, W, DIGEST_ELEMS> {
+    fn from(root: [W; DIGEST_ELEMS]) -> Self {
+        Self::new(vec![root])
+    }
+}
+
+/// A cap is equal to a digest if it's the single root equal to that digest.
+impl<F, W: PartialEq, const DIGEST_ELEMS: usize> PartialEq<[W; DIGEST_ELEMS]>
+    for MerkleCap<F, W, DIGEST_ELEMS>
+{
+    fn eq(&self, other: &[W; DIGEST_ELEMS]) -> bool {
+        matches!(self.cap.as_slice(), [root] if root == other)
+    }
+}
+
+impl<F, W, const DIGEST_ELEMS: usize> IntoIterator for MerkleCap<F, W, DIGEST_ELEMS> {
+    type Item = Hash<F, W, DIGEST_ELEMS>;
+    type IntoIter = core::iter::Map<
+        vec::IntoIter<[W; DIGEST_ELEMS]>,
+        fn([W; DIGEST_ELEMS]) -> Hash<F, W, DIGEST_ELEMS>,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.cap.into_iter().map(Hash::from as fn(_) -> _)
+    }
+}
@@ -0,0 +1,92 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use p3_air::{Air, BaseAir};
+use p3_baby_bear::GenericPoseidon2LinearLayersBabyBear;
+use p3_field::{FieldAlgebra, PackedValue};
+use p3_keccak_air::KeccakAir;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_poseidon2_air::{Poseidon2Air, RoundConstants};
+use p3_uni_stark::testing::{Challenge, MyConfig, Val};
+use p3_uni_stark::{
+    get_symbolic_constraints, ConstraintDag, PackedChallenge, PackedVal, ProverConstraintFolder,
+    SymbolicAirBuilder,
+};
+use rand::{thread_rng, Rng};
+
+/// The number of packed points `quotient_values` evaluates the constraint DAG on at once.
+const BATCH_SIZE: usize = 16;
+
+/// Compare evaluating an AIR's constraints directly on packed windows of random values, as the
+/// prover does by default at each quotient domain point, with evaluating its compiled constraint
+/// DAG, as it does for AIRs which opt in via `BaseAir::compile_constraints`.
+fn bench_air<A>(c: &mut Criterion, name: &str, air: &A)
+where
+    A: Air<SymbolicAirBuilder<Val>> + for<'a> Air<ProverConstraintFolder<'a, MyConfig>>,
+{
+    let mut rng = thread_rng();
+    let width = <A as BaseAir<Val>>::width(air);
+    let main = RowMajorMatrix::new(
+        (0..2 * width)
+            .map(|_| PackedVal::<MyConfig>::from_fn(|_| rng.gen()))
+            .collect(),
+        width,
+    );
+    let empty = RowMajorMatrix::new(vec![], 0);
+    let empty_ext = RowMajorMatrix::new(vec![], 0);
+    let public_values = vec![];
+    let constraints = get_symbolic_constraints::<Val, _>(air, 0, 0);
+    let dag = ConstraintDag::new(&constraints);
+    let alpha_powers = rng
+        .gen::<Challenge>()
+        .powers()
+        .take(dag.num_constraints())
+        .collect::<Vec<_>>();
+
+    let [is_first_row, is_last_row, is_transition] =
+        [(); 3].map(|_| PackedVal::<MyConfig>::from_fn(|_| rng.gen()));
+
+    let folder = || ProverConstraintFolder::<MyConfig> {
+        preprocessed: empty.as_view(),
+        main: main.as_view(),
+        permutation: empty_ext.as_view(),
+        permutation_challenges: &[],
+        cumulative_sum: PackedChallenge::<MyConfig>::ZERO,
+        public_values: &public_values,
+        is_first_row,
+        is_last_row,
+        is_transition,
+        alpha_powers: &alpha_powers,
+        accumulator: PackedChallenge::<MyConfig>::ZERO,
+        constraint_index: 0,
+    };
+
+    let mut g = c.benchmark_group(name);
+    g.bench_function("air", |b| {
+        b.iter(|| {
+            let mut folders = (0..BATCH_SIZE).map(|_| folder()).collect::<Vec<_>>();
+            for folder in &mut folders {
+                air.eval(folder);
+            }
+            black_box(folders.iter().map(|f| f.accumulator).collect::<Vec<_>>())
+        })
+    });
+    g.bench_function("dag", |b| {
+        b.iter(|| {
+            let mut folders = (0..BATCH_SIZE).map(|_| folder()).collect::<Vec<_>>();
+            dag.eval(&mut folders);
+            black_box(folders.iter().map(|f| f.accumulator).collect::<Vec<_>>())
+        })
+    });
+}
+
+fn bench_keccak(c: &mut Criterion) {
+    bench_air(c, "KeccakAir", &KeccakAir {});
+}
+
+fn bench_poseidon2(c: &mut Criterion) {
+    let air: Poseidon2Air<Val, GenericPoseidon2LinearLayersBabyBear, 16, 7, 1, 4, 20> =
+        Poseidon2Air::new(RoundConstants::from_rng(&mut thread_rng()));
+    bench_air(c, "Poseidon2Air", &air);
+}
+
+criterion_group!(benches, bench_keccak, bench_poseidon2);
+criterion_main!(benches);
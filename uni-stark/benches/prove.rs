@@ -0,0 +1,165 @@
+use criterion::measurement::Measurement;
+use criterion::{criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion};
+use p3_air::{Air, BaseAir, PairBuilder};
+use p3_baby_bear::BabyBear;
+use p3_challenger::{HashChallenger, SerializingChallenger32};
+use p3_circle::CirclePcs;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::{Field, PrimeField32};
+use p3_fri::{FriConfig, TwoAdicFriPcs};
+use p3_keccak::Keccak256Hash;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_mersenne_31::Mersenne31;
+use p3_symmetric::{CompressionFunctionFromHasher, SerializingHasher32};
+use p3_uni_stark::{prove_with_key, setup, StarkConfig, StarkGenericConfig, Val};
+
+/// The first main column equals a preprocessed row counter, and each further column is the square
+/// of the previous one.
+struct RepeatedSquaresAir {
+    log_height: usize,
+    width: usize,
+}
+
+impl RepeatedSquaresAir {
+    fn generate_trace<F: Field>(&self) -> RowMajorMatrix<F> {
+        let values = (0..1 << self.log_height)
+            .flat_map(|i| {
+                core::iter::successors(Some(F::from_canonical_usize(i)), |x| Some(x.square()))
+                    .take(self.width)
+            })
+            .collect();
+        RowMajorMatrix::new(values, self.width)
+    }
+}
+
+impl<F: Field> BaseAir<F> for RepeatedSquaresAir {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        let values = (0..1 << self.log_height)
+            .map(F::from_canonical_usize)
+            .collect();
+        Some(RowMajorMatrix::new_col(values))
+    }
+}
+
+impl<AB: PairBuilder> Air<AB> for RepeatedSquaresAir {
+    fn eval(&self, builder: &mut AB) {
+        let preprocessed = builder.preprocessed();
+        let main = builder.main();
+        let local = main.row_slice(0);
+
+        builder.assert_eq(local[0], preprocessed.row_slice(0)[0]);
+        for j in 1..self.width {
+            builder.assert_eq(local[j], local[j - 1] * local[j - 1]);
+        }
+    }
+}
+
+type ByteHash = Keccak256Hash;
+type FieldHash = SerializingHasher32<ByteHash>;
+type MyCompress = CompressionFunctionFromHasher<ByteHash, 2, 32>;
+type ValMmcs<F> = MerkleTreeMmcs<F, u8, FieldHash, MyCompress, 32>;
+type Challenger<F> = SerializingChallenger32<F, HashChallenger<u8, ByteHash, 32>>;
+
+fn make_val_mmcs<F>() -> ValMmcs<F> {
+    ValMmcs::new(FieldHash::new(ByteHash {}), MyCompress::new(ByteHash {}))
+}
+
+fn make_fri_config<F: Field, EF>(
+    val_mmcs: &ValMmcs<F>,
+) -> FriConfig<ExtensionMmcs<F, EF, ValMmcs<F>>> {
+    FriConfig {
+        log_blowup: 1,
+        log_arity: 1,
+        log_final_poly_len: 0,
+        num_queries: 100,
+        proof_of_work_bits: 16,
+        mmcs: ExtensionMmcs::new(val_mmcs.clone()),
+    }
+}
+
+fn make_challenger<F: PrimeField32>() -> Challenger<F> {
+    Challenger::from_hasher(vec![], ByteHash {})
+}
+
+fn bench_prove(c: &mut Criterion) {
+    let log_width = 4;
+
+    let mut g = c.benchmark_group("prove");
+    g.sample_size(10);
+    for log_height in [12, 16] {
+        let air = RepeatedSquaresAir {
+            log_height,
+            width: 1 << log_width,
+        };
+        prove_two_adic(&mut g, &air);
+        prove_circle(&mut g, &air);
+    }
+}
+
+fn prove_two_adic<M: Measurement>(g: &mut BenchmarkGroup<M>, air: &RepeatedSquaresAir) {
+    type Val = BabyBear;
+    type Challenge = BinomialExtensionField<Val, 4>;
+    type Dft = Radix2DitParallel<Val>;
+    type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs<Val>, ExtensionMmcs<Val, Challenge, ValMmcs<Val>>>;
+
+    let val_mmcs = make_val_mmcs();
+    let fri_config = make_fri_config(&val_mmcs);
+    let pcs = Pcs::new(Dft::default(), val_mmcs, fri_config);
+    let config = StarkConfig::<_, Challenge, Challenger<Val>>::new(pcs);
+    bench_config(g, "BabyBear,TwoAdicFriPcs", &config, air, make_challenger);
+}
+
+fn prove_circle<M: Measurement>(g: &mut BenchmarkGroup<M>, air: &RepeatedSquaresAir) {
+    type Val = Mersenne31;
+    type Challenge = BinomialExtensionField<Val, 3>;
+    type Pcs = CirclePcs<Val, ValMmcs<Val>, ExtensionMmcs<Val, Challenge, ValMmcs<Val>>>;
+
+    let val_mmcs = make_val_mmcs();
+    let fri_config = make_fri_config(&val_mmcs);
+    let pcs = Pcs::new(val_mmcs, fri_config);
+    let config = StarkConfig::<_, Challenge, Challenger<Val>>::new(pcs);
+    bench_config(g, "Mersenne31,CirclePcs", &config, air, make_challenger);
+}
+
+fn bench_config<SC, M>(
+    g: &mut BenchmarkGroup<M>,
+    name: &str,
+    config: &SC,
+    air: &RepeatedSquaresAir,
+    make_challenger: impl Fn() -> SC::Challenger,
+) where
+    SC: StarkGenericConfig,
+    M: Measurement,
+{
+    let (proving_key, _) = setup(config, air).expect("setup failed");
+    let trace = air.generate_trace::<Val<SC>>();
+    g.bench_with_input(
+        BenchmarkId::new(
+            name,
+            format!("log_n={},width={}", air.log_height, air.width),
+        ),
+        &trace,
+        |b, trace| {
+            b.iter_batched(
+                || trace.clone(),
+                |trace| {
+                    let mut challenger = make_challenger();
+                    prove_with_key(config, &proving_key, air, &mut challenger, trace, &vec![])
+                        .expect("proving failed")
+                },
+                criterion::BatchSize::LargeInput,
+            )
+        },
+    );
+}
+
+criterion_group!(benches, bench_prove);
+criterion_main!(benches);
@@ -0,0 +1,430 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::{izip, Itertools};
+use p3_air::{Air, BaseAir};
+use p3_challenger::{CanObserve, CanSample, FieldChallenger};
+use p3_commit::{Pcs, PolynomialSpace};
+use p3_field::FieldAlgebra;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_util::log2_strict_usize;
+use tracing::{info_span, instrument};
+
+use crate::prover::{
+    check_blowup, check_trace_shape, commit_witness, generate_permutation_trace, quotient_values,
+};
+use crate::symbolic_builder::log_quotient_degree;
+use crate::{
+    get_symbolic_constraints, setup_batch, BatchProof, BatchProvingKey, Commitments, ConstraintDag,
+    OpenedValues, ProverConstraintFolder, ProverError, StarkGenericConfig, SymbolicAirBuilder,
+    SymbolicExpression, Val,
+};
+
+/// Prove that each trace satisfies the corresponding AIR, and that the interactions between the
+/// tables balance, i.e. that the cumulative sums of their permutation arguments add up to zero.
+///
+/// The traces may have different heights. Heterogeneous tables can be proven together by wrapping
+/// their AIRs in an enum which implements `Air` by dispatching to the wrapped AIR.
+///
+/// This runs [`setup_batch`] internally. When proving many batches of the same AIRs, prefer
+/// calling [`setup_batch`] once and then [`prove_batch_with_key`].
+#[instrument(skip_all)]
+#[allow(clippy::multiple_bound_locations)] // cfg not supported in where clauses?
+pub fn prove_batch<
+    SC,
+    #[cfg(debug_assertions)] A: for<'a> Air<crate::check_constraints::DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>,
+    #[cfg(not(debug_assertions))] A,
+>(
+    config: &SC,
+    airs: &[A],
+    challenger: &mut SC::Challenger,
+    traces: Vec<RowMajorMatrix<Val<SC>>>,
+    public_values: &[Vec<Val<SC>>],
+) -> Result<BatchProof<SC>, ProverError>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<ProverConstraintFolder<'a, SC>>,
+{
+    let (proving_key, _) = setup_batch(config, airs)?;
+    prove_batch_with_key(
+        config,
+        &proving_key,
+        airs,
+        challenger,
+        traces,
+        public_values,
+    )
+}
+
+/// Prove a batch of tables, using a proving key previously produced by [`setup_batch`].
+#[instrument(skip_all)]
+#[allow(clippy::multiple_bound_locations)] // cfg not supported in where clauses?
+pub fn prove_batch_with_key<
+    SC,
+    #[cfg(debug_assertions)] A: for<'a> Air<crate::check_constraints::DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>,
+    #[cfg(not(debug_assertions))] A,
+>(
+    config: &SC,
+    proving_key: &BatchProvingKey<SC>,
+    airs: &[A],
+    challenger: &mut SC::Challenger,
+    traces: Vec<RowMajorMatrix<Val<SC>>>,
+    public_values: &[Vec<Val<SC>>],
+) -> Result<BatchProof<SC>, ProverError>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<ProverConstraintFolder<'a, SC>>,
+{
+    let num_tables = airs.len();
+    for actual in [traces.len(), public_values.len(), proving_key.num_tables()] {
+        if actual != num_tables {
+            return Err(ProverError::BatchSizeMismatch {
+                expected: num_tables,
+                actual,
+            });
+        }
+    }
+
+    let log_degrees = airs
+        .iter()
+        .zip(&traces)
+        .enumerate()
+        .map(|(table, (air, trace))| {
+            let expected_degree_bits = proving_key
+                .preprocessed_trace(table)
+                .map(|preprocessed| log2_strict_usize(preprocessed.height()));
+            check_trace_shape(air, trace, expected_degree_bits, &public_values[table])
+                .map_err(|error| ProverError::in_table(table, error))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let degrees = traces.iter().map(Matrix::height).collect_vec();
+    let permutation_widths = airs
+        .iter()
+        .map(|air| <A as BaseAir<Val<SC>>>::permutation_width(air))
+        .collect_vec();
+    let has_permutation = permutation_widths.iter().any(|&width| width > 0);
+
+    // Without permutation traces, all constraints can be checked up front. Otherwise we must wait
+    // until the permutation challenges have been sampled.
+    #[cfg(debug_assertions)]
+    if !has_permutation {
+        for (table, (air, trace)) in airs.iter().zip(&traces).enumerate() {
+            crate::check_constraints::check_constraints::<_, SC::Challenge, _>(
+                air,
+                proving_key.preprocessed_trace(table),
+                trace,
+                None,
+                &[],
+                SC::Challenge::ZERO,
+                &public_values[table],
+            )
+            .map_err(|error| ProverError::in_table(table, error))?;
+        }
+    }
+
+    let (constraints, log_quotient_degrees): (Vec<_>, Vec<_>) = airs
+        .iter()
+        .enumerate()
+        .map(|(table, air)| {
+            let preprocessed_width = proving_key
+                .preprocessed_trace(table)
+                .map_or(0, Matrix::width);
+            let symbolic_constraints = get_symbolic_constraints::<Val<SC>, A>(
+                air,
+                preprocessed_width,
+                public_values[table].len(),
+            );
+            let constraint_degree = symbolic_constraints
+                .iter()
+                .map(SymbolicExpression::degree_multiple)
+                .max()
+                .unwrap_or(0);
+            let constraint_dag = air
+                .compile_constraints()
+                .then(|| ConstraintDag::new(&symbolic_constraints));
+            (
+                (constraint_dag, symbolic_constraints.len()),
+                log_quotient_degree(constraint_degree, SC::IS_ZK),
+            )
+        })
+        .unzip();
+
+    let pcs = config.pcs();
+    for (table, &log_quotient_degree) in log_quotient_degrees.iter().enumerate() {
+        let has_preprocessed = proving_key.preprocessed_trace(table).is_some();
+        check_blowup::<SC>(pcs, log_quotient_degree, has_preprocessed)
+            .map_err(|error| ProverError::in_table(table, error))?;
+    }
+    let trace_domains = degrees
+        .iter()
+        .map(|&degree| pcs.natural_domain_for_degree(degree))
+        .collect_vec();
+
+    // The main traces are needed again after they're committed, to generate permutation traces.
+    let main_traces = has_permutation.then(|| traces.clone());
+
+    let (main_commit, main_data) = info_span!("commit to trace data").in_scope(|| {
+        commit_witness::<SC>(
+            pcs,
+            izip!(trace_domains.iter().copied(), traces).collect_vec(),
+        )
+    })?;
+
+    // Observe the instance, starting with the verifying key.
+    proving_key.verifying_key.observe(challenger);
+    for &log_degree in &log_degrees {
+        challenger.observe(Val::<SC>::from_canonical_usize(log_degree));
+    }
+
+    challenger.observe(main_commit.clone());
+    for public_values in public_values {
+        challenger.observe_slice(public_values);
+    }
+
+    // All tables share the same permutation challenges, so that their interactions can balance.
+    let mut permutation_challenges = vec![];
+    let mut cumulative_sums = vec![SC::Challenge::ZERO; num_tables];
+    let permutation = if let Some(main_traces) = main_traces {
+        let num_permutation_challenges = airs
+            .iter()
+            .map(|air| <A as BaseAir<Val<SC>>>::num_permutation_challenges(air))
+            .max()
+            .unwrap_or(0);
+        permutation_challenges = (0..num_permutation_challenges)
+            .map(|_| challenger.sample_ext_element())
+            .collect();
+
+        let permutation_traces = info_span!("generate permutation traces").in_scope(|| {
+            airs.iter()
+                .zip(&main_traces)
+                .enumerate()
+                .map(|(table, (air, main_trace))| {
+                    (permutation_widths[table] > 0)
+                        .then(|| {
+                            generate_permutation_trace(
+                                air,
+                                proving_key.preprocessed_trace(table),
+                                main_trace,
+                                &permutation_challenges,
+                            )
+                        })
+                        .transpose()
+                        .map_err(|error| ProverError::in_table(table, error))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+        for (cumulative_sum, air, permutation_trace) in
+            izip!(&mut cumulative_sums, airs, &permutation_traces)
+        {
+            if let Some(permutation_trace) = permutation_trace {
+                *cumulative_sum = permutation_trace.get(
+                    permutation_trace.height() - 1,
+                    <A as BaseAir<Val<SC>>>::cumulative_sum_column(air),
+                );
+            }
+        }
+
+        #[cfg(debug_assertions)]
+        {
+            for (table, (air, main_trace)) in airs.iter().zip(&main_traces).enumerate() {
+                crate::check_constraints::check_constraints(
+                    air,
+                    proving_key.preprocessed_trace(table),
+                    main_trace,
+                    permutation_traces[table].as_ref(),
+                    &permutation_challenges,
+                    cumulative_sums[table],
+                    &public_values[table],
+                )
+                .map_err(|error| ProverError::in_table(table, error))?;
+            }
+            if cumulative_sums.iter().copied().sum::<SC::Challenge>() != SC::Challenge::ZERO {
+                return Err(ProverError::NonzeroCumulativeSum);
+            }
+        }
+
+        let (permutation_commit, permutation_data) = info_span!("commit to permutation traces")
+            .in_scope(|| {
+                commit_witness::<SC>(
+                    pcs,
+                    permutation_traces
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(table, permutation_trace)| {
+                            permutation_trace
+                                .as_ref()
+                                .map(|trace| (trace_domains[table], trace.flatten_to_base()))
+                        })
+                        .collect_vec(),
+                )
+            })?;
+        challenger.observe(permutation_commit.clone());
+        for &cumulative_sum in &cumulative_sums {
+            challenger.observe_ext_element(cumulative_sum);
+        }
+        Some((permutation_commit, permutation_data))
+    } else {
+        None
+    };
+
+    let alpha: SC::Challenge = challenger.sample_ext_element();
+
+    let preprocessed_indices =
+        round_indices(proving_key.preprocessed_traces.iter().map(Option::is_some));
+    let permutation_indices = round_indices(permutation_widths.iter().map(|&width| width > 0));
+
+    let mut quotients = vec![];
+    for (table, (air, (constraint_dag, constraint_count))) in
+        airs.iter().zip(&constraints).enumerate()
+    {
+        let trace_domain = trace_domains[table];
+        let quotient_degree = 1 << log_quotient_degrees[table];
+        let quotient_domain = trace_domain
+            .create_disjoint_domain(1 << (log_degrees[table] + log_quotient_degrees[table]));
+
+        let trace_on_quotient_domain =
+            pcs.get_evaluations_on_domain(&main_data, table, quotient_domain);
+        let preprocessed_on_quotient_domain = preprocessed_indices[table].map(|index| {
+            let (_, preprocessed_data) = proving_key.preprocessed.as_ref().unwrap();
+            pcs.get_evaluations_on_domain(preprocessed_data, index, quotient_domain)
+        });
+        let permutation_on_quotient_domain = permutation_indices[table].map(|index| {
+            let (_, permutation_data) = permutation.as_ref().unwrap();
+            pcs.get_evaluations_on_domain(permutation_data, index, quotient_domain)
+        });
+
+        let quotient_values = quotient_values::<SC, _, _>(
+            air,
+            constraint_dag.as_ref(),
+            *constraint_count,
+            &public_values[table],
+            trace_domain,
+            quotient_domain,
+            preprocessed_on_quotient_domain,
+            trace_on_quotient_domain,
+            permutation_on_quotient_domain,
+            &permutation_challenges,
+            cumulative_sums[table],
+            alpha,
+        );
+        let quotient_flat = RowMajorMatrix::new_col(quotient_values).flatten_to_base();
+        quotients.push((quotient_domain, quotient_flat, quotient_degree));
+    }
+
+    let (quotient_commit, quotient_data) =
+        info_span!("commit to quotient poly chunks").in_scope(|| pcs.commit_quotients(quotients));
+    challenger.observe(quotient_commit.clone());
+
+    let commitments = Commitments {
+        trace: main_commit,
+        permutation: permutation.as_ref().map(|(commit, _)| commit.clone()),
+        quotient_chunks: quotient_commit,
+    };
+
+    let zeta: SC::Challenge = challenger.sample();
+    let zeta_nexts = trace_domains
+        .iter()
+        .map(|domain| domain.next_point(zeta).unwrap())
+        .collect_vec();
+
+    let (opened_values, opening_proof) = info_span!("open").in_scope(|| {
+        let local_and_next = |tables: &[Option<usize>]| {
+            tables
+                .iter()
+                .enumerate()
+                .filter(|(_, index)| index.is_some())
+                .map(|(table, _)| vec![zeta, zeta_nexts[table]])
+                .collect_vec()
+        };
+        let mut rounds = vec![
+            (
+                &main_data,
+                (0..num_tables)
+                    .map(|table| vec![zeta, zeta_nexts[table]])
+                    .collect_vec(),
+            ),
+            (
+                &quotient_data,
+                // open every chunk at zeta
+                log_quotient_degrees
+                    .iter()
+                    .flat_map(|&log_quotient_degree| {
+                        (0..1 << log_quotient_degree).map(|_| vec![zeta])
+                    })
+                    .collect_vec(),
+            ),
+        ];
+        if let Some((_, preprocessed_data)) = &proving_key.preprocessed {
+            rounds.push((preprocessed_data, local_and_next(&preprocessed_indices)));
+        }
+        if let Some((_, permutation_data)) = &permutation {
+            rounds.push((permutation_data, local_and_next(&permutation_indices)));
+        }
+        pcs.open(rounds, challenger)
+    });
+
+    // The optional rounds follow, in the order they were pushed above.
+    let mut optional_rounds = opened_values[2..].iter();
+    let preprocessed_openings = proving_key
+        .preprocessed
+        .as_ref()
+        .map(|_| optional_rounds.next().unwrap());
+    let permutation_openings = permutation
+        .as_ref()
+        .map(|_| optional_rounds.next().unwrap());
+    let local_and_next = |round: Option<&Vec<Vec<Vec<SC::Challenge>>>>, index: Option<usize>| {
+        index.map_or_else(
+            || (vec![], vec![]),
+            |index| {
+                let matrix = &round.unwrap()[index];
+                (matrix[0].clone(), matrix[1].clone())
+            },
+        )
+    };
+
+    let mut quotient_openings = opened_values[1].iter();
+    let opened_values = (0..num_tables)
+        .map(|table| {
+            let (preprocessed_local, preprocessed_next) =
+                local_and_next(preprocessed_openings, preprocessed_indices[table]);
+            let (permutation_local, permutation_next) =
+                local_and_next(permutation_openings, permutation_indices[table]);
+            OpenedValues {
+                preprocessed_local,
+                preprocessed_next,
+                trace_local: opened_values[0][table][0].clone(),
+                trace_next: opened_values[0][table][1].clone(),
+                permutation_local,
+                permutation_next,
+                quotient_chunks: quotient_openings
+                    .by_ref()
+                    .take(1 << log_quotient_degrees[table])
+                    .map(|chunk| chunk[0].clone())
+                    .collect(),
+            }
+        })
+        .collect();
+
+    Ok(BatchProof {
+        commitments,
+        opened_values,
+        cumulative_sums,
+        opening_proof,
+        degree_bits: log_degrees,
+    })
+}
+
+/// For a round which contains a matrix only for those tables where `present` is true, map each
+/// table to the index of its matrix within the round.
+pub(crate) fn round_indices(present: impl Iterator<Item = bool>) -> Vec<Option<usize>> {
+    let mut next_index = 0;
+    present
+        .map(|present| {
+            present.then(|| {
+                next_index += 1;
+                next_index - 1
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,270 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use itertools::{izip, Itertools};
+use p3_air::{Air, BaseAir};
+use p3_challenger::{CanObserve, CanSample, FieldChallenger};
+use p3_commit::{Pcs, PolynomialSpace};
+use p3_field::FieldAlgebra;
+use tracing::instrument;
+
+use crate::symbolic_builder::{get_log_quotient_degree, SymbolicAirBuilder};
+use crate::verifier::{opened_values_have_shape, verify_constraints, witness_domain};
+use crate::{
+    setup_batch, AirFingerprint, BatchProof, BatchVerifyingKey, PcsError, StarkGenericConfig, Val,
+    VerificationError, VerifierConstraintFolder,
+};
+
+/// Verify a proof of a batch of tables, produced by [`prove_batch`](crate::prove_batch).
+///
+/// This runs [`setup_batch`] internally to derive the verifying key. When verifying many proofs
+/// for the same AIRs, prefer calling [`setup_batch`] once and then [`verify_batch_with_key`].
+#[instrument(skip_all)]
+pub fn verify_batch<SC, A>(
+    config: &SC,
+    airs: &[A],
+    challenger: &mut SC::Challenger,
+    proof: &BatchProof<SC>,
+    public_values: &[Vec<Val<SC>>],
+) -> Result<(), VerificationError<PcsError<SC>>>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+{
+    let (_, verifying_key) =
+        setup_batch(config, airs).map_err(|_| VerificationError::InvalidPreprocessedTrace)?;
+    verify_batch_with_key(
+        config,
+        &verifying_key,
+        airs,
+        challenger,
+        proof,
+        public_values,
+    )
+}
+
+/// Verify a proof of a batch of tables, using a verifying key previously produced by
+/// [`setup_batch`].
+#[instrument(skip_all)]
+pub fn verify_batch_with_key<SC, A>(
+    config: &SC,
+    verifying_key: &BatchVerifyingKey<SC>,
+    airs: &[A],
+    challenger: &mut SC::Challenger,
+    proof: &BatchProof<SC>,
+    public_values: &[Vec<Val<SC>>],
+) -> Result<(), VerificationError<PcsError<SC>>>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+{
+    let BatchProof {
+        commitments,
+        opened_values,
+        cumulative_sums,
+        opening_proof,
+        degree_bits,
+    } = proof;
+
+    let num_tables = airs.len();
+    if verifying_key.num_tables() != num_tables
+        || public_values.len() != num_tables
+        || opened_values.len() != num_tables
+        || cumulative_sums.len() != num_tables
+        || degree_bits.len() != num_tables
+    {
+        return Err(VerificationError::InvalidProofShape);
+    }
+    // The key must have been set up for this configuration and these AIRs, as it is what binds
+    // the transcript to them.
+    if verifying_key.pcs_parameters() != config.pcs().parameters()
+        || airs.iter().enumerate().any(|(table, air)| {
+            *verifying_key.air_fingerprint(table)
+                != AirFingerprint::new::<Val<SC>, _>(air, verifying_key.preprocessed_width(table))
+        })
+    {
+        return Err(VerificationError::VerifyingKeyMismatch);
+    }
+
+    let permutation_widths = airs
+        .iter()
+        .map(|air| <A as BaseAir<Val<SC>>>::permutation_width(air))
+        .collect_vec();
+    let has_permutation = permutation_widths.iter().any(|&width| width > 0);
+    if commitments.permutation.is_some() != has_permutation {
+        return Err(VerificationError::InvalidProofShape);
+    }
+
+    let mut log_quotient_degrees = Vec::with_capacity(num_tables);
+    for (table, air) in airs.iter().enumerate() {
+        if public_values[table].len() != air.num_public_values() {
+            return Err(VerificationError::InvalidProofShape);
+        }
+        let preprocessed_width = verifying_key.preprocessed_width(table);
+        let log_quotient_degree = get_log_quotient_degree::<Val<SC>, A>(
+            air,
+            preprocessed_width,
+            public_values[table].len(),
+            SC::IS_ZK,
+        );
+        let valid_shape = verifying_key
+            .degree_bits(table)
+            .is_none_or(|bits| bits == degree_bits[table])
+            // Only tables with a permutation argument may claim a nonzero cumulative sum.
+            && (permutation_widths[table] > 0 || cumulative_sums[table] == SC::Challenge::ZERO)
+            && opened_values_have_shape::<Val<SC>, SC::Challenge, A>(
+                air,
+                &opened_values[table],
+                preprocessed_width,
+                1 << log_quotient_degree,
+            );
+        if !valid_shape {
+            return Err(VerificationError::InvalidProofShape);
+        }
+        log_quotient_degrees.push(log_quotient_degree);
+    }
+
+    let pcs = config.pcs();
+    let trace_domains = degree_bits
+        .iter()
+        .map(|&bits| pcs.natural_domain_for_degree(1 << bits))
+        .collect_vec();
+    let quotient_chunks_domains = izip!(&trace_domains, degree_bits, &log_quotient_degrees)
+        .map(|(trace_domain, &bits, &log_quotient_degree)| {
+            trace_domain
+                .create_disjoint_domain(1 << (bits + log_quotient_degree))
+                .split_domains(1 << log_quotient_degree)
+        })
+        .collect_vec();
+
+    // Observe the instance, starting with the verifying key.
+    verifying_key.observe(challenger);
+    for &bits in degree_bits {
+        challenger.observe(Val::<SC>::from_canonical_usize(bits));
+    }
+
+    challenger.observe(commitments.trace.clone());
+    for public_values in public_values {
+        challenger.observe_slice(public_values);
+    }
+    let mut permutation_challenges = vec![];
+    if let Some(permutation_commit) = &commitments.permutation {
+        let num_permutation_challenges = airs
+            .iter()
+            .map(|air| <A as BaseAir<Val<SC>>>::num_permutation_challenges(air))
+            .max()
+            .unwrap_or(0);
+        permutation_challenges = (0..num_permutation_challenges)
+            .map(|_| challenger.sample_ext_element())
+            .collect();
+        challenger.observe(permutation_commit.clone());
+        for &cumulative_sum in cumulative_sums {
+            challenger.observe_ext_element(cumulative_sum);
+        }
+    }
+    if cumulative_sums.iter().copied().sum::<SC::Challenge>() != SC::Challenge::ZERO {
+        return Err(VerificationError::NonzeroCumulativeSum);
+    }
+
+    let alpha: SC::Challenge = challenger.sample_ext_element();
+    challenger.observe(commitments.quotient_chunks.clone());
+
+    let zeta: SC::Challenge = challenger.sample();
+    let zeta_nexts = trace_domains
+        .iter()
+        .map(|domain| domain.next_point(zeta).unwrap())
+        .collect_vec();
+
+    // Preprocessed traces don't depend on the witness, so aren't randomized in zero-knowledge mode.
+    let local_and_next = |table: usize, witness: bool, local: &[_], next: &[_]| {
+        let domain = if witness {
+            witness_domain::<SC>(pcs, trace_domains[table])
+        } else {
+            trace_domains[table]
+        };
+        (
+            domain,
+            vec![(zeta, local.to_vec()), (zeta_nexts[table], next.to_vec())],
+        )
+    };
+    let mut rounds = vec![
+        (
+            commitments.trace.clone(),
+            opened_values
+                .iter()
+                .enumerate()
+                .map(|(table, values)| {
+                    local_and_next(table, true, &values.trace_local, &values.trace_next)
+                })
+                .collect_vec(),
+        ),
+        (
+            commitments.quotient_chunks.clone(),
+            izip!(&quotient_chunks_domains, opened_values)
+                .flat_map(|(domains, values)| {
+                    izip!(domains, &values.quotient_chunks).map(|(domain, chunk)| {
+                        (
+                            witness_domain::<SC>(pcs, *domain),
+                            vec![(zeta, chunk.clone())],
+                        )
+                    })
+                })
+                .collect_vec(),
+        ),
+    ];
+    if let Some(preprocessed_commit) = verifying_key.preprocessed_commitment() {
+        rounds.push((
+            preprocessed_commit.clone(),
+            opened_values
+                .iter()
+                .enumerate()
+                .filter(|(table, _)| verifying_key.degree_bits(*table).is_some())
+                .map(|(table, values)| {
+                    local_and_next(
+                        table,
+                        false,
+                        &values.preprocessed_local,
+                        &values.preprocessed_next,
+                    )
+                })
+                .collect_vec(),
+        ));
+    }
+    if let Some(permutation_commit) = &commitments.permutation {
+        rounds.push((
+            permutation_commit.clone(),
+            opened_values
+                .iter()
+                .enumerate()
+                .filter(|(table, _)| permutation_widths[*table] > 0)
+                .map(|(table, values)| {
+                    local_and_next(
+                        table,
+                        true,
+                        &values.permutation_local,
+                        &values.permutation_next,
+                    )
+                })
+                .collect_vec(),
+        ));
+    }
+
+    pcs.verify(rounds, opening_proof, challenger)
+        .map_err(VerificationError::InvalidOpeningArgument)?;
+
+    for (table, air) in airs.iter().enumerate() {
+        verify_constraints::<SC, A>(
+            air,
+            &opened_values[table],
+            trace_domains[table],
+            &quotient_chunks_domains[table],
+            &permutation_challenges,
+            cumulative_sums[table],
+            &public_values[table],
+            alpha,
+            zeta,
+        )?;
+    }
+
+    Ok(())
+}
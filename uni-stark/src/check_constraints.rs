@@ -1,23 +1,181 @@
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
 
-use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues};
-use p3_field::Field;
+use hashbrown::HashSet;
+use p3_air::{
+    Air, AirBuilder, AirBuilderWithPublicValues, ExtensionBuilder, MultiTableAirBuilder,
+    PairBuilder, PermutationAirBuilder,
+};
+use p3_field::{ExtensionField, Field};
 use p3_matrix::dense::{RowMajorMatrix, RowMajorMatrixView};
 use p3_matrix::stack::VerticalPair;
 use p3_matrix::Matrix;
 use tracing::instrument;
 
-#[instrument(name = "check constraints", skip_all)]
-pub(crate) fn check_constraints<F, A>(air: &A, main: &RowMajorMatrix<F>, public_values: &Vec<F>)
+use crate::symbolic_builder::{get_symbolic_constraints, SymbolicAirBuilder};
+use crate::symbolic_expression::SymbolicExpression;
+use crate::symbolic_variable::Entry;
+use crate::ProverError;
+
+type ViewPair<'a, T> = VerticalPair<RowMajorMatrixView<'a, T>, RowMajorMatrixView<'a, T>>;
+
+/// A constraint which doesn't vanish on some row of a trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FailedConstraint<EF> {
+    pub row: usize,
+    /// The index of the constraint, in the order the AIR asserts its constraints.
+    pub constraint: usize,
+    /// The label the AIR gave the constraint, if any. See `AirBuilder::label`.
+    pub label: Option<&'static str>,
+    /// The value of the constraint on this row.
+    pub value: EF,
+    /// The main trace columns the constraint reads, ordered by index and then by row.
+    pub columns: Vec<ConstraintColumn<EF>>,
+}
+
+/// A main trace column read by a failed constraint, with its value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintColumn<EF> {
+    /// The index of the column in the main trace.
+    pub index: usize,
+    /// Whether the constraint reads the column on the next row, rather than the failing one.
+    pub next_row: bool,
+    /// The name the AIR gave the column, if any. See `BaseAir::column_names`.
+    pub name: Option<String>,
+    /// The value the constraint reads.
+    pub value: EF,
+}
+
+impl<EF: Display> Display for ConstraintColumn<EF> {
+    /// Formats the column as its name, or `main[i]` if it has none, with a `'` if it's read on the
+    /// next row, followed by its value.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{name}")?,
+            None => write!(f, "main[{}]", self.index)?,
+        }
+        if self.next_row {
+            write!(f, "'")?;
+        }
+        write!(f, " = {}", self.value)
+    }
+}
+
+/// The outcome of checking every constraint of an AIR on every row of a trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintReport<EF> {
+    /// The number of rows checked.
+    pub height: usize,
+    /// The largest number of constraints asserted on any row.
+    pub num_constraints: usize,
+    /// Every failed constraint, ordered by row and then by constraint index.
+    pub failures: Vec<FailedConstraint<EF>>,
+}
+
+impl<EF> ConstraintReport<EF> {
+    /// Whether every constraint holds on every row.
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl<EF: Display> Display for ConstraintReport<EF> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return write!(
+                f,
+                "all {} constraints hold on all {} rows",
+                self.num_constraints, self.height
+            );
+        }
+        write!(
+            f,
+            "{} constraint failures on {} rows:",
+            self.failures.len(),
+            self.height
+        )?;
+        for failure in &self.failures {
+            write!(
+                f,
+                "\n  row {}, constraint {}",
+                failure.row, failure.constraint
+            )?;
+            if let Some(label) = failure.label {
+                write!(f, " ({label})")?;
+            }
+            write!(f, ": {}", failure.value)?;
+            for (i, column) in failure.columns.iter().enumerate() {
+                write!(f, "{}{column}", if i == 0 { " [" } else { ", " })?;
+            }
+            if !failure.columns.is_empty() {
+                write!(f, "]")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check that the constraints of `air` hold on every row of the given traces, collecting all
+/// failures rather than stopping at the first one.
+///
+/// Unlike the checks the prover runs in debug builds, this is available in all builds, and is
+/// meant for debugging AIRs and witness generation. AIRs without a permutation trace can pass
+/// `None`, no challenges and a zero cumulative sum.
+///
+/// Each failure lists the main trace columns its constraint reads, named by
+/// `BaseAir::column_names`, which are found by evaluating the constraints symbolically.
+///
+/// Returns an error if the preprocessed or permutation trace height differs from that of the main
+/// trace.
+#[instrument(name = "check all constraints", skip_all)]
+pub fn check_all_constraints<F, EF, A>(
+    air: &A,
+    preprocessed: Option<&RowMajorMatrix<F>>,
+    main: &RowMajorMatrix<F>,
+    permutation: Option<&RowMajorMatrix<EF>>,
+    permutation_challenges: &[EF],
+    cumulative_sum: EF,
+    public_values: &[F],
+) -> Result<ConstraintReport<EF>, ProverError>
 where
     F: Field,
-    A: for<'a> Air<DebugConstraintBuilder<'a, F>>,
+    EF: ExtensionField<F>,
+    A: for<'a> Air<DebugConstraintBuilder<'a, F, EF>> + Air<SymbolicAirBuilder<F>>,
 {
     let height = main.height();
+    if let Some(preprocessed) = preprocessed {
+        if preprocessed.height() != height {
+            return Err(ProverError::TraceHeightMismatch {
+                expected: preprocessed.height(),
+                actual: height,
+            });
+        }
+    }
+    if let Some(permutation) = permutation {
+        if permutation.height() != height {
+            return Err(ProverError::PermutationTraceHeightMismatch {
+                expected: height,
+                actual: permutation.height(),
+            });
+        }
+    }
 
-    (0..height).for_each(|i| {
+    let mut num_constraints = 0;
+    let mut failures = Vec::new();
+    for i in 0..height {
         let i_next = (i + 1) % height;
 
+        let (preprocessed_local, preprocessed_next) = preprocessed
+            .map(|p| (p.row_slice(i).to_vec(), p.row_slice(i_next).to_vec()))
+            .unwrap_or_default();
+        let preprocessed = VerticalPair::new(
+            RowMajorMatrixView::new_row(&preprocessed_local),
+            RowMajorMatrixView::new_row(&preprocessed_next),
+        );
+
         let local = main.row_slice(i);
         let next = main.row_slice(i_next);
         let main = VerticalPair::new(
@@ -25,39 +183,178 @@ where
             RowMajorMatrixView::new_row(&*next),
         );
 
+        let (permutation_local, permutation_next) = permutation
+            .map(|p| (p.row_slice(i).to_vec(), p.row_slice(i_next).to_vec()))
+            .unwrap_or_default();
+        let permutation = VerticalPair::new(
+            RowMajorMatrixView::new_row(&permutation_local),
+            RowMajorMatrixView::new_row(&permutation_next),
+        );
+
         let mut builder = DebugConstraintBuilder {
-            row_index: i,
+            row: i,
+            preprocessed,
             main,
+            permutation,
+            permutation_challenges,
+            cumulative_sum,
             public_values,
             is_first_row: F::from_bool(i == 0),
             is_last_row: F::from_bool(i == height - 1),
             is_transition: F::from_bool(i != height - 1),
+            constraint_index: 0,
+            label: None,
+            failures: &mut failures,
         };
 
         air.eval(&mut builder);
-    });
+        num_constraints = num_constraints.max(builder.constraint_index);
+    }
+
+    if !failures.is_empty() {
+        let constraints = get_symbolic_constraints::<F, A>(
+            air,
+            preprocessed.map_or(0, Matrix::width),
+            public_values.len(),
+        );
+        let names = air.column_names().unwrap_or_default();
+        for failure in &mut failures {
+            let Some(constraint) = constraints.get(failure.constraint) else {
+                continue;
+            };
+            failure.columns = main_columns(constraint)
+                .into_iter()
+                .map(|(index, next_row)| {
+                    let row = (failure.row + usize::from(next_row)) % height;
+                    ConstraintColumn {
+                        index,
+                        next_row,
+                        name: names.get(index).cloned(),
+                        value: EF::from(main.get(row, index)),
+                    }
+                })
+                .collect();
+        }
+    }
+
+    Ok(ConstraintReport {
+        height,
+        num_constraints,
+        failures,
+    })
+}
+
+/// The main trace columns `constraint` reads, as pairs of their index and whether they're read on
+/// the next row, in ascending order.
+fn main_columns<F: Field>(constraint: &SymbolicExpression<F>) -> BTreeSet<(usize, bool)> {
+    let mut columns = BTreeSet::new();
+    // Subexpressions may be shared, so visit each only once.
+    let mut visited = HashSet::new();
+    let mut stack = vec![constraint];
+    while let Some(expr) = stack.pop() {
+        if !visited.insert(expr as *const SymbolicExpression<F>) {
+            continue;
+        }
+        match expr {
+            SymbolicExpression::Variable(v) => {
+                if let Entry::Main { offset } = v.entry {
+                    columns.insert((v.index, offset == 1));
+                }
+            }
+            SymbolicExpression::Add { x, y, .. }
+            | SymbolicExpression::Sub { x, y, .. }
+            | SymbolicExpression::Mul { x, y, .. } => {
+                stack.push(x);
+                stack.push(y);
+            }
+            SymbolicExpression::Neg { x, .. } => stack.push(x),
+            SymbolicExpression::IsFirstRow
+            | SymbolicExpression::IsLastRow
+            | SymbolicExpression::IsTransition
+            | SymbolicExpression::Constant(_) => {}
+        }
+    }
+    columns
+}
+
+/// Check the constraints of `air` as the prover does in debug builds, reporting the first failure.
+#[cfg(debug_assertions)]
+pub(crate) fn check_constraints<F, EF, A>(
+    air: &A,
+    preprocessed: Option<&RowMajorMatrix<F>>,
+    main: &RowMajorMatrix<F>,
+    permutation: Option<&RowMajorMatrix<EF>>,
+    permutation_challenges: &[EF],
+    cumulative_sum: EF,
+    public_values: &[F],
+) -> Result<(), ProverError>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    A: for<'a> Air<DebugConstraintBuilder<'a, F, EF>> + Air<SymbolicAirBuilder<F>>,
+{
+    let report = check_all_constraints(
+        air,
+        preprocessed,
+        main,
+        permutation,
+        permutation_challenges,
+        cumulative_sum,
+        public_values,
+    )?;
+    match report.failures.first() {
+        Some(failure) => Err(ProverError::ConstraintFailure {
+            row: failure.row,
+            constraint: failure.constraint,
+            label: failure.label,
+        }),
+        None => Ok(()),
+    }
 }
 
-/// An `AirBuilder` which asserts that each constraint is zero, allowing any failed constraints to
-/// be detected early.
+/// An `AirBuilder` which evaluates each constraint on a single row, recording those which aren't
+/// zero along with their values and labels.
 #[derive(Debug)]
-pub struct DebugConstraintBuilder<'a, F: Field> {
-    row_index: usize,
-    main: VerticalPair<RowMajorMatrixView<'a, F>, RowMajorMatrixView<'a, F>>,
+pub struct DebugConstraintBuilder<'a, F: Field, EF: ExtensionField<F> = F> {
+    row: usize,
+    preprocessed: ViewPair<'a, F>,
+    main: ViewPair<'a, F>,
+    permutation: ViewPair<'a, EF>,
+    permutation_challenges: &'a [EF],
+    cumulative_sum: EF,
     public_values: &'a [F],
     is_first_row: F,
     is_last_row: F,
     is_transition: F,
+    constraint_index: usize,
+    label: Option<&'static str>,
+    failures: &'a mut Vec<FailedConstraint<EF>>,
+}
+
+impl<'a, F: Field, EF: ExtensionField<F>> DebugConstraintBuilder<'a, F, EF> {
+    fn check(&mut self, value: EF) {
+        if !value.is_zero() {
+            self.failures.push(FailedConstraint {
+                row: self.row,
+                constraint: self.constraint_index,
+                label: self.label,
+                value,
+                columns: Vec::new(),
+            });
+        }
+        self.constraint_index += 1;
+    }
 }
 
-impl<'a, F> AirBuilder for DebugConstraintBuilder<'a, F>
+impl<'a, F, EF> AirBuilder for DebugConstraintBuilder<'a, F, EF>
 where
     F: Field,
+    EF: ExtensionField<F>,
 {
     type F = F;
     type Expr = F;
     type Var = F;
-    type M = VerticalPair<RowMajorMatrixView<'a, F>, RowMajorMatrixView<'a, F>>;
+    type M = ViewPair<'a, F>;
 
     fn main(&self) -> Self::M {
         self.main
@@ -80,29 +377,64 @@ where
     }
 
     fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
-        assert_eq!(
-            x.into(),
-            F::ZERO,
-            "constraints had nonzero value on row {}",
-            self.row_index
-        );
+        self.check(EF::from(x.into()));
     }
 
-    fn assert_eq<I1: Into<Self::Expr>, I2: Into<Self::Expr>>(&mut self, x: I1, y: I2) {
-        let x = x.into();
-        let y = y.into();
-        assert_eq!(
-            x, y,
-            "values didn't match on row {}: {} != {}",
-            self.row_index, x, y
-        );
+    fn label(&mut self, label: &'static str) {
+        self.label = Some(label);
     }
 }
 
-impl<'a, F: Field> AirBuilderWithPublicValues for DebugConstraintBuilder<'a, F> {
+impl<'a, F: Field, EF: ExtensionField<F>> AirBuilderWithPublicValues
+    for DebugConstraintBuilder<'a, F, EF>
+{
     type PublicVar = Self::F;
 
     fn public_values(&self) -> &[Self::F] {
         self.public_values
     }
 }
+
+impl<'a, F: Field, EF: ExtensionField<F>> PairBuilder for DebugConstraintBuilder<'a, F, EF> {
+    fn preprocessed(&self) -> Self::M {
+        self.preprocessed
+    }
+}
+
+impl<'a, F: Field, EF: ExtensionField<F>> ExtensionBuilder for DebugConstraintBuilder<'a, F, EF> {
+    type EF = EF;
+    type ExprEF = EF;
+    type VarEF = EF;
+
+    fn assert_zero_ext<I>(&mut self, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        self.check(x.into());
+    }
+}
+
+impl<'a, F: Field, EF: ExtensionField<F>> PermutationAirBuilder
+    for DebugConstraintBuilder<'a, F, EF>
+{
+    type MP = ViewPair<'a, EF>;
+    type RandomVar = EF;
+
+    fn permutation(&self) -> Self::MP {
+        self.permutation
+    }
+
+    fn permutation_randomness(&self) -> &[Self::RandomVar] {
+        self.permutation_challenges
+    }
+}
+
+impl<'a, F: Field, EF: ExtensionField<F>> MultiTableAirBuilder
+    for DebugConstraintBuilder<'a, F, EF>
+{
+    type Sum = EF;
+
+    fn cumulative_sum(&self) -> Self::Sum {
+        self.cumulative_sum
+    }
+}
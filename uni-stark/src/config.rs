@@ -1,14 +1,30 @@
 use core::marker::PhantomData;
 
 use p3_challenger::{CanObserve, CanSample, FieldChallenger};
-use p3_commit::{Pcs, PolynomialSpace};
+use p3_commit::{ConfigFingerprint, Pcs, PolynomialSpace};
 use p3_field::{ExtensionField, Field};
+use p3_util::pretty_name;
 
 pub type PcsError<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<
     <SC as StarkGenericConfig>::Challenge,
     <SC as StarkGenericConfig>::Challenger,
 >>::Error;
 
+pub type Com<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<
+    <SC as StarkGenericConfig>::Challenge,
+    <SC as StarkGenericConfig>::Challenger,
+>>::Commitment;
+
+pub type PcsProverData<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<
+    <SC as StarkGenericConfig>::Challenge,
+    <SC as StarkGenericConfig>::Challenger,
+>>::ProverData;
+
+pub type PcsProof<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<
+    <SC as StarkGenericConfig>::Challenge,
+    <SC as StarkGenericConfig>::Challenger,
+>>::Proof;
+
 pub type Domain<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<
     <SC as StarkGenericConfig>::Challenge,
     <SC as StarkGenericConfig>::Challenger,
@@ -33,7 +49,26 @@ pub trait StarkGenericConfig {
         + CanObserve<<Self::Pcs as Pcs<Self::Challenge, Self::Challenger>>::Commitment>
         + CanSample<Self::Challenge>;
 
+    /// Whether proofs are zero-knowledge, which requires a hiding PCS.
+    ///
+    /// In zero-knowledge mode, the trace and permutation polynomials are randomized to twice their
+    /// degree, and the quotient chunks are masked, so that opened values are independent of the
+    /// witness. This roughly doubles the quotient degree, and the FRI blowup must be at least the
+    /// quotient degree when there's a preprocessed trace, or half of it otherwise.
+    const IS_ZK: bool = <Self::Pcs as Pcs<Self::Challenge, Self::Challenger>>::ZK;
+
     fn pcs(&self) -> &Self::Pcs;
+
+    /// The fingerprint of this configuration, which encoded proofs carry.
+    ///
+    /// It names the type of the PCS's proofs rather than the PCS, so that it covers their hashers
+    /// but not, for instance, the DFT used to compute them.
+    fn fingerprint(&self) -> ConfigFingerprint {
+        ConfigFingerprint::new::<Val<Self>, Self::Challenge>(
+            self.pcs().parameters(),
+            pretty_name::<PcsProof<Self>>(),
+        )
+    }
 }
 
 #[derive(Debug)]
@@ -0,0 +1,633 @@
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use hashbrown::HashMap;
+use p3_commit::PolynomialSpace;
+use p3_field::{Field, FieldAlgebra};
+use p3_matrix::Matrix;
+
+use crate::symbolic_expression::SymbolicExpression;
+use crate::{
+    Domain, Entry, PackedChallenge, PackedVal, ProverConstraintFolder, StarkGenericConfig,
+};
+
+/// An AIR's symbolic constraints, simplified and hash-consed into a DAG in which every distinct
+/// subexpression appears once, compiled for repeated evaluation over packed quotient domain points.
+///
+/// Building the DAG folds constants, removes identities such as `x * 1` and `x + 0`, orders the
+/// operands of commutative operations canonically, and merges structurally equal subexpressions.
+/// Constraints guarded by `IsFirstRow`, `IsLastRow` or `IsTransition` are accumulated separately,
+/// so that each selector is multiplied in once rather than once per constraint.
+///
+/// Like [`SymbolicAirBuilder`](crate::SymbolicAirBuilder), this relies on the AIR building its
+/// extension field expressions from permutation columns, challenges, the cumulative sum and base
+/// field values only, so the prover only uses it for AIRs which opt in via
+/// [`BaseAir::compile_constraints`](p3_air::BaseAir::compile_constraints).
+#[derive(Clone, Debug)]
+pub struct ConstraintDag<F> {
+    /// Instructions in topological order. Slots are reused once the value in them is last used,
+    /// which keeps the values needed at any one time small enough to stay in cache.
+    instructions: Vec<Instruction<F>>,
+    num_nodes: usize,
+    num_base_slots: usize,
+    num_ext_slots: usize,
+    num_constraints: usize,
+}
+
+/// A compiled DAG node. Operands and results are the indices of base field slots, except where an
+/// instruction's name or doc says they are extension field valued. Nodes depending on permutation
+/// columns, permutation challenges or the cumulative sum are extension field valued, and others are
+/// base field valued.
+#[derive(Copy, Clone, Debug)]
+enum Instruction<F> {
+    Preprocessed {
+        out: u32,
+        offset: u32,
+        index: u32,
+    },
+    Main {
+        out: u32,
+        offset: u32,
+        index: u32,
+    },
+    Public {
+        out: u32,
+        index: u32,
+    },
+    Selector {
+        out: u32,
+        selector: Selector,
+    },
+    Constant {
+        out: u32,
+        value: F,
+    },
+    Add {
+        out: u32,
+        x: u32,
+        y: u32,
+    },
+    Sub {
+        out: u32,
+        x: u32,
+        y: u32,
+    },
+    Neg {
+        out: u32,
+        x: u32,
+    },
+    Mul {
+        out: u32,
+        x: u32,
+        y: u32,
+    },
+    ExtPermutation {
+        out: u32,
+        offset: u32,
+        index: u32,
+    },
+    ExtChallenge {
+        out: u32,
+        index: u32,
+    },
+    ExtCumulativeSum {
+        out: u32,
+    },
+    ExtAdd {
+        out: u32,
+        x: u32,
+        y: u32,
+    },
+    /// Add the base field value `y` to the extension field value `x`.
+    ExtAddBase {
+        out: u32,
+        x: u32,
+        y: u32,
+    },
+    ExtSub {
+        out: u32,
+        x: u32,
+        y: u32,
+    },
+    /// Subtract the base field value `y` from the extension field value `x`.
+    ExtSubBase {
+        out: u32,
+        x: u32,
+        y: u32,
+    },
+    /// Subtract the extension field value `y` from the base field value `x`.
+    BaseSubExt {
+        out: u32,
+        x: u32,
+        y: u32,
+    },
+    ExtNeg {
+        out: u32,
+        x: u32,
+    },
+    ExtMul {
+        out: u32,
+        x: u32,
+        y: u32,
+    },
+    /// Multiply the extension field value `x` by the base field value `y`.
+    ExtMulBase {
+        out: u32,
+        x: u32,
+        y: u32,
+    },
+    /// Add a constraint's value, times its power of alpha, to the accumulator for its selector.
+    Accumulate {
+        selector: Option<Selector>,
+        x: u32,
+        constraint: u32,
+    },
+    /// Like `Accumulate`, for an extension field valued constraint.
+    AccumulateExt {
+        selector: Option<Selector>,
+        x: u32,
+        constraint: u32,
+    },
+}
+
+/// Where the value of a node is stored during evaluation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Slot {
+    Base(u32),
+    Ext(u32),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Selector {
+    FirstRow,
+    LastRow,
+    Transition,
+}
+
+/// A DAG node, whose operands are the indices of other nodes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Node<F> {
+    Variable { entry: Entry, index: usize },
+    Selector(Selector),
+    Constant(F),
+    Add(usize, usize),
+    Sub(usize, usize),
+    Neg(usize),
+    Mul(usize, usize),
+}
+
+impl<F: Field> ConstraintDag<F> {
+    pub fn new(constraints: &[SymbolicExpression<F>]) -> Self {
+        let mut builder = DagBuilder::default();
+        let roots = constraints
+            .iter()
+            .map(|constraint| {
+                let root = builder.insert(constraint);
+                // Split off a selector guarding the whole constraint, if there is one.
+                match builder.nodes[root] {
+                    Node::Mul(x, y) => match (builder.selector(x), builder.selector(y)) {
+                        (Some(selector), _) => (Some(selector), y),
+                        (_, Some(selector)) => (Some(selector), x),
+                        _ => (None, root),
+                    },
+                    _ => (None, root),
+                }
+            })
+            .collect::<Vec<_>>();
+        builder.compile(roots)
+    }
+
+    /// The number of distinct nodes evaluated at each point.
+    pub fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.num_constraints
+    }
+
+    /// Evaluate the constraints on each folder's window and fold them into its accumulator, with
+    /// the same result as evaluating the AIR they came from on each folder in turn.
+    ///
+    /// Every instruction is applied to all the windows before moving on to the next, so passing
+    /// several folders at once amortizes the cost of interpreting the DAG.
+    pub fn eval<SC>(&self, folders: &mut [ProverConstraintFolder<'_, SC>])
+    where
+        SC: StarkGenericConfig,
+        Domain<SC>: PolynomialSpace<Val = F>,
+    {
+        let n = folders.len();
+        let mut base = vec![PackedVal::<SC>::ZERO; self.num_base_slots * n];
+        let mut ext = vec![PackedChallenge::<SC>::ZERO; self.num_ext_slots * n];
+        // One accumulator for unguarded constraints, and one for each selector.
+        let mut accumulators = vec![[PackedChallenge::<SC>::ZERO; 4]; n];
+
+        // The values of slot `s` for the different folders are stored at `s * n..(s + 1) * n`.
+        let slot = |s: u32| s as usize * n;
+        macro_rules! apply {
+            ($slots:ident[$out:ident], |$i:ident| $value:expr) => {
+                for $i in 0..n {
+                    $slots[slot($out) + $i] = $value;
+                }
+            };
+        }
+
+        for &instruction in &self.instructions {
+            match instruction {
+                Instruction::Preprocessed { out, offset, index } => {
+                    for (i, folder) in folders.iter().enumerate() {
+                        base[slot(out) + i] =
+                            folder.preprocessed.get(offset as usize, index as usize);
+                    }
+                }
+                Instruction::Main { out, offset, index } => {
+                    for (i, folder) in folders.iter().enumerate() {
+                        base[slot(out) + i] = folder.main.get(offset as usize, index as usize);
+                    }
+                }
+                Instruction::Public { out, index } => {
+                    for (i, folder) in folders.iter().enumerate() {
+                        base[slot(out) + i] =
+                            PackedVal::<SC>::from_f(folder.public_values[index as usize]);
+                    }
+                }
+                Instruction::Selector { out, selector } => {
+                    for (i, folder) in folders.iter().enumerate() {
+                        base[slot(out) + i] = match selector {
+                            Selector::FirstRow => folder.is_first_row,
+                            Selector::LastRow => folder.is_last_row,
+                            Selector::Transition => folder.is_transition,
+                        };
+                    }
+                }
+                Instruction::Constant { out, value } => {
+                    base[slot(out)..slot(out) + n].fill(PackedVal::<SC>::from_f(value));
+                }
+                Instruction::Add { out, x, y } => {
+                    apply!(base[out], |i| base[slot(x) + i] + base[slot(y) + i])
+                }
+                Instruction::Sub { out, x, y } => {
+                    apply!(base[out], |i| base[slot(x) + i] - base[slot(y) + i])
+                }
+                Instruction::Neg { out, x } => apply!(base[out], |i| -base[slot(x) + i]),
+                Instruction::Mul { out, x, y } => {
+                    apply!(base[out], |i| base[slot(x) + i] * base[slot(y) + i])
+                }
+                Instruction::ExtPermutation { out, offset, index } => {
+                    for (i, folder) in folders.iter().enumerate() {
+                        ext[slot(out) + i] =
+                            folder.permutation.get(offset as usize, index as usize);
+                    }
+                }
+                Instruction::ExtChallenge { out, index } => {
+                    for (i, folder) in folders.iter().enumerate() {
+                        ext[slot(out) + i] = folder.permutation_challenges[index as usize];
+                    }
+                }
+                Instruction::ExtCumulativeSum { out } => {
+                    for (i, folder) in folders.iter().enumerate() {
+                        ext[slot(out) + i] = folder.cumulative_sum;
+                    }
+                }
+                Instruction::ExtAdd { out, x, y } => {
+                    apply!(ext[out], |i| ext[slot(x) + i] + ext[slot(y) + i])
+                }
+                Instruction::ExtAddBase { out, x, y } => {
+                    apply!(ext[out], |i| ext[slot(x) + i] + base[slot(y) + i])
+                }
+                Instruction::ExtSub { out, x, y } => {
+                    apply!(ext[out], |i| ext[slot(x) + i] - ext[slot(y) + i])
+                }
+                Instruction::ExtSubBase { out, x, y } => {
+                    apply!(ext[out], |i| ext[slot(x) + i] - base[slot(y) + i])
+                }
+                Instruction::BaseSubExt { out, x, y } => apply!(ext[out], |i| {
+                    PackedChallenge::<SC>::from(base[slot(x) + i]) - ext[slot(y) + i]
+                }),
+                Instruction::ExtNeg { out, x } => apply!(ext[out], |i| -ext[slot(x) + i]),
+                Instruction::ExtMul { out, x, y } => {
+                    apply!(ext[out], |i| ext[slot(x) + i] * ext[slot(y) + i])
+                }
+                Instruction::ExtMulBase { out, x, y } => {
+                    apply!(ext[out], |i| ext[slot(x) + i] * base[slot(y) + i])
+                }
+                Instruction::Accumulate {
+                    selector,
+                    x,
+                    constraint,
+                } => {
+                    let accumulator = selector.map_or(0, |s| s as usize + 1);
+                    for (i, folder) in folders.iter().enumerate() {
+                        let alpha_power = PackedChallenge::<SC>::from_f(
+                            folder.alpha_powers[folder.constraint_index + constraint as usize],
+                        );
+                        accumulators[i][accumulator] += alpha_power * base[slot(x) + i];
+                    }
+                }
+                Instruction::AccumulateExt {
+                    selector,
+                    x,
+                    constraint,
+                } => {
+                    let accumulator = selector.map_or(0, |s| s as usize + 1);
+                    for (i, folder) in folders.iter().enumerate() {
+                        let alpha_power = PackedChallenge::<SC>::from_f(
+                            folder.alpha_powers[folder.constraint_index + constraint as usize],
+                        );
+                        accumulators[i][accumulator] += alpha_power * ext[slot(x) + i];
+                    }
+                }
+            }
+        }
+
+        for (folder, [unguarded, first_row, last_row, transition]) in
+            folders.iter_mut().zip(accumulators)
+        {
+            folder.accumulator += unguarded
+                + first_row * folder.is_first_row
+                + last_row * folder.is_last_row
+                + transition * folder.is_transition;
+            folder.constraint_index += self.num_constraints;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct DagBuilder<F> {
+    nodes: Vec<Node<F>>,
+    node_indices: HashMap<Node<F>, usize>,
+    /// Indices of the nodes already built for subexpressions shared through an `Rc`.
+    shared: HashMap<*const SymbolicExpression<F>, usize>,
+}
+
+impl<F> Default for DagBuilder<F> {
+    fn default() -> Self {
+        Self {
+            nodes: vec![],
+            node_indices: HashMap::new(),
+            shared: HashMap::new(),
+        }
+    }
+}
+
+impl<F: Field> DagBuilder<F> {
+    fn insert(&mut self, expr: &SymbolicExpression<F>) -> usize {
+        let node = match expr {
+            SymbolicExpression::Variable(v) => Node::Variable {
+                entry: v.entry,
+                index: v.index,
+            },
+            SymbolicExpression::IsFirstRow => Node::Selector(Selector::FirstRow),
+            SymbolicExpression::IsLastRow => Node::Selector(Selector::LastRow),
+            SymbolicExpression::IsTransition => Node::Selector(Selector::Transition),
+            SymbolicExpression::Constant(c) => Node::Constant(*c),
+            SymbolicExpression::Add { x, y, .. } => Node::Add(self.insert_rc(x), self.insert_rc(y)),
+            SymbolicExpression::Sub { x, y, .. } => Node::Sub(self.insert_rc(x), self.insert_rc(y)),
+            SymbolicExpression::Neg { x, .. } => Node::Neg(self.insert_rc(x)),
+            SymbolicExpression::Mul { x, y, .. } => Node::Mul(self.insert_rc(x), self.insert_rc(y)),
+        };
+        self.insert_node(node)
+    }
+
+    fn insert_rc(&mut self, expr: &Rc<SymbolicExpression<F>>) -> usize {
+        let ptr = Rc::as_ptr(expr);
+        if let Some(&index) = self.shared.get(&ptr) {
+            return index;
+        }
+        let index = self.insert(expr);
+        self.shared.insert(ptr, index);
+        index
+    }
+
+    /// Simplify `node`, then return the index of an equal node, adding it if there is none.
+    fn insert_node(&mut self, node: Node<F>) -> usize {
+        let node = match self.simplify(node) {
+            Ok(node) => node,
+            Err(index) => return index,
+        };
+        if let Some(&index) = self.node_indices.get(&node) {
+            return index;
+        }
+        self.nodes.push(node);
+        self.node_indices.insert(node, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    /// Returns either a canonical node equal to `node`, or the index of an existing one.
+    fn simplify(&mut self, node: Node<F>) -> Result<Node<F>, usize> {
+        let constant = |index: usize| match self.nodes[index] {
+            Node::Constant(c) => Some(c),
+            _ => None,
+        };
+        Ok(match node {
+            Node::Add(x, y) => match (constant(x), constant(y)) {
+                (Some(a), Some(b)) => Node::Constant(a + b),
+                (Some(a), _) if a.is_zero() => return Err(y),
+                (_, Some(b)) if b.is_zero() => return Err(x),
+                _ => Node::Add(x.min(y), x.max(y)),
+            },
+            Node::Sub(x, y) => match (constant(x), constant(y)) {
+                (Some(a), Some(b)) => Node::Constant(a - b),
+                (_, Some(b)) if b.is_zero() => return Err(x),
+                (Some(a), _) if a.is_zero() => return self.simplify(Node::Neg(y)),
+                _ if x == y => Node::Constant(F::ZERO),
+                _ => Node::Sub(x, y),
+            },
+            Node::Neg(x) => match self.nodes[x] {
+                Node::Constant(c) => Node::Constant(-c),
+                Node::Neg(y) => return Err(y),
+                _ => Node::Neg(x),
+            },
+            Node::Mul(x, y) => match (constant(x), constant(y)) {
+                (Some(a), Some(b)) => Node::Constant(a * b),
+                (Some(a), _) | (_, Some(a)) if a.is_zero() => Node::Constant(F::ZERO),
+                (Some(a), _) if a.is_one() => return Err(y),
+                (_, Some(b)) if b.is_one() => return Err(x),
+                (Some(a), _) if a == F::NEG_ONE => return self.simplify(Node::Neg(y)),
+                (_, Some(b)) if b == F::NEG_ONE => return self.simplify(Node::Neg(x)),
+                _ => Node::Mul(x.min(y), x.max(y)),
+            },
+            leaf => leaf,
+        })
+    }
+
+    fn selector(&self, index: usize) -> Option<Selector> {
+        match self.nodes[index] {
+            Node::Selector(selector) => Some(selector),
+            _ => None,
+        }
+    }
+
+    /// Drop the nodes the constraints don't depend on, and assign every other node a slot.
+    fn compile(self, roots: Vec<(Option<Selector>, usize)>) -> ConstraintDag<F> {
+        let operands = |node: &Node<F>| match *node {
+            Node::Add(x, y) | Node::Sub(x, y) | Node::Mul(x, y) => vec![x, y],
+            Node::Neg(x) => vec![x],
+            _ => vec![],
+        };
+
+        // Count the uses of each node, by the constraints and by the nodes they depend on.
+        // Operands come before the nodes using them, so one backwards pass reaches all of them.
+        let mut uses = vec![0; self.nodes.len()];
+        let mut constraints_at = vec![vec![]; self.nodes.len()];
+        for (constraint, &(selector, root)) in roots.iter().enumerate() {
+            uses[root] += 1;
+            constraints_at[root].push((selector, constraint));
+        }
+        for index in (0..self.nodes.len()).rev() {
+            if uses[index] > 0 {
+                for operand in operands(&self.nodes[index]) {
+                    uses[operand] += 1;
+                }
+            }
+        }
+
+        let mut slots: Vec<Option<Slot>> = vec![None; self.nodes.len()];
+        let mut allocator = SlotAllocator::default();
+        let mut instructions = vec![];
+        let mut num_nodes = 0;
+        for (index, node) in self.nodes.iter().enumerate() {
+            if uses[index] == 0 {
+                continue;
+            }
+            num_nodes += 1;
+            let slot = |operand: usize| slots[operand].unwrap();
+            let is_ext = match *node {
+                Node::Variable { entry, .. } => matches!(
+                    entry,
+                    Entry::Permutation { .. } | Entry::Challenge | Entry::CumulativeSum
+                ),
+                Node::Selector(_) | Node::Constant(_) => false,
+                Node::Add(x, y) | Node::Sub(x, y) | Node::Mul(x, y) => {
+                    matches!(slot(x), Slot::Ext(_)) || matches!(slot(y), Slot::Ext(_))
+                }
+                Node::Neg(x) => matches!(slot(x), Slot::Ext(_)),
+            };
+
+            // Operands are read before the result is written, so the result may reuse their slots.
+            for operand in operands(node) {
+                uses[operand] -= 1;
+                if uses[operand] == 0 {
+                    allocator.free(slot(operand));
+                }
+            }
+            let out = allocator.alloc(is_ext);
+            instructions.push(Self::instruction(node, out, slot));
+            slots[index] = Some(out);
+
+            for &(selector, constraint) in &constraints_at[index] {
+                let constraint = constraint as u32;
+                instructions.push(match out {
+                    Slot::Base(x) => Instruction::Accumulate {
+                        selector,
+                        x,
+                        constraint,
+                    },
+                    Slot::Ext(x) => Instruction::AccumulateExt {
+                        selector,
+                        x,
+                        constraint,
+                    },
+                });
+                uses[index] -= 1;
+            }
+            if uses[index] == 0 {
+                allocator.free(out);
+            }
+        }
+
+        ConstraintDag {
+            instructions,
+            num_nodes,
+            num_base_slots: allocator.num_base_slots as usize,
+            num_ext_slots: allocator.num_ext_slots as usize,
+            num_constraints: roots.len(),
+        }
+    }
+
+    /// The instruction evaluating `node` into `out`, given the slots holding its operands.
+    fn instruction(node: &Node<F>, out: Slot, slot: impl Fn(usize) -> Slot) -> Instruction<F> {
+        use Slot::{Base, Ext};
+        let (Base(out) | Ext(out)) = out;
+        match *node {
+            Node::Variable { entry, index } => {
+                let index = index as u32;
+                match entry {
+                    Entry::Preprocessed { offset } => Instruction::Preprocessed {
+                        out,
+                        offset: offset as u32,
+                        index,
+                    },
+                    Entry::Main { offset } => Instruction::Main {
+                        out,
+                        offset: offset as u32,
+                        index,
+                    },
+                    Entry::Permutation { offset } => Instruction::ExtPermutation {
+                        out,
+                        offset: offset as u32,
+                        index,
+                    },
+                    Entry::Public => Instruction::Public { out, index },
+                    Entry::Challenge => Instruction::ExtChallenge { out, index },
+                    Entry::CumulativeSum => Instruction::ExtCumulativeSum { out },
+                }
+            }
+            Node::Selector(selector) => Instruction::Selector { out, selector },
+            Node::Constant(value) => Instruction::Constant { out, value },
+            Node::Add(x, y) => match (slot(x), slot(y)) {
+                (Base(x), Base(y)) => Instruction::Add { out, x, y },
+                (Ext(x), Ext(y)) => Instruction::ExtAdd { out, x, y },
+                (Ext(x), Base(y)) | (Base(y), Ext(x)) => Instruction::ExtAddBase { out, x, y },
+            },
+            Node::Sub(x, y) => match (slot(x), slot(y)) {
+                (Base(x), Base(y)) => Instruction::Sub { out, x, y },
+                (Ext(x), Ext(y)) => Instruction::ExtSub { out, x, y },
+                (Ext(x), Base(y)) => Instruction::ExtSubBase { out, x, y },
+                (Base(x), Ext(y)) => Instruction::BaseSubExt { out, x, y },
+            },
+            Node::Neg(x) => match slot(x) {
+                Base(x) => Instruction::Neg { out, x },
+                Ext(x) => Instruction::ExtNeg { out, x },
+            },
+            Node::Mul(x, y) => match (slot(x), slot(y)) {
+                (Base(x), Base(y)) => Instruction::Mul { out, x, y },
+                (Ext(x), Ext(y)) => Instruction::ExtMul { out, x, y },
+                (Ext(x), Base(y)) | (Base(y), Ext(x)) => Instruction::ExtMulBase { out, x, y },
+            },
+        }
+    }
+}
+
+/// Hands out slots for node values, preferring ones freed by values that are no longer needed.
+#[derive(Debug, Default)]
+struct SlotAllocator {
+    num_base_slots: u32,
+    num_ext_slots: u32,
+    free_base_slots: Vec<u32>,
+    free_ext_slots: Vec<u32>,
+}
+
+impl SlotAllocator {
+    fn alloc(&mut self, is_ext: bool) -> Slot {
+        if is_ext {
+            Slot::Ext(self.free_ext_slots.pop().unwrap_or_else(|| {
+                self.num_ext_slots += 1;
+                self.num_ext_slots - 1
+            }))
+        } else {
+            Slot::Base(self.free_base_slots.pop().unwrap_or_else(|| {
+                self.num_base_slots += 1;
+                self.num_base_slots - 1
+            }))
+        }
+    }
+
+    fn free(&mut self, slot: Slot) {
+        match slot {
+            Slot::Base(i) => self.free_base_slots.push(i),
+            Slot::Ext(i) => self.free_ext_slots.push(i),
+        }
+    }
+}
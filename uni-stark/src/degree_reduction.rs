@@ -0,0 +1,477 @@
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+use hashbrown::HashMap;
+use p3_air::{Air, AirBuilderWithPublicValues, BaseAir, PairBuilder};
+use p3_field::{Field, FieldAlgebra};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+
+use crate::symbolic_expression::SymbolicExpression;
+use crate::symbolic_variable::SymbolicVariable;
+use crate::{get_symbolic_constraints, Entry, SymbolicAirBuilder};
+
+/// An AIR whose constraints are those of another AIR, rewritten to have degree at most a given
+/// bound by moving high degree subexpressions into auxiliary main trace columns.
+///
+/// Each auxiliary column is appended after the original columns, and is constrained to equal the
+/// subexpression it replaces. [`DegreeReducedAir::extend_trace`] fills in these columns, so an AIR
+/// written with natural constraints can be proven as
+///
+/// ```ignore
+/// let air = DegreeReducedAir::new(&air, 3)?;
+/// let trace = air.extend_trace(&trace, &public_values);
+/// let proof = prove(&config, &air, &mut challenger, trace, &public_values)?;
+/// ```
+///
+/// AIRs with permutation columns are not supported, since the symbolic constraints represent their
+/// extension field values by single base field variables.
+#[derive(Clone, Debug)]
+pub struct DegreeReducedAir<F> {
+    width: usize,
+    preprocessed: Option<RowMajorMatrix<F>>,
+    num_public_values: usize,
+    /// The rewritten constraints, followed by the definitions of the auxiliary columns.
+    constraints: ExpressionArena<F>,
+    /// The auxiliary columns' values in terms of the original columns, for filling them in.
+    aux_columns: ExpressionArena<F>,
+}
+
+/// An error encountered while reducing the degree of an AIR's constraints.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DegreeReductionError {
+    /// The target degree is below 2, the lowest degree at which products can be split.
+    MaxDegreeTooSmall { max_degree: usize },
+    /// The AIR has permutation columns, which are not supported.
+    PermutationColumns,
+    /// Reducing a constraint would move a row selector into an auxiliary column, whose values
+    /// can't depend on which row they are in.
+    SelectorInAuxColumn { constraint: usize },
+}
+
+impl Display for DegreeReductionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MaxDegreeTooSmall { max_degree } => {
+                write!(f, "cannot reduce constraints to degree {max_degree} < 2")
+            }
+            Self::PermutationColumns => {
+                write!(f, "cannot reduce the degree of an AIR with permutation columns")
+            }
+            Self::SelectorInAuxColumn { constraint } => write!(
+                f,
+                "reducing constraint {constraint} would move a row selector into an auxiliary column"
+            ),
+        }
+    }
+}
+
+impl<F: Field> DegreeReducedAir<F> {
+    /// Rewrite the constraints of `air` to have degree at most `max_degree`, adding as few
+    /// auxiliary columns as the greedy rewriting finds. Constraints already within the bound are
+    /// kept as they are.
+    pub fn new<A>(air: &A, max_degree: usize) -> Result<Self, DegreeReductionError>
+    where
+        A: Air<SymbolicAirBuilder<F>>,
+    {
+        if max_degree < 2 {
+            return Err(DegreeReductionError::MaxDegreeTooSmall { max_degree });
+        }
+        if air.permutation_width() > 0 {
+            return Err(DegreeReductionError::PermutationColumns);
+        }
+        let width = air.width();
+        let preprocessed = air.preprocessed_trace();
+        let preprocessed_width = preprocessed.as_ref().map_or(0, Matrix::width);
+        let num_public_values = air.num_public_values();
+        let constraints = get_symbolic_constraints(air, preprocessed_width, num_public_values);
+
+        let mut reducer = Reducer {
+            width,
+            max_degree,
+            constraint: 0,
+            aux_columns: vec![],
+        };
+        let mut reduced = constraints
+            .iter()
+            .enumerate()
+            .map(|(constraint, expr)| {
+                reducer.constraint = constraint;
+                reducer.reduce(expr, max_degree)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        reduced.extend(
+            reducer
+                .aux_columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| reducer.aux_variable(i) - column.definition.clone()),
+        );
+
+        let definitions = reducer
+            .aux_columns
+            .iter()
+            .map(|column| column.definition.clone())
+            .collect::<Vec<_>>();
+        Ok(Self {
+            width,
+            preprocessed,
+            num_public_values,
+            constraints: ExpressionArena::new(&reduced, width, false),
+            aux_columns: ExpressionArena::new(&definitions, width, true),
+        })
+    }
+
+    /// The number of auxiliary columns appended to the original trace.
+    pub fn num_aux_columns(&self) -> usize {
+        self.aux_columns.roots.len()
+    }
+
+    /// Append the auxiliary columns to a trace of the original AIR.
+    pub fn extend_trace(
+        &self,
+        trace: &RowMajorMatrix<F>,
+        public_values: &[F],
+    ) -> RowMajorMatrix<F> {
+        assert_eq!(trace.width(), self.width, "trace width mismatch");
+        let height = trace.height();
+        let new_width = self.width + self.num_aux_columns();
+        let mut values = Vec::with_capacity(height * new_width);
+        for i in 0..height {
+            let i_next = (i + 1) % height;
+            let (local, next) = (trace.row_slice(i), trace.row_slice(i_next));
+            let preprocessed = self
+                .preprocessed
+                .as_ref()
+                .map(|preprocessed| (preprocessed.row_slice(i), preprocessed.row_slice(i_next)));
+            // The auxiliary columns don't depend on the selectors, but these are their values.
+            let selectors = [i == 0, i == height - 1, i != height - 1].map(F::from_bool);
+            let nodes = self.aux_columns.eval(
+                |entry, index| match entry {
+                    Entry::Main { offset: 0 } => local[index],
+                    Entry::Main { .. } => next[index],
+                    Entry::Preprocessed { offset } => {
+                        let (local, next) = preprocessed.as_ref().expect("no preprocessed trace");
+                        if offset == 0 {
+                            local[index]
+                        } else {
+                            next[index]
+                        }
+                    }
+                    Entry::Public => public_values[index],
+                    _ => unreachable!("permutation columns are not supported"),
+                },
+                selectors,
+            );
+            values.extend_from_slice(&local);
+            values.extend(self.aux_columns.roots.iter().map(|&root| nodes[root]));
+        }
+        RowMajorMatrix::new(values, new_width)
+    }
+}
+
+impl<F: Field> BaseAir<F> for DegreeReducedAir<F> {
+    fn width(&self) -> usize {
+        self.width + self.num_aux_columns()
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        self.preprocessed.clone()
+    }
+
+    fn num_public_values(&self) -> usize {
+        self.num_public_values
+    }
+}
+
+impl<F, AB> Air<AB> for DegreeReducedAir<F>
+where
+    F: Field,
+    AB: AirBuilderWithPublicValues<F = F> + PairBuilder,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let preprocessed = builder.preprocessed();
+        let public_values = builder
+            .public_values()
+            .iter()
+            .map(|&value| value.into())
+            .collect::<Vec<AB::Expr>>();
+        let (is_first_row, is_last_row, is_transition) = (
+            builder.is_first_row(),
+            builder.is_last_row(),
+            builder.is_transition(),
+        );
+
+        let nodes = self.constraints.eval(
+            |entry, index| match entry {
+                Entry::Main { offset } => main.row_slice(offset)[index].into(),
+                Entry::Preprocessed { offset } => preprocessed.row_slice(offset)[index].into(),
+                Entry::Public => public_values[index].clone(),
+                _ => unreachable!("permutation columns are not supported"),
+            },
+            [is_first_row, is_last_row, is_transition],
+        );
+        for &root in &self.constraints.roots {
+            builder.assert_zero(nodes[root].clone());
+        }
+    }
+}
+
+/// An auxiliary column, and the subexpression it replaces.
+struct AuxColumn<F> {
+    expr: SymbolicExpression<F>,
+    /// `expr`, itself reduced to the target degree.
+    definition: SymbolicExpression<F>,
+}
+
+struct Reducer<F> {
+    width: usize,
+    max_degree: usize,
+    /// The index of the constraint being reduced, for error reporting.
+    constraint: usize,
+    aux_columns: Vec<AuxColumn<F>>,
+}
+
+impl<F: Field> Reducer<F> {
+    /// Rewrite `expr` to have degree at most `limit`, which must be at least 2 for products.
+    fn reduce(
+        &mut self,
+        expr: &SymbolicExpression<F>,
+        limit: usize,
+    ) -> Result<SymbolicExpression<F>, DegreeReductionError> {
+        if expr.degree_multiple() <= limit {
+            return Ok(expr.clone());
+        }
+        Ok(match expr {
+            SymbolicExpression::Add { x, y, .. } => {
+                self.reduce(x, limit)? + self.reduce(y, limit)?
+            }
+            SymbolicExpression::Sub { x, y, .. } => {
+                self.reduce(x, limit)? - self.reduce(y, limit)?
+            }
+            SymbolicExpression::Neg { x, .. } => -self.reduce(x, limit)?,
+            SymbolicExpression::Mul { x, y, .. } => {
+                let (low, high) = if x.degree_multiple() <= y.degree_multiple() {
+                    (x, y)
+                } else {
+                    (y, x)
+                };
+                // Leave room for the higher degree operand to have degree at least one.
+                let low = self.reduce_or_replace(low, limit - 1)?;
+                let high = self.reduce_or_replace(high, limit - low.degree_multiple())?;
+                low * high
+            }
+            _ => unreachable!("leaves have degree at most 1"),
+        })
+    }
+
+    /// Like `reduce`, but replaces `expr` with an auxiliary column when `limit` is too small to
+    /// leave room for splitting it.
+    fn reduce_or_replace(
+        &mut self,
+        expr: &SymbolicExpression<F>,
+        limit: usize,
+    ) -> Result<SymbolicExpression<F>, DegreeReductionError> {
+        if expr.degree_multiple() <= limit {
+            Ok(expr.clone())
+        } else if limit < 2 {
+            self.replace(expr)
+        } else {
+            self.reduce(expr, limit)
+        }
+    }
+
+    /// Returns the auxiliary column for `expr`, adding one if there is none yet.
+    fn replace(
+        &mut self,
+        expr: &SymbolicExpression<F>,
+    ) -> Result<SymbolicExpression<F>, DegreeReductionError> {
+        if let Some(i) = self
+            .aux_columns
+            .iter()
+            .position(|column| same_expression(&column.expr, expr))
+        {
+            return Ok(self.aux_variable(i));
+        }
+        if has_selector(expr) {
+            return Err(DegreeReductionError::SelectorInAuxColumn {
+                constraint: self.constraint,
+            });
+        }
+        let definition = self.reduce(expr, self.max_degree)?;
+        self.aux_columns.push(AuxColumn {
+            expr: expr.clone(),
+            definition,
+        });
+        Ok(self.aux_variable(self.aux_columns.len() - 1))
+    }
+
+    fn aux_variable(&self, i: usize) -> SymbolicExpression<F> {
+        SymbolicVariable::new(Entry::Main { offset: 0 }, self.width + i).into()
+    }
+}
+
+/// Whether two expressions are structurally equal.
+fn same_expression<F: Field>(a: &SymbolicExpression<F>, b: &SymbolicExpression<F>) -> bool {
+    let same = |x: &Rc<SymbolicExpression<F>>, y: &Rc<SymbolicExpression<F>>| {
+        Rc::ptr_eq(x, y) || same_expression(x, y)
+    };
+    match (a, b) {
+        (SymbolicExpression::Variable(a), SymbolicExpression::Variable(b)) => {
+            a.entry == b.entry && a.index == b.index
+        }
+        (SymbolicExpression::IsFirstRow, SymbolicExpression::IsFirstRow)
+        | (SymbolicExpression::IsLastRow, SymbolicExpression::IsLastRow)
+        | (SymbolicExpression::IsTransition, SymbolicExpression::IsTransition) => true,
+        (SymbolicExpression::Constant(a), SymbolicExpression::Constant(b)) => a == b,
+        (
+            SymbolicExpression::Add { x: ax, y: ay, .. },
+            SymbolicExpression::Add { x: bx, y: by, .. },
+        )
+        | (
+            SymbolicExpression::Sub { x: ax, y: ay, .. },
+            SymbolicExpression::Sub { x: bx, y: by, .. },
+        )
+        | (
+            SymbolicExpression::Mul { x: ax, y: ay, .. },
+            SymbolicExpression::Mul { x: bx, y: by, .. },
+        ) => same(ax, bx) && same(ay, by),
+        (SymbolicExpression::Neg { x: a, .. }, SymbolicExpression::Neg { x: b, .. }) => same(a, b),
+        _ => false,
+    }
+}
+
+fn has_selector<F>(expr: &SymbolicExpression<F>) -> bool {
+    match expr {
+        SymbolicExpression::IsFirstRow
+        | SymbolicExpression::IsLastRow
+        | SymbolicExpression::IsTransition => true,
+        SymbolicExpression::Variable(_) | SymbolicExpression::Constant(_) => false,
+        SymbolicExpression::Add { x, y, .. }
+        | SymbolicExpression::Sub { x, y, .. }
+        | SymbolicExpression::Mul { x, y, .. } => has_selector(x) || has_selector(y),
+        SymbolicExpression::Neg { x, .. } => has_selector(x),
+    }
+}
+
+/// Expressions flattened into a list of nodes in topological order, which unlike
+/// `SymbolicExpression` can be shared between threads.
+#[derive(Clone, Debug)]
+struct ExpressionArena<F> {
+    nodes: Vec<ArenaNode<F>>,
+    /// The index of each expression's node.
+    roots: Vec<usize>,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum ArenaNode<F> {
+    Variable {
+        entry: Entry,
+        index: usize,
+    },
+    /// `IsFirstRow`, `IsLastRow` or `IsTransition`.
+    Selector(usize),
+    Constant(F),
+    Add(usize, usize),
+    Sub(usize, usize),
+    Neg(usize),
+    Mul(usize, usize),
+}
+
+impl<F: Field> ExpressionArena<F> {
+    /// Flatten `exprs`. With `inline_aux`, the `i`th expression may read auxiliary columns before
+    /// the `i`th, which are replaced by their expressions.
+    fn new(exprs: &[SymbolicExpression<F>], width: usize, inline_aux: bool) -> Self {
+        let mut arena = Self {
+            nodes: vec![],
+            roots: vec![],
+        };
+        let mut shared = HashMap::new();
+        for expr in exprs {
+            let root = arena.insert(expr, width, inline_aux, &mut shared);
+            arena.roots.push(root);
+        }
+        arena
+    }
+
+    fn insert(
+        &mut self,
+        expr: &SymbolicExpression<F>,
+        width: usize,
+        inline_aux: bool,
+        shared: &mut HashMap<*const SymbolicExpression<F>, usize>,
+    ) -> usize {
+        let node = match expr {
+            SymbolicExpression::Variable(v) => match v.entry {
+                Entry::Main { offset: 0 } if inline_aux && v.index >= width => {
+                    return self.roots[v.index - width];
+                }
+                entry => ArenaNode::Variable {
+                    entry,
+                    index: v.index,
+                },
+            },
+            SymbolicExpression::IsFirstRow => ArenaNode::Selector(0),
+            SymbolicExpression::IsLastRow => ArenaNode::Selector(1),
+            SymbolicExpression::IsTransition => ArenaNode::Selector(2),
+            SymbolicExpression::Constant(c) => ArenaNode::Constant(*c),
+            SymbolicExpression::Add { x, y, .. } => ArenaNode::Add(
+                self.insert_rc(x, width, inline_aux, shared),
+                self.insert_rc(y, width, inline_aux, shared),
+            ),
+            SymbolicExpression::Sub { x, y, .. } => ArenaNode::Sub(
+                self.insert_rc(x, width, inline_aux, shared),
+                self.insert_rc(y, width, inline_aux, shared),
+            ),
+            SymbolicExpression::Neg { x, .. } => {
+                ArenaNode::Neg(self.insert_rc(x, width, inline_aux, shared))
+            }
+            SymbolicExpression::Mul { x, y, .. } => ArenaNode::Mul(
+                self.insert_rc(x, width, inline_aux, shared),
+                self.insert_rc(y, width, inline_aux, shared),
+            ),
+        };
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    fn insert_rc(
+        &mut self,
+        expr: &Rc<SymbolicExpression<F>>,
+        width: usize,
+        inline_aux: bool,
+        shared: &mut HashMap<*const SymbolicExpression<F>, usize>,
+    ) -> usize {
+        if let Some(&index) = shared.get(&Rc::as_ptr(expr)) {
+            return index;
+        }
+        let index = self.insert(expr, width, inline_aux, shared);
+        shared.insert(Rc::as_ptr(expr), index);
+        index
+    }
+
+    /// Evaluate every node, given the values of variables and of the first row, last row and
+    /// transition selectors.
+    fn eval<E>(&self, variable: impl Fn(Entry, usize) -> E, selectors: [E; 3]) -> Vec<E>
+    where
+        E: FieldAlgebra + From<F>,
+    {
+        let mut values: Vec<E> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let value = match *node {
+                ArenaNode::Variable { entry, index } => variable(entry, index),
+                ArenaNode::Selector(i) => selectors[i].clone(),
+                ArenaNode::Constant(c) => c.into(),
+                ArenaNode::Add(x, y) => values[x].clone() + values[y].clone(),
+                ArenaNode::Sub(x, y) => values[x].clone() - values[y].clone(),
+                ArenaNode::Neg(x) => -values[x].clone(),
+                ArenaNode::Mul(x, y) => values[x].clone() * values[y].clone(),
+            };
+            values.push(value);
+        }
+        values
+    }
+}
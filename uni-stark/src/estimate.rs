@@ -0,0 +1,119 @@
+use alloc::vec;
+use core::mem::size_of;
+
+use p3_air::Air;
+use p3_commit::{HashCount, MatrixShape, PcsCostModel};
+use p3_field::FieldExtensionAlgebra;
+
+use crate::symbolic_builder::get_log_quotient_degree;
+use crate::{StarkGenericConfig, SymbolicAirBuilder, Val};
+
+/// The estimated shape, size and cost of a proof, as computed by [`estimate_proof`].
+///
+/// Sizes are in bytes, assuming a fixed-width serialization as described for
+/// [`MmcsCostModel`](p3_commit::MmcsCostModel). Hashing done by the challenger, such as
+/// proof-of-work grinding, isn't counted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofEstimate {
+    pub log_quotient_degree: usize,
+    /// The number of base field columns committed in each round.
+    pub preprocessed_width: usize,
+    pub main_width: usize,
+    pub permutation_width: usize,
+    pub quotient_width: usize,
+    /// The size of the commitments in the proof.
+    pub commitments_bytes: usize,
+    /// The size of the trace, permutation and quotient values opened at the out-of-domain points.
+    pub opened_values_bytes: usize,
+    /// The size of the opening proof's commit phase, e.g. FRI's commitments and their openings.
+    pub commit_phase_bytes: usize,
+    /// The size of the opening proof's openings of committed matrices, and their Merkle proofs.
+    pub input_proof_bytes: usize,
+    /// The prover's hashing, excluding the preprocessed commitment, which is made during setup.
+    pub prover_hashes: HashCount,
+    pub verifier_hashes: HashCount,
+}
+
+impl ProofEstimate {
+    pub const fn quotient_degree(&self) -> usize {
+        1 << self.log_quotient_degree
+    }
+
+    /// The estimated size of the whole proof.
+    pub const fn proof_bytes(&self) -> usize {
+        self.commitments_bytes
+            + self.opened_values_bytes
+            + self.commit_phase_bytes
+            + self.input_proof_bytes
+    }
+}
+
+/// Estimate the shape, size and cost of a proof of a trace of height `2^log_degree` for `air`.
+///
+/// This only needs the AIR's symbolic constraints and dimensions, so it's much cheaper than
+/// proving, and can be used to compare configurations.
+pub fn estimate_proof<SC, A>(config: &SC, air: &A, log_degree: usize) -> ProofEstimate
+where
+    SC: StarkGenericConfig,
+    SC::Pcs: PcsCostModel,
+    A: Air<SymbolicAirBuilder<Val<SC>>>,
+{
+    let preprocessed_width = air.preprocessed_trace().map_or(0, |trace| trace.width);
+    let log_quotient_degree = get_log_quotient_degree::<Val<SC>, A>(
+        air,
+        preprocessed_width,
+        air.num_public_values(),
+        SC::IS_ZK,
+    );
+    let quotient_degree = 1 << log_quotient_degree;
+    let challenge_degree = <SC::Challenge as FieldExtensionAlgebra<Val<SC>>>::D;
+    let main_width = air.width();
+    let permutation_width = air.permutation_width() * challenge_degree;
+
+    // Witness-dependent polynomials are randomized to twice their degree in zero-knowledge mode.
+    let log_witness_degree = log_degree + usize::from(SC::IS_ZK);
+    let shape = |log_degree, width, num_points| MatrixShape {
+        log_degree,
+        width,
+        num_points,
+    };
+    // The rounds in the order they're committed. Trace columns are opened at `zeta` and
+    // `zeta_next`, and quotient chunks at `zeta` only.
+    let mut rounds = vec![vec![shape(log_witness_degree, main_width, 2)]];
+    if permutation_width > 0 {
+        rounds.push(vec![shape(log_witness_degree, permutation_width, 2)]);
+    }
+    rounds.push(vec![
+        shape(log_witness_degree, challenge_degree, 1);
+        quotient_degree
+    ]);
+    if preprocessed_width > 0 {
+        rounds.push(vec![shape(log_degree, preprocessed_width, 2)]);
+    }
+    let pcs_cost = config.pcs().estimate_cost(&rounds);
+
+    let num_commitments = 2 + usize::from(permutation_width > 0);
+    let challenge_bytes = size_of::<SC::Challenge>();
+    let opened_values_bytes = challenge_bytes
+        * (2 * (main_width + preprocessed_width + permutation_width)
+            + quotient_degree * challenge_degree);
+    let num_witness_rounds = rounds.len() - usize::from(preprocessed_width > 0);
+    let commit_hashes: HashCount = pcs_cost.commit_hashes[..num_witness_rounds]
+        .iter()
+        .copied()
+        .sum();
+
+    ProofEstimate {
+        log_quotient_degree,
+        preprocessed_width,
+        main_width,
+        permutation_width,
+        quotient_width: quotient_degree * challenge_degree,
+        commitments_bytes: num_commitments * pcs_cost.commitment_bytes,
+        opened_values_bytes,
+        commit_phase_bytes: pcs_cost.commit_phase_bytes,
+        input_proof_bytes: pcs_cost.input_proof_bytes,
+        prover_hashes: commit_hashes + pcs_cost.open_hashes,
+        verifier_hashes: pcs_cost.verify_hashes,
+    }
+}
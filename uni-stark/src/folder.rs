@@ -1,6 +1,9 @@
 use alloc::vec::Vec;
 
-use p3_air::{AirBuilder, AirBuilderWithPublicValues};
+use p3_air::{
+    AirBuilder, AirBuilderWithPublicValues, ExtensionBuilder, MultiTableAirBuilder, PairBuilder,
+    PermutationAirBuilder,
+};
 use p3_field::FieldAlgebra;
 use p3_matrix::dense::RowMajorMatrixView;
 use p3_matrix::stack::VerticalPair;
@@ -9,7 +12,11 @@ use crate::{PackedChallenge, PackedVal, StarkGenericConfig, Val};
 
 #[derive(Debug)]
 pub struct ProverConstraintFolder<'a, SC: StarkGenericConfig> {
+    pub preprocessed: RowMajorMatrixView<'a, PackedVal<SC>>,
     pub main: RowMajorMatrixView<'a, PackedVal<SC>>,
+    pub permutation: RowMajorMatrixView<'a, PackedChallenge<SC>>,
+    pub permutation_challenges: &'a [PackedChallenge<SC>],
+    pub cumulative_sum: PackedChallenge<SC>,
     pub public_values: &'a Vec<Val<SC>>,
     pub is_first_row: PackedVal<SC>,
     pub is_last_row: PackedVal<SC>,
@@ -23,7 +30,11 @@ type ViewPair<'a, T> = VerticalPair<RowMajorMatrixView<'a, T>, RowMajorMatrixVie
 
 #[derive(Debug)]
 pub struct VerifierConstraintFolder<'a, SC: StarkGenericConfig> {
+    pub preprocessed: ViewPair<'a, SC::Challenge>,
     pub main: ViewPair<'a, SC::Challenge>,
+    pub permutation: ViewPair<'a, SC::Challenge>,
+    pub permutation_challenges: &'a [SC::Challenge],
+    pub cumulative_sum: SC::Challenge,
     pub public_values: &'a Vec<Val<SC>>,
     pub is_first_row: SC::Challenge,
     pub is_last_row: SC::Challenge,
@@ -80,6 +91,54 @@ impl<'a, SC: StarkGenericConfig> AirBuilderWithPublicValues for ProverConstraint
     }
 }
 
+impl<'a, SC: StarkGenericConfig> PairBuilder for ProverConstraintFolder<'a, SC> {
+    #[inline]
+    fn preprocessed(&self) -> Self::M {
+        self.preprocessed
+    }
+}
+
+impl<'a, SC: StarkGenericConfig> ExtensionBuilder for ProverConstraintFolder<'a, SC> {
+    type EF = SC::Challenge;
+    type ExprEF = PackedChallenge<SC>;
+    type VarEF = PackedChallenge<SC>;
+
+    #[inline]
+    fn assert_zero_ext<I>(&mut self, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        let x: PackedChallenge<SC> = x.into();
+        let alpha_power = self.alpha_powers[self.constraint_index];
+        self.accumulator += PackedChallenge::<SC>::from_f(alpha_power) * x;
+        self.constraint_index += 1;
+    }
+}
+
+impl<'a, SC: StarkGenericConfig> PermutationAirBuilder for ProverConstraintFolder<'a, SC> {
+    type MP = RowMajorMatrixView<'a, PackedChallenge<SC>>;
+    type RandomVar = PackedChallenge<SC>;
+
+    #[inline]
+    fn permutation(&self) -> Self::MP {
+        self.permutation
+    }
+
+    #[inline]
+    fn permutation_randomness(&self) -> &[Self::RandomVar] {
+        self.permutation_challenges
+    }
+}
+
+impl<'a, SC: StarkGenericConfig> MultiTableAirBuilder for ProverConstraintFolder<'a, SC> {
+    type Sum = PackedChallenge<SC>;
+
+    #[inline]
+    fn cumulative_sum(&self) -> Self::Sum {
+        self.cumulative_sum
+    }
+}
+
 impl<'a, SC: StarkGenericConfig> AirBuilder for VerifierConstraintFolder<'a, SC> {
     type F = Val<SC>;
     type Expr = SC::Challenge;
@@ -120,3 +179,43 @@ impl<'a, SC: StarkGenericConfig> AirBuilderWithPublicValues for VerifierConstrai
         self.public_values
     }
 }
+
+impl<'a, SC: StarkGenericConfig> PairBuilder for VerifierConstraintFolder<'a, SC> {
+    fn preprocessed(&self) -> Self::M {
+        self.preprocessed
+    }
+}
+
+impl<'a, SC: StarkGenericConfig> ExtensionBuilder for VerifierConstraintFolder<'a, SC> {
+    type EF = SC::Challenge;
+    type ExprEF = SC::Challenge;
+    type VarEF = SC::Challenge;
+
+    fn assert_zero_ext<I>(&mut self, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        self.assert_zero(x);
+    }
+}
+
+impl<'a, SC: StarkGenericConfig> PermutationAirBuilder for VerifierConstraintFolder<'a, SC> {
+    type MP = ViewPair<'a, SC::Challenge>;
+    type RandomVar = SC::Challenge;
+
+    fn permutation(&self) -> Self::MP {
+        self.permutation
+    }
+
+    fn permutation_randomness(&self) -> &[Self::RandomVar] {
+        self.permutation_challenges
+    }
+}
+
+impl<'a, SC: StarkGenericConfig> MultiTableAirBuilder for VerifierConstraintFolder<'a, SC> {
+    type Sum = SC::Challenge;
+
+    fn cumulative_sum(&self) -> Self::Sum {
+        self.cumulative_sum
+    }
+}
@@ -0,0 +1,521 @@
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use hashbrown::HashMap;
+use p3_air::{Air, BaseAir};
+use p3_challenger::CanObserve;
+use p3_commit::Pcs;
+use p3_field::{Field, FieldAlgebra};
+use p3_keccak::Keccak256Hash;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_symmetric::CryptographicHasher;
+use p3_util::log2_strict_usize;
+use serde::{Deserialize, Serialize};
+use tracing::{info_span, instrument};
+
+use crate::{
+    get_symbolic_constraints, Com, Entry, PcsProverData, ProverError, StarkGenericConfig,
+    SymbolicAirBuilder, SymbolicExpression, Val,
+};
+
+/// Prover-side data for an AIR which only needs to be computed once, and can then be reused for
+/// any number of proofs. See [`setup`].
+pub struct StarkProvingKey<SC: StarkGenericConfig> {
+    pub(crate) preprocessed: Option<PreprocessedProverData<SC>>,
+    pub(crate) verifying_key: StarkVerifyingKey<SC>,
+}
+
+/// The committed preprocessed trace, along with the PCS data needed to open it.
+pub struct PreprocessedProverData<SC: StarkGenericConfig> {
+    pub(crate) trace: RowMajorMatrix<Val<SC>>,
+    pub(crate) width: usize,
+    pub(crate) degree_bits: usize,
+    pub(crate) data: PcsProverData<SC>,
+}
+
+/// Verifier-side counterpart of [`StarkProvingKey`].
+///
+/// Besides the preprocessed commitment, the key records the PCS parameters and a fingerprint of
+/// the AIR. Provers and verifiers observe all of it before anything else (see
+/// [`StarkVerifyingKey::observe`]), and verifiers reject keys set up for another configuration or
+/// AIR than theirs, so a proof for one AIR or configuration is never accepted for another.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct StarkVerifyingKey<SC: StarkGenericConfig> {
+    pub(crate) pcs_parameters: Vec<usize>,
+    pub(crate) air: AirFingerprint,
+    pub(crate) preprocessed: Option<PreprocessedVerifierKey<Com<SC>>>,
+}
+
+/// The shape and constraints of an AIR, as recorded in a verifying key.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AirFingerprint {
+    pub(crate) width: usize,
+    pub(crate) num_public_values: usize,
+    /// A Keccak-256 digest of an encoding of the AIR's symbolic constraints, which is computed
+    /// once at setup, so that observing the key costs the same however many constraints there are.
+    pub(crate) constraints_digest: [u8; 32],
+}
+
+/// Commitment to the preprocessed trace, along with its dimensions.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PreprocessedVerifierKey<Com> {
+    pub(crate) width: usize,
+    pub(crate) degree_bits: usize,
+    pub(crate) commitment: Com,
+}
+
+impl<SC: StarkGenericConfig> StarkProvingKey<SC> {
+    /// The log2 of the trace height this key was set up for, or `None` if the AIR has no
+    /// preprocessed trace, in which case any height is supported.
+    pub fn degree_bits(&self) -> Option<usize> {
+        self.preprocessed.as_ref().map(|p| p.degree_bits)
+    }
+
+    pub fn preprocessed_width(&self) -> usize {
+        self.preprocessed.as_ref().map_or(0, |p| p.width)
+    }
+
+    pub fn preprocessed_trace(&self) -> Option<&RowMajorMatrix<Val<SC>>> {
+        self.preprocessed.as_ref().map(|p| &p.trace)
+    }
+
+    pub const fn verifying_key(&self) -> &StarkVerifyingKey<SC> {
+        &self.verifying_key
+    }
+}
+
+impl<SC: StarkGenericConfig> Clone for StarkVerifyingKey<SC> {
+    fn clone(&self) -> Self {
+        Self {
+            pcs_parameters: self.pcs_parameters.clone(),
+            air: self.air.clone(),
+            preprocessed: self.preprocessed.clone(),
+        }
+    }
+}
+
+impl<SC: StarkGenericConfig> StarkVerifyingKey<SC> {
+    /// The log2 of the trace height this key was set up for, or `None` if the AIR has no
+    /// preprocessed trace, in which case any height is supported.
+    pub fn degree_bits(&self) -> Option<usize> {
+        self.preprocessed.as_ref().map(|p| p.degree_bits)
+    }
+
+    pub fn preprocessed_width(&self) -> usize {
+        self.preprocessed.as_ref().map_or(0, |p| p.width)
+    }
+
+    pub fn preprocessed_commitment(&self) -> Option<&Com<SC>> {
+        self.preprocessed.as_ref().map(|p| &p.commitment)
+    }
+
+    pub fn pcs_parameters(&self) -> &[usize] {
+        &self.pcs_parameters
+    }
+
+    pub const fn air_fingerprint(&self) -> &AirFingerprint {
+        &self.air
+    }
+
+    /// The field elements observed by [`StarkVerifyingKey::observe`], which is followed by the
+    /// preprocessed commitment, if there is one.
+    pub fn instance_data(&self) -> Vec<Val<SC>> {
+        let mut data = vec![];
+        encode_usizes(&mut data, &self.pcs_parameters);
+        self.air.encode_into(&mut data);
+        match &self.preprocessed {
+            Some(p) => encode_usizes(&mut data, &[p.width, p.degree_bits]),
+            None => encode_usizes(&mut data, &[]),
+        }
+        data
+    }
+
+    /// Bind the challenger's transcript to this key.
+    pub fn observe(&self, challenger: &mut SC::Challenger) {
+        challenger.observe_slice(&self.instance_data());
+        if let Some(preprocessed) = &self.preprocessed {
+            challenger.observe(preprocessed.commitment.clone());
+        }
+    }
+}
+
+impl AirFingerprint {
+    pub fn new<F, A>(air: &A, preprocessed_width: usize) -> Self
+    where
+        F: Field,
+        A: Air<SymbolicAirBuilder<F>>,
+    {
+        let num_public_values = air.num_public_values();
+        let constraints =
+            get_symbolic_constraints::<F, A>(air, preprocessed_width, num_public_values);
+        let encoding = postcard::to_allocvec(&encode_constraints(&constraints))
+            .expect("unable to serialize constraints");
+        Self {
+            width: air.width(),
+            num_public_values,
+            constraints_digest: Keccak256Hash.hash_iter(encoding),
+        }
+    }
+
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    pub const fn num_public_values(&self) -> usize {
+        self.num_public_values
+    }
+
+    pub const fn constraints_digest(&self) -> &[u8; 32] {
+        &self.constraints_digest
+    }
+
+    /// Encode the dimensions, followed by the constraint digest as 16-bit limbs, which are
+    /// canonical in any field this crate supports.
+    fn encode_into<F: FieldAlgebra>(&self, data: &mut Vec<F>) {
+        encode_usizes(data, &[self.width, self.num_public_values]);
+        data.extend(
+            self.constraints_digest
+                .chunks_exact(2)
+                .map(|limb| F::from_canonical_u16(u16::from_le_bytes([limb[0], limb[1]]))),
+        );
+    }
+}
+
+/// Encode some sizes, prefixed by how many there are.
+fn encode_usizes<F: FieldAlgebra>(data: &mut Vec<F>, values: &[usize]) {
+    data.push(F::from_canonical_usize(values.len()));
+    data.extend(values.iter().map(|&value| F::from_canonical_usize(value)));
+}
+
+/// Encode symbolic constraints as field elements.
+///
+/// Each distinct subexpression is listed once, after its operands, which it refers to by their
+/// positions in the list, followed by the position of each constraint. The encoding only depends
+/// on the constraints as the AIR builds them, and not on how they are simplified or compiled for
+/// evaluation, so optimizing the quotient computation doesn't change any verifying keys.
+fn encode_constraints<F: Field>(constraints: &[SymbolicExpression<F>]) -> Vec<F> {
+    let mut encoder = ConstraintEncoder::default();
+    let roots = constraints
+        .iter()
+        .map(|constraint| encoder.insert(constraint))
+        .collect::<Vec<_>>();
+    let mut data = vec![F::from_canonical_usize(encoder.nodes.len())];
+    data.extend(encoder.nodes.into_iter().flatten());
+    encode_usizes(&mut data, &roots);
+    data
+}
+
+struct ConstraintEncoder<F> {
+    /// Each distinct subexpression, as a tag and two operands.
+    nodes: Vec<[F; 3]>,
+    node_indices: HashMap<[F; 3], usize>,
+    /// Indices of the nodes already encoded for subexpressions shared through an `Rc`.
+    shared: HashMap<*const SymbolicExpression<F>, usize>,
+}
+
+impl<F> Default for ConstraintEncoder<F> {
+    fn default() -> Self {
+        Self {
+            nodes: vec![],
+            node_indices: HashMap::new(),
+            shared: HashMap::new(),
+        }
+    }
+}
+
+impl<F: Field> ConstraintEncoder<F> {
+    fn insert(&mut self, expr: &SymbolicExpression<F>) -> usize {
+        let node = |tag: u32, a: usize, b: usize| {
+            [
+                F::from_canonical_u32(tag),
+                F::from_canonical_usize(a),
+                F::from_canonical_usize(b),
+            ]
+        };
+        let encoded = match expr {
+            SymbolicExpression::Variable(v) => match v.entry {
+                Entry::Preprocessed { offset } => node(0, offset, v.index),
+                Entry::Main { offset } => node(1, offset, v.index),
+                Entry::Permutation { offset } => node(2, offset, v.index),
+                Entry::Public => node(3, 0, v.index),
+                Entry::Challenge => node(4, 0, v.index),
+                Entry::CumulativeSum => node(5, 0, 0),
+            },
+            SymbolicExpression::IsFirstRow => node(6, 0, 0),
+            SymbolicExpression::IsLastRow => node(7, 0, 0),
+            SymbolicExpression::IsTransition => node(8, 0, 0),
+            SymbolicExpression::Constant(c) => [F::from_canonical_u32(9), *c, F::ZERO],
+            SymbolicExpression::Add { x, y, .. } => node(10, self.insert_rc(x), self.insert_rc(y)),
+            SymbolicExpression::Sub { x, y, .. } => node(11, self.insert_rc(x), self.insert_rc(y)),
+            SymbolicExpression::Neg { x, .. } => node(12, self.insert_rc(x), 0),
+            SymbolicExpression::Mul { x, y, .. } => node(13, self.insert_rc(x), self.insert_rc(y)),
+        };
+        if let Some(&index) = self.node_indices.get(&encoded) {
+            return index;
+        }
+        self.nodes.push(encoded);
+        self.node_indices.insert(encoded, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    fn insert_rc(&mut self, expr: &Rc<SymbolicExpression<F>>) -> usize {
+        let ptr = Rc::as_ptr(expr);
+        if let Some(&index) = self.shared.get(&ptr) {
+            return index;
+        }
+        let index = self.insert(expr);
+        self.shared.insert(ptr, index);
+        index
+    }
+}
+
+/// Commit to the AIR's preprocessed trace, if it has one.
+///
+/// The preprocessed trace fixes the height of every trace proven with the resulting keys. Returns
+/// an error if its height is zero or not a power of two.
+#[instrument(skip_all)]
+pub fn setup<SC, A>(
+    config: &SC,
+    air: &A,
+) -> Result<(StarkProvingKey<SC>, StarkVerifyingKey<SC>), ProverError>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>>,
+{
+    let pcs = config.pcs();
+    let preprocessed_trace = air.preprocessed_trace();
+    let air_fingerprint = AirFingerprint::new::<Val<SC>, _>(
+        air,
+        preprocessed_trace.as_ref().map_or(0, Matrix::width),
+    );
+    let Some(preprocessed_trace) = preprocessed_trace else {
+        let verifying_key = StarkVerifyingKey {
+            pcs_parameters: pcs.parameters(),
+            air: air_fingerprint,
+            preprocessed: None,
+        };
+        return Ok((
+            StarkProvingKey {
+                preprocessed: None,
+                verifying_key: verifying_key.clone(),
+            },
+            verifying_key,
+        ));
+    };
+
+    let width = preprocessed_trace.width();
+    let degree = preprocessed_trace.height();
+    let degree_bits = preprocessed_degree_bits(degree)?;
+
+    let domain = pcs.natural_domain_for_degree(degree);
+    let (commitment, data) = info_span!("commit to preprocessed trace")
+        .in_scope(|| pcs.commit(vec![(domain, preprocessed_trace.clone())]));
+
+    let vk = PreprocessedVerifierKey {
+        width,
+        degree_bits,
+        commitment,
+    };
+    let pk = PreprocessedProverData {
+        trace: preprocessed_trace,
+        width,
+        degree_bits,
+        data,
+    };
+    let verifying_key = StarkVerifyingKey {
+        pcs_parameters: pcs.parameters(),
+        air: air_fingerprint,
+        preprocessed: Some(vk),
+    };
+    Ok((
+        StarkProvingKey {
+            preprocessed: Some(pk),
+            verifying_key: verifying_key.clone(),
+        },
+        verifying_key,
+    ))
+}
+
+/// The log of a preprocessed trace's height, which must be a power of two.
+fn preprocessed_degree_bits(height: usize) -> Result<usize, ProverError> {
+    if !height.is_power_of_two() {
+        return Err(ProverError::InvalidTraceHeight { height });
+    }
+    Ok(log2_strict_usize(height))
+}
+
+/// Prover-side data for a batch of AIRs which are proven together. See [`setup_batch`].
+pub struct BatchProvingKey<SC: StarkGenericConfig> {
+    /// The preprocessed trace of each table, if it has one.
+    pub(crate) preprocessed_traces: Vec<Option<RowMajorMatrix<Val<SC>>>>,
+    /// The commitment to all preprocessed traces, which share a single round, along with the PCS
+    /// data needed to open it. `None` if no table has a preprocessed trace.
+    pub(crate) preprocessed: Option<(Com<SC>, PcsProverData<SC>)>,
+    pub(crate) verifying_key: BatchVerifyingKey<SC>,
+}
+
+/// Verifier-side counterpart of [`BatchProvingKey`].
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BatchVerifyingKey<SC: StarkGenericConfig> {
+    pub(crate) pcs_parameters: Vec<usize>,
+    pub(crate) airs: Vec<AirFingerprint>,
+    /// The dimensions of each table's preprocessed trace, if it has one.
+    pub(crate) preprocessed_dims: Vec<Option<PreprocessedDims>>,
+    pub(crate) preprocessed_commitment: Option<Com<SC>>,
+}
+
+/// The dimensions of one table's preprocessed trace within a batch.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct PreprocessedDims {
+    pub(crate) width: usize,
+    pub(crate) degree_bits: usize,
+}
+
+impl<SC: StarkGenericConfig> BatchProvingKey<SC> {
+    pub fn num_tables(&self) -> usize {
+        self.preprocessed_traces.len()
+    }
+
+    pub fn preprocessed_trace(&self, table: usize) -> Option<&RowMajorMatrix<Val<SC>>> {
+        self.preprocessed_traces[table].as_ref()
+    }
+
+    pub const fn verifying_key(&self) -> &BatchVerifyingKey<SC> {
+        &self.verifying_key
+    }
+}
+
+impl<SC: StarkGenericConfig> Clone for BatchVerifyingKey<SC> {
+    fn clone(&self) -> Self {
+        Self {
+            pcs_parameters: self.pcs_parameters.clone(),
+            airs: self.airs.clone(),
+            preprocessed_dims: self.preprocessed_dims.clone(),
+            preprocessed_commitment: self.preprocessed_commitment.clone(),
+        }
+    }
+}
+
+impl<SC: StarkGenericConfig> BatchVerifyingKey<SC> {
+    pub fn num_tables(&self) -> usize {
+        self.preprocessed_dims.len()
+    }
+
+    /// The log2 of the trace height the given table was set up for, or `None` if it has no
+    /// preprocessed trace, in which case any height is supported.
+    pub fn degree_bits(&self, table: usize) -> Option<usize> {
+        self.preprocessed_dims[table].map(|dims| dims.degree_bits)
+    }
+
+    pub fn preprocessed_width(&self, table: usize) -> usize {
+        self.preprocessed_dims[table].map_or(0, |dims| dims.width)
+    }
+
+    pub fn preprocessed_commitment(&self) -> Option<&Com<SC>> {
+        self.preprocessed_commitment.as_ref()
+    }
+
+    pub fn pcs_parameters(&self) -> &[usize] {
+        &self.pcs_parameters
+    }
+
+    pub fn air_fingerprint(&self, table: usize) -> &AirFingerprint {
+        &self.airs[table]
+    }
+
+    /// The field elements observed by [`BatchVerifyingKey::observe`], which is followed by the
+    /// preprocessed commitment, if there is one.
+    pub fn instance_data(&self) -> Vec<Val<SC>> {
+        let mut data = vec![];
+        encode_usizes(&mut data, &self.pcs_parameters);
+        data.push(Val::<SC>::from_canonical_usize(self.num_tables()));
+        for (air, dims) in self.airs.iter().zip(&self.preprocessed_dims) {
+            air.encode_into(&mut data);
+            match dims {
+                Some(dims) => encode_usizes(&mut data, &[dims.width, dims.degree_bits]),
+                None => encode_usizes(&mut data, &[]),
+            }
+        }
+        data
+    }
+
+    /// Bind the challenger's transcript to this key.
+    pub fn observe(&self, challenger: &mut SC::Challenger) {
+        challenger.observe_slice(&self.instance_data());
+        if let Some(commitment) = &self.preprocessed_commitment {
+            challenger.observe(commitment.clone());
+        }
+    }
+}
+
+/// Commit to the preprocessed traces of a batch of AIRs, all in a single round.
+///
+/// As with [`setup`], each preprocessed trace fixes the height of the corresponding table, and
+/// an error is returned if a preprocessed trace's height is zero or not a power of two.
+#[instrument(skip_all)]
+pub fn setup_batch<SC, A>(
+    config: &SC,
+    airs: &[A],
+) -> Result<(BatchProvingKey<SC>, BatchVerifyingKey<SC>), ProverError>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>>,
+{
+    let preprocessed_traces = airs
+        .iter()
+        .map(BaseAir::preprocessed_trace)
+        .collect::<Vec<_>>();
+    let preprocessed_dims = preprocessed_traces
+        .iter()
+        .enumerate()
+        .map(|(table, trace)| {
+            trace
+                .as_ref()
+                .map(|trace| {
+                    Ok(PreprocessedDims {
+                        width: trace.width(),
+                        degree_bits: preprocessed_degree_bits(trace.height())
+                            .map_err(|error| ProverError::in_table(table, error))?,
+                    })
+                })
+                .transpose()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let air_fingerprints = airs
+        .iter()
+        .zip(&preprocessed_dims)
+        .map(|(air, dims)| {
+            AirFingerprint::new::<Val<SC>, _>(air, dims.map_or(0, |dims| dims.width))
+        })
+        .collect();
+
+    let pcs = config.pcs();
+    let domains_and_traces = preprocessed_traces
+        .iter()
+        .flatten()
+        .map(|trace| (pcs.natural_domain_for_degree(trace.height()), trace.clone()))
+        .collect::<Vec<_>>();
+    let preprocessed = (!domains_and_traces.is_empty()).then(|| {
+        info_span!("commit to preprocessed traces").in_scope(|| pcs.commit(domains_and_traces))
+    });
+    let preprocessed_commitment = preprocessed.as_ref().map(|(commit, _)| commit.clone());
+
+    let verifying_key = BatchVerifyingKey {
+        pcs_parameters: pcs.parameters(),
+        airs: air_fingerprints,
+        preprocessed_dims,
+        preprocessed_commitment,
+    };
+    Ok((
+        BatchProvingKey {
+            preprocessed_traces,
+            preprocessed,
+            verifying_key: verifying_key.clone(),
+        },
+        verifying_key,
+    ))
+}
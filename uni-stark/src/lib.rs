@@ -4,25 +4,38 @@
 
 extern crate alloc;
 
+mod batch_prover;
+mod batch_verifier;
+mod check_constraints;
 mod config;
+mod constraint_dag;
+mod degree_reduction;
+mod estimate;
 mod folder;
+mod keys;
 mod proof;
 mod prover;
+mod soundness;
 mod symbolic_builder;
 mod symbolic_expression;
 mod symbolic_variable;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
 mod verifier;
 mod zerofier_coset;
 
-#[cfg(debug_assertions)]
-mod check_constraints;
-
-#[cfg(debug_assertions)]
+pub use batch_prover::*;
+pub use batch_verifier::*;
 pub use check_constraints::*;
 pub use config::*;
+pub use constraint_dag::*;
+pub use degree_reduction::*;
+pub use estimate::*;
 pub use folder::*;
+pub use keys::*;
 pub use proof::*;
 pub use prover::*;
+pub use soundness::*;
 pub use symbolic_builder::*;
 pub use symbolic_expression::*;
 pub use symbolic_variable::*;
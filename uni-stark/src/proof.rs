@@ -1,18 +1,9 @@
 use alloc::vec::Vec;
 
-use p3_commit::Pcs;
+use p3_commit::{decode_proof, encode_proof, ProofFormatError};
 use serde::{Deserialize, Serialize};
 
-use crate::StarkGenericConfig;
-
-type Com<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<
-    <SC as StarkGenericConfig>::Challenge,
-    <SC as StarkGenericConfig>::Challenger,
->>::Commitment;
-type PcsProof<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<
-    <SC as StarkGenericConfig>::Challenge,
-    <SC as StarkGenericConfig>::Challenger,
->>::Proof;
+use crate::{Com, PcsProof, StarkGenericConfig};
 
 #[derive(Serialize, Deserialize)]
 #[serde(bound = "")]
@@ -23,15 +14,119 @@ pub struct Proof<SC: StarkGenericConfig> {
     pub(crate) degree_bits: usize,
 }
 
+impl<SC: StarkGenericConfig> Proof<SC> {
+    pub const fn commitments(&self) -> &Commitments<Com<SC>> {
+        &self.commitments
+    }
+
+    /// The values of the committed polynomials opened at the out-of-domain point.
+    pub const fn opened_values(&self) -> &OpenedValues<SC::Challenge> {
+        &self.opened_values
+    }
+
+    pub const fn opening_proof(&self) -> &PcsProof<SC> {
+        &self.opening_proof
+    }
+
+    pub const fn degree_bits(&self) -> usize {
+        self.degree_bits
+    }
+
+    /// Encode the proof with [`encode_proof`], tagged with the fingerprint of `config`.
+    pub fn to_bytes(&self, config: &SC) -> Result<Vec<u8>, postcard::Error> {
+        encode_proof(&config.fingerprint(), self)
+    }
+
+    /// Decode a proof encoded by [`Self::to_bytes`], checking that it was made under a
+    /// configuration with the same fingerprint as `config`.
+    pub fn from_bytes(config: &SC, bytes: &[u8]) -> Result<Self, ProofFormatError> {
+        decode_proof(&config.fingerprint(), bytes)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Commitments<Com> {
     pub(crate) trace: Com,
+    /// Commitment to the permutation trace, if the AIR has one.
+    pub(crate) permutation: Option<Com>,
     pub(crate) quotient_chunks: Com,
 }
 
+impl<Com> Commitments<Com> {
+    pub const fn trace(&self) -> &Com {
+        &self.trace
+    }
+
+    pub const fn permutation(&self) -> Option<&Com> {
+        self.permutation.as_ref()
+    }
+
+    pub const fn quotient_chunks(&self) -> &Com {
+        &self.quotient_chunks
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OpenedValues<Challenge> {
+    /// Openings of the preprocessed trace; empty if the AIR has no preprocessed columns.
+    pub(crate) preprocessed_local: Vec<Challenge>,
+    pub(crate) preprocessed_next: Vec<Challenge>,
     pub(crate) trace_local: Vec<Challenge>,
     pub(crate) trace_next: Vec<Challenge>,
+    /// Openings of the flattened permutation trace, i.e. `D` base field columns for each extension
+    /// field column; empty if the AIR has no permutation trace.
+    pub(crate) permutation_local: Vec<Challenge>,
+    pub(crate) permutation_next: Vec<Challenge>,
     pub(crate) quotient_chunks: Vec<Vec<Challenge>>,
 }
+
+impl<Challenge> OpenedValues<Challenge> {
+    pub fn preprocessed_local(&self) -> &[Challenge] {
+        &self.preprocessed_local
+    }
+
+    pub fn preprocessed_next(&self) -> &[Challenge] {
+        &self.preprocessed_next
+    }
+
+    pub fn trace_local(&self) -> &[Challenge] {
+        &self.trace_local
+    }
+
+    pub fn trace_next(&self) -> &[Challenge] {
+        &self.trace_next
+    }
+
+    /// Openings of each quotient chunk, i.e. `D` base field columns per chunk.
+    pub fn quotient_chunks(&self) -> &[Vec<Challenge>] {
+        &self.quotient_chunks
+    }
+}
+
+/// A proof of a batch of tables, whose traces are committed together and which may interact with
+/// one another through their permutation arguments.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BatchProof<SC: StarkGenericConfig> {
+    /// Commitments shared by all tables; each round holds one matrix (or, for quotients, one
+    /// matrix per chunk) for each table which has data in that round.
+    pub(crate) commitments: Commitments<Com<SC>>,
+    pub(crate) opened_values: Vec<OpenedValues<SC::Challenge>>,
+    /// The claimed cumulative sum of each table's permutation argument; these must add up to zero.
+    pub(crate) cumulative_sums: Vec<SC::Challenge>,
+    pub(crate) opening_proof: PcsProof<SC>,
+    pub(crate) degree_bits: Vec<usize>,
+}
+
+impl<SC: StarkGenericConfig> BatchProof<SC> {
+    /// Encode the proof with [`encode_proof`], tagged with the fingerprint of `config`.
+    pub fn to_bytes(&self, config: &SC) -> Result<Vec<u8>, postcard::Error> {
+        encode_proof(&config.fingerprint(), self)
+    }
+
+    /// Decode a proof encoded by [`Self::to_bytes`], checking that it was made under a
+    /// configuration with the same fingerprint as `config`.
+    pub fn from_bytes(config: &SC, bytes: &[u8]) -> Result<Self, ProofFormatError> {
+        decode_proof(&config.fingerprint(), bytes)
+    }
+}
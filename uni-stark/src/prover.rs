@@ -1,27 +1,38 @@
+use alloc::boxed::Box;
 use alloc::vec;
 use alloc::vec::Vec;
 
-use itertools::{izip, Itertools};
-use p3_air::Air;
+use itertools::Itertools;
+use p3_air::{Air, BaseAir};
 use p3_challenger::{CanObserve, CanSample, FieldChallenger};
 use p3_commit::{Pcs, PolynomialSpace};
-use p3_field::{FieldAlgebra, FieldExtensionAlgebra, PackedValue};
+use p3_field::{ExtensionField, Field, FieldAlgebra, FieldExtensionAlgebra, PackedValue};
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::Matrix;
 use p3_maybe_rayon::prelude::*;
-use p3_util::{log2_ceil_usize, log2_strict_usize};
+use p3_util::log2_strict_usize;
 use tracing::{info_span, instrument};
 
+use crate::symbolic_builder::log_quotient_degree;
 use crate::{
-    get_symbolic_constraints, Commitments, Domain, OpenedValues, PackedChallenge, PackedVal, Proof,
-    ProverConstraintFolder, StarkGenericConfig, SymbolicAirBuilder, SymbolicExpression, Val,
+    get_symbolic_constraints, setup, Com, Commitments, ConstraintDag, Domain, OpenedValues,
+    PackedChallenge, PackedVal, PcsProverData, Proof, ProverConstraintFolder, StarkGenericConfig,
+    StarkProvingKey, SymbolicAirBuilder, SymbolicExpression, Val,
 };
 
+/// Prove that `trace` satisfies `air`.
+///
+/// Returns an error if the trace or public values don't have the shape `air` expects. In debug
+/// builds, the constraints are also checked up front, and the first failure is reported.
+///
+/// This runs [`setup`] internally, committing to the AIR's preprocessed trace (if any) on every
+/// call. When proving many traces for the same AIR, prefer calling [`setup`] once and then
+/// [`prove_with_key`].
 #[instrument(skip_all)]
 #[allow(clippy::multiple_bound_locations)] // cfg not supported in where clauses?
 pub fn prove<
     SC,
-    #[cfg(debug_assertions)] A: for<'a> Air<crate::check_constraints::DebugConstraintBuilder<'a, Val<SC>>>,
+    #[cfg(debug_assertions)] A: for<'a> Air<crate::check_constraints::DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>,
     #[cfg(not(debug_assertions))] A,
 >(
     config: &SC,
@@ -29,65 +40,163 @@ pub fn prove<
     challenger: &mut SC::Challenger,
     trace: RowMajorMatrix<Val<SC>>,
     public_values: &Vec<Val<SC>>,
-) -> Proof<SC>
+) -> Result<Proof<SC>, ProverError>
 where
     SC: StarkGenericConfig,
     A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<ProverConstraintFolder<'a, SC>>,
 {
-    #[cfg(debug_assertions)]
-    crate::check_constraints::check_constraints(air, &trace, public_values);
+    let (proving_key, _) = setup(config, air)?;
+    prove_with_key(config, &proving_key, air, challenger, trace, public_values)
+}
 
+/// Prove that `trace` satisfies `air`, using a proving key previously produced by [`setup`].
+#[instrument(skip_all)]
+#[allow(clippy::multiple_bound_locations)] // cfg not supported in where clauses?
+pub fn prove_with_key<
+    SC,
+    #[cfg(debug_assertions)] A: for<'a> Air<crate::check_constraints::DebugConstraintBuilder<'a, Val<SC>, SC::Challenge>>,
+    #[cfg(not(debug_assertions))] A,
+>(
+    config: &SC,
+    proving_key: &StarkProvingKey<SC>,
+    air: &A,
+    challenger: &mut SC::Challenger,
+    trace: RowMajorMatrix<Val<SC>>,
+    public_values: &Vec<Val<SC>>,
+) -> Result<Proof<SC>, ProverError>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<ProverConstraintFolder<'a, SC>>,
+{
+    let preprocessed = proving_key.preprocessed.as_ref();
+    let log_degree = check_trace_shape(
+        air,
+        &trace,
+        preprocessed.map(|p| p.degree_bits),
+        public_values,
+    )?;
     let degree = trace.height();
-    let log_degree = log2_strict_usize(degree);
+    let preprocessed_width = proving_key.preprocessed_width();
+    let permutation_width = air.permutation_width();
+
+    // Without a permutation trace, all constraints can be checked up front. Otherwise we must wait
+    // until the permutation challenges have been sampled.
+    #[cfg(debug_assertions)]
+    if permutation_width == 0 {
+        crate::check_constraints::check_constraints::<_, SC::Challenge, _>(
+            air,
+            proving_key.preprocessed_trace(),
+            &trace,
+            None,
+            &[],
+            SC::Challenge::ZERO,
+            public_values,
+        )?;
+    }
 
-    let symbolic_constraints = get_symbolic_constraints::<Val<SC>, A>(air, 0, public_values.len());
-    let constraint_count = symbolic_constraints.len();
+    let symbolic_constraints =
+        get_symbolic_constraints::<Val<SC>, A>(air, preprocessed_width, public_values.len());
+    let constraint_dag = air
+        .compile_constraints()
+        .then(|| ConstraintDag::new(&symbolic_constraints));
     let constraint_degree = symbolic_constraints
         .iter()
         .map(SymbolicExpression::degree_multiple)
         .max()
         .unwrap_or(0);
-    let log_quotient_degree = log2_ceil_usize(constraint_degree - 1);
+    let log_quotient_degree = log_quotient_degree(constraint_degree, SC::IS_ZK);
     let quotient_degree = 1 << log_quotient_degree;
 
     let pcs = config.pcs();
+    check_blowup::<SC>(pcs, log_quotient_degree, preprocessed.is_some())?;
     let trace_domain = pcs.natural_domain_for_degree(degree);
 
-    let (trace_commit, trace_data) =
-        info_span!("commit to trace data").in_scope(|| pcs.commit(vec![(trace_domain, trace)]));
+    // The main trace is needed again after it's committed, to generate the permutation trace.
+    let main_trace = (permutation_width > 0).then(|| trace.clone());
 
-    // Observe the instance.
+    let (trace_commit, trace_data) = info_span!("commit to trace data")
+        .in_scope(|| commit_witness::<SC>(pcs, vec![(trace_domain, trace)]))?;
+
+    // Observe the instance, starting with the verifying key.
+    proving_key.verifying_key.observe(challenger);
     challenger.observe(Val::<SC>::from_canonical_usize(log_degree));
-    // TODO: Might be best practice to include other instance data here; see verifier comment.
 
     challenger.observe(trace_commit.clone());
     challenger.observe_slice(public_values);
+
+    let mut permutation_challenges = vec![];
+    let permutation = if let Some(main_trace) = main_trace {
+        permutation_challenges = (0..air.num_permutation_challenges())
+            .map(|_| challenger.sample_ext_element())
+            .collect();
+        let permutation_trace = info_span!("generate permutation trace").in_scope(|| {
+            generate_permutation_trace(
+                air,
+                proving_key.preprocessed_trace(),
+                &main_trace,
+                &permutation_challenges,
+            )
+        })?;
+
+        #[cfg(debug_assertions)]
+        crate::check_constraints::check_constraints(
+            air,
+            proving_key.preprocessed_trace(),
+            &main_trace,
+            Some(&permutation_trace),
+            &permutation_challenges,
+            SC::Challenge::ZERO,
+            public_values,
+        )?;
+
+        let (permutation_commit, permutation_data) = info_span!("commit to permutation trace")
+            .in_scope(|| {
+                commit_witness::<SC>(
+                    pcs,
+                    vec![(trace_domain, permutation_trace.flatten_to_base())],
+                )
+            })?;
+        challenger.observe(permutation_commit.clone());
+        Some((permutation_commit, permutation_data))
+    } else {
+        None
+    };
+
     let alpha: SC::Challenge = challenger.sample_ext_element();
 
     let quotient_domain =
         trace_domain.create_disjoint_domain(1 << (log_degree + log_quotient_degree));
 
     let trace_on_quotient_domain = pcs.get_evaluations_on_domain(&trace_data, 0, quotient_domain);
+    let preprocessed_on_quotient_domain = preprocessed
+        .map(|preprocessed| pcs.get_evaluations_on_domain(&preprocessed.data, 0, quotient_domain));
+    let permutation_on_quotient_domain = permutation
+        .as_ref()
+        .map(|(_, data)| pcs.get_evaluations_on_domain(data, 0, quotient_domain));
 
-    let quotient_values = quotient_values(
+    let quotient_values = quotient_values::<SC, _, _>(
         air,
+        constraint_dag.as_ref(),
+        symbolic_constraints.len(),
         public_values,
         trace_domain,
         quotient_domain,
+        preprocessed_on_quotient_domain,
         trace_on_quotient_domain,
+        permutation_on_quotient_domain,
+        &permutation_challenges,
+        SC::Challenge::ZERO,
         alpha,
-        constraint_count,
     );
     let quotient_flat = RowMajorMatrix::new_col(quotient_values).flatten_to_base();
-    let quotient_chunks = quotient_domain.split_evals(quotient_degree, quotient_flat);
-    let qc_domains = quotient_domain.split_domains(quotient_degree);
 
     let (quotient_commit, quotient_data) = info_span!("commit to quotient poly chunks")
-        .in_scope(|| pcs.commit(izip!(qc_domains, quotient_chunks).collect_vec()));
+        .in_scope(|| pcs.commit_quotients(vec![(quotient_domain, quotient_flat, quotient_degree)]));
     challenger.observe(quotient_commit.clone());
 
     let commitments = Commitments {
         trace: trace_commit,
+        permutation: permutation.as_ref().map(|(commit, _)| commit.clone()),
         quotient_chunks: quotient_commit,
     };
 
@@ -95,43 +204,235 @@ where
     let zeta_next = trace_domain.next_point(zeta).unwrap();
 
     let (opened_values, opening_proof) = info_span!("open").in_scope(|| {
-        pcs.open(
-            vec![
-                (&trace_data, vec![vec![zeta, zeta_next]]),
-                (
-                    &quotient_data,
-                    // open every chunk at zeta
-                    (0..quotient_degree).map(|_| vec![zeta]).collect_vec(),
-                ),
-            ],
-            challenger,
-        )
+        let mut rounds = vec![
+            (&trace_data, vec![vec![zeta, zeta_next]]),
+            (
+                &quotient_data,
+                // open every chunk at zeta
+                (0..quotient_degree).map(|_| vec![zeta]).collect_vec(),
+            ),
+        ];
+        if let Some(preprocessed) = preprocessed {
+            rounds.push((&preprocessed.data, vec![vec![zeta, zeta_next]]));
+        }
+        if let Some((_, permutation_data)) = &permutation {
+            rounds.push((permutation_data, vec![vec![zeta, zeta_next]]));
+        }
+        pcs.open(rounds, challenger)
     });
     let trace_local = opened_values[0][0][0].clone();
     let trace_next = opened_values[0][0][1].clone();
     let quotient_chunks = opened_values[1].iter().map(|v| v[0].clone()).collect_vec();
+    // The optional rounds follow, in the order they were pushed above.
+    let mut optional_rounds = opened_values[2..].iter();
+    let mut local_and_next = |present: bool| {
+        if present {
+            let round = optional_rounds.next().unwrap();
+            (round[0][0].clone(), round[0][1].clone())
+        } else {
+            (vec![], vec![])
+        }
+    };
+    let (preprocessed_local, preprocessed_next) = local_and_next(preprocessed.is_some());
+    let (permutation_local, permutation_next) = local_and_next(permutation.is_some());
     let opened_values = OpenedValues {
+        preprocessed_local,
+        preprocessed_next,
         trace_local,
         trace_next,
+        permutation_local,
+        permutation_next,
         quotient_chunks,
     };
-    Proof {
+    Ok(Proof {
         commitments,
         opened_values,
         opening_proof,
         degree_bits: log_degree,
+    })
+}
+
+/// An error encountered while generating a proof, typically due to an invalid witness.
+#[derive(Debug)]
+pub enum ProverError {
+    /// The height of the trace, or of the AIR's preprocessed trace, is zero or not a power of two.
+    InvalidTraceHeight { height: usize },
+    /// The trace height doesn't match the height the proving key was set up for.
+    TraceHeightMismatch { expected: usize, actual: usize },
+    /// The trace width doesn't match `BaseAir::width`.
+    TraceWidthMismatch { expected: usize, actual: usize },
+    /// The number of public values doesn't match `BaseAir::num_public_values`.
+    PublicValuesLengthMismatch { expected: usize, actual: usize },
+    /// A constraint had a nonzero value on some row. Constraints are only checked in debug builds.
+    ConstraintFailure {
+        row: usize,
+        /// The index of the constraint, in the order the AIR asserts its constraints.
+        constraint: usize,
+        /// The label the AIR gave the constraint, if any. See `AirBuilder::label`.
+        label: Option<&'static str>,
+    },
+    /// A batch was given a different number of traces or sets of public values than AIRs, or a
+    /// proving key set up for a different number of AIRs.
+    BatchSizeMismatch { expected: usize, actual: usize },
+    /// The cumulative sums of the tables in a batch don't add up to zero, meaning some interaction
+    /// between tables is unbalanced. This is only checked in debug builds.
+    NonzeroCumulativeSum,
+    /// The PCS's blowup is too small to evaluate the traces over the quotient domain, which the
+    /// constraint degree requires to be `2^log_quotient_degree` times as large as the trace domain.
+    InsufficientBlowup {
+        log_quotient_degree: usize,
+        max_log_quotient_degree: usize,
+    },
+    /// The PCS claims to hide, but doesn't support `Pcs::commit_randomized`.
+    RandomizedCommitmentUnsupported,
+    /// The AIR has a nonzero `BaseAir::permutation_width`, but generated no permutation trace.
+    MissingPermutationTrace,
+    /// The permutation trace width doesn't match `BaseAir::permutation_width`.
+    PermutationTraceWidthMismatch { expected: usize, actual: usize },
+    /// The permutation trace height doesn't match the main trace height.
+    PermutationTraceHeightMismatch { expected: usize, actual: usize },
+    /// An error in one of the tables of a batch.
+    Table {
+        table: usize,
+        error: Box<ProverError>,
+    },
+}
+
+impl ProverError {
+    pub(crate) fn in_table(table: usize, error: Self) -> Self {
+        Self::Table {
+            table,
+            error: Box::new(error),
+        }
     }
 }
 
+/// Commit to witness-dependent polynomials, randomizing them in zero-knowledge mode.
+pub(crate) fn commit_witness<SC: StarkGenericConfig>(
+    pcs: &SC::Pcs,
+    evaluations: Vec<(Domain<SC>, RowMajorMatrix<Val<SC>>)>,
+) -> Result<(Com<SC>, PcsProverData<SC>), ProverError> {
+    if SC::IS_ZK {
+        pcs.commit_randomized(evaluations)
+            .ok_or(ProverError::RandomizedCommitmentUnsupported)
+    } else {
+        Ok(pcs.commit(evaluations))
+    }
+}
+
+/// Generate the permutation trace of an AIR with a nonzero permutation width, checking that it has
+/// the expected dimensions.
+pub(crate) fn generate_permutation_trace<F, EF, A>(
+    air: &A,
+    preprocessed: Option<&RowMajorMatrix<F>>,
+    main: &RowMajorMatrix<F>,
+    challenges: &[EF],
+) -> Result<RowMajorMatrix<EF>, ProverError>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    A: BaseAir<F>,
+{
+    let permutation_trace = air
+        .generate_permutation_trace(preprocessed, main, challenges)
+        .ok_or(ProverError::MissingPermutationTrace)?;
+    let expected_width = air.permutation_width();
+    if permutation_trace.width() != expected_width {
+        return Err(ProverError::PermutationTraceWidthMismatch {
+            expected: expected_width,
+            actual: permutation_trace.width(),
+        });
+    }
+    if permutation_trace.height() != main.height() {
+        return Err(ProverError::PermutationTraceHeightMismatch {
+            expected: main.height(),
+            actual: permutation_trace.height(),
+        });
+    }
+    Ok(permutation_trace)
+}
+
+/// Check that the PCS can evaluate the committed traces over a quotient domain
+/// `2^log_quotient_degree` times as large as the trace domain.
+///
+/// In zero-knowledge mode the witness traces are randomized to twice their degree, so their LDEs
+/// are twice as tall, but preprocessed traces aren't randomized.
+pub(crate) fn check_blowup<SC: StarkGenericConfig>(
+    pcs: &SC::Pcs,
+    log_quotient_degree: usize,
+    has_preprocessed: bool,
+) -> Result<(), ProverError> {
+    let Some(log_blowup) = pcs.log_blowup() else {
+        return Ok(());
+    };
+    let max_log_quotient_degree = log_blowup + usize::from(SC::IS_ZK && !has_preprocessed);
+    if log_quotient_degree > max_log_quotient_degree {
+        return Err(ProverError::InsufficientBlowup {
+            log_quotient_degree,
+            max_log_quotient_degree,
+        });
+    }
+    Ok(())
+}
+
+/// Check that `trace` and `public_values` have the dimensions expected by `air`, returning the log2
+/// of the trace height.
+pub(crate) fn check_trace_shape<F, A>(
+    air: &A,
+    trace: &RowMajorMatrix<F>,
+    expected_degree_bits: Option<usize>,
+    public_values: &[F],
+) -> Result<usize, ProverError>
+where
+    F: Send + Sync + Clone,
+    A: BaseAir<F>,
+{
+    let height = trace.height();
+    if !height.is_power_of_two() {
+        return Err(ProverError::InvalidTraceHeight { height });
+    }
+    let log_degree = log2_strict_usize(height);
+    if let Some(expected) = expected_degree_bits {
+        if expected != log_degree {
+            return Err(ProverError::TraceHeightMismatch {
+                expected: 1 << expected,
+                actual: height,
+            });
+        }
+    }
+    if trace.width() != air.width() {
+        return Err(ProverError::TraceWidthMismatch {
+            expected: air.width(),
+            actual: trace.width(),
+        });
+    }
+    if public_values.len() != air.num_public_values() {
+        return Err(ProverError::PublicValuesLengthMismatch {
+            expected: air.num_public_values(),
+            actual: public_values.len(),
+        });
+    }
+    Ok(log_degree)
+}
+
+/// The number of packed quotient domain points at which constraints are evaluated at once.
+const QUOTIENT_BATCH_SIZE: usize = 16;
+
 #[instrument(name = "compute quotient polynomial", skip_all)]
-fn quotient_values<SC, A, Mat>(
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn quotient_values<SC, A, Mat>(
     air: &A,
+    constraint_dag: Option<&ConstraintDag<Val<SC>>>,
+    constraint_count: usize,
     public_values: &Vec<Val<SC>>,
     trace_domain: Domain<SC>,
     quotient_domain: Domain<SC>,
+    preprocessed_on_quotient_domain: Option<Mat>,
     trace_on_quotient_domain: Mat,
+    permutation_on_quotient_domain: Option<Mat>,
+    permutation_challenges: &[SC::Challenge],
+    cumulative_sum: SC::Challenge,
     alpha: SC::Challenge,
-    constraint_count: usize,
 ) -> Vec<SC::Challenge>
 where
     SC: StarkGenericConfig,
@@ -140,6 +441,18 @@ where
 {
     let quotient_size = quotient_domain.size();
     let width = trace_on_quotient_domain.width();
+    let preprocessed_width = preprocessed_on_quotient_domain
+        .as_ref()
+        .map_or(0, Matrix::width);
+    let ext_degree = <SC::Challenge as FieldExtensionAlgebra<Val<SC>>>::D;
+    let permutation_width = permutation_on_quotient_domain
+        .as_ref()
+        .map_or(0, |permutation| permutation.width() / ext_degree);
+    let permutation_challenges = permutation_challenges
+        .iter()
+        .map(|&challenge| PackedChallenge::<SC>::from_f(challenge))
+        .collect_vec();
+    let cumulative_sum = PackedChallenge::<SC>::from_f(cumulative_sum);
     let mut sels = trace_domain.selectors_on_coset(quotient_domain);
 
     let qdb = log2_strict_usize(quotient_domain.size()) - log2_strict_usize(trace_domain.size());
@@ -157,43 +470,98 @@ where
     let mut alpha_powers = alpha.powers().take(constraint_count).collect_vec();
     alpha_powers.reverse();
 
-    (0..quotient_size)
+    // Evaluating a compiled DAG at several packed points at once amortizes the cost of
+    // interpreting it.
+    let num_packed_points = quotient_size.max(PackedVal::<SC>::WIDTH) / PackedVal::<SC>::WIDTH;
+    (0..num_packed_points)
         .into_par_iter()
-        .step_by(PackedVal::<SC>::WIDTH)
-        .flat_map_iter(|i_start| {
-            let i_range = i_start..i_start + PackedVal::<SC>::WIDTH;
-
-            let is_first_row = *PackedVal::<SC>::from_slice(&sels.is_first_row[i_range.clone()]);
-            let is_last_row = *PackedVal::<SC>::from_slice(&sels.is_last_row[i_range.clone()]);
-            let is_transition = *PackedVal::<SC>::from_slice(&sels.is_transition[i_range.clone()]);
-            let inv_zeroifier = *PackedVal::<SC>::from_slice(&sels.inv_zeroifier[i_range.clone()]);
-
-            let main = RowMajorMatrix::new(
-                trace_on_quotient_domain.vertically_packed_row_pair(i_start, next_step),
-                width,
-            );
-
-            let accumulator = PackedChallenge::<SC>::ZERO;
-            let mut folder = ProverConstraintFolder {
-                main: main.as_view(),
-                public_values,
-                is_first_row,
-                is_last_row,
-                is_transition,
-                alpha_powers: &alpha_powers,
-                accumulator,
-                constraint_index: 0,
-            };
-            air.eval(&mut folder);
-
-            // quotient(x) = constraints(x) / Z_H(x)
-            let quotient = folder.accumulator * inv_zeroifier;
+        .step_by(QUOTIENT_BATCH_SIZE)
+        .flat_map_iter(|batch_start| {
+            let batch_end = core::cmp::min(batch_start + QUOTIENT_BATCH_SIZE, num_packed_points);
+            let windows = (batch_start..batch_end)
+                .map(|packed_index| {
+                    let i_start = packed_index * PackedVal::<SC>::WIDTH;
+                    let preprocessed = RowMajorMatrix::new(
+                        preprocessed_on_quotient_domain.as_ref().map_or_else(
+                            Vec::new,
+                            |preprocessed| {
+                                preprocessed.vertically_packed_row_pair(i_start, next_step)
+                            },
+                        ),
+                        preprocessed_width,
+                    );
+                    let main = RowMajorMatrix::new(
+                        trace_on_quotient_domain.vertically_packed_row_pair(i_start, next_step),
+                        width,
+                    );
+                    let permutation = RowMajorMatrix::new(
+                        permutation_on_quotient_domain.as_ref().map_or_else(
+                            Vec::new,
+                            |permutation| {
+                                // Each extension field column was committed as `D` base field columns.
+                                permutation
+                                    .vertically_packed_row_pair::<PackedVal<SC>>(i_start, next_step)
+                                    .chunks_exact(ext_degree)
+                                    .map(PackedChallenge::<SC>::from_base_slice)
+                                    .collect()
+                            },
+                        ),
+                        permutation_width,
+                    );
+                    (i_start, preprocessed, main, permutation)
+                })
+                .collect_vec();
 
-            // "Transpose" D packed base coefficients into WIDTH scalar extension coefficients.
-            (0..core::cmp::min(quotient_size, PackedVal::<SC>::WIDTH)).map(move |idx_in_packing| {
-                SC::Challenge::from_base_fn(|coeff_idx| {
-                    quotient.as_base_slice()[coeff_idx].as_slice()[idx_in_packing]
+            let mut folders = windows
+                .iter()
+                .map(|(i_start, preprocessed, main, permutation)| {
+                    let i_range = *i_start..i_start + PackedVal::<SC>::WIDTH;
+                    ProverConstraintFolder::<SC> {
+                        preprocessed: preprocessed.as_view(),
+                        main: main.as_view(),
+                        permutation: permutation.as_view(),
+                        permutation_challenges: &permutation_challenges,
+                        cumulative_sum,
+                        public_values,
+                        is_first_row: *PackedVal::<SC>::from_slice(
+                            &sels.is_first_row[i_range.clone()],
+                        ),
+                        is_last_row: *PackedVal::<SC>::from_slice(
+                            &sels.is_last_row[i_range.clone()],
+                        ),
+                        is_transition: *PackedVal::<SC>::from_slice(&sels.is_transition[i_range]),
+                        alpha_powers: &alpha_powers,
+                        accumulator: PackedChallenge::<SC>::ZERO,
+                        constraint_index: 0,
+                    }
                 })
+                .collect_vec();
+            match constraint_dag {
+                Some(constraint_dag) => constraint_dag.eval(&mut folders),
+                None => folders.iter_mut().for_each(|folder| air.eval(folder)),
+            }
+
+            let quotients = folders
+                .iter()
+                .zip(&windows)
+                .map(|(folder, &(i_start, ..))| {
+                    let inv_zeroifier = *PackedVal::<SC>::from_slice(
+                        &sels.inv_zeroifier[i_start..i_start + PackedVal::<SC>::WIDTH],
+                    );
+                    // quotient(x) = constraints(x) / Z_H(x)
+                    folder.accumulator * inv_zeroifier
+                })
+                .collect_vec();
+
+            // "Transpose" D packed base coefficients into WIDTH scalar extension coefficients.
+            quotients.into_iter().flat_map(move |quotient| {
+                (0..core::cmp::min(quotient_size, PackedVal::<SC>::WIDTH)).map(
+                    move |idx_in_packing| {
+                        SC::Challenge::from_base_fn(|coeff_idx| {
+                            quotient.as_base_slice()[coeff_idx].as_slice()[idx_in_packing]
+                        })
+                    },
+                )
             })
         })
         .collect()
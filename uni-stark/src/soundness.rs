@@ -0,0 +1,289 @@
+use core::f64::consts::LN_2;
+
+use p3_air::Air;
+use p3_field::{Field, FieldExtensionAlgebra};
+
+use crate::symbolic_builder::log_quotient_degree;
+use crate::{
+    get_symbolic_constraints, StarkGenericConfig, SymbolicAirBuilder, SymbolicExpression, Val,
+};
+
+/// The largest number of queries [`StarkSoundness::min_num_queries`] will consider.
+const MAX_NUM_QUERIES: usize = 1000;
+
+/// The range of Johnson bound proximity parameters `m` to optimize over for proven soundness.
+const JOHNSON_PARAMETERS: core::ops::RangeInclusive<usize> = 3..=100;
+
+/// Which analysis of FRI and DEEP-ALI to compute soundness with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecurityRegime {
+    /// Assume the [ethSTARK](https://eprint.iacr.org/2021/582) conjecture, under which each FRI
+    /// query has an error of at most the rate, and the algebraic steps behave as in the unique
+    /// decoding regime. Queries are counted as in `p3_fri::FriConfig::conjectured_soundness_bits`.
+    Conjectured,
+    /// Proven soundness in the list decoding regime up to the Johnson bound, following ethSTARK
+    /// and the summary of FRI's soundness in [eprint 2022/1216](https://eprint.iacr.org/2022/1216).
+    Proven,
+}
+
+/// The soundness of each step of a proof, in bits, i.e. `-log2` of the probability that a false
+/// statement passes the step. Round-by-round, a proof is as sound as its weakest step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SoundnessBreakdown {
+    /// Combining the constraints with powers of `alpha`.
+    pub constraint_batching: f64,
+    /// Checking the quotient at the out-of-domain point `zeta` (DEEP-ALI).
+    pub out_of_domain: f64,
+    /// Combining every opened column and point into a single FRI codeword.
+    pub fri_batching: f64,
+    /// FRI's commit phase, which folds the codeword once per round.
+    pub fri_commit_phase: f64,
+    /// FRI's queries, including the proof-of-work grinding that precedes them.
+    pub fri_queries: f64,
+}
+
+impl SoundnessBreakdown {
+    pub fn bits(&self) -> f64 {
+        [
+            self.constraint_batching,
+            self.out_of_domain,
+            self.fri_batching,
+            self.fri_commit_phase,
+            self.fri_queries,
+        ]
+        .into_iter()
+        .fold(f64::INFINITY, f64::min)
+    }
+}
+
+/// The parameters which determine the soundness of a uni-stark proof with a FRI-based PCS.
+///
+/// Build one for an AIR with [`StarkSoundness::new`], then compute its security level with
+/// [`StarkSoundness::bits`], or the number of queries needed for a given level with
+/// [`StarkSoundness::min_num_queries`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StarkSoundness {
+    /// The log2 of the size of the field challenges are drawn from.
+    pub challenge_bits: f64,
+    /// The log2 of the degree of the committed polynomials. This is the log2 of the trace height,
+    /// plus one in zero-knowledge mode.
+    pub log_degree: usize,
+    /// The AIR's maximum constraint degree.
+    pub constraint_degree: usize,
+    pub num_constraints: usize,
+    /// The number of columns and points opened, each of which is a term in FRI's batched codeword.
+    pub num_openings: usize,
+    pub log_blowup: usize,
+    /// The log2 of FRI's folding arity.
+    pub log_arity: usize,
+    /// The log2 of the length of FRI's final polynomial.
+    pub log_final_poly_len: usize,
+    pub num_queries: usize,
+    pub proof_of_work_bits: usize,
+}
+
+impl StarkSoundness {
+    /// Describe proofs of traces of height `2^log_height` for `air`, with the given FRI parameters.
+    ///
+    /// This assumes FRI folds in pairs down to a constant. Set `log_arity` and
+    /// `log_final_poly_len` to describe other FRI configurations.
+    pub fn new<SC, A>(
+        air: &A,
+        log_height: usize,
+        log_blowup: usize,
+        num_queries: usize,
+        proof_of_work_bits: usize,
+    ) -> Self
+    where
+        SC: StarkGenericConfig,
+        A: Air<SymbolicAirBuilder<Val<SC>>>,
+    {
+        let preprocessed_width = air.preprocessed_trace().map_or(0, |trace| trace.width);
+        let constraints = get_symbolic_constraints::<Val<SC>, A>(
+            air,
+            preprocessed_width,
+            air.num_public_values(),
+        );
+        let constraint_degree = constraints
+            .iter()
+            .map(SymbolicExpression::degree_multiple)
+            .max()
+            .unwrap_or(0);
+        let quotient_degree = 1 << log_quotient_degree(constraint_degree, SC::IS_ZK);
+        let challenge_degree = <SC::Challenge as FieldExtensionAlgebra<Val<SC>>>::D;
+
+        // Trace columns are opened at `zeta` and `zeta_next`, and quotient chunks at `zeta`.
+        let trace_width =
+            air.width() + preprocessed_width + air.permutation_width() * challenge_degree;
+        let num_openings = 2 * trace_width + quotient_degree * challenge_degree;
+
+        Self {
+            challenge_bits: log2_field_order::<SC::Challenge>(),
+            log_degree: log_height + usize::from(SC::IS_ZK),
+            constraint_degree: constraint_degree.max(1),
+            num_constraints: constraints.len(),
+            num_openings,
+            log_blowup,
+            log_arity: 1,
+            log_final_poly_len: 0,
+            num_queries,
+            proof_of_work_bits,
+        }
+    }
+
+    /// The soundness of each step under the given analysis. For proven soundness, this uses the
+    /// Johnson bound proximity parameter which maximizes the overall soundness.
+    pub fn breakdown(&self, regime: SecurityRegime) -> SoundnessBreakdown {
+        match regime {
+            SecurityRegime::Conjectured => self.conjectured_breakdown(),
+            SecurityRegime::Proven => JOHNSON_PARAMETERS
+                .map(|m| self.proven_breakdown(m))
+                .max_by(|x, y| x.bits().total_cmp(&y.bits()))
+                .unwrap(),
+        }
+    }
+
+    /// The overall soundness in bits under the given analysis.
+    pub fn bits(&self, regime: SecurityRegime) -> f64 {
+        self.breakdown(regime).bits()
+    }
+
+    /// The fewest queries needed to reach `target_bits` of soundness, keeping the other parameters
+    /// fixed. Returns `None` if no number of queries suffices, e.g. because the challenge field is
+    /// too small.
+    pub fn min_num_queries(&self, target_bits: f64, regime: SecurityRegime) -> Option<usize> {
+        (1..=MAX_NUM_QUERIES).find(|&num_queries| {
+            let candidate = Self {
+                num_queries,
+                ..*self
+            };
+            candidate.bits(regime) >= target_bits
+        })
+    }
+
+    fn conjectured_breakdown(&self) -> SoundnessBreakdown {
+        let degree = (1u64 << self.log_degree) as f64;
+        let lde_size = (1u64 << (self.log_degree + self.log_blowup)) as f64;
+        SoundnessBreakdown {
+            constraint_batching: self.challenge_bits - log2(self.num_constraints as f64),
+            out_of_domain: self.challenge_bits - log2(self.deep_degree(degree)),
+            fri_batching: self.challenge_bits - log2(self.batching_terms() * lde_size),
+            // Folding by `a` over a domain of size `n` fails for at most `(a - 1) n` challenges.
+            fri_commit_phase: self.challenge_bits
+                - log2(
+                    self.folding_rounds()
+                        .map(|(log_height, log_arity)| {
+                            ((1u64 << log_arity) - 1) as f64 * (1u64 << log_height) as f64
+                        })
+                        .sum(),
+                ),
+            fri_queries: (self.num_queries * self.log_blowup + self.proof_of_work_bits) as f64,
+        }
+    }
+
+    /// Proven soundness with the Johnson bound proximity parameter `m`.
+    fn proven_breakdown(&self, m: usize) -> SoundnessBreakdown {
+        let m = m as f64;
+        let degree = (1u64 << self.log_degree) as f64;
+        let lde_size = (1u64 << (self.log_degree + self.log_blowup)) as f64;
+        let sqrt_rate = sqrt(pow(0.5, self.log_blowup));
+        // The size of the list of codewords within the Johnson bound of any word.
+        let list_size = (m + 0.5) / sqrt_rate;
+        // The proximity gaps error of a single random linear combination of codewords.
+        let proximity_gap =
+            pow(m + 0.5, 7) * lde_size * lde_size / (2.0 * sqrt_rate * sqrt_rate * sqrt_rate);
+        let total_arity: f64 = self
+            .folding_rounds()
+            .map(|(_, log_arity)| (1u64 << log_arity) as f64)
+            .sum();
+        SoundnessBreakdown {
+            constraint_batching: self.challenge_bits
+                - log2(list_size * self.num_constraints as f64),
+            out_of_domain: self.challenge_bits
+                - log2(list_size * list_size * self.deep_degree(degree)),
+            fri_batching: self.challenge_bits - log2(self.batching_terms() * proximity_gap),
+            fri_commit_phase: self.challenge_bits
+                - log2(
+                    proximity_gap + (2.0 * m + 1.0) * (lde_size + 1.0) * total_arity / sqrt_rate,
+                ),
+            fri_queries: self.proof_of_work_bits as f64
+                - self.num_queries as f64 * log2(sqrt_rate * (1.0 + 0.5 / m)),
+        }
+    }
+
+    /// The log2 size of the domain each FRI folding round starts from, and the round's log2 arity.
+    fn folding_rounds(&self) -> impl Iterator<Item = (usize, usize)> {
+        let log_final_height = self.log_blowup + self.log_final_poly_len.min(self.log_degree);
+        let log_arity = self.log_arity.max(1);
+        let mut log_height = self.log_degree + self.log_blowup;
+        core::iter::from_fn(move || {
+            (log_height > log_final_height).then(|| {
+                let round = (log_height, log_arity.min(log_height - log_final_height));
+                log_height -= round.1;
+                round
+            })
+        })
+    }
+
+    /// The degree of the polynomial checked at the out-of-domain point, plus the number of
+    /// points at which the second opening point can collide with the trace domain.
+    fn deep_degree(&self, degree: f64) -> f64 {
+        self.constraint_degree as f64 * degree + degree - 1.0
+    }
+
+    /// The number of terms whose coefficients are powers of the FRI batching challenge, less one.
+    fn batching_terms(&self) -> f64 {
+        self.num_openings.saturating_sub(1).max(1) as f64
+    }
+}
+
+/// `log2` of the order of `F`, accurate to within floating point precision.
+fn log2_field_order<F: Field>() -> f64 {
+    let order = F::order();
+    let bits = order.bits();
+    let shift = bits.saturating_sub(f64::MANTISSA_DIGITS as u64);
+    let top = (order >> shift)
+        .to_u64_digits()
+        .first()
+        .copied()
+        .unwrap_or(0);
+    shift as f64 + log2(top as f64)
+}
+
+/// `log2(x)`, which `core` doesn't provide.
+fn log2(x: f64) -> f64 {
+    if x < 1.0 {
+        return if x > 0.0 {
+            -log2(1.0 / x)
+        } else {
+            f64::NEG_INFINITY
+        };
+    }
+    // Write x = 2^e * y for y in [1, 2), and use log(y) = 2 atanh((y - 1) / (y + 1)).
+    let bits = x.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i64 - 1023;
+    let y = f64::from_bits((bits & ((1 << 52) - 1)) | (1023 << 52));
+    let t = (y - 1.0) / (y + 1.0);
+    let t_squared = t * t;
+    let mut power = t;
+    let mut atanh = 0.0;
+    for k in 0..30 {
+        atanh += power / (2 * k + 1) as f64;
+        power *= t_squared;
+    }
+    exponent as f64 + 2.0 * atanh / LN_2
+}
+
+/// `sqrt(x)` for positive `x`, by Newton's method, which `core` doesn't provide.
+fn sqrt(x: f64) -> f64 {
+    let mut y = x.max(1.0);
+    for _ in 0..100 {
+        y = 0.5 * (y + x / y);
+    }
+    y
+}
+
+/// `x^n`, which `core` doesn't provide for floats.
+fn pow(x: f64, n: usize) -> f64 {
+    (0..n).fold(1.0, |acc, _| acc * x)
+}
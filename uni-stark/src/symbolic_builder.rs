@@ -1,7 +1,10 @@
 use alloc::vec;
 use alloc::vec::Vec;
 
-use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, PairBuilder};
+use p3_air::{
+    Air, AirBuilder, AirBuilderWithPublicValues, ExtensionBuilder, MultiTableAirBuilder,
+    PairBuilder, PermutationAirBuilder,
+};
 use p3_field::Field;
 use p3_matrix::dense::RowMajorMatrix;
 use p3_util::log2_ceil_usize;
@@ -16,19 +19,30 @@ pub fn get_log_quotient_degree<F, A>(
     air: &A,
     preprocessed_width: usize,
     num_public_values: usize,
+    is_zk: bool,
 ) -> usize
 where
     F: Field,
     A: Air<SymbolicAirBuilder<F>>,
 {
+    let constraint_degree = get_max_constraint_degree(air, preprocessed_width, num_public_values);
+    log_quotient_degree(constraint_degree, is_zk)
+}
+
+/// The log2 of the number of quotient chunks for constraints of the given degree.
+pub(crate) fn log_quotient_degree(constraint_degree: usize, is_zk: bool) -> usize {
     // We pad to at least degree 2, since a quotient argument doesn't make sense with smaller degrees.
-    let constraint_degree =
-        get_max_constraint_degree(air, preprocessed_width, num_public_values).max(2);
+    let constraint_degree = constraint_degree.max(2);
 
     // The quotient's actual degree is approximately (max_constraint_degree - 1) n,
-    // where subtracting 1 comes from division by the zerofier.
+    // where subtracting 1 comes from division by the zerofier. In zero-knowledge mode the traces
+    // are randomized to degree 2n, making it (2 max_constraint_degree - 1) n.
     // But we pad it to a power of two so that we can efficiently decompose the quotient.
-    log2_ceil_usize(constraint_degree - 1)
+    if is_zk {
+        log2_ceil_usize(2 * constraint_degree - 1)
+    } else {
+        log2_ceil_usize(constraint_degree - 1)
+    }
 }
 
 #[instrument(name = "infer constraint degree", skip_all, level = "debug")]
@@ -58,22 +72,39 @@ where
     F: Field,
     A: Air<SymbolicAirBuilder<F>>,
 {
-    let mut builder = SymbolicAirBuilder::new(preprocessed_width, air.width(), num_public_values);
+    let mut builder = SymbolicAirBuilder::new(
+        preprocessed_width,
+        air.width(),
+        num_public_values,
+        air.permutation_width(),
+        air.num_permutation_challenges(),
+    );
     air.eval(&mut builder);
     builder.constraints()
 }
 
 /// An `AirBuilder` for evaluating constraints symbolically, and recording them for later use.
+///
+/// Extension field values, such as permutation columns and challenges, are represented by a single
+/// symbolic variable each; this is sufficient for inferring constraint degrees.
 #[derive(Debug)]
 pub struct SymbolicAirBuilder<F: Field> {
     preprocessed: RowMajorMatrix<SymbolicVariable<F>>,
     main: RowMajorMatrix<SymbolicVariable<F>>,
+    permutation: RowMajorMatrix<SymbolicVariable<F>>,
+    permutation_challenges: Vec<SymbolicVariable<F>>,
     public_values: Vec<SymbolicVariable<F>>,
     constraints: Vec<SymbolicExpression<F>>,
 }
 
 impl<F: Field> SymbolicAirBuilder<F> {
-    pub(crate) fn new(preprocessed_width: usize, width: usize, num_public_values: usize) -> Self {
+    pub(crate) fn new(
+        preprocessed_width: usize,
+        width: usize,
+        num_public_values: usize,
+        permutation_width: usize,
+        num_permutation_challenges: usize,
+    ) -> Self {
         let prep_values = [0, 1]
             .into_iter()
             .flat_map(|offset| {
@@ -87,12 +118,24 @@ impl<F: Field> SymbolicAirBuilder<F> {
                 (0..width).map(move |index| SymbolicVariable::new(Entry::Main { offset }, index))
             })
             .collect();
+        let permutation_values = [0, 1]
+            .into_iter()
+            .flat_map(|offset| {
+                (0..permutation_width)
+                    .map(move |index| SymbolicVariable::new(Entry::Permutation { offset }, index))
+            })
+            .collect();
+        let permutation_challenges = (0..num_permutation_challenges)
+            .map(|index| SymbolicVariable::new(Entry::Challenge, index))
+            .collect();
         let public_values = (0..num_public_values)
             .map(move |index| SymbolicVariable::new(Entry::Public, index))
             .collect();
         Self {
             preprocessed: RowMajorMatrix::new(prep_values, preprocessed_width),
             main: RowMajorMatrix::new(main_values, width),
+            permutation: RowMajorMatrix::new(permutation_values, permutation_width),
+            permutation_challenges,
             public_values,
             constraints: vec![],
         }
@@ -146,3 +189,37 @@ impl<F: Field> PairBuilder for SymbolicAirBuilder<F> {
         self.preprocessed.clone()
     }
 }
+
+impl<F: Field> ExtensionBuilder for SymbolicAirBuilder<F> {
+    type EF = F;
+    type ExprEF = SymbolicExpression<F>;
+    type VarEF = SymbolicVariable<F>;
+
+    fn assert_zero_ext<I>(&mut self, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        self.constraints.push(x.into());
+    }
+}
+
+impl<F: Field> PermutationAirBuilder for SymbolicAirBuilder<F> {
+    type MP = RowMajorMatrix<Self::VarEF>;
+    type RandomVar = SymbolicVariable<F>;
+
+    fn permutation(&self) -> Self::MP {
+        self.permutation.clone()
+    }
+
+    fn permutation_randomness(&self) -> &[Self::RandomVar] {
+        &self.permutation_challenges
+    }
+}
+
+impl<F: Field> MultiTableAirBuilder for SymbolicAirBuilder<F> {
+    type Sum = SymbolicVariable<F>;
+
+    fn cumulative_sum(&self) -> Self::Sum {
+        SymbolicVariable::new(Entry::CumulativeSum, 0)
+    }
+}
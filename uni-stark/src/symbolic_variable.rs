@@ -7,11 +7,19 @@ use crate::symbolic_expression::SymbolicExpression;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Entry {
-    Preprocessed { offset: usize },
-    Main { offset: usize },
-    Permutation { offset: usize },
+    Preprocessed {
+        offset: usize,
+    },
+    Main {
+        offset: usize,
+    },
+    Permutation {
+        offset: usize,
+    },
     Public,
     Challenge,
+    /// The claimed cumulative sum of this table's permutation argument.
+    CumulativeSum,
 }
 
 /// A variable within the evaluation window, i.e. a column in either the local or next row.
@@ -34,7 +42,7 @@ impl<F> SymbolicVariable<F> {
     pub const fn degree_multiple(&self) -> usize {
         match self.entry {
             Entry::Preprocessed { .. } | Entry::Main { .. } | Entry::Permutation { .. } => 1,
-            Entry::Public | Entry::Challenge => 0,
+            Entry::Public | Entry::Challenge | Entry::CumulativeSum => 0,
         }
     }
 }
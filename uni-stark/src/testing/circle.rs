@@ -0,0 +1,51 @@
+//! A Circle STARK configuration over Mersenne31, with Merkle trees hashing with Keccak-256.
+
+use alloc::vec;
+
+use p3_challenger::{HashChallenger, SerializingChallenger32};
+use p3_circle::CirclePcs;
+use p3_commit::ExtensionMmcs;
+use p3_field::extension::BinomialExtensionField;
+use p3_fri::FriConfig;
+use p3_keccak::Keccak256Hash;
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_mersenne_31::Mersenne31;
+use p3_symmetric::{CompressionFunctionFromHasher, SerializingHasher32};
+
+use crate::StarkConfig;
+
+pub type Val = Mersenne31;
+pub type Challenge = BinomialExtensionField<Val, 3>;
+pub type ByteHash = Keccak256Hash;
+pub type FieldHash = SerializingHasher32<ByteHash>;
+pub type MyCompress = CompressionFunctionFromHasher<ByteHash, 2, 32>;
+pub type ValMmcs = MerkleTreeMmcs<Val, u8, FieldHash, MyCompress, 32>;
+pub type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+pub type Challenger = SerializingChallenger32<Val, HashChallenger<u8, ByteHash, 32>>;
+pub type Pcs = CirclePcs<Val, ValMmcs, ChallengeMmcs>;
+pub type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+
+/// A circle PCS with FRI at the given blowup and number of queries, with arity 2, a constant final
+/// polynomial and 8 bits of proof of work.
+pub fn make_pcs(log_blowup: usize, num_queries: usize) -> Pcs {
+    let val_mmcs = ValMmcs::new(FieldHash::new(ByteHash {}), MyCompress::new(ByteHash {}));
+    let fri_config = FriConfig {
+        log_blowup,
+        log_arity: 1,
+        log_final_poly_len: 0,
+        num_queries,
+        proof_of_work_bits: 8,
+        mmcs: ChallengeMmcs::new(val_mmcs.clone()),
+    };
+    Pcs::new(val_mmcs, fri_config)
+}
+
+/// A configuration committing with [`make_pcs`] at the given blowup, with 40 queries.
+pub fn make_config(log_blowup: usize) -> MyConfig {
+    MyConfig::new(make_pcs(log_blowup, 40))
+}
+
+/// A challenger for [`MyConfig`], which starts from an empty transcript.
+pub fn make_challenger() -> Challenger {
+    Challenger::from_hasher(vec![], ByteHash {})
+}
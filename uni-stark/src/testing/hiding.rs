@@ -0,0 +1,58 @@
+//! A zero-knowledge variant of the BabyBear configuration, with hiding Merkle trees and a
+//! [`HidingFriPcs`] which randomizes traces, whose hashes and randomness are all seeded so that
+//! tests are deterministic.
+
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::Field;
+use p3_fri::{FriConfig, HidingFriPcs};
+use p3_merkle_tree::MerkleTreeHidingMmcs;
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::{Challenge, Challenger, Dft, MyCompress, MyHash, Perm, Val};
+use crate::StarkConfig;
+
+pub type ValMmcs = MerkleTreeHidingMmcs<
+    <Val as Field>::Packing,
+    <Val as Field>::Packing,
+    MyHash,
+    MyCompress,
+    StdRng,
+    8,
+    4,
+>;
+pub type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+pub type Pcs = HidingFriPcs<Val, Dft, ValMmcs, ChallengeMmcs, StdRng>;
+pub type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+
+/// A configuration with FRI at the given blowup and number of queries, with 8 bits of proof of
+/// work, and the permutation it hashes with, for making challengers.
+///
+/// Traces must have more rows than the number of points their polynomials are opened at, i.e.
+/// `num_queries` plus two, for their randomization to hide them.
+pub fn make_config(log_blowup: usize, num_queries: usize) -> (MyConfig, Perm) {
+    let perm = Perm::new_from_rng_128(&mut StdRng::seed_from_u64(0));
+    let val_mmcs = MerkleTreeHidingMmcs::new(
+        PaddingFreeSponge::new(perm.clone()),
+        TruncatedPermutation::new(perm.clone()),
+        StdRng::seed_from_u64(1),
+    );
+    let fri_config = FriConfig {
+        log_blowup,
+        log_arity: 1,
+        log_final_poly_len: 0,
+        num_queries,
+        proof_of_work_bits: 8,
+        mmcs: ExtensionMmcs::new(val_mmcs.clone()),
+    };
+    let pcs = HidingFriPcs::new(
+        Radix2DitParallel::default(),
+        val_mmcs,
+        fri_config,
+        4,
+        StdRng::seed_from_u64(2),
+    );
+    (StarkConfig::new(pcs), perm)
+}
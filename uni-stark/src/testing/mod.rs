@@ -0,0 +1,89 @@
+//! Configurations for tests: FRI with Merkle trees hashing with a width 16 Poseidon2 permutation,
+//! over BabyBear unless another field and permutation are given.
+
+use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::extension::BinomialExtensionField;
+use p3_field::Field;
+use p3_fri::{FriConfig, TwoAdicFriPcs};
+use p3_merkle_tree::MerkleTreeMmcs;
+use p3_symmetric::{CryptographicPermutation, PaddingFreeSponge, TruncatedPermutation};
+use rand::thread_rng;
+
+use crate::StarkConfig;
+
+pub mod circle;
+pub mod hiding;
+
+pub type Val = BabyBear;
+pub type Perm = Poseidon2BabyBear<16>;
+pub type MyHash<P = Perm> = PaddingFreeSponge<P, 16, 8, 8>;
+pub type MyCompress<P = Perm> = TruncatedPermutation<P, 2, 8, 16>;
+pub type ValMmcs<F = Val, P = Perm> =
+    MerkleTreeMmcs<<F as Field>::Packing, <F as Field>::Packing, MyHash<P>, MyCompress<P>, 8>;
+pub type Challenge<F = Val> = BinomialExtensionField<F, 4>;
+pub type ChallengeMmcs<F = Val, P = Perm> = ExtensionMmcs<F, Challenge<F>, ValMmcs<F, P>>;
+pub type Challenger<F = Val, P = Perm> = DuplexChallenger<F, P, 16, 8>;
+pub type Dft<F = Val> = Radix2DitParallel<F>;
+pub type Pcs<F = Val, P = Perm> = TwoAdicFriPcs<F, Dft<F>, ValMmcs<F, P>, ChallengeMmcs<F, P>>;
+pub type MyConfig<F = Val, P = Perm> = StarkConfig<Pcs<F, P>, Challenge<F>, Challenger<F, P>>;
+
+/// A Merkle tree MMCS hashing and compressing with `perm`.
+pub fn make_mmcs<F: Field, P: Clone>(perm: &P) -> ValMmcs<F, P> {
+    MerkleTreeMmcs::new(
+        PaddingFreeSponge::new(perm.clone()),
+        TruncatedPermutation::new(perm.clone()),
+    )
+}
+
+/// FRI parameters with arity 2 and a constant final polynomial, committing with
+/// [`make_mmcs`].
+pub fn make_fri_config<F: Field, P: Clone>(
+    perm: &P,
+    log_blowup: usize,
+    num_queries: usize,
+    proof_of_work_bits: usize,
+) -> FriConfig<ChallengeMmcs<F, P>> {
+    FriConfig {
+        log_blowup,
+        log_arity: 1,
+        log_final_poly_len: 0,
+        num_queries,
+        proof_of_work_bits,
+        mmcs: ExtensionMmcs::new(make_mmcs(perm)),
+    }
+}
+
+/// A configuration committing with [`make_mmcs`] and the given FRI parameters, whose MMCS must
+/// also have been made with `perm`.
+pub fn make_config_with_fri<F: Field, P: CryptographicPermutation<[F; 16]>>(
+    perm: &P,
+    fri_config: FriConfig<ChallengeMmcs<F, P>>,
+) -> MyConfig<F, P> {
+    StarkConfig::new(TwoAdicFriPcs::new(
+        Radix2DitParallel::default(),
+        make_mmcs(perm),
+        fri_config,
+    ))
+}
+
+/// A configuration hashing with `perm`, with the given FRI parameters.
+pub fn make_config_with_perm<F: Field, P: CryptographicPermutation<[F; 16]>>(
+    perm: &P,
+    log_blowup: usize,
+    num_queries: usize,
+    proof_of_work_bits: usize,
+) -> MyConfig<F, P> {
+    let fri_config = make_fri_config(perm, log_blowup, num_queries, proof_of_work_bits);
+    make_config_with_fri(perm, fri_config)
+}
+
+/// A BabyBear configuration hashing with a random permutation, which is returned for making
+/// challengers, with FRI at the given blowup, 40 queries and 8 bits of proof of work.
+pub fn make_config(log_blowup: usize) -> (MyConfig, Perm) {
+    let perm = Perm::new_from_rng_128(&mut thread_rng());
+    let config = make_config_with_perm(&perm, log_blowup, 40, 8);
+    (config, perm)
+}
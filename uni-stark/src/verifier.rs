@@ -5,14 +5,21 @@ use itertools::Itertools;
 use p3_air::{Air, BaseAir};
 use p3_challenger::{CanObserve, CanSample, FieldChallenger};
 use p3_commit::{Pcs, PolynomialSpace};
-use p3_field::{Field, FieldAlgebra, FieldExtensionAlgebra};
+use p3_field::{ExtensionField, Field, FieldAlgebra, FieldExtensionAlgebra};
 use p3_matrix::dense::RowMajorMatrixView;
 use p3_matrix::stack::VerticalPair;
 use tracing::instrument;
 
 use crate::symbolic_builder::{get_log_quotient_degree, SymbolicAirBuilder};
-use crate::{PcsError, Proof, StarkGenericConfig, Val, VerifierConstraintFolder};
+use crate::{
+    setup, AirFingerprint, Domain, OpenedValues, PcsError, Proof, StarkGenericConfig,
+    StarkVerifyingKey, Val, VerifierConstraintFolder,
+};
 
+/// Verify a proof of `air`.
+///
+/// This runs [`setup`] internally to derive the verifying key. When verifying many proofs for the
+/// same AIR, prefer calling [`setup`] once and then [`verify_with_key`].
 #[instrument(skip_all)]
 pub fn verify<SC, A>(
     config: &SC,
@@ -21,6 +28,32 @@ pub fn verify<SC, A>(
     proof: &Proof<SC>,
     public_values: &Vec<Val<SC>>,
 ) -> Result<(), VerificationError<PcsError<SC>>>
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+{
+    let (_, verifying_key) =
+        setup(config, air).map_err(|_| VerificationError::InvalidPreprocessedTrace)?;
+    verify_with_key(
+        config,
+        &verifying_key,
+        air,
+        challenger,
+        proof,
+        public_values,
+    )
+}
+
+/// Verify a proof of `air`, using a verifying key previously produced by [`setup`].
+#[instrument(skip_all)]
+pub fn verify_with_key<SC, A>(
+    config: &SC,
+    verifying_key: &StarkVerifyingKey<SC>,
+    air: &A,
+    challenger: &mut SC::Challenger,
+    proof: &Proof<SC>,
+    public_values: &Vec<Val<SC>>,
+) -> Result<(), VerificationError<PcsError<SC>>>
 where
     SC: StarkGenericConfig,
     A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
@@ -32,8 +65,29 @@ where
         degree_bits,
     } = proof;
 
+    let preprocessed = verifying_key.preprocessed.as_ref();
+    let preprocessed_width = verifying_key.preprocessed_width();
+    // The key must have been set up for this configuration and AIR, as it is what binds the
+    // transcript to them.
+    if verifying_key.pcs_parameters() != config.pcs().parameters()
+        || *verifying_key.air_fingerprint()
+            != AirFingerprint::new::<Val<SC>, _>(air, preprocessed_width)
+    {
+        return Err(VerificationError::VerifyingKeyMismatch);
+    }
+    if preprocessed.is_some_and(|p| p.degree_bits != *degree_bits)
+        || public_values.len() != air.num_public_values()
+    {
+        return Err(VerificationError::InvalidProofShape);
+    }
+
     let degree = 1 << degree_bits;
-    let log_quotient_degree = get_log_quotient_degree::<Val<SC>, A>(air, 0, public_values.len());
+    let log_quotient_degree = get_log_quotient_degree::<Val<SC>, A>(
+        air,
+        preprocessed_width,
+        public_values.len(),
+        SC::IS_ZK,
+    );
     let quotient_degree = 1 << log_quotient_degree;
 
     let pcs = config.pcs();
@@ -42,59 +96,165 @@ where
         trace_domain.create_disjoint_domain(1 << (degree_bits + log_quotient_degree));
     let quotient_chunks_domains = quotient_domain.split_domains(quotient_degree);
 
-    let air_width = <A as BaseAir<Val<SC>>>::width(air);
-    let valid_shape = opened_values.trace_local.len() == air_width
-        && opened_values.trace_next.len() == air_width
-        && opened_values.quotient_chunks.len() == quotient_degree
-        && opened_values
-            .quotient_chunks
-            .iter()
-            .all(|qc| qc.len() == <SC::Challenge as FieldExtensionAlgebra<Val<SC>>>::D);
-    if !valid_shape {
+    let permutation_width = <A as BaseAir<Val<SC>>>::permutation_width(air);
+    if commitments.permutation.is_some() != (permutation_width > 0)
+        || !opened_values_have_shape::<Val<SC>, SC::Challenge, A>(
+            air,
+            opened_values,
+            preprocessed_width,
+            quotient_degree,
+        )
+    {
         return Err(VerificationError::InvalidProofShape);
     }
 
-    // Observe the instance.
+    // Observe the instance, starting with the verifying key, which encodes the AIR and the PCS
+    // parameters. This protects against transcript collisions between distinct instances.
+    verifying_key.observe(challenger);
     challenger.observe(Val::<SC>::from_canonical_usize(proof.degree_bits));
-    // TODO: Might be best practice to include other instance data here in the transcript, like some
-    // encoding of the AIR. This protects against transcript collisions between distinct instances.
-    // Practically speaking though, the only related known attack is from failing to include public
-    // values. It's not clear if failing to include other instance data could enable a transcript
-    // collision, since most such changes would completely change the set of satisfying witnesses.
 
     challenger.observe(commitments.trace.clone());
     challenger.observe_slice(public_values);
+    let mut permutation_challenges = vec![];
+    if let Some(permutation_commit) = &commitments.permutation {
+        permutation_challenges = (0..air.num_permutation_challenges())
+            .map(|_| challenger.sample_ext_element())
+            .collect();
+        challenger.observe(permutation_commit.clone());
+    }
     let alpha: SC::Challenge = challenger.sample_ext_element();
     challenger.observe(commitments.quotient_chunks.clone());
 
     let zeta: SC::Challenge = challenger.sample();
     let zeta_next = trace_domain.next_point(zeta).unwrap();
 
-    pcs.verify(
-        vec![
-            (
-                commitments.trace.clone(),
-                vec![(
-                    trace_domain,
-                    vec![
-                        (zeta, opened_values.trace_local.clone()),
-                        (zeta_next, opened_values.trace_next.clone()),
-                    ],
-                )],
-            ),
-            (
-                commitments.quotient_chunks.clone(),
-                quotient_chunks_domains
-                    .iter()
-                    .zip(&opened_values.quotient_chunks)
-                    .map(|(domain, values)| (*domain, vec![(zeta, values.clone())]))
-                    .collect_vec(),
-            ),
-        ],
-        opening_proof,
-        challenger,
+    let mut rounds = vec![
+        (
+            commitments.trace.clone(),
+            vec![(
+                witness_domain::<SC>(pcs, trace_domain),
+                vec![
+                    (zeta, opened_values.trace_local.clone()),
+                    (zeta_next, opened_values.trace_next.clone()),
+                ],
+            )],
+        ),
+        (
+            commitments.quotient_chunks.clone(),
+            quotient_chunks_domains
+                .iter()
+                .zip(&opened_values.quotient_chunks)
+                .map(|(domain, values)| {
+                    (
+                        witness_domain::<SC>(pcs, *domain),
+                        vec![(zeta, values.clone())],
+                    )
+                })
+                .collect_vec(),
+        ),
+    ];
+    if let Some(preprocessed) = preprocessed {
+        rounds.push((
+            preprocessed.commitment.clone(),
+            vec![(
+                trace_domain,
+                vec![
+                    (zeta, opened_values.preprocessed_local.clone()),
+                    (zeta_next, opened_values.preprocessed_next.clone()),
+                ],
+            )],
+        ));
+    }
+
+    if let Some(permutation_commit) = &commitments.permutation {
+        rounds.push((
+            permutation_commit.clone(),
+            vec![(
+                witness_domain::<SC>(pcs, trace_domain),
+                vec![
+                    (zeta, opened_values.permutation_local.clone()),
+                    (zeta_next, opened_values.permutation_next.clone()),
+                ],
+            )],
+        ));
+    }
+
+    pcs.verify(rounds, opening_proof, challenger)
+        .map_err(VerificationError::InvalidOpeningArgument)?;
+
+    verify_constraints::<SC, A>(
+        air,
+        opened_values,
+        trace_domain,
+        &quotient_chunks_domains,
+        &permutation_challenges,
+        SC::Challenge::ZERO,
+        public_values,
+        alpha,
+        zeta,
     )
-    .map_err(VerificationError::InvalidOpeningArgument)?;
+}
+
+/// The domain to open a witness-dependent polynomial over, given the domain it was committed with.
+/// See [`Pcs::commit_randomized`] for how it differs in zero-knowledge mode.
+pub(crate) fn witness_domain<SC: StarkGenericConfig>(
+    pcs: &SC::Pcs,
+    domain: Domain<SC>,
+) -> Domain<SC> {
+    if SC::IS_ZK {
+        pcs.natural_domain_for_degree(2 * domain.size())
+    } else {
+        domain
+    }
+}
+
+/// Check that `opened_values` has the dimensions expected for `air`.
+pub(crate) fn opened_values_have_shape<F, EF, A>(
+    air: &A,
+    opened_values: &OpenedValues<EF>,
+    preprocessed_width: usize,
+    quotient_degree: usize,
+) -> bool
+where
+    F: Field,
+    EF: ExtensionField<F>,
+    A: BaseAir<F>,
+{
+    let air_width = air.width();
+    let ext_degree = EF::D;
+    let permutation_width = air.permutation_width();
+    opened_values.preprocessed_local.len() == preprocessed_width
+        && opened_values.preprocessed_next.len() == preprocessed_width
+        && opened_values.trace_local.len() == air_width
+        && opened_values.trace_next.len() == air_width
+        && opened_values.permutation_local.len() == permutation_width * ext_degree
+        && opened_values.permutation_next.len() == permutation_width * ext_degree
+        && opened_values.quotient_chunks.len() == quotient_degree
+        && opened_values
+            .quotient_chunks
+            .iter()
+            .all(|qc| qc.len() == ext_degree)
+}
+
+/// Check that the constraints of `air`, evaluated on the values opened at `zeta`, match the opened
+/// quotient chunks.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn verify_constraints<SC, A>(
+    air: &A,
+    opened_values: &OpenedValues<SC::Challenge>,
+    trace_domain: Domain<SC>,
+    quotient_chunks_domains: &[Domain<SC>],
+    permutation_challenges: &[SC::Challenge],
+    cumulative_sum: SC::Challenge,
+    public_values: &Vec<Val<SC>>,
+    alpha: SC::Challenge,
+    zeta: SC::Challenge,
+) -> Result<(), VerificationError<PcsError<SC>>>
+where
+    SC: StarkGenericConfig,
+    A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+{
+    let ext_degree = <SC::Challenge as FieldExtensionAlgebra<Val<SC>>>::D;
 
     let zps = quotient_chunks_domains
         .iter()
@@ -126,13 +286,41 @@ where
 
     let sels = trace_domain.selectors_at_point(zeta);
 
+    let preprocessed = VerticalPair::new(
+        RowMajorMatrixView::new_row(&opened_values.preprocessed_local),
+        RowMajorMatrixView::new_row(&opened_values.preprocessed_next),
+    );
     let main = VerticalPair::new(
         RowMajorMatrixView::new_row(&opened_values.trace_local),
         RowMajorMatrixView::new_row(&opened_values.trace_next),
     );
 
+    // Recombine each group of `D` opened base field columns into one extension field column.
+    let unflatten = |values: &[SC::Challenge]| {
+        values
+            .chunks_exact(ext_degree)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .map(|(e_i, &c)| SC::Challenge::monomial(e_i) * c)
+                    .sum()
+            })
+            .collect_vec()
+    };
+    let permutation_local = unflatten(&opened_values.permutation_local);
+    let permutation_next = unflatten(&opened_values.permutation_next);
+    let permutation = VerticalPair::new(
+        RowMajorMatrixView::new_row(&permutation_local),
+        RowMajorMatrixView::new_row(&permutation_next),
+    );
+
     let mut folder = VerifierConstraintFolder {
+        preprocessed,
         main,
+        permutation,
+        permutation_challenges,
+        cumulative_sum,
         public_values,
         is_first_row: sels.is_first_row,
         is_last_row: sels.is_last_row,
@@ -155,9 +343,17 @@ where
 #[derive(Debug)]
 pub enum VerificationError<PcsErr> {
     InvalidProofShape,
+    /// The verifying key was set up for a different PCS configuration or AIR.
+    VerifyingKeyMismatch,
+    /// The AIR's preprocessed trace height is zero or not a power of two, so no verifying key can
+    /// be set up for it.
+    InvalidPreprocessedTrace,
     /// An error occurred while verifying the claimed openings.
     InvalidOpeningArgument(PcsErr),
     /// Out-of-domain evaluation mismatch, i.e. `constraints(zeta)` did not match
     /// `quotient(zeta) Z_H(zeta)`.
     OodEvaluationMismatch,
+    /// The claimed cumulative sums of the tables in a batch proof didn't add up to zero, meaning
+    /// some interaction between tables was unbalanced.
+    NonzeroCumulativeSum,
 }
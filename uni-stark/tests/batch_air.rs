@@ -0,0 +1,234 @@
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_baby_bear::GenericPoseidon2LinearLayersBabyBear;
+use p3_field::{Field, FieldAlgebra};
+use p3_keccak_air::KeccakAir;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_poseidon2_air::{Poseidon2Air, RoundConstants};
+use p3_uni_stark::testing::{make_config, Challenger, Val};
+use p3_uni_stark::{
+    prove_batch, prove_batch_with_key, setup_batch, verify_batch, verify_batch_with_key,
+    ProverError, VerificationError,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Asserts `y = x^degree` on every row, where `x` counts up from the public value `start`.
+pub struct PowerAir {
+    degree: u64,
+}
+
+impl PowerAir {
+    fn generate_trace<F: Field>(&self, log_height: usize, start: u64) -> RowMajorMatrix<F> {
+        let values = (0..1 << log_height)
+            .flat_map(|i| {
+                let x = F::from_canonical_u64(start + i);
+                [x, x.exp_u64(self.degree)]
+            })
+            .collect();
+        RowMajorMatrix::new(values, 2)
+    }
+}
+
+impl<F> BaseAir<F> for PowerAir {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn num_public_values(&self) -> usize {
+        1
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for PowerAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let start = builder.public_values()[0];
+
+        let x: AB::Expr = local[0].into();
+        builder.assert_eq(local[1], x.exp_u64(self.degree));
+        builder.when_first_row().assert_eq(local[0], start);
+        builder
+            .when_transition()
+            .assert_eq(next[0], local[0] + AB::Expr::ONE);
+    }
+}
+
+type BabyBearPoseidon2Air =
+    Poseidon2Air<Val, GenericPoseidon2LinearLayersBabyBear, 16, 7, 1, 4, 20>;
+
+/// The Keccak and Poseidon2 chips, which a batch needs in a single type.
+enum Chip {
+    Keccak(KeccakAir),
+    Poseidon2(Box<BabyBearPoseidon2Air>),
+}
+
+impl BaseAir<Val> for Chip {
+    fn width(&self) -> usize {
+        match self {
+            Self::Keccak(air) => <KeccakAir as BaseAir<Val>>::width(air),
+            Self::Poseidon2(air) => air.width(),
+        }
+    }
+}
+
+impl<AB: AirBuilder<F = Val>> Air<AB> for Chip {
+    fn eval(&self, builder: &mut AB) {
+        match self {
+            Self::Keccak(air) => air.eval(builder),
+            Self::Poseidon2(air) => air.eval(builder),
+        }
+    }
+}
+
+/// Tables of differing heights and constraint degrees, along with their traces and public values.
+fn make_batch() -> (Vec<PowerAir>, Vec<RowMajorMatrix<Val>>, Vec<Vec<Val>>) {
+    let tables = [(2, 3, 10), (3, 6, 0), (5, 5, 7)];
+    let airs = tables
+        .iter()
+        .map(|&(degree, _, _)| PowerAir { degree })
+        .collect::<Vec<_>>();
+    let traces = airs
+        .iter()
+        .zip(tables)
+        .map(|(air, (_, log_height, start))| air.generate_trace(log_height, start))
+        .collect();
+    let public_values = tables
+        .iter()
+        .map(|&(_, _, start)| vec![Val::from_canonical_u64(start)])
+        .collect();
+    (airs, traces, public_values)
+}
+
+#[test]
+fn test_batch_mixed_heights_and_degrees() {
+    let (config, perm) = make_config(2);
+    let (airs, traces, public_values) = make_batch();
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove_batch(&config, &airs, &mut challenger, traces, &public_values)
+        .expect("proving failed");
+
+    let serialized_proof = postcard::to_allocvec(&proof).expect("unable to serialize proof");
+    let proof = postcard::from_bytes(&serialized_proof).expect("unable to deserialize proof");
+
+    let mut challenger = Challenger::new(perm);
+    verify_batch(&config, &airs, &mut challenger, &proof, &public_values)
+        .expect("verification failed");
+}
+
+#[test]
+fn test_batch_keccak_and_poseidon2() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let constants = RoundConstants::from_rng(&mut rng);
+    let keccak_trace =
+        p3_keccak_air::generate_trace_rows::<Val>((0..2).map(|_| rng.gen()).collect());
+    let poseidon2_trace = p3_poseidon2_air::generate_trace_rows::<
+        Val,
+        GenericPoseidon2LinearLayersBabyBear,
+        16,
+        7,
+        1,
+        4,
+        20,
+    >((0..8).map(|_| rng.gen()).collect(), &constants);
+    assert_ne!(keccak_trace.height(), poseidon2_trace.height());
+
+    let airs = [
+        Chip::Keccak(KeccakAir {}),
+        Chip::Poseidon2(Box::new(Poseidon2Air::new(constants))),
+    ];
+    let traces = vec![keccak_trace, poseidon2_trace];
+    let public_values = vec![vec![]; 2];
+
+    let (config, perm) = make_config(2);
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove_batch(&config, &airs, &mut challenger, traces, &public_values)
+        .expect("proving failed");
+
+    let mut challenger = Challenger::new(perm);
+    verify_batch(&config, &airs, &mut challenger, &proof, &public_values)
+        .expect("verification failed");
+}
+
+#[test]
+fn test_batch_wrong_instance() {
+    let (config, perm) = make_config(2);
+    let (airs, traces, mut public_values) = make_batch();
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove_batch(&config, &airs, &mut challenger, traces, &public_values)
+        .expect("proving failed");
+
+    // Dropping a table changes the shape of the batch.
+    let mut challenger = Challenger::new(perm.clone());
+    assert!(matches!(
+        verify_batch(
+            &config,
+            &airs[1..],
+            &mut challenger,
+            &proof,
+            &public_values[1..]
+        ),
+        Err(VerificationError::InvalidProofShape)
+    ));
+
+    // As is a verifying key set up for another configuration.
+    let (other_config, _) = make_config(1);
+    let (_, other_verifying_key) = setup_batch(&other_config, &airs).expect("setup failed");
+    let mut challenger = Challenger::new(perm.clone());
+    assert!(matches!(
+        verify_batch_with_key(
+            &config,
+            &other_verifying_key,
+            &airs,
+            &mut challenger,
+            &proof,
+            &public_values
+        ),
+        Err(VerificationError::VerifyingKeyMismatch)
+    ));
+
+    public_values[2][0] += Val::ONE;
+    let mut challenger = Challenger::new(perm);
+    assert!(verify_batch(&config, &airs, &mut challenger, &proof, &public_values).is_err());
+}
+
+#[test]
+fn test_batch_invalid_table() {
+    let (config, perm) = make_config(2);
+    let (airs, mut traces, public_values) = make_batch();
+    traces[1].values.truncate(5 * 2);
+
+    let mut challenger = Challenger::new(perm);
+    let result = prove_batch(&config, &airs, &mut challenger, traces, &public_values);
+    assert!(matches!(
+        result,
+        Err(ProverError::Table { table: 1, error })
+            if matches!(*error, ProverError::InvalidTraceHeight { height: 5 })
+    ));
+}
+
+#[test]
+fn test_batch_proving_key_mismatch() {
+    let (config, perm) = make_config(2);
+    let (airs, traces, public_values) = make_batch();
+    let (proving_key, _) = setup_batch(&config, &airs[1..]).expect("setup failed");
+
+    let mut challenger = Challenger::new(perm);
+    assert!(matches!(
+        prove_batch_with_key(
+            &config,
+            &proving_key,
+            &airs,
+            &mut challenger,
+            traces,
+            &public_values
+        ),
+        Err(ProverError::BatchSizeMismatch {
+            expected: 3,
+            actual: 2
+        })
+    ));
+}
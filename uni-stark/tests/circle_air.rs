@@ -0,0 +1,138 @@
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir, PairBuilder};
+use p3_field::{Field, FieldAlgebra};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_uni_stark::testing::circle::{make_challenger, make_config, Val};
+use p3_uni_stark::{prove, prove_batch, verify, verify_batch};
+
+/// An AIR whose preprocessed column counts the rows, and whose main trace holds `x_i = start + i^2`
+/// and `y_i = i^2 x_i`, where `start` and the final `x` are public values.
+pub struct SquareSumAir {
+    log_height: usize,
+}
+
+impl SquareSumAir {
+    fn generate_trace<F: Field>(&self, start: u64) -> (RowMajorMatrix<F>, Vec<F>) {
+        let n = 1 << self.log_height;
+        let values = (0..n)
+            .flat_map(|i| {
+                let c = F::from_canonical_u64(i);
+                let x = F::from_canonical_u64(start) + c.square();
+                [x, c.square() * x]
+            })
+            .collect();
+        let end = F::from_canonical_u64(start + (n - 1) * (n - 1));
+        let public_values = vec![F::from_canonical_u64(start), end];
+        (RowMajorMatrix::new(values, 2), public_values)
+    }
+}
+
+impl<F: Field> BaseAir<F> for SquareSumAir {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        let values = (0..1 << self.log_height)
+            .map(F::from_canonical_usize)
+            .collect();
+        Some(RowMajorMatrix::new_col(values))
+    }
+
+    fn num_public_values(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues + PairBuilder> Air<AB> for SquareSumAir {
+    fn eval(&self, builder: &mut AB) {
+        let preprocessed = builder.preprocessed();
+        let main = builder.main();
+        let (start, end) = (builder.public_values()[0], builder.public_values()[1]);
+
+        let c = preprocessed.row_slice(0)[0];
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let (x, y, x_next) = (local[0], local[1], next[0]);
+
+        builder.assert_eq(y, x * c * c);
+        builder.when_first_row().assert_eq(x, start);
+        // (c + 1)^2 - c^2 = 2c + 1
+        builder
+            .when_transition()
+            .assert_eq(x_next, x + c.into().double() + AB::Expr::ONE);
+        builder.when_last_row().assert_eq(x, end);
+    }
+}
+
+#[test]
+fn test_circle_prove_verify() {
+    let config = make_config(1);
+    // The circle PCS needs at least 4 rows.
+    for log_height in 2..8 {
+        let air = SquareSumAir { log_height };
+        let (trace, public_values) = air.generate_trace::<Val>(5);
+
+        let proof = prove(&config, &air, &mut make_challenger(), trace, &public_values)
+            .expect("proving failed");
+
+        let serialized_proof = postcard::to_allocvec(&proof).expect("unable to serialize proof");
+        let proof = postcard::from_bytes(&serialized_proof).expect("unable to deserialize proof");
+
+        verify(
+            &config,
+            &air,
+            &mut make_challenger(),
+            &proof,
+            &public_values,
+        )
+        .expect("verification failed");
+    }
+}
+
+#[test]
+fn test_circle_wrong_public_value() {
+    let config = make_config(1);
+    let air = SquareSumAir { log_height: 5 };
+    let (trace, mut public_values) = air.generate_trace::<Val>(5);
+
+    let proof = prove(&config, &air, &mut make_challenger(), trace, &public_values)
+        .expect("proving failed");
+
+    public_values[1] += Val::ONE;
+    assert!(verify(
+        &config,
+        &air,
+        &mut make_challenger(),
+        &proof,
+        &public_values
+    )
+    .is_err());
+}
+
+#[test]
+fn test_circle_batch() {
+    let config = make_config(1);
+    let airs = [3, 6, 4].map(|log_height| SquareSumAir { log_height });
+    let (traces, public_values): (Vec<_>, Vec<_>) = airs
+        .iter()
+        .zip([0, 7, 100])
+        .map(|(air, start)| air.generate_trace::<Val>(start))
+        .unzip();
+
+    let proof = prove_batch(
+        &config,
+        &airs,
+        &mut make_challenger(),
+        traces,
+        &public_values,
+    )
+    .expect("proving failed");
+    verify_batch(
+        &config,
+        &airs,
+        &mut make_challenger(),
+        &proof,
+        &public_values,
+    )
+    .expect("verification failed");
+}
@@ -0,0 +1,376 @@
+use p3_air::{
+    Air, AirBuilder, AirBuilderWithPublicValues, BaseAir, ExtensionBuilder, MultiTableAirBuilder,
+    PairBuilder, PermutationAirBuilder,
+};
+use p3_field::{ExtensionField, Field, FieldAlgebra, FieldExtensionAlgebra, PackedValue};
+use p3_keccak_air::KeccakAir;
+use p3_lookup::RangeCheckAir;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_uni_stark::testing::{make_config, Challenge, Challenger, MyConfig, Val};
+use p3_uni_stark::{
+    get_symbolic_constraints, prove, verify, ConstraintDag, PackedChallenge, PackedVal,
+    ProverConstraintFolder, SymbolicAirBuilder,
+};
+use rand::{thread_rng, Rng};
+
+/// An AIR over columns `a` and `b` whose constraints are written with redundant subexpressions and
+/// identities for the constraint DAG to simplify away.
+pub struct RedundantAir;
+
+impl<F> BaseAir<F> for RedundantAir {
+    fn width(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for RedundantAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let (a, b): (AB::Expr, AB::Expr) = (local[0].into(), local[1].into());
+        let public = builder.public_values()[0];
+
+        // (a + b)^2 = (b + a)^2 always holds, so this folds to zero.
+        builder.assert_zero(
+            (a.clone() + b.clone()) * (a.clone() + b.clone())
+                - (b.clone() + a.clone()) * (b.clone() + a.clone()) * AB::Expr::ONE,
+        );
+        builder.assert_eq(
+            b.clone() * AB::Expr::ONE + AB::Expr::ZERO,
+            a.clone() * a.clone(),
+        );
+        builder.when_first_row().assert_eq(a.clone(), public);
+        builder
+            .when_transition()
+            .assert_eq(next[0], a.clone() * a.clone() + b.clone() * AB::Expr::ZERO);
+        builder
+            .when_last_row()
+            .assert_eq(-(-b), a.clone() * a - AB::Expr::ZERO);
+    }
+}
+
+/// An AIR mixing base and extension field values in every way the constraint DAG distinguishes.
+///
+/// Its permutation trace has a grand product `z`, checking that main column `b` is a permutation
+/// of main column `a` plus preprocessed column `c`, and a running sum `s` of `gamma c - a`, ending
+/// at the cumulative sum.
+pub struct ExtensionAir;
+
+impl<F: Field> BaseAir<F> for ExtensionAir {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        Some(RowMajorMatrix::new_col(
+            (0..8).map(F::from_canonical_u32).collect(),
+        ))
+    }
+
+    fn permutation_width(&self) -> usize {
+        2
+    }
+
+    fn num_permutation_challenges(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: MultiTableAirBuilder + PairBuilder> Air<AB> for ExtensionAir {
+    fn eval(&self, builder: &mut AB) {
+        let preprocessed = builder.preprocessed();
+        let main = builder.main();
+        let permutation = builder.permutation();
+        let (r, gamma): (AB::ExprEF, AB::ExprEF) = {
+            let challenges = builder.permutation_randomness();
+            (challenges[0].into(), challenges[1].into())
+        };
+        let cumulative_sum: AB::ExprEF = builder.cumulative_sum().into();
+
+        let c: AB::Expr = preprocessed.row_slice(0)[0].into();
+        let c_next: AB::Expr = preprocessed.row_slice(1)[0].into();
+        let (a, b): (AB::Expr, AB::Expr) = {
+            let local = main.row_slice(0);
+            (local[0].into(), local[1].into())
+        };
+        let (z, s): (AB::ExprEF, AB::ExprEF) = {
+            let local = permutation.row_slice(0);
+            (local[0].into(), local[1].into())
+        };
+        let (z_next, s_next): (AB::ExprEF, AB::ExprEF) = {
+            let next = permutation.row_slice(1);
+            (next[0].into(), next[1].into())
+        };
+
+        builder.when_first_row().assert_one_ext(z.clone());
+        builder.when_transition().assert_eq_ext(
+            z_next * (r.clone() - b.clone()),
+            z * (r - (a.clone() + c.clone())),
+        );
+        builder
+            .when_transition()
+            .assert_eq_ext(s_next - s.clone(), gamma * c_next - a.clone());
+        builder.when_last_row().assert_eq_ext(
+            -(AB::ExprEF::from(a.clone() * b.clone()) - s),
+            cumulative_sum + c.clone(),
+        );
+        builder.assert_zero(a * c - b);
+    }
+}
+
+/// An AIR over columns `a` and `b = a^2`, whose one constraint is scaled by an extension field
+/// constant, which a constraint DAG can't represent.
+pub struct ExtensionConstantAir;
+
+impl<F> BaseAir<F> for ExtensionConstantAir {
+    fn width(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: ExtensionBuilder> Air<AB> for ExtensionConstantAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let (a, b): (AB::Expr, AB::Expr) = (local[0].into(), local[1].into());
+        let scale = AB::ExprEF::from_base_fn(|i| AB::Expr::from_canonical_usize(i + 1));
+        builder.assert_zero_ext(scale * (b - a.clone() * a));
+    }
+}
+
+/// Wraps an AIR, choosing whether the prover evaluates its constraints from a compiled DAG.
+pub struct Compiled<A> {
+    air: A,
+    compile: bool,
+}
+
+impl<F: Field, A: BaseAir<F>> BaseAir<F> for Compiled<A> {
+    fn width(&self) -> usize {
+        self.air.width()
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        self.air.preprocessed_trace()
+    }
+
+    fn permutation_width(&self) -> usize {
+        self.air.permutation_width()
+    }
+
+    fn num_permutation_challenges(&self) -> usize {
+        self.air.num_permutation_challenges()
+    }
+
+    fn generate_permutation_trace<EF>(
+        &self,
+        preprocessed: Option<&RowMajorMatrix<F>>,
+        main: &RowMajorMatrix<F>,
+        challenges: &[EF],
+    ) -> Option<RowMajorMatrix<EF>>
+    where
+        EF: ExtensionField<F>,
+    {
+        self.air
+            .generate_permutation_trace(preprocessed, main, challenges)
+    }
+
+    fn compile_constraints(&self) -> bool {
+        self.compile
+    }
+
+    fn num_public_values(&self) -> usize {
+        self.air.num_public_values()
+    }
+}
+
+impl<AB: AirBuilder, A: Air<AB>> Air<AB> for Compiled<A> {
+    fn eval(&self, builder: &mut AB) {
+        self.air.eval(builder);
+    }
+}
+
+/// Check that evaluating the DAG compiled from `air`'s constraints on several random windows at
+/// once gives the same accumulators as evaluating `air` on each of them.
+fn do_test_dag_matches_air<A>(air: &A, num_public_values: usize)
+where
+    A: Air<SymbolicAirBuilder<Val>> + for<'a> Air<ProverConstraintFolder<'a, MyConfig>>,
+{
+    let mut rng = thread_rng();
+    let preprocessed_width = <A as BaseAir<Val>>::preprocessed_trace(air).map_or(0, |t| t.width());
+    let width = <A as BaseAir<Val>>::width(air);
+    let permutation_width = <A as BaseAir<Val>>::permutation_width(air);
+    let num_challenges = <A as BaseAir<Val>>::num_permutation_challenges(air);
+    let constraints =
+        get_symbolic_constraints::<Val, _>(air, preprocessed_width, num_public_values);
+    let dag = ConstraintDag::new(&constraints);
+    assert_eq!(dag.num_constraints(), constraints.len());
+
+    let alpha_powers = rng
+        .gen::<Challenge>()
+        .powers()
+        .take(dag.num_constraints())
+        .collect::<Vec<_>>();
+    let public_values = (0..num_public_values)
+        .map(|_| rng.gen())
+        .collect::<Vec<Val>>();
+    let permutation_challenges = (0..num_challenges)
+        .map(|_| rand_packed_ext(&mut rng))
+        .collect::<Vec<_>>();
+    let windows = (0..3)
+        .map(|_| Window {
+            preprocessed: rand_window(preprocessed_width, || rand_packed(&mut rng)),
+            main: rand_window(width, || rand_packed(&mut rng)),
+            permutation: rand_window(permutation_width, || rand_packed_ext(&mut rng)),
+            cumulative_sum: rand_packed_ext(&mut rng),
+            is_last_row: rand_packed(&mut rng),
+            is_transition: rand_packed(&mut rng),
+        })
+        .collect::<Vec<_>>();
+    let folders = || {
+        windows
+            .iter()
+            .map(|window| ProverConstraintFolder::<MyConfig> {
+                preprocessed: window.preprocessed.as_view(),
+                main: window.main.as_view(),
+                permutation: window.permutation.as_view(),
+                permutation_challenges: &permutation_challenges,
+                cumulative_sum: window.cumulative_sum,
+                public_values: &public_values,
+                is_first_row: PackedVal::<MyConfig>::from_fn(|i| Val::from_bool(i == 0)),
+                is_last_row: window.is_last_row,
+                is_transition: window.is_transition,
+                alpha_powers: &alpha_powers,
+                accumulator: PackedChallenge::<MyConfig>::ZERO,
+                constraint_index: 0,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut expected = folders();
+    let mut actual = folders();
+    for folder in &mut expected {
+        air.eval(folder);
+    }
+    dag.eval(&mut actual);
+    for (expected, actual) in expected.iter().zip(&actual) {
+        assert_eq!(actual.accumulator, expected.accumulator);
+        assert_eq!(actual.constraint_index, expected.constraint_index);
+    }
+}
+
+/// The values a `ProverConstraintFolder` reads, other than those shared by every window.
+struct Window {
+    preprocessed: RowMajorMatrix<PackedVal<MyConfig>>,
+    main: RowMajorMatrix<PackedVal<MyConfig>>,
+    permutation: RowMajorMatrix<PackedChallenge<MyConfig>>,
+    cumulative_sum: PackedChallenge<MyConfig>,
+    is_last_row: PackedVal<MyConfig>,
+    is_transition: PackedVal<MyConfig>,
+}
+
+/// A random window of two rows of the given width.
+fn rand_window<T: Clone + Send + Sync>(
+    width: usize,
+    mut rand: impl FnMut() -> T,
+) -> RowMajorMatrix<T> {
+    RowMajorMatrix::new((0..2 * width).map(|_| rand()).collect(), width)
+}
+
+fn rand_packed(rng: &mut impl Rng) -> PackedVal<MyConfig> {
+    PackedVal::<MyConfig>::from_fn(|_| rng.gen())
+}
+
+fn rand_packed_ext(rng: &mut impl Rng) -> PackedChallenge<MyConfig> {
+    PackedChallenge::<MyConfig>::from_base_fn(|_| rand_packed(rng))
+}
+
+#[test]
+fn test_dag_matches_redundant_air() {
+    do_test_dag_matches_air(&RedundantAir, 1);
+}
+
+#[test]
+fn test_dag_matches_keccak_air() {
+    do_test_dag_matches_air(&KeccakAir {}, 0);
+}
+
+#[test]
+fn test_dag_matches_extension_air() {
+    do_test_dag_matches_air(&ExtensionAir, 0);
+}
+
+#[test]
+fn test_dag_matches_logup_air() {
+    // LogUp lookups into a preprocessed table, sent from two main columns.
+    do_test_dag_matches_air(&RangeCheckAir::new(3, 2).with_bus(5), 0);
+}
+
+#[test]
+fn test_dag_quotient_matches_air_quotient() {
+    let (config, perm) = make_config(2);
+    let mut rng = thread_rng();
+    let values = (0..32).map(|_| rng.gen_range(0..16)).collect::<Vec<u32>>();
+
+    // Without zero knowledge, proving is deterministic, so the quotients can be compared directly.
+    let proofs = [false, true].map(|compile| {
+        let air = Compiled {
+            air: RangeCheckAir::new(4, 2),
+            compile,
+        };
+        let trace = air.air.generate_trace::<Val>(&values);
+        let mut challenger = Challenger::new(perm.clone());
+        prove(&config, &air, &mut challenger, trace, &vec![]).expect("proving failed")
+    });
+    let [air_proof, dag_proof] = &proofs;
+    assert_eq!(
+        dag_proof.commitments().quotient_chunks(),
+        air_proof.commitments().quotient_chunks()
+    );
+    assert_eq!(
+        dag_proof.opened_values().quotient_chunks(),
+        air_proof.opened_values().quotient_chunks()
+    );
+}
+
+#[test]
+fn test_extension_constant_air() {
+    // The prover evaluates this AIR's constraints directly, since it doesn't opt in to compiling
+    // them, and its extension field constant isn't lost.
+    let (config, perm) = make_config(1);
+    let trace = RowMajorMatrix::new(
+        (0..8u32)
+            .flat_map(|i| [i, i * i].map(Val::from_canonical_u32))
+            .collect(),
+        2,
+    );
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(
+        &config,
+        &ExtensionConstantAir,
+        &mut challenger,
+        trace,
+        &vec![],
+    )
+    .expect("proving failed");
+
+    let mut challenger = Challenger::new(perm);
+    verify(
+        &config,
+        &ExtensionConstantAir,
+        &mut challenger,
+        &proof,
+        &vec![],
+    )
+    .expect("verification failed");
+}
+
+#[test]
+fn test_dag_simplifies_redundant_air() {
+    let constraints = get_symbolic_constraints::<Val, _>(&RedundantAir, 0, 1);
+    let dag = ConstraintDag::new(&constraints);
+    assert_eq!(dag.num_constraints(), 5);
+    // The constant zero, `a`, `b`, `a^2`, `b - a^2`, the public value, `a - public`, `next[0]` and
+    // `next[0] - a^2`, with the last row constraint sharing `b - a^2` with the second.
+    assert_eq!(dag.num_nodes(), 9);
+}
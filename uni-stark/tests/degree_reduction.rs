@@ -0,0 +1,165 @@
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir, PairBuilder};
+use p3_field::{Field, FieldAlgebra};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_uni_stark::testing::{make_config, Challenger, Val};
+use p3_uni_stark::{
+    check_all_constraints, get_log_quotient_degree, get_max_constraint_degree, prove, verify,
+    DegreeReducedAir, DegreeReductionError,
+};
+
+/// An AIR with a preprocessed row counter `c` and main columns `x = start + c` and
+/// `y = (x + c)^7`, whose last row satisfies `x^3 y = end`. Its constraints have degree up to 7.
+pub struct PowerAir {
+    log_height: usize,
+}
+
+impl PowerAir {
+    fn generate_trace<F: Field>(&self, start: u64) -> (RowMajorMatrix<F>, Vec<F>) {
+        let n = 1 << self.log_height;
+        let values = (0..n)
+            .flat_map(|i| {
+                let x = F::from_canonical_u64(start + i);
+                [x, (x + F::from_canonical_u64(i)).exp_u64(7)]
+            })
+            .collect::<Vec<_>>();
+        let (x, y) = (values[2 * n as usize - 2], values[2 * n as usize - 1]);
+        let public_values = vec![F::from_canonical_u64(start), x.cube() * y];
+        (RowMajorMatrix::new(values, 2), public_values)
+    }
+}
+
+impl<F: Field> BaseAir<F> for PowerAir {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        let values = (0..1 << self.log_height)
+            .map(F::from_canonical_usize)
+            .collect();
+        Some(RowMajorMatrix::new_col(values))
+    }
+
+    fn num_public_values(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues + PairBuilder> Air<AB> for PowerAir {
+    fn eval(&self, builder: &mut AB) {
+        let preprocessed = builder.preprocessed();
+        let main = builder.main();
+        let (start, end) = (builder.public_values()[0], builder.public_values()[1]);
+
+        let c = preprocessed.row_slice(0)[0];
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let (x, y): (AB::Expr, AB::Expr) = (local[0].into(), local[1].into());
+
+        builder.when_first_row().assert_eq(x.clone(), start);
+        builder
+            .when_transition()
+            .assert_eq(next[0], x.clone() + AB::Expr::ONE);
+        builder.assert_eq(y.clone(), (x.clone() + c).exp_u64(7));
+        builder.when_last_row().assert_eq(x.cube() * y, end);
+    }
+}
+
+#[test]
+fn test_reduced_degree() {
+    let air = PowerAir { log_height: 3 };
+    assert_eq!(get_max_constraint_degree::<Val, _>(&air, 1, 2), 7);
+
+    for max_degree in [2, 3, 4] {
+        let reduced = DegreeReducedAir::<Val>::new(&air, max_degree).unwrap();
+        assert!(reduced.num_aux_columns() > 0);
+        assert_eq!(
+            BaseAir::<Val>::width(&reduced),
+            2 + reduced.num_aux_columns()
+        );
+        assert!(get_max_constraint_degree::<Val, _>(&reduced, 1, 2) <= max_degree);
+
+        let (trace, public_values) = air.generate_trace::<Val>(3);
+        let trace = reduced.extend_trace(&trace, &public_values);
+        let preprocessed = BaseAir::<Val>::preprocessed_trace(&reduced).unwrap();
+        let report = check_all_constraints::<_, Val, _>(
+            &reduced,
+            Some(&preprocessed),
+            &trace,
+            None,
+            &[],
+            Val::ZERO,
+            &public_values,
+        )
+        .unwrap();
+        assert!(report.is_ok(), "{report}");
+    }
+}
+
+#[test]
+fn test_reduced_aux_columns_are_constrained() {
+    let air = PowerAir { log_height: 3 };
+    let reduced = DegreeReducedAir::<Val>::new(&air, 3).unwrap();
+    let (trace, public_values) = air.generate_trace::<Val>(3);
+    let mut trace = reduced.extend_trace(&trace, &public_values);
+    let width = trace.width();
+    trace.values[4 * width + width - 1] += Val::ONE;
+
+    let preprocessed = BaseAir::<Val>::preprocessed_trace(&reduced).unwrap();
+    let report = check_all_constraints::<_, Val, _>(
+        &reduced,
+        Some(&preprocessed),
+        &trace,
+        None,
+        &[],
+        Val::ZERO,
+        &public_values,
+    )
+    .unwrap();
+    assert!(report.failures.iter().any(|failure| failure.row == 4));
+}
+
+#[test]
+fn test_low_degree_air_is_unchanged() {
+    let air = PowerAir { log_height: 3 };
+    let reduced = DegreeReducedAir::<Val>::new(&air, 7).unwrap();
+    assert_eq!(reduced.num_aux_columns(), 0);
+}
+
+#[test]
+fn test_max_degree_too_small() {
+    let air = PowerAir { log_height: 3 };
+    assert_eq!(
+        DegreeReducedAir::<Val>::new(&air, 1).unwrap_err(),
+        DegreeReductionError::MaxDegreeTooSmall { max_degree: 1 }
+    );
+}
+
+#[test]
+fn test_prove_reduced() {
+    let air = PowerAir { log_height: 4 };
+    let reduced = DegreeReducedAir::<Val>::new(&air, 3).unwrap();
+    assert_eq!(get_log_quotient_degree::<Val, _>(&air, 1, 2, false), 3);
+    assert_eq!(get_log_quotient_degree::<Val, _>(&reduced, 1, 2, false), 1);
+
+    let (config, perm) = make_config(1);
+
+    let (trace, public_values) = air.generate_trace::<Val>(5);
+    let trace = reduced.extend_trace(&trace, &public_values);
+    let proof = prove(
+        &config,
+        &reduced,
+        &mut Challenger::new(perm.clone()),
+        trace,
+        &public_values,
+    )
+    .expect("proving failed");
+    verify(
+        &config,
+        &reduced,
+        &mut Challenger::new(perm),
+        &proof,
+        &public_values,
+    )
+    .expect("verification failed");
+}
@@ -0,0 +1,99 @@
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::{Field, FieldAlgebra};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_uni_stark::testing::{make_config_with_perm, Challenger, Perm, Val};
+use p3_uni_stark::{estimate_proof, prove, verify};
+use rand::thread_rng;
+
+const WIDTH: usize = 6;
+
+/// Each column is cubed and incremented from one row to the next.
+struct CubesAir;
+
+impl<F> BaseAir<F> for CubesAir {
+    fn width(&self) -> usize {
+        WIDTH
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for CubesAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        for (&x, &y) in local.iter().zip(next.iter()) {
+            let x: AB::Expr = x.into();
+            builder
+                .when_transition()
+                .assert_eq(y, x.clone() * x.clone() * x + AB::Expr::ONE);
+        }
+    }
+}
+
+fn generate_trace<F: Field>(log_height: usize) -> RowMajorMatrix<F> {
+    let mut values = (0..WIDTH).map(F::from_canonical_usize).collect::<Vec<_>>();
+    for row in 1..1 << log_height {
+        let x = values[(row - 1) * WIDTH..row * WIDTH].to_vec();
+        values.extend(x.into_iter().map(|x| x.cube() + F::ONE));
+    }
+    RowMajorMatrix::new(values, WIDTH)
+}
+
+#[test]
+fn test_estimate_matches_proof() {
+    let perm = Perm::new_from_rng_128(&mut thread_rng());
+    let config = make_config_with_perm(&perm, 2, 28, 8);
+    let log_degree = 8;
+
+    let estimate = estimate_proof(&config, &CubesAir, log_degree);
+    assert_eq!(estimate.quotient_degree(), 2);
+    assert_eq!(estimate.main_width, WIDTH);
+    assert_eq!(estimate.preprocessed_width, 0);
+    assert_eq!(estimate.permutation_width, 0);
+    assert_eq!(estimate.quotient_width, 2 * 4);
+
+    let trace = generate_trace::<Val>(log_degree);
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &CubesAir, &mut challenger, trace, &vec![]).expect("proving failed");
+    let mut challenger = Challenger::new(perm);
+    verify(&config, &CubesAir, &mut challenger, &proof, &vec![]).expect("verification failed");
+
+    // FRI folds the LDE of height 2^10 down to the blowup of 2^2.
+    let fri_proof = proof.opening_proof();
+    assert_eq!(fri_proof.commit_phase_commits.len(), 8);
+    assert_eq!(fri_proof.commit_phase_openings.len(), 8);
+    assert_eq!(fri_proof.commit_phase_openings[0].sibling_values.len(), 28);
+
+    // Postcard encodes field elements as varints of up to 5 bytes, rather than the 4 bytes the
+    // estimate assumes, and adds length prefixes.
+    let proof_bytes = postcard::to_allocvec(&proof)
+        .expect("unable to serialize proof")
+        .len();
+    assert!(estimate.proof_bytes() <= proof_bytes);
+    assert!(proof_bytes <= estimate.proof_bytes() * 5 / 4 + 1024);
+}
+
+#[test]
+fn test_estimate_scales_with_queries() {
+    let perm = Perm::new_from_rng_128(&mut thread_rng());
+    let estimate = estimate_proof(&make_config_with_perm(&perm, 2, 20, 8), &CubesAir, 10);
+    let more_queries = estimate_proof(&make_config_with_perm(&perm, 2, 40, 8), &CubesAir, 10);
+
+    // Query openings and the verifier's work grow with the number of queries, but less than
+    // linearly, as more queries share Merkle path siblings. Commitments and the prover's hashing
+    // don't depend on it.
+    assert!(more_queries.input_proof_bytes > estimate.input_proof_bytes);
+    assert!(more_queries.input_proof_bytes < 2 * estimate.input_proof_bytes);
+    assert!(more_queries.verifier_hashes.compressions > estimate.verifier_hashes.compressions);
+    assert!(more_queries.verifier_hashes.compressions < 2 * estimate.verifier_hashes.compressions);
+    assert_eq!(more_queries.commitments_bytes, estimate.commitments_bytes);
+    assert_eq!(
+        more_queries.opened_values_bytes,
+        estimate.opened_values_bytes
+    );
+    assert_eq!(more_queries.prover_hashes, estimate.prover_hashes);
+
+    // The prover hashes every row of the trace and quotient LDEs, of height 2^12, and of the
+    // commit phase codewords, of heights 2^11 down to 2^2.
+    assert_eq!(estimate.prover_hashes.hashes, 2 * (1 << 12) + (1 << 12) - 4);
+}
@@ -1,18 +1,15 @@
 use std::borrow::Borrow;
 
 use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
-use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
-use p3_challenger::DuplexChallenger;
-use p3_commit::ExtensionMmcs;
-use p3_dft::Radix2DitParallel;
-use p3_field::extension::BinomialExtensionField;
+use p3_baby_bear::BabyBear;
 use p3_field::{Field, FieldAlgebra, PrimeField64};
-use p3_fri::{FriConfig, TwoAdicFriPcs};
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::Matrix;
-use p3_merkle_tree::MerkleTreeMmcs;
-use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
-use p3_uni_stark::{prove, verify, StarkConfig};
+use p3_uni_stark::testing::{make_config_with_perm, Challenger, Perm, Val};
+use p3_uni_stark::{
+    check_all_constraints, prove, prove_with_key, setup, verify, verify_with_key, ProverError,
+    VerificationError,
+};
 use rand::thread_rng;
 
 /// For testing the public values feature
@@ -22,6 +19,14 @@ impl<F> BaseAir<F> for FibonacciAir {
     fn width(&self) -> usize {
         NUM_FIBONACCI_COLS
     }
+
+    fn column_names(&self) -> Option<Vec<String>> {
+        Some(vec!["left".into(), "right".into()])
+    }
+
+    fn num_public_values(&self) -> usize {
+        3
+    }
 }
 
 impl<AB: AirBuilderWithPublicValues> Air<AB> for FibonacciAir {
@@ -39,10 +44,12 @@ impl<AB: AirBuilderWithPublicValues> Air<AB> for FibonacciAir {
 
         let mut when_first_row = builder.when_first_row();
 
+        when_first_row.label("initial values");
         when_first_row.assert_eq(local.left, a);
         when_first_row.assert_eq(local.right, b);
 
         let mut when_transition = builder.when_transition();
+        when_transition.label("transition");
 
         // a' <- b
         when_transition.assert_eq(local.right, next.left);
@@ -50,10 +57,39 @@ impl<AB: AirBuilderWithPublicValues> Air<AB> for FibonacciAir {
         // b' <- a + b
         when_transition.assert_eq(local.left + local.right, next.right);
 
+        builder.label("result");
         builder.when_last_row().assert_eq(local.right, x);
     }
 }
 
+/// An AIR of the same shape as `FibonacciAir`, which only checks the initial values.
+pub struct InitialValuesAir {}
+
+impl<F> BaseAir<F> for InitialValuesAir {
+    fn width(&self) -> usize {
+        NUM_FIBONACCI_COLS
+    }
+
+    fn num_public_values(&self) -> usize {
+        3
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for InitialValuesAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let pis = builder.public_values();
+        let (a, b) = (pis[0], pis[1]);
+
+        let local = main.row_slice(0);
+        let local: &FibonacciRow<AB::Var> = (*local).borrow();
+
+        let mut when_first_row = builder.when_first_row();
+        when_first_row.assert_eq(local.left, a);
+        when_first_row.assert_eq(local.right, b);
+    }
+}
+
 pub fn generate_trace_rows<F: PrimeField64>(a: u64, b: u64, n: usize) -> RowMajorMatrix<F> {
     assert!(n.is_power_of_two());
 
@@ -98,43 +134,19 @@ impl<F> Borrow<FibonacciRow<F>> for [F] {
     }
 }
 
-type Val = BabyBear;
-type Perm = Poseidon2BabyBear<16>;
-type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
-type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
-type ValMmcs =
-    MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
-type Challenge = BinomialExtensionField<Val, 4>;
-type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
-type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
-type Dft = Radix2DitParallel<Val>;
-type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
-type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
-
 /// n-th Fibonacci number expected to be x
 fn test_public_value_impl(n: usize, x: u64) {
     let perm = Perm::new_from_rng_128(&mut thread_rng());
-    let hash = MyHash::new(perm.clone());
-    let compress = MyCompress::new(perm.clone());
-    let val_mmcs = ValMmcs::new(hash, compress);
-    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
-    let dft = Dft::default();
     let trace = generate_trace_rows::<Val>(0, 1, n);
-    let fri_config = FriConfig {
-        log_blowup: 2,
-        num_queries: 28,
-        proof_of_work_bits: 8,
-        mmcs: challenge_mmcs,
-    };
-    let pcs = Pcs::new(dft, val_mmcs, fri_config);
-    let config = MyConfig::new(pcs);
+    let config = make_config_with_perm(&perm, 2, 28, 8);
     let mut challenger = Challenger::new(perm.clone());
     let pis = vec![
         BabyBear::from_canonical_u64(0),
         BabyBear::from_canonical_u64(1),
         BabyBear::from_canonical_u64(x),
     ];
-    let proof = prove(&config, &FibonacciAir {}, &mut challenger, trace, &pis);
+    let proof =
+        prove(&config, &FibonacciAir {}, &mut challenger, trace, &pis).expect("proving failed");
     let mut challenger = Challenger::new(perm);
     verify(&config, &FibonacciAir {}, &mut challenger, &proof, &pis).expect("verification failed");
 }
@@ -151,28 +163,272 @@ fn test_public_value() {
 
 #[cfg(debug_assertions)]
 #[test]
-#[should_panic(expected = "assertion `left == right` failed: constraints had nonzero value")]
 fn test_incorrect_public_value() {
     let perm = Perm::new_from_rng_128(&mut thread_rng());
-    let hash = MyHash::new(perm.clone());
-    let compress = MyCompress::new(perm.clone());
-    let val_mmcs = ValMmcs::new(hash, compress);
-    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
-    let dft = Dft::default();
-    let fri_config = FriConfig {
-        log_blowup: 2,
-        num_queries: 28,
-        proof_of_work_bits: 8,
-        mmcs: challenge_mmcs,
-    };
     let trace = generate_trace_rows::<Val>(0, 1, 1 << 3);
-    let pcs = Pcs::new(dft, val_mmcs, fri_config);
-    let config = MyConfig::new(pcs);
+    let config = make_config_with_perm(&perm, 2, 28, 8);
     let mut challenger = Challenger::new(perm.clone());
     let pis = vec![
         BabyBear::from_canonical_u64(0),
         BabyBear::from_canonical_u64(1),
         BabyBear::from_canonical_u64(123_123), // incorrect result
     ];
-    prove(&config, &FibonacciAir {}, &mut challenger, trace, &pis);
+    // The last row constraint, asserted fifth, fails.
+    assert!(matches!(
+        prove(&config, &FibonacciAir {}, &mut challenger, trace, &pis),
+        Err(ProverError::ConstraintFailure {
+            row: 7,
+            constraint: 4,
+            label: Some("result"),
+        })
+    ));
+}
+
+#[test]
+fn test_invalid_trace_shape() {
+    let perm = Perm::new_from_rng_128(&mut thread_rng());
+    let config = make_config_with_perm(&perm, 2, 28, 8);
+    let pis = vec![
+        BabyBear::from_canonical_u64(0),
+        BabyBear::from_canonical_u64(1),
+        BabyBear::from_canonical_u64(21),
+    ];
+    let prove = |trace, pis: &Vec<Val>| {
+        let mut challenger = Challenger::new(perm.clone());
+        prove(&config, &FibonacciAir {}, &mut challenger, trace, pis)
+    };
+
+    let mut trace = generate_trace_rows::<Val>(0, 1, 1 << 3);
+    trace.values.truncate(6 * NUM_FIBONACCI_COLS);
+    assert!(matches!(
+        prove(trace, &pis),
+        Err(ProverError::InvalidTraceHeight { height: 6 })
+    ));
+
+    let trace = RowMajorMatrix::new(Val::zero_vec(8 * 3), 3);
+    assert!(matches!(
+        prove(trace, &pis),
+        Err(ProverError::TraceWidthMismatch {
+            expected: 2,
+            actual: 3
+        })
+    ));
+
+    let trace = generate_trace_rows::<Val>(0, 1, 1 << 3);
+    assert!(matches!(
+        prove(trace, &pis[..2].to_vec()),
+        Err(ProverError::PublicValuesLengthMismatch {
+            expected: 3,
+            actual: 2
+        })
+    ));
+}
+
+#[test]
+fn test_proof_bound_to_config() {
+    let perm = Perm::new_from_rng_128(&mut thread_rng());
+    let config = make_config_with_perm(&perm, 2, 28, 8);
+    let pis = vec![
+        BabyBear::from_canonical_u64(0),
+        BabyBear::from_canonical_u64(1),
+        BabyBear::from_canonical_u64(21),
+    ];
+    let trace = generate_trace_rows::<Val>(0, 1, 1 << 3);
+    let (proving_key, verifying_key) = setup(&config, &FibonacciAir {}).expect("setup failed");
+    // The FRI parameters, then the cap heights of the input and FRI Merkle trees.
+    assert_eq!(verifying_key.pcs_parameters(), [2, 1, 0, 28, 8, 0, 0]);
+    assert_eq!(verifying_key.air_fingerprint().width(), NUM_FIBONACCI_COLS);
+    // The PCS parameters and the AIR's dimensions, each prefixed by their count, the 16 limbs of
+    // the constraint digest, and an empty list of preprocessed dimensions.
+    assert_eq!(verifying_key.instance_data().len(), 8 + 3 + 16 + 1);
+    assert_eq!(
+        proving_key.verifying_key().instance_data(),
+        verifying_key.instance_data()
+    );
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove_with_key(
+        &config,
+        &proving_key,
+        &FibonacciAir {},
+        &mut challenger,
+        trace,
+        &pis,
+    )
+    .expect("proving failed");
+    let mut challenger = Challenger::new(perm.clone());
+    verify(&config, &FibonacciAir {}, &mut challenger, &proof, &pis).expect("verification failed");
+
+    // The proof-of-work witness also has 7 leading zero bits, but the parameters are part of the
+    // transcript, so a verifier with a weaker configuration still rejects the proof.
+    let weaker_config = make_config_with_perm(&perm, 2, 28, 7);
+    let mut challenger = Challenger::new(perm.clone());
+    assert!(verify(
+        &weaker_config,
+        &FibonacciAir {},
+        &mut challenger,
+        &proof,
+        &pis
+    )
+    .is_err());
+
+    // A verifying key is only accepted with the configuration and AIR it was set up for.
+    let mut challenger = Challenger::new(perm.clone());
+    assert!(matches!(
+        verify_with_key(
+            &weaker_config,
+            &verifying_key,
+            &FibonacciAir {},
+            &mut challenger,
+            &proof,
+            &pis
+        ),
+        Err(VerificationError::VerifyingKeyMismatch)
+    ));
+    let mut challenger = Challenger::new(perm.clone());
+    assert!(matches!(
+        verify_with_key(
+            &config,
+            &verifying_key,
+            &InitialValuesAir {},
+            &mut challenger,
+            &proof,
+            &pis
+        ),
+        Err(VerificationError::VerifyingKeyMismatch)
+    ));
+    let (_, other_verifying_key) = setup(&config, &InitialValuesAir {}).expect("setup failed");
+    let mut challenger = Challenger::new(perm);
+    assert!(matches!(
+        verify_with_key(
+            &config,
+            &other_verifying_key,
+            &FibonacciAir {},
+            &mut challenger,
+            &proof,
+            &pis
+        ),
+        Err(VerificationError::VerifyingKeyMismatch)
+    ));
+}
+
+#[test]
+fn test_constraint_report() {
+    let pis = vec![
+        BabyBear::from_canonical_u64(0),
+        BabyBear::from_canonical_u64(1),
+        BabyBear::from_canonical_u64(21),
+    ];
+    let mut trace = generate_trace_rows::<Val>(0, 1, 1 << 3);
+    // Corrupt `right` on row 3, breaking the transitions into and out of it.
+    trace.values[3 * NUM_FIBONACCI_COLS + 1] += Val::ONE;
+
+    let report = check_all_constraints::<_, Val, _>(
+        &FibonacciAir {},
+        None,
+        &trace,
+        None,
+        &[],
+        Val::ZERO,
+        &pis,
+    )
+    .unwrap();
+    assert!(!report.is_ok());
+    assert_eq!(report.height, 8);
+    assert_eq!(report.num_constraints, 5);
+    let failures = report
+        .failures
+        .iter()
+        .map(|failure| {
+            (
+                failure.row,
+                failure.constraint,
+                failure.label,
+                failure.value,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        failures,
+        [
+            (2, 3, Some("transition"), Val::NEG_ONE),
+            (3, 2, Some("transition"), Val::ONE),
+            (3, 3, Some("transition"), Val::ONE),
+        ]
+    );
+    assert!(report
+        .to_string()
+        .contains("row 2, constraint 3 (transition): "));
+    // The failure names the columns the constraint reads, with `'` marking the next row.
+    assert_eq!(
+        report.failures[0]
+            .columns
+            .iter()
+            .map(|column| (column.index, column.next_row, column.value))
+            .collect::<Vec<_>>(),
+        [
+            (0, false, Val::ONE),
+            (1, false, Val::TWO),
+            (1, true, Val::from_canonical_u64(4)),
+        ]
+    );
+    assert!(report
+        .to_string()
+        .contains("[left = 1, right = 2, right' = 4]"));
+
+    let trace = generate_trace_rows::<Val>(0, 1, 1 << 3);
+    let report = check_all_constraints::<_, Val, _>(
+        &FibonacciAir {},
+        None,
+        &trace,
+        None,
+        &[],
+        Val::ZERO,
+        &pis,
+    )
+    .unwrap();
+    assert!(report.is_ok());
+}
+
+#[test]
+fn test_constraint_report_height_mismatch() {
+    let pis = vec![
+        BabyBear::ZERO,
+        BabyBear::ONE,
+        BabyBear::from_canonical_u64(21),
+    ];
+    let trace = generate_trace_rows::<Val>(0, 1, 1 << 3);
+    let preprocessed = RowMajorMatrix::new_col(Val::zero_vec(1 << 2));
+    assert!(matches!(
+        check_all_constraints::<_, Val, _>(
+            &FibonacciAir {},
+            Some(&preprocessed),
+            &trace,
+            None,
+            &[],
+            Val::ZERO,
+            &pis,
+        ),
+        Err(ProverError::TraceHeightMismatch {
+            expected: 4,
+            actual: 8
+        })
+    ));
+
+    let permutation = RowMajorMatrix::new_col(Val::zero_vec(1 << 4));
+    assert!(matches!(
+        check_all_constraints(
+            &FibonacciAir {},
+            None,
+            &trace,
+            Some(&permutation),
+            &[],
+            Val::ZERO,
+            &pis,
+        ),
+        Err(ProverError::PermutationTraceHeightMismatch {
+            expected: 8,
+            actual: 16
+        })
+    ));
 }
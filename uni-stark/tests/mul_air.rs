@@ -3,24 +3,13 @@ use std::marker::PhantomData;
 
 use itertools::Itertools;
 use p3_air::{Air, AirBuilder, BaseAir};
-use p3_baby_bear::{BabyBear, Poseidon2BabyBear};
-use p3_challenger::{DuplexChallenger, HashChallenger, SerializingChallenger32};
-use p3_circle::CirclePcs;
 use p3_commit::testing::TrivialPcs;
-use p3_commit::ExtensionMmcs;
+use p3_commit::ProofFormatError;
 use p3_dft::Radix2DitParallel;
-use p3_field::extension::BinomialExtensionField;
 use p3_field::{Field, FieldAlgebra};
-use p3_fri::{FriConfig, TwoAdicFriPcs};
-use p3_keccak::Keccak256Hash;
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::Matrix;
-use p3_merkle_tree::MerkleTreeMmcs;
-use p3_mersenne_31::Mersenne31;
-use p3_symmetric::{
-    CompressionFunctionFromHasher, PaddingFreeSponge, SerializingHasher32, TruncatedPermutation,
-};
-use p3_uni_stark::{prove, verify, StarkConfig, StarkGenericConfig, Val};
+use p3_uni_stark::{prove, verify, Proof, StarkConfig, StarkGenericConfig, Val};
 use rand::distributions::{Distribution, Standard};
 use rand::{thread_rng, Rng};
 
@@ -126,13 +115,13 @@ where
     let trace = air.random_valid_trace(log_height, true);
 
     let mut p_challenger = challenger.clone();
-    let proof = prove(&config, &air, &mut p_challenger, trace, &vec![]);
+    let proof = prove(&config, &air, &mut p_challenger, trace, &vec![]).expect("proving failed");
 
-    let serialized_proof = postcard::to_allocvec(&proof).expect("unable to serialize proof");
+    let serialized_proof = proof.to_bytes(&config).expect("unable to serialize proof");
     tracing::debug!("serialized_proof len: {} bytes", serialized_proof.len());
 
     let deserialized_proof =
-        postcard::from_bytes(&serialized_proof).expect("unable to deserialize proof");
+        Proof::from_bytes(&config, &serialized_proof).expect("unable to deserialize proof");
 
     let mut v_challenger = challenger.clone();
     verify(
@@ -145,20 +134,13 @@ where
 }
 
 fn do_test_bb_trivial(degree: u64, log_n: usize) -> Result<(), impl Debug> {
-    type Val = BabyBear;
-    type Challenge = BinomialExtensionField<Val, 4>;
+    use p3_uni_stark::testing::{Challenge, Challenger, Perm, Val};
 
-    type Perm = Poseidon2BabyBear<16>;
     let perm = Perm::new_from_rng_128(&mut thread_rng());
 
-    type Dft = Radix2DitParallel<Val>;
-    let dft = Dft::default();
-
-    type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
-
     type Pcs = TrivialPcs<Val, Radix2DitParallel<Val>>;
     let pcs = TrivialPcs {
-        dft,
+        dft: Radix2DitParallel::default(),
         log_n,
         _phantom: PhantomData,
     };
@@ -189,42 +171,45 @@ fn prove_bb_trivial_deg4() -> Result<(), impl Debug> {
     do_test_bb_trivial(4, 8)
 }
 
-fn do_test_bb_twoadic(log_blowup: usize, degree: u64, log_n: usize) -> Result<(), impl Debug> {
-    type Val = BabyBear;
-    type Challenge = BinomialExtensionField<Val, 4>;
+#[test]
+fn proof_for_other_config_is_rejected() {
+    use p3_uni_stark::testing::{Challenge, Challenger, Perm, Val};
 
-    type Perm = Poseidon2BabyBear<16>;
     let perm = Perm::new_from_rng_128(&mut thread_rng());
 
-    type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
-    let hash = MyHash::new(perm.clone());
-
-    type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
-    let compress = MyCompress::new(perm.clone());
-
-    type ValMmcs =
-        MerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, MyHash, MyCompress, 8>;
-    let val_mmcs = ValMmcs::new(hash, compress);
-
-    type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
-    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
-
-    type Dft = Radix2DitParallel<Val>;
-    let dft = Dft::default();
+    type Pcs = TrivialPcs<Val, Radix2DitParallel<Val>>;
+    type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
+    let config = |log_n| {
+        MyConfig::new(Pcs {
+            dft: Radix2DitParallel::default(),
+            log_n,
+            _phantom: PhantomData,
+        })
+    };
 
-    type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+    let air = MulAir::default();
+    let trace = air.random_valid_trace(1 << 8, true);
+    let proof = prove(&config(8), &air, &mut Challenger::new(perm), trace, &vec![])
+        .expect("proving failed");
+    let bytes = proof.to_bytes(&config(8)).unwrap();
+
+    assert!(Proof::from_bytes(&config(8), &bytes).is_ok());
+    assert!(matches!(
+        Proof::from_bytes(&config(9), &bytes),
+        Err(ProofFormatError::ConfigMismatch { .. })
+    ));
+    // Plain `postcard` encodings have no header.
+    assert!(matches!(
+        Proof::from_bytes(&config(8), &postcard::to_allocvec(&proof).unwrap()),
+        Err(ProofFormatError::BadMagic)
+    ));
+}
 
-    let fri_config = FriConfig {
-        log_blowup,
-        num_queries: 40,
-        proof_of_work_bits: 8,
-        mmcs: challenge_mmcs,
-    };
-    type Pcs = TwoAdicFriPcs<Val, Dft, ValMmcs, ChallengeMmcs>;
-    let pcs = Pcs::new(dft, val_mmcs, fri_config);
+fn do_test_bb_twoadic(log_blowup: usize, degree: u64, log_n: usize) -> Result<(), impl Debug> {
+    use p3_uni_stark::testing::{make_config_with_perm, Challenger, Perm};
 
-    type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
-    let config = MyConfig::new(pcs);
+    let perm = Perm::new_from_rng_128(&mut thread_rng());
+    let config = make_config_with_perm(&perm, log_blowup, 40, 8);
 
     let air = MulAir {
         degree,
@@ -255,41 +240,9 @@ fn prove_bb_twoadic_deg5() -> Result<(), impl Debug> {
 }
 
 fn do_test_m31_circle(log_blowup: usize, degree: u64, log_n: usize) -> Result<(), impl Debug> {
-    type Val = Mersenne31;
-    type Challenge = BinomialExtensionField<Val, 3>;
-
-    type ByteHash = Keccak256Hash;
-    type FieldHash = SerializingHasher32<ByteHash>;
-    let byte_hash = ByteHash {};
-    let field_hash = FieldHash::new(byte_hash);
-
-    type MyCompress = CompressionFunctionFromHasher<ByteHash, 2, 32>;
-    let compress = MyCompress::new(byte_hash);
+    use p3_uni_stark::testing::circle::{make_challenger, make_config};
 
-    type ValMmcs = MerkleTreeMmcs<Val, u8, FieldHash, MyCompress, 32>;
-    let val_mmcs = ValMmcs::new(field_hash, compress);
-
-    type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
-    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
-
-    type Challenger = SerializingChallenger32<Val, HashChallenger<u8, ByteHash, 32>>;
-
-    let fri_config = FriConfig {
-        log_blowup,
-        num_queries: 40,
-        proof_of_work_bits: 8,
-        mmcs: challenge_mmcs,
-    };
-
-    type Pcs = CirclePcs<Val, ValMmcs, ChallengeMmcs>;
-    let pcs = Pcs {
-        mmcs: val_mmcs,
-        fri_config,
-        _phantom: PhantomData,
-    };
-
-    type MyConfig = StarkConfig<Pcs, Challenge, Challenger>;
-    let config = MyConfig::new(pcs);
+    let config = make_config(log_blowup);
 
     let air = MulAir {
         degree,
@@ -297,12 +250,7 @@ fn do_test_m31_circle(log_blowup: usize, degree: u64, log_n: usize) -> Result<()
         uses_transition_constraints: true,
     };
 
-    do_test(
-        config,
-        air,
-        1 << log_n,
-        Challenger::from_hasher(vec![], byte_hash),
-    )
+    do_test(config, air, 1 << log_n, make_challenger())
 }
 
 #[test]
@@ -0,0 +1,239 @@
+use p3_air::{Air, BaseAir, ExtensionBuilder, PermutationAirBuilder};
+use p3_field::{ExtensionField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_uni_stark::testing::{make_config, Challenger, Val};
+use p3_uni_stark::{prove, verify, ProverError};
+
+/// Checks that the second main column is a permutation of the first, using a grand product
+/// `z` in the permutation trace, with `z_0 = 1` and `z_{i+1} = z_i (r - a_i) / (r - b_i)`.
+pub struct PermutationCheckAir;
+
+impl<F: Field> BaseAir<F> for PermutationCheckAir {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn permutation_width(&self) -> usize {
+        1
+    }
+
+    fn num_permutation_challenges(&self) -> usize {
+        1
+    }
+
+    fn generate_permutation_trace<EF>(
+        &self,
+        _preprocessed: Option<&RowMajorMatrix<F>>,
+        main: &RowMajorMatrix<F>,
+        challenges: &[EF],
+    ) -> Option<RowMajorMatrix<EF>>
+    where
+        EF: ExtensionField<F>,
+    {
+        let r = challenges[0];
+        let mut z = EF::ONE;
+        let values = main
+            .rows()
+            .map(|mut row| {
+                let (a, b) = (row.next().unwrap(), row.next().unwrap());
+                let current = z;
+                z *= (r - a) / (r - b);
+                current
+            })
+            .collect();
+        Some(RowMajorMatrix::new(values, 1))
+    }
+}
+
+impl<AB: PermutationAirBuilder> Air<AB> for PermutationCheckAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let a = AB::ExprEF::from(local[0].into());
+        let b = AB::ExprEF::from(local[1].into());
+
+        let permutation = builder.permutation();
+        let z: AB::ExprEF = permutation.row_slice(0)[0].into();
+        let z_next: AB::ExprEF = permutation.row_slice(1)[0].into();
+        let r: AB::ExprEF = builder.permutation_randomness()[0].into();
+
+        builder.when_first_row().assert_one_ext(z.clone());
+        builder.when_transition().assert_eq_ext(
+            z_next * (r.clone() - b.clone()),
+            z.clone() * (r.clone() - a.clone()),
+        );
+        builder
+            .when_last_row()
+            .assert_eq_ext(z * (r.clone() - a), r - b);
+    }
+}
+
+/// `PermutationCheckAir`, with a permutation trace generator which misbehaves.
+pub enum FaultyPermutationAir {
+    /// Generates no permutation trace.
+    Missing,
+    /// Generates a permutation trace half as tall as the main trace.
+    Short,
+}
+
+impl<F: Field> BaseAir<F> for FaultyPermutationAir {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn permutation_width(&self) -> usize {
+        1
+    }
+
+    fn num_permutation_challenges(&self) -> usize {
+        1
+    }
+
+    fn generate_permutation_trace<EF>(
+        &self,
+        preprocessed: Option<&RowMajorMatrix<F>>,
+        main: &RowMajorMatrix<F>,
+        challenges: &[EF],
+    ) -> Option<RowMajorMatrix<EF>>
+    where
+        EF: ExtensionField<F>,
+    {
+        match self {
+            Self::Missing => None,
+            Self::Short => PermutationCheckAir
+                .generate_permutation_trace(preprocessed, main, challenges)
+                .map(|mut trace| {
+                    trace.values.truncate(main.height() / 2);
+                    trace
+                }),
+        }
+    }
+}
+
+impl<AB: PermutationAirBuilder> Air<AB> for FaultyPermutationAir {
+    fn eval(&self, builder: &mut AB) {
+        PermutationCheckAir.eval(builder);
+    }
+}
+
+fn generate_trace<F: Field>(log_height: usize, valid: bool) -> RowMajorMatrix<F> {
+    let n = 1 << log_height;
+    let mut values = Vec::with_capacity(2 * n);
+    for i in 0..n {
+        // The second column is the first one reversed.
+        values.push(F::from_canonical_usize(i * i));
+        values.push(F::from_canonical_usize((n - 1 - i) * (n - 1 - i)));
+    }
+    if !valid {
+        values[1] += F::ONE;
+    }
+    RowMajorMatrix::new(values, 2)
+}
+
+#[test]
+fn test_permutation_trace() {
+    let (config, perm) = make_config(1);
+    let trace = generate_trace::<Val>(6, true);
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(
+        &config,
+        &PermutationCheckAir,
+        &mut challenger,
+        trace,
+        &vec![],
+    )
+    .expect("proving failed");
+
+    let serialized_proof = postcard::to_allocvec(&proof).expect("unable to serialize proof");
+    let proof = postcard::from_bytes(&serialized_proof).expect("unable to deserialize proof");
+
+    let mut challenger = Challenger::new(perm);
+    verify(
+        &config,
+        &PermutationCheckAir,
+        &mut challenger,
+        &proof,
+        &vec![],
+    )
+    .expect("verification failed");
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_permutation_trace_not_a_permutation() {
+    let (config, perm) = make_config(1);
+    let trace = generate_trace::<Val>(6, false);
+
+    let mut challenger = Challenger::new(perm);
+    assert!(matches!(
+        prove(
+            &config,
+            &PermutationCheckAir,
+            &mut challenger,
+            trace,
+            &vec![],
+        ),
+        Err(ProverError::ConstraintFailure { .. })
+    ));
+}
+
+#[cfg(not(debug_assertions))]
+#[test]
+fn test_permutation_trace_not_a_permutation() {
+    let (config, perm) = make_config(1);
+    let trace = generate_trace::<Val>(6, false);
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(
+        &config,
+        &PermutationCheckAir,
+        &mut challenger,
+        trace,
+        &vec![],
+    )
+    .expect("proving failed");
+
+    let mut challenger = Challenger::new(perm);
+    assert!(verify(
+        &config,
+        &PermutationCheckAir,
+        &mut challenger,
+        &proof,
+        &vec![]
+    )
+    .is_err());
+}
+
+#[test]
+fn test_faulty_permutation_trace() {
+    let (config, perm) = make_config(1);
+
+    let mut challenger = Challenger::new(perm.clone());
+    assert!(matches!(
+        prove(
+            &config,
+            &FaultyPermutationAir::Missing,
+            &mut challenger,
+            generate_trace::<Val>(6, true),
+            &vec![],
+        ),
+        Err(ProverError::MissingPermutationTrace)
+    ));
+
+    let mut challenger = Challenger::new(perm);
+    assert!(matches!(
+        prove(
+            &config,
+            &FaultyPermutationAir::Short,
+            &mut challenger,
+            generate_trace::<Val>(6, true),
+            &vec![],
+        ),
+        Err(ProverError::PermutationTraceHeightMismatch {
+            expected: 64,
+            actual: 32
+        })
+    ));
+}
@@ -0,0 +1,176 @@
+use p3_air::{Air, AirBuilder, BaseAir, PairBuilder};
+use p3_field::{Field, FieldAlgebra};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_uni_stark::testing::{make_config, Challenger, Val};
+use p3_uni_stark::{
+    prove, prove_with_key, setup, verify, verify_with_key, ProverError, VerificationError,
+};
+
+/// An AIR whose single preprocessed column holds `offset + i` on row `i`, and whose single main
+/// column must hold the square of the preprocessed column.
+pub struct SquaresAir {
+    log_height: usize,
+    offset: u64,
+}
+
+impl SquaresAir {
+    fn generate_trace<F: Field>(&self) -> RowMajorMatrix<F> {
+        let values = (0..1 << self.log_height)
+            .map(|i| F::from_canonical_u64(self.offset + i).square())
+            .collect();
+        RowMajorMatrix::new(values, 1)
+    }
+}
+
+impl<F: Field> BaseAir<F> for SquaresAir {
+    fn width(&self) -> usize {
+        1
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        let values = (0..1 << self.log_height)
+            .map(|i| F::from_canonical_u64(self.offset + i))
+            .collect();
+        Some(RowMajorMatrix::new(values, 1))
+    }
+}
+
+impl<AB: PairBuilder> Air<AB> for SquaresAir {
+    fn eval(&self, builder: &mut AB) {
+        let preprocessed = builder.preprocessed();
+        let main = builder.main();
+
+        let c = preprocessed.row_slice(0)[0];
+        let c_next = preprocessed.row_slice(1)[0];
+        let x = main.row_slice(0)[0];
+        let x_next = main.row_slice(1)[0];
+
+        builder.assert_eq(x, c * c);
+        // (c + 1)^2 - c^2 = c + (c + 1)
+        builder
+            .when_transition()
+            .assert_eq(x_next - x, c_next.into() + c);
+    }
+}
+
+/// An AIR whose single main column must equal its single preprocessed column, which has five
+/// rows, not a power of two.
+pub struct UnpaddedAir;
+
+impl<F: Field> BaseAir<F> for UnpaddedAir {
+    fn width(&self) -> usize {
+        1
+    }
+
+    fn preprocessed_trace(&self) -> Option<RowMajorMatrix<F>> {
+        Some(RowMajorMatrix::new_col(F::zero_vec(5)))
+    }
+}
+
+impl<AB: PairBuilder> Air<AB> for UnpaddedAir {
+    fn eval(&self, builder: &mut AB) {
+        let c = builder.preprocessed().row_slice(0)[0];
+        let x = builder.main().row_slice(0)[0];
+        builder.assert_eq(x, c);
+    }
+}
+
+#[test]
+fn test_preprocessed_reused_keys() {
+    let (config, perm) = make_config(1);
+    let air = SquaresAir {
+        log_height: 6,
+        offset: 3,
+    };
+    let (pk, vk) = setup(&config, &air).expect("setup failed");
+    assert_eq!(pk.degree_bits(), Some(6));
+    assert_eq!(vk.preprocessed_width(), 1);
+
+    for _ in 0..2 {
+        let trace = air.generate_trace::<Val>();
+        let mut challenger = Challenger::new(perm.clone());
+        let proof = prove_with_key(&config, &pk, &air, &mut challenger, trace, &vec![])
+            .expect("proving failed");
+        let mut challenger = Challenger::new(perm.clone());
+        verify_with_key(&config, &vk, &air, &mut challenger, &proof, &vec![])
+            .expect("verification failed");
+    }
+}
+
+#[test]
+fn test_preprocessed_without_explicit_setup() {
+    let (config, perm) = make_config(1);
+    let air = SquaresAir {
+        log_height: 5,
+        offset: 0,
+    };
+    let trace = air.generate_trace::<Val>();
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut challenger, trace, &vec![]).expect("proving failed");
+    let mut challenger = Challenger::new(perm);
+    verify(&config, &air, &mut challenger, &proof, &vec![]).expect("verification failed");
+}
+
+#[test]
+fn test_preprocessed_wrong_verifying_key() {
+    let (config, perm) = make_config(1);
+    let air = SquaresAir {
+        log_height: 5,
+        offset: 0,
+    };
+    let trace = air.generate_trace::<Val>();
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut challenger, trace, &vec![]).expect("proving failed");
+
+    // A verifying key committing to a different preprocessed trace must be rejected.
+    let other_air = SquaresAir {
+        log_height: 5,
+        offset: 1,
+    };
+    let (_, other_vk) = setup(&config, &other_air).expect("setup failed");
+    let mut challenger = Challenger::new(perm.clone());
+    assert!(verify_with_key(&config, &other_vk, &air, &mut challenger, &proof, &vec![]).is_err());
+
+    // As must a verifying key for a different trace height.
+    let other_air = SquaresAir {
+        log_height: 6,
+        offset: 0,
+    };
+    let (_, other_vk) = setup(&config, &other_air).expect("setup failed");
+    let mut challenger = Challenger::new(perm);
+    assert!(matches!(
+        verify_with_key(&config, &other_vk, &air, &mut challenger, &proof, &vec![]),
+        Err(VerificationError::InvalidProofShape)
+    ));
+}
+
+#[test]
+fn test_preprocessed_invalid_height() {
+    let (config, perm) = make_config(1);
+    assert!(matches!(
+        setup(&config, &UnpaddedAir),
+        Err(ProverError::InvalidTraceHeight { height: 5 })
+    ));
+
+    let trace = RowMajorMatrix::new_col(vec![Val::ZERO; 8]);
+    let mut challenger = Challenger::new(perm.clone());
+    assert!(matches!(
+        prove(&config, &UnpaddedAir, &mut challenger, trace, &vec![]),
+        Err(ProverError::InvalidTraceHeight { height: 5 })
+    ));
+
+    // Verifying any proof against this AIR fails, rather than panicking.
+    let air = SquaresAir {
+        log_height: 3,
+        offset: 0,
+    };
+    let trace = air.generate_trace::<Val>();
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut challenger, trace, &vec![]).expect("proving failed");
+    let mut challenger = Challenger::new(perm);
+    assert!(matches!(
+        verify(&config, &UnpaddedAir, &mut challenger, &proof, &vec![]),
+        Err(VerificationError::InvalidPreprocessedTrace)
+    ));
+}
@@ -0,0 +1,135 @@
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::FieldAlgebra;
+use p3_matrix::Matrix;
+use p3_uni_stark::testing::MyConfig;
+use p3_uni_stark::{SecurityRegime, StarkSoundness};
+
+const WIDTH: usize = 4;
+
+/// Each column is cubed and incremented from one row to the next.
+struct CubesAir;
+
+impl<F> BaseAir<F> for CubesAir {
+    fn width(&self) -> usize {
+        WIDTH
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for CubesAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        for (&x, &y) in local.iter().zip(next.iter()) {
+            let x: AB::Expr = x.into();
+            builder
+                .when_transition()
+                .assert_eq(y, x.clone() * x.clone() * x + AB::Expr::ONE);
+        }
+    }
+}
+
+#[test]
+fn test_soundness_parameters() {
+    let soundness = StarkSoundness::new::<MyConfig, _>(&CubesAir, 16, 1, 100, 16);
+    assert_eq!(soundness.log_degree, 16);
+    assert_eq!(soundness.constraint_degree, 3);
+    assert_eq!(soundness.num_constraints, WIDTH);
+    // Each column at two points, and two quotient chunks of four base columns at one point.
+    assert_eq!(soundness.num_openings, 2 * WIDTH + 2 * 4);
+    assert!((soundness.challenge_bits - 4.0 * 30.907).abs() < 0.01);
+}
+
+#[test]
+fn test_conjectured_soundness() {
+    let soundness = StarkSoundness::new::<MyConfig, _>(&CubesAir, 16, 1, 80, 16);
+    let breakdown = soundness.breakdown(SecurityRegime::Conjectured);
+    assert_eq!(breakdown.fri_queries, 96.0);
+
+    // The algebraic steps are limited by the 124 bit challenge field, less the log2 of the
+    // number of field elements at which a false statement could pass.
+    assert!((breakdown.constraint_batching - (soundness.challenge_bits - 2.0)).abs() < 1e-9);
+    assert!((breakdown.out_of_domain - (soundness.challenge_bits - 18.0)).abs() < 1e-4);
+    assert_eq!(breakdown.bits(), breakdown.fri_queries);
+    assert_eq!(soundness.bits(SecurityRegime::Conjectured), 96.0);
+
+    // With more queries, batching the 16 opened columns into one codeword over the LDE domain of
+    // size 2^17 becomes the weakest step.
+    let more_queries = StarkSoundness {
+        num_queries: 100,
+        ..soundness
+    };
+    let breakdown = more_queries.breakdown(SecurityRegime::Conjectured);
+    assert_eq!(breakdown.bits(), breakdown.fri_batching);
+    assert!(breakdown.bits() < 116.0);
+}
+
+#[test]
+fn test_proven_soundness_is_lower() {
+    let soundness = StarkSoundness::new::<MyConfig, _>(&CubesAir, 16, 1, 100, 16);
+    let conjectured = soundness.breakdown(SecurityRegime::Conjectured);
+    let proven = soundness.breakdown(SecurityRegime::Proven);
+    assert!(proven.constraint_batching < conjectured.constraint_batching);
+    assert!(proven.out_of_domain < conjectured.out_of_domain);
+    assert!(proven.fri_batching < conjectured.fri_batching);
+    assert!(proven.fri_commit_phase < conjectured.fri_commit_phase);
+    assert!(proven.fri_queries < conjectured.fri_queries);
+    assert!(proven.bits() < conjectured.bits());
+    assert!(proven.bits() > 0.0);
+}
+
+#[test]
+fn test_min_num_queries() {
+    let soundness = StarkSoundness::new::<MyConfig, _>(&CubesAir, 16, 2, 1, 20);
+    assert_eq!(
+        soundness.min_num_queries(100.0, SecurityRegime::Conjectured),
+        Some(40)
+    );
+
+    let num_queries = soundness
+        .min_num_queries(60.0, SecurityRegime::Proven)
+        .expect("no number of queries reaches 60 bits");
+    let with_queries = |num_queries| StarkSoundness {
+        num_queries,
+        ..soundness
+    };
+    assert!(with_queries(num_queries).bits(SecurityRegime::Proven) >= 60.0);
+    assert!(with_queries(num_queries - 1).bits(SecurityRegime::Proven) < 60.0);
+    assert!(num_queries > 30);
+
+    // The challenge field bounds the soundness of the algebraic steps, whatever the queries.
+    assert_eq!(
+        soundness.min_num_queries(110.0, SecurityRegime::Conjectured),
+        None
+    );
+    assert_eq!(
+        soundness.min_num_queries(80.0, SecurityRegime::Proven),
+        None
+    );
+}
+
+#[test]
+fn test_folding_parameters() {
+    let soundness = StarkSoundness::new::<MyConfig, _>(&CubesAir, 16, 1, 80, 16);
+    let higher_arity = StarkSoundness {
+        log_arity: 3,
+        ..soundness
+    };
+    let final_poly = StarkSoundness {
+        log_final_poly_len: 8,
+        ..soundness
+    };
+    let commit_phase = |soundness: StarkSoundness| {
+        soundness
+            .breakdown(SecurityRegime::Conjectured)
+            .fri_commit_phase
+    };
+
+    // Each fold by 8 fails for seven times as many challenges as a fold by 2 of the same domain,
+    // while stopping at the final polynomial skips the smallest domains.
+    assert!(commit_phase(higher_arity) < commit_phase(soundness) - 1.0);
+    assert!(commit_phase(final_poly) > commit_phase(soundness));
+    assert_eq!(
+        higher_arity.bits(SecurityRegime::Conjectured),
+        soundness.bits(SecurityRegime::Conjectured)
+    );
+}
@@ -0,0 +1,125 @@
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::{Field, FieldAlgebra, FieldExtensionAlgebra, PrimeField32};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_uni_stark::testing::hiding::{make_config, MyConfig};
+use p3_uni_stark::testing::{Challenge, Challenger, Perm, Val};
+use p3_uni_stark::{prove, prove_batch, verify, verify_batch, Proof, ProverError};
+
+/// Asserts that the first column is constant, and that the second is its square.
+pub struct SquareAir;
+
+impl SquareAir {
+    fn generate_trace<F: Field>(&self, log_height: usize, value: F) -> RowMajorMatrix<F> {
+        RowMajorMatrix::new([value, value.square()].repeat(1 << log_height), 2)
+    }
+}
+
+impl<F> BaseAir<F> for SquareAir {
+    fn width(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for SquareAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        builder.assert_eq(local[1], local[0] * local[0]);
+        builder.when_transition().assert_eq(next[0], local[0]);
+    }
+}
+
+/// The number of FRI queries of the test configurations.
+const NUM_QUERIES: usize = 20;
+
+/// Traces must have more rows than the number of points their polynomials are opened at, i.e.
+/// `NUM_QUERIES` plus two, for their randomization to hide them.
+const LOG_HEIGHT: usize = 5;
+
+fn prove_square(config: &MyConfig, perm: &Perm, value: Val) -> Proof<MyConfig> {
+    let trace = SquareAir.generate_trace(LOG_HEIGHT, value);
+    let mut challenger = Challenger::new(perm.clone());
+    prove(config, &SquareAir, &mut challenger, trace, &vec![]).expect("proving failed")
+}
+
+#[test]
+fn test_zk_prove_verify() {
+    let (config, perm) = make_config(2, NUM_QUERIES);
+    let proof = prove_square(&config, &perm, Val::from_canonical_u32(3));
+
+    let serialized_proof = postcard::to_allocvec(&proof).expect("unable to serialize proof");
+    let proof: Proof<MyConfig> =
+        postcard::from_bytes(&serialized_proof).expect("unable to deserialize proof");
+    // Randomized traces double the quotient degree.
+    assert_eq!(proof.opened_values().quotient_chunks().len(), 4);
+
+    let mut challenger = Challenger::new(perm);
+    verify(&config, &SquareAir, &mut challenger, &proof, &vec![]).expect("verification failed");
+}
+
+#[test]
+fn test_zk_batch() {
+    let (config, perm) = make_config(2, NUM_QUERIES);
+    let airs = [SquareAir, SquareAir];
+    let traces = vec![
+        SquareAir.generate_trace(LOG_HEIGHT, Val::from_canonical_u32(3)),
+        SquareAir.generate_trace(LOG_HEIGHT + 1, Val::from_canonical_u32(5)),
+    ];
+    let public_values = vec![vec![]; 2];
+
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove_batch(&config, &airs, &mut challenger, traces, &public_values)
+        .expect("proving failed");
+
+    let mut challenger = Challenger::new(perm);
+    verify_batch(&config, &airs, &mut challenger, &proof, &public_values)
+        .expect("verification failed");
+}
+
+/// Without zero knowledge, the opened value of a constant column is the constant itself. Here we
+/// check that for two different constants, the opened trace and quotient values look uniformly
+/// random, by looking at how often their low bits are set across many proofs.
+#[test]
+fn test_zk_opened_values_independent_of_witness() {
+    const NUM_PROOFS: usize = 64;
+    let (config, perm) = make_config(2, NUM_QUERIES);
+    let low_bit = |value: Challenge| {
+        <Challenge as FieldExtensionAlgebra<Val>>::as_base_slice(&value)[0].as_canonical_u32() & 1
+    };
+
+    for witness in [0, 1].map(Val::from_canonical_u32) {
+        let mut trace_low_bits = 0;
+        let mut quotient_low_bits = 0;
+        for _ in 0..NUM_PROOFS {
+            let proof = prove_square(&config, &perm, witness);
+            let opened_values = proof.opened_values();
+            let trace_value = opened_values.trace_local()[0];
+            assert_ne!(trace_value, Challenge::from_base(witness));
+            trace_low_bits += low_bit(trace_value);
+            quotient_low_bits += low_bit(opened_values.quotient_chunks()[0][0]);
+        }
+        // Each count is binomially distributed with a standard deviation of 4, so for uniformly
+        // random values this fails for around one seed in 1e4. The seed is fixed, so it doesn't
+        // fail at random.
+        for count in [trace_low_bits, quotient_low_bits] {
+            assert!((16..=48).contains(&count), "biased low bits: {count}");
+        }
+    }
+}
+
+/// Randomized traces have twice the degree, so in zero-knowledge mode a degree-2 AIR needs a
+/// quotient domain 4 times as large as the trace domain, which a blowup of 1 can't provide.
+#[test]
+fn test_zk_insufficient_blowup() {
+    let (config, perm) = make_config(0, NUM_QUERIES);
+    let trace = SquareAir.generate_trace(LOG_HEIGHT, Val::from_canonical_u32(3));
+    let mut challenger = Challenger::new(perm);
+    assert!(matches!(
+        prove(&config, &SquareAir, &mut challenger, trace, &vec![]),
+        Err(ProverError::InsufficientBlowup {
+            log_quotient_degree: 2,
+            max_log_quotient_degree: 1,
+        })
+    ));
+}